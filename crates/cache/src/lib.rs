@@ -31,6 +31,12 @@ impl PersistentCache {
         Ok(())
     }
 
+    /// Write pending changes to disk
+    pub fn flush(&self) -> Result<()> {
+        self.db.flush()?;
+        Ok(())
+    }
+
     /// Clear all entries
     pub fn clear(&self) -> Result<()> {
         self.db.clear()?;
@@ -0,0 +1,36 @@
+// A mock API through `configureServer`, plus a fallback page once Nexus's
+// own routes found nothing.
+module.exports = function api() {
+  return {
+    name: 'api',
+    apply: 'serve',
+    configureServer(server) {
+      server.middlewares.use('/api', (req, res, next) => {
+        if (req.url === '/fail') {
+          return next(new Error('api down'));
+        }
+        if (req.method === 'POST') {
+          let body = '';
+          req.on('data', (chunk) => (body += chunk));
+          req.on('end', () => {
+            res.setHeader('Content-Type', 'application/json');
+            res.end(JSON.stringify({ echo: JSON.parse(body) }));
+          });
+          return;
+        }
+        if (req.url !== '/hello') {
+          return next();
+        }
+        res.statusCode = 201;
+        res.setHeader('Content-Type', 'application/json');
+        res.end(JSON.stringify({ hello: server.config.command }));
+      });
+      return () => {
+        server.middlewares.use((req, res) => {
+          res.writeHead(200, { 'Content-Type': 'text/plain' });
+          res.end(`fallback ${req.url}`);
+        });
+      };
+    },
+  };
+};
@@ -0,0 +1,20 @@
+// A `pre` transform using the plugin context and the object hook form.
+module.exports = function banner(text) {
+  return {
+    name: 'banner',
+    enforce: 'pre',
+    transform: {
+      order: 'pre',
+      async handler(code, id) {
+        if (!id.endsWith('.ts')) {
+          return null;
+        }
+        if (code.includes('@no-banner')) {
+          this.error(`banner not allowed in ${id}`);
+        }
+        const resolved = await this.resolve('virtual:env', id);
+        return { code: `/* ${text} (${resolved.id.slice(1)}) */\n${code}`, map: { mappings: '' } };
+      },
+    },
+  };
+};
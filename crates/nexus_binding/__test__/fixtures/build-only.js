@@ -0,0 +1,16 @@
+// Only applies to `build`, and sees the resolved config.
+module.exports = function buildOnly(resolved) {
+  return {
+    name: 'build-only',
+    apply: (config, env) => env.command === 'build',
+    config(config) {
+      config.define = { __MODE__: JSON.stringify(config.mode) };
+    },
+    configResolved(config) {
+      resolved.push(config);
+    },
+    transform(code) {
+      return code.replace('__MODE__', this.meta.watchMode ? '"watch"' : '"once"');
+    },
+  };
+};
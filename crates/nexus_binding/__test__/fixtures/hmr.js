@@ -0,0 +1,16 @@
+// `handleHotUpdate`: changes to `.data` files reload their importers,
+// changes to `.log` files are ignored.
+module.exports = function hmr(seen) {
+  return {
+    name: 'hmr',
+    async handleHotUpdate(ctx) {
+      if (ctx.file.endsWith('.log')) {
+        return [];
+      }
+      if (ctx.file.endsWith('.data')) {
+        seen.push(await ctx.read());
+        return [ctx.server.moduleGraph.getModuleById(ctx.file.replace(/\.data$/, '.ts'))];
+      }
+    },
+  };
+};
@@ -0,0 +1,26 @@
+// `transformIndexHtml` in its three return shapes.
+module.exports = function html() {
+  return [
+    {
+      name: 'html-title',
+      transformIndexHtml: (html) => html.replace(/<title>.*<\/title>/, '<title>Nexus</title>'),
+    },
+    {
+      name: 'html-tags',
+      transformIndexHtml: {
+        order: 'post',
+        handler: (html, ctx) => [
+          { tag: 'meta', attrs: { name: 'page', content: ctx.path }, injectTo: 'head' },
+          { tag: 'script', attrs: { type: 'module', async: true }, children: 'window.ready = true', injectTo: 'body' },
+        ],
+      },
+    },
+    {
+      name: 'html-legacy',
+      transformIndexHtml: {
+        enforce: 'pre',
+        transform: (html) => ({ html: html.replace('<body>', '<body class="app">'), tags: [{ tag: 'noscript', children: 'JS required', injectTo: 'body-prepend' }] }),
+      },
+    },
+  ];
+};
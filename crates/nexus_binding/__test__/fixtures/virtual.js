@@ -0,0 +1,17 @@
+// `import { env } from 'virtual:env'`, the usual virtual module pattern.
+module.exports = function virtualEnv(values) {
+  const id = 'virtual:env';
+  return {
+    name: 'virtual-env',
+    resolveId(source) {
+      if (source === id) {
+        return '\0' + id;
+      }
+    },
+    load(id) {
+      if (id === '\0virtual:env') {
+        return `export const env = ${JSON.stringify(values)};`;
+      }
+    },
+  };
+};
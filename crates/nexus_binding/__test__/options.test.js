@@ -0,0 +1,22 @@
+// Project options handed to the native binding. Needs NEXUS_BINDING
+// pointing at a built `.node` file.
+
+const { test } = require('node:test');
+const assert = require('node:assert');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+
+test('build with mode and define', { skip: !process.env.NEXUS_BINDING && 'NEXUS_BINDING not set' }, async () => {
+  const binding = require(path.resolve(process.env.NEXUS_BINDING));
+  const app = fs.mkdtempSync(path.join(os.tmpdir(), 'nexus-options-app-'));
+  fs.mkdirSync(path.join(app, 'src'));
+  fs.writeFileSync(path.join(app, '.env.staging'), 'NEXUS_API_URL=https://staging.example.com\n');
+  fs.writeFileSync(path.join(app, 'src/index.js'), 'console.log(import.meta.env.MODE, import.meta.env.NEXUS_API_URL, __APP_VERSION__);');
+
+  await binding.build(app, [], { mode: 'staging', define: { __APP_VERSION__: '"1.2.0"' } });
+
+  const main = fs.readFileSync(path.join(app, 'dist/assets/main.js'), 'utf8');
+  assert.match(main, /console\.log\("staging", "https:\/\/staging\.example\.com", "1\.2\.0"\)/);
+  fs.rmSync(app, { recursive: true });
+});
@@ -0,0 +1,38 @@
+// `toNative`: what JS plugins look like to the native binding.
+
+const { test } = require('node:test');
+const assert = require('node:assert');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { toNative } = require('../plugins');
+
+const transform = (code) => code;
+
+test('filters become Rust regex sources', () => {
+  const native = toNative({ name: 'filters', transform, filter: { transform: [/\.TSX?$/i, /^\/src\/.*$/ms, /\.vue$/gu, '\\.css$'] } });
+  assert.deepStrictEqual(native.filter.transform, ['(?i)\\.TSX?$', '(?ms)^\\/src\\/.*$', '\\.vue$', '\\.css$']);
+  assert.deepStrictEqual(toNative({ name: 'escaped', transform, filter: { transform: /\(?=x\)|[(?!]/ } }).filter.transform, ['\\(?=x\\)|[(?!]']);
+});
+
+test('unsupported filters are rejected', () => {
+  const native = (filter) => () => toNative({ name: 'strict', transform, filter: { transform: filter } });
+  assert.throws(native(/^(?!\/node_modules\/).*\.ts$/), /plugin strict: transform filter .* uses lookarounds/);
+  assert.throws(native(/(?<=src\/)a\.ts$/), /uses lookarounds/);
+  assert.throws(native(/(['"]).*\1/), /uses backreferences/);
+  assert.throws(native(/(?<q>['"]).*\k<q>/), /uses backreferences/);
+  assert.throws(native(/[\p{L}--a]/v), /uses the v flag/);
+});
+
+test('flags reach the native engine', { skip: !process.env.NEXUS_BINDING && 'NEXUS_BINDING not set' }, async () => {
+  const binding = require(path.resolve(process.env.NEXUS_BINDING));
+  const app = fs.mkdtempSync(path.join(os.tmpdir(), 'nexus-filter-app-'));
+  fs.mkdirSync(path.join(app, 'src'));
+  fs.writeFileSync(path.join(app, 'src/index.js'), "console.log('hi');");
+
+  const tag = { name: 'tag', transform: (code) => `${code}\nconsole.log('tagged');`, filter: { transform: /^\/SRC\/.*\.JS$/is } };
+  await binding.build(app, [toNative(tag)]);
+
+  assert.match(fs.readFileSync(path.join(app, 'dist/assets/main.js'), 'utf8'), /console\.log\("tagged"\)|console\.log\('tagged'\)/);
+  fs.rmSync(app, { recursive: true });
+});
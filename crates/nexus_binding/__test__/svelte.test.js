@@ -0,0 +1,68 @@
+// The Svelte compiler adapter, over a fake `svelte/compiler`. With
+// NEXUS_BINDING pointing at a built `.node` file, a build runs too.
+
+const { test } = require('node:test');
+const assert = require('node:assert');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { svelteCompiler } = require('../svelte');
+
+function fakeCompiler(version, seen = []) {
+  return {
+    VERSION: version,
+    compile(source, options) {
+      seen.push(options);
+      if (source.includes('{#if')) {
+        throw Object.assign(new Error('Expected {/if}'), { frame: '1: {#if ok}' });
+      }
+      const css = /<style>([^]*)<\/style>/.exec(source);
+      return {
+        js: { code: `export default function App() { return ${JSON.stringify(source.replace(/<style>[^]*/, '').trim())}; }` },
+        css: css ? { code: css[1].replace('.title', `.title.${options.cssHash({ hash: () => 'h4sh', filename: options.filename })}`) } : null,
+      };
+    },
+  };
+}
+
+async function compile(compiler, call) {
+  const [reply] = JSON.parse(await compiler.compileBatch(JSON.stringify([call])));
+  return reply;
+}
+
+test('compiles through the given compiler', async () => {
+  const seen = [];
+  const compiler = svelteCompiler(os.tmpdir(), fakeCompiler('5.1.0', seen));
+  assert.strictEqual(compiler.name, 'svelte@5.1.0');
+  const reply = await compile(compiler, { source: '<h1 class="title">hi</h1><style>.title { color: red; }</style>', id: '/src/App.svelte', dev: true });
+  assert.deepStrictEqual(reply.value.css, '.title.svelte-h4sh { color: red; }');
+  assert.deepStrictEqual([seen[0].generate, seen[0].css, seen[0].hmr, seen[0].dev], ['client', 'external', true, true]);
+
+  const legacy = [];
+  await compile(svelteCompiler(os.tmpdir(), fakeCompiler('4.2.0', legacy)), { source: '<p>hi</p>', id: '/src/App.svelte', dev: true });
+  assert.deepStrictEqual([legacy[0].generate, legacy[0].css, 'hmr' in legacy[0]], ['dom', 'external', false]);
+
+  const broken = await compile(compiler, { source: '{#if ok}', id: '/src/Broken.svelte', dev: false });
+  assert.strictEqual(broken.error, 'Expected {/if}\n1: {#if ok}');
+});
+
+test('off without a compiler', () => {
+  assert.strictEqual(svelteCompiler(fs.mkdtempSync(path.join(os.tmpdir(), 'nexus-no-svelte-'))), undefined);
+  assert.strictEqual(svelteCompiler(os.tmpdir(), false), undefined);
+});
+
+test('build through the native binding', { skip: !process.env.NEXUS_BINDING && 'NEXUS_BINDING not set' }, async () => {
+  const binding = require(path.resolve(process.env.NEXUS_BINDING));
+  const app = fs.mkdtempSync(path.join(os.tmpdir(), 'nexus-svelte-app-'));
+  fs.mkdirSync(path.join(app, 'src'));
+  fs.writeFileSync(path.join(app, 'src/index.js'), "import App from './App.svelte';\nconsole.log(App());");
+  fs.writeFileSync(path.join(app, 'src/App.svelte'), '<h1 class="title">hi</h1><style>.title { color: red; }</style>');
+
+  await binding.build(app, [], {}, svelteCompiler(app, fakeCompiler('5.1.0')));
+
+  const main = fs.readFileSync(path.join(app, 'dist/assets/main.js'), 'utf8');
+  assert.match(main, /<h1 class=\\"title\\">hi<\/h1>/);
+  const css = fs.readFileSync(path.join(app, 'dist/assets/style.css'), 'utf8');
+  assert.match(css, /\.title\.svelte-h4sh/);
+  fs.rmSync(app, { recursive: true });
+});
@@ -0,0 +1,143 @@
+// Conformance of the Vite plugin layer: fixture plugins go through `fromVite`
+// and `toNative`, and are called the way the native binding calls them.
+// With NEXUS_BINDING pointing at a built `.node` file, a build runs too.
+
+const { test } = require('node:test');
+const assert = require('node:assert');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { fromVite, toViteId, fromViteId } = require('../vite');
+const { toNative } = require('../plugins');
+
+const virtualEnv = require('./fixtures/virtual');
+const banner = require('./fixtures/banner');
+const html = require('./fixtures/html');
+const api = require('./fixtures/api');
+const hmr = require('./fixtures/hmr');
+const buildOnly = require('./fixtures/build-only');
+
+const root = path.join(os.tmpdir(), 'nexus-vite');
+
+// One call across the JSON boundary, as a batch of one.
+async function call(plugin, hook, args) {
+  const native = toNative(plugin);
+  assert.ok(native[`${hook}Batch`], `${plugin.name} has no ${hook}`);
+  const [reply] = JSON.parse(await native[`${hook}Batch`](JSON.stringify([args])));
+  if (reply.error) {
+    throw new Error(reply.error);
+  }
+  return reply.value;
+}
+
+function byName(plugins, name) {
+  return plugins.find((plugin) => plugin.name === name);
+}
+
+test('ids map between Vite and Nexus', () => {
+  assert.strictEqual(toViteId(root, '/src/a.ts'), path.join(root, 'src/a.ts'));
+  assert.strictEqual(toViteId(root, '/@id/__x00__virtual:env'), '\0virtual:env');
+  assert.strictEqual(fromViteId(root, '\0virtual:env'), path.join(root, '@id/__x00__virtual:env'));
+  assert.strictEqual(fromViteId(root, path.join(root, 'src/a.ts')), path.join(root, 'src/a.ts'));
+});
+
+test('resolveId, load and transform', async () => {
+  const plugins = await fromVite([virtualEnv({ debug: true }), [banner('hi'), false]], { root, command: 'build' });
+  assert.deepStrictEqual(plugins.map((p) => [p.name, p.order]), [['virtual-env', undefined], ['banner', 'pre']]);
+
+  const env = byName(plugins, 'virtual-env');
+  const resolved = await call(env, 'resolveId', { specifier: 'virtual:env', importer: path.join(root, 'src/index.tsx') });
+  assert.strictEqual(resolved, path.join(root, '@id/__x00__virtual:env'));
+  assert.strictEqual(await call(env, 'resolveId', { specifier: './a', importer: path.join(root, 'src/index.tsx') }), null);
+  assert.strictEqual(await call(env, 'load', { id: '/@id/__x00__virtual:env', path: resolved }), 'export const env = {"debug":true};');
+
+  const bannered = await call(byName(plugins, 'banner'), 'transform', { code: 'let a;', id: '/src/a.ts' });
+  assert.deepStrictEqual(bannered, { code: '/* hi (virtual:env) */\nlet a;', map: '{"mappings":""}' });
+  assert.strictEqual(await call(byName(plugins, 'banner'), 'transform', { code: 'a', id: '/src/a.css' }), null);
+  await assert.rejects(call(byName(plugins, 'banner'), 'transform', { code: '// @no-banner', id: '/src/b.ts' }), {
+    message: `banner not allowed in ${path.join(root, 'src/b.ts')}`,
+  });
+});
+
+test('transformIndexHtml', async () => {
+  const plugins = await fromVite([html()], { root, command: 'build' });
+  let page = '<html><head><title>Vite</title></head><body><div id="root"></div></body></html>';
+  for (const plugin of plugins) {
+    page = (await call(plugin, 'transformIndexHtml', { html: page, path: '/index.html' })) ?? page;
+  }
+  assert.strictEqual(
+    page,
+    '<html><head><title>Nexus</title><meta name="page" content="/index.html"></meta></head>' +
+      '<body class="app"><noscript>JS required</noscript><div id="root"></div>' +
+      '<script type="module" async>window.ready = true</script></body></html>',
+  );
+});
+
+test('configureServer', async () => {
+  const plugins = await fromVite([api()], { root, command: 'serve' });
+  const request = (method, url, body = '') => ({ method, url, headers: {}, body });
+  const plugin = plugins[0];
+
+  assert.deepStrictEqual(await call(plugin, 'handleRequest', { request: request('GET', '/api/hello'), phase: 'pre' }), {
+    status: 201,
+    headers: { 'content-type': 'application/json' },
+    body: '{"hello":"serve"}',
+  });
+  const echo = await call(plugin, 'handleRequest', { request: request('POST', '/api/echo', '{"a":1}'), phase: 'pre' });
+  assert.strictEqual(echo.body, '{"echo":{"a":1}}');
+  assert.strictEqual(await call(plugin, 'handleRequest', { request: request('GET', '/src/index.tsx'), phase: 'pre' }), null);
+  await assert.rejects(call(plugin, 'handleRequest', { request: request('GET', '/api/fail'), phase: 'pre' }), { message: 'api down' });
+
+  const fallback = await call(plugin, 'handleRequest', { request: request('GET', '/missing'), phase: 'post' });
+  assert.deepStrictEqual(fallback, { status: 200, headers: { 'content-type': 'text/plain' }, body: 'fallback /missing' });
+
+  assert.deepStrictEqual(await fromVite([api()], { root, command: 'build' }), []);
+});
+
+test('handleHotUpdate', async () => {
+  fs.mkdirSync(path.join(root, 'src'), { recursive: true });
+  fs.writeFileSync(path.join(root, 'src/app.data'), 'data');
+  const seen = [];
+  const [plugin] = await fromVite([hmr(seen)], { root, command: 'serve' });
+
+  const modules = ['/src/app.data', '/src/main.tsx'];
+  assert.deepStrictEqual(await call(plugin, 'handleHotUpdate', { file: '/src/app.data', modules }), ['/src/app.ts']);
+  assert.deepStrictEqual(await call(plugin, 'handleHotUpdate', { file: '/src/app.log', modules }), []);
+  assert.strictEqual(await call(plugin, 'handleHotUpdate', { file: '/src/main.tsx', modules }), null);
+  assert.deepStrictEqual(seen, ['data']);
+});
+
+test('apply, config and configResolved', async () => {
+  const resolved = [];
+  assert.deepStrictEqual(await fromVite([buildOnly(resolved)], { root, command: 'serve' }), []);
+  const [plugin] = await fromVite([buildOnly(resolved)], { root, command: 'build' });
+  assert.strictEqual(resolved.length, 1);
+  assert.deepStrictEqual([resolved[0].command, resolved[0].mode, resolved[0].define], ['build', 'production', { __MODE__: '"production"' }]);
+  assert.deepStrictEqual(await call(plugin, 'transform', { code: 'x = __MODE__', id: '/src/a.ts' }), { code: 'x = "once"' });
+});
+
+test('cache keys', async () => {
+  const [plain] = await fromVite([banner('hi')], { root, command: 'build' });
+  assert.strictEqual(toNative(plain).cacheKey, 'banner');
+  const [keyed] = await fromVite([{ ...banner('hi'), version: '2.1.0' }], { root, command: 'build', configHash: 'c0ffee' });
+  assert.strictEqual(toNative(keyed).cacheKey, 'banner@2.1.0@c0ffee', 'a changed config file invalidates cached transforms');
+});
+
+test('build through the native binding', { skip: !process.env.NEXUS_BINDING && 'NEXUS_BINDING not set' }, async () => {
+  const binding = require(path.resolve(process.env.NEXUS_BINDING));
+  const app = fs.mkdtempSync(path.join(os.tmpdir(), 'nexus-vite-app-'));
+  fs.mkdirSync(path.join(app, 'src'));
+  fs.writeFileSync(path.join(app, 'index.html'), '<html><head><title>Vite</title></head><body></body></html>');
+  fs.writeFileSync(path.join(app, 'src/index.tsx'), "import { env } from 'virtual:env';\nimport { a } from './a';\nconsole.log(env, a);");
+  fs.writeFileSync(path.join(app, 'src/a.ts'), 'export const a: number = 1;');
+
+  const plugins = await fromVite([virtualEnv({ debug: false }), banner('hi'), html(), api()], { root: app, command: 'build' });
+  await binding.build(app, plugins.map(toNative));
+
+  const main = fs.readFileSync(path.join(app, 'dist/assets/main.js'), 'utf8');
+  assert.match(main, /const env = \{ "debug": false \}|debug: false/);
+  const page = fs.readFileSync(path.join(app, 'dist/index.html'), 'utf8');
+  assert.match(page, /<title>Nexus<\/title><meta name="page" content="\/index.html"><\/meta>/);
+  assert.match(page, /<script src="\/assets\/main.js"><\/script>\n<script type="module" async>/);
+  fs.rmSync(app, { recursive: true });
+});
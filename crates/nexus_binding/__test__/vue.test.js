@@ -0,0 +1,131 @@
+// The Vue compiler adapter, over a fake `vue/compiler-sfc`. With
+// NEXUS_BINDING pointing at a built `.node` file, a build runs too.
+
+const { test } = require('node:test');
+const assert = require('node:assert');
+const fs = require('fs');
+const os = require('os');
+const path = require('path');
+const { vueCompiler } = require('../vue');
+
+function block(source, tag) {
+  const match = new RegExp(`<${tag}([^>]*)>([^]*?)</${tag}>`).exec(source);
+  return match && { attrs: match[1], content: match[2] };
+}
+
+// Like the real one without `sass` installed, it fails on `lang="scss"`.
+function fakeCompiler(seen = []) {
+  return {
+    version: '3.5.0',
+    parse(source) {
+      const script = block(source, 'script');
+      const template = block(source, 'template');
+      const style = block(source, 'style');
+      const lang = style && /lang="(\w+)"/.exec(style.attrs);
+      const module = style && /module(?:="(\w+)")?/.exec(style.attrs);
+      return {
+        descriptor: {
+          script: script && !script.attrs.includes('setup') ? { content: script.content } : null,
+          scriptSetup: script && script.attrs.includes('setup') ? { content: script.content } : null,
+          template: template && { content: template.content },
+          styles: style ? [{ content: style.content, scoped: style.attrs.includes('scoped'), lang: lang ? lang[1] : undefined, module: module ? module[1] || true : false }] : [],
+          slotted: false,
+        },
+        errors: source.includes('<template><p>') ? ['Element is missing end tag.'] : [],
+      };
+    },
+    compileScript(descriptor, options) {
+      seen.push(['script', options]);
+      return { content: `const ${options.genDefaultAs} = { setup() { ${descriptor.scriptSetup.content.trim()} } };`, bindings: { count: 'setup-ref' } };
+    },
+    compileTemplate(options) {
+      seen.push(['template', options]);
+      return { code: `export function render(_ctx) { return ${JSON.stringify(options.source.trim())}; }`, errors: [] };
+    },
+    async compileStyleAsync(options) {
+      seen.push(['style', options]);
+      if (options.preprocessLang) {
+        return { code: '', errors: [new Error(`Preprocessor dependency "${options.preprocessLang === 'scss' ? 'sass' : options.preprocessLang}" not found. Did you install it?`)] };
+      }
+      return { code: options.scoped ? options.source.replace('.counter', `.counter[${options.id}]`) : options.source, errors: [] };
+    },
+  };
+}
+
+const COUNTER = '<script setup>const count = ref(0);</script>\n<template><button class="counter">{{ count }}</button></template>\n<style scoped>.counter { color: red; }</style>';
+
+async function compile(compiler, call) {
+  const [reply] = JSON.parse(await compiler.compileBatch(JSON.stringify([call])));
+  return reply;
+}
+
+test('compiles through the given compiler', async () => {
+  const seen = [];
+  const compiler = vueCompiler(os.tmpdir(), fakeCompiler(seen));
+  assert.strictEqual(compiler.name, 'vue@3.5.0');
+
+  const dev = await compile(compiler, { source: COUNTER, id: '/src/Counter.vue', dev: true });
+  assert.match(dev.value.js, /^const __sfc__ = \{ setup\(\) \{ const count = ref\(0\); \} \};\nfunction render\(_ctx\)/);
+  assert.match(dev.value.js, /__sfc__\.render = render;\n__sfc__\.__scopeId = "data-v-[0-9a-f]{8}";$/);
+  assert.match(dev.value.css, /^\.counter\[data-v-[0-9a-f]{8}\] \{ color: red; \}$/);
+  assert.deepStrictEqual([dev.value.script, dev.value.template], ['const count = ref(0);', 'true<button class="counter">{{ count }}</button>']);
+  const [, template] = seen.find(([kind]) => kind === 'template');
+  assert.deepStrictEqual(template.compilerOptions.bindingMetadata, { count: 'setup-ref' }, 'the template sees the setup bindings');
+
+  seen.length = 0;
+  const prod = await compile(compiler, { source: COUNTER, id: '/src/Counter.vue', dev: false });
+  assert.ok(!prod.value.js.includes('function render'), 'the template is inlined into setup');
+  assert.deepStrictEqual(seen.map(([kind]) => kind), ['script', 'style']);
+  assert.strictEqual(seen[0][1].inlineTemplate, true);
+});
+
+test('styles it cannot preprocess fail the module', async () => {
+  const seen = [];
+  const compiler = vueCompiler(os.tmpdir(), fakeCompiler(seen));
+  const theme = '<template><b class="theme">hi</b></template>\n<style lang="scss">$c: red; .theme { color: $c; }</style>';
+  const reply = await compile(compiler, { source: theme, id: '/src/Theme.vue', dev: true });
+  assert.strictEqual(reply.error, 'Preprocessor dependency "sass" not found. Did you install it?');
+  assert.strictEqual(seen.find(([kind]) => kind === 'style')[1].preprocessLang, 'scss');
+
+  const broken = await compile(compiler, { source: '<template><p>', id: '/src/Broken.vue', dev: true });
+  assert.strictEqual(broken.error, 'Element is missing end tag.');
+});
+
+test('module styles come back by name', async () => {
+  const compiler = vueCompiler(os.tmpdir(), fakeCompiler());
+  const card = (attrs) => `<template><b :class="$style.counter">hi</b></template>\n<style ${attrs}>.counter { color: red; }</style>`;
+  const plain = await compile(compiler, { source: card('module'), id: '/src/Card.vue', dev: true });
+  assert.deepStrictEqual([plain.value.css, plain.value.modules], [null, [['$style', '.counter { color: red; }']]]);
+  const named = await compile(compiler, { source: card('module="classes" scoped'), id: '/src/Card.vue', dev: true });
+  assert.match(named.value.modules[0][1], /^\.counter\[data-v-[0-9a-f]{8}\] \{/);
+  assert.strictEqual(named.value.modules[0][0], 'classes');
+});
+
+test('none without vue installed, leaving the built-in compiler', () => {
+  assert.strictEqual(vueCompiler(fs.mkdtempSync(path.join(os.tmpdir(), 'nexus-no-vue-'))), undefined);
+  assert.strictEqual(vueCompiler(os.tmpdir(), false), undefined);
+});
+
+test('build through the native binding', { skip: !process.env.NEXUS_BINDING && 'NEXUS_BINDING not set' }, async () => {
+  const binding = require(path.resolve(process.env.NEXUS_BINDING));
+  const app = fs.mkdtempSync(path.join(os.tmpdir(), 'nexus-vue-app-'));
+  fs.mkdirSync(path.join(app, 'src'));
+  fs.writeFileSync(path.join(app, 'src/index.js'), "import Counter from './Counter.vue';\nconsole.log(Counter);");
+  fs.writeFileSync(path.join(app, 'src/Counter.vue'), COUNTER);
+  await binding.build(app, [], {}, undefined, vueCompiler(app, fakeCompiler()));
+
+  const css = fs.readFileSync(path.join(app, 'dist/assets/style.css'), 'utf8');
+  assert.match(css, /\.counter\[data-v-[0-9a-f]{8}\]/);
+  const main = fs.readFileSync(path.join(app, 'dist/assets/main.js'), 'utf8');
+  assert.match(main, /__nexus_register__\("\/src\/Counter.vue"/);
+
+  fs.writeFileSync(path.join(app, 'src/Theme.vue'), '<template><b>hi</b></template>\n<style lang="less">@c: red;</style>');
+  fs.writeFileSync(path.join(app, 'src/index.js'), "import Theme from './Theme.vue';\nconsole.log(Theme);");
+  await assert.rejects(binding.build(app, [], {}, undefined, vueCompiler(app, fakeCompiler())), /Preprocessor dependency "less" not found/);
+
+  fs.writeFileSync(path.join(app, 'src/index.js'), "import Counter from './Counter.vue';\nconsole.log(Counter);");
+  await binding.build(app, [], {}, undefined, vueCompiler(app));
+  const native = fs.readFileSync(path.join(app, 'dist/assets/main.js'), 'utf8');
+  assert.match(native, /_toDisplayString\(_ctx\.count\)/, 'without vue installed, the built-in compiler compiles it');
+  fs.rmSync(app, { recursive: true });
+});
@@ -1 +1,92 @@
-export declare function startServer(root: string, port: number): void;
+/** Matched natively by the Rust regex engine: flags `i`, `m` and `s` apply, lookarounds and backreferences are rejected. */
+export type FilterPattern = RegExp | string | Array<RegExp | string>;
+
+type MaybePromise<T> = T | Promise<T>;
+
+export interface NexusPlugin {
+  name: string;
+  /** Changes whenever the plugin's output would, e.g. with its version or options; cached transforms from another key are dropped. Defaults to `name`. */
+  cacheKey?: string;
+  /** Relative to the built-in compilers: `pre` plugins transform sources, the others compiled JS. */
+  order?: 'pre' | 'post';
+  /** Modules a hook runs for, checked natively before calling into JS: the specifier for `resolveId`, the module id otherwise. */
+  filter?: {
+    resolveId?: FilterPattern;
+    load?: FilterPattern;
+    transform?: FilterPattern;
+  };
+  /** A path, absolute or relative to the importer's directory. */
+  resolveId?(specifier: string, importer: string): MaybePromise<string | null | undefined>;
+  load?(id: string, path: string): MaybePromise<string | null | undefined>;
+  transform?(code: string, id: string): MaybePromise<string | { code: string; map?: string | object | null } | null | undefined>;
+  /** `path` is the page's URL path, e.g. `/index.html`. */
+  transformIndexHtml?(html: string, path: string): MaybePromise<string | null | undefined>;
+  /** Dev server: the module ids to update when `file` changes; an empty list sends no update. */
+  handleHotUpdate?(update: { file: string; modules: string[] }): MaybePromise<string[] | null | undefined>;
+  /** Dev server: answers a request, before Nexus's own routes (`pre`) or after they found nothing (`post`). */
+  handleRequest?(request: NexusRequest, phase: 'pre' | 'post'): MaybePromise<NexusResponse | null | undefined>;
+}
+
+export interface NexusRequest {
+  method: string;
+  url: string;
+  headers: Record<string, string>;
+  body: string;
+}
+
+export interface NexusResponse {
+  status?: number;
+  headers?: Record<string, string>;
+  body?: string;
+}
+
+/** The parts of a Vite plugin Nexus understands; see `fromVite`. */
+export interface VitePlugin {
+  name: string;
+  enforce?: 'pre' | 'post';
+  apply?: 'serve' | 'build' | ((config: object, env: { command: 'serve' | 'build'; mode: string }) => boolean);
+  [hook: string]: unknown;
+}
+
+export type VitePluginOption = VitePlugin | false | null | undefined | VitePluginOption[];
+
+/**
+ * Adapts Vite plugins for `startServer` or `build`: `resolveId`, `load`, `transform`,
+ * `configureServer`, `transformIndexHtml` and `handleHotUpdate`, plus `config` and `configResolved`.
+ */
+export declare function fromVite(plugins: VitePluginOption[], options: { root: string; command: 'serve' | 'build'; mode?: string; configHash?: string }): Promise<NexusPlugin[]>;
+
+export type Loader =
+  | 'js' | 'jsx' | 'ts' | 'tsx' | 'css' | 'json' | 'text' | 'base64' | 'dataurl'
+  | 'file' | 'copy' | 'empty' | 'asset' | 'svg' | 'wasm' | 'vue' | 'svelte' | 'mdx';
+
+/** What Nexus uses of `svelte/compiler`. */
+export interface SvelteCompilerModule {
+  VERSION: string;
+  compile(source: string, options: object): { js: { code: string }; css?: { code: string } | null };
+}
+
+/** What Nexus uses of `vue/compiler-sfc` (Vue 3.3 or later). */
+export interface VueCompilerModule {
+  version: string;
+  parse(source: string, options: object): { descriptor: any; errors: Array<Error | string> };
+  compileScript(descriptor: any, options: object): { content: string; bindings?: object };
+  compileTemplate(options: object): { code: string; errors: Array<Error | string> };
+  compileStyleAsync(options: object): Promise<{ code: string; errors: Error[] }>;
+}
+
+export interface ProjectOptions {
+  /** Loaders by extension (`.glsl`) or glob over module ids (`src/raw/**`, or `*.frag` in any directory), on top of the defaults. */
+  loader?: Record<string, Loader>;
+  /** Selects `.env.[mode]` and sets `import.meta.env.MODE`; `development` for the dev server, `production` for builds. */
+  mode?: string;
+  /** Compile-time replacements. Strings are JS expressions (`'"1.2.0"'`); other values are serialized with `JSON.stringify`. */
+  define?: Record<string, string | number | boolean | null | object>;
+  /** The Svelte compiler; by default `svelte/compiler` from the project, when installed. `false` turns `.svelte` off. */
+  svelte?: SvelteCompilerModule | false;
+  /** The Vue compiler; by default `vue/compiler-sfc` from the project, when installed, else Nexus' own. `false` picks Nexus' own. */
+  vue?: VueCompilerModule | false;
+}
+
+export declare function startServer(root: string, port: number, plugins?: NexusPlugin[], options?: ProjectOptions): void;
+export declare function build(root: string, plugins?: NexusPlugin[], options?: ProjectOptions): Promise<void>;
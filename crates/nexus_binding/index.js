@@ -36,4 +36,22 @@ try {
   );
 }
 
-module.exports.startServer = binding.startServer;
+const { toNative } = require('./plugins');
+const { fromVite } = require('./vite');
+const { svelteCompiler } = require('./svelte');
+const { vueCompiler } = require('./vue');
+
+// `define` values are JS expressions; anything else is serialized to one.
+const project = ({ loader, mode, define }) => ({
+  loader,
+  mode,
+  define: define && Object.fromEntries(
+    Object.entries(define).map(([key, value]) => [key, typeof value === 'string' ? value : JSON.stringify(value)])
+  ),
+});
+
+module.exports.startServer = (root, port, plugins = [], options = {}) =>
+  binding.startServer(root, port, plugins.map(toNative), project(options), svelteCompiler(root, options.svelte), vueCompiler(root, options.vue));
+module.exports.build = (root, plugins = [], options = {}) =>
+  binding.build(root, plugins.map(toNative), project(options), svelteCompiler(root, options.svelte), vueCompiler(root, options.vue));
+module.exports.fromVite = fromVite;
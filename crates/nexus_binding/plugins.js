@@ -0,0 +1,88 @@
+/* Nexus Core - JS plugin adapter */
+/* Turns plugins from nexus.config.ts into what the native binding expects: */
+/* one batch function per hook, taking and returning JSON (see src/plugin.rs). */
+
+const HOOKS = {
+  resolveId: (plugin, call) => plugin.resolveId(call.specifier, call.importer),
+  load: (plugin, call) => plugin.load(call.id, call.path),
+  transform: async (plugin, call) => {
+    const output = await plugin.transform(call.code, call.id);
+    if (output == null || typeof output === 'string') {
+      return output == null ? null : { code: output };
+    }
+    const map = output.map == null || typeof output.map === 'string' ? output.map : JSON.stringify(output.map);
+    return { code: output.code, map: map == null ? null : map };
+  },
+  transformIndexHtml: (plugin, call) => plugin.transformIndexHtml(call.html, call.path),
+  handleHotUpdate: (plugin, call) => plugin.handleHotUpdate({ file: call.file, modules: call.modules }),
+  handleRequest: (plugin, call) => plugin.handleRequest(call.request, call.phase),
+};
+
+// Filters are matched natively, by the Rust regex engine. Flags that change
+// what matches become inline flags; `g`, `y` and `d` don't affect a match
+// test, and Rust regexes are Unicode-aware without `u`.
+const INLINE_FLAGS = 'ims';
+const IGNORED_FLAGS = 'gyud';
+
+// What the Rust engine can't do, found outside escapes and character classes.
+function unsupportedSyntax(source) {
+  let inClass = false;
+  for (let i = 0; i < source.length; i++) {
+    const char = source[i];
+    if (char === '\\') {
+      const next = source[i + 1];
+      if (!inClass && (/[1-9]/.test(next) || source.startsWith('k<', i + 1))) {
+        return 'backreferences';
+      }
+      i++;
+    } else if (char === '[') {
+      inClass = true;
+    } else if (char === ']') {
+      inClass = false;
+    } else if (!inClass && char === '(' && /^\(\?<?[=!]/.test(source.slice(i, i + 4))) {
+      return 'lookarounds';
+    }
+  }
+  return null;
+}
+
+function regexSource(pattern, plugin, hook) {
+  if (typeof pattern === 'string') {
+    return pattern;
+  }
+  const unsupported = [...pattern.flags].filter((flag) => !INLINE_FLAGS.includes(flag) && !IGNORED_FLAGS.includes(flag));
+  const syntax = unsupported.length > 0 ? `the ${unsupported.join('')} flag` : unsupportedSyntax(pattern.source);
+  if (syntax) {
+    throw new Error(`plugin ${plugin.name}: ${hook} filter ${pattern} uses ${syntax}, which native filters don't support; check the id in the hook instead`);
+  }
+  const flags = [...pattern.flags].filter((flag) => INLINE_FLAGS.includes(flag)).join('');
+  return (flags ? `(?${flags})` : '') + pattern.source;
+}
+
+function toNative(plugin) {
+  const native = { name: plugin.name, cacheKey: plugin.cacheKey, order: plugin.order, filter: {} };
+  for (const [hook, invoke] of Object.entries(HOOKS)) {
+    if (typeof plugin[hook] !== 'function') {
+      continue;
+    }
+    const filter = plugin.filter && plugin.filter[hook];
+    if (filter) {
+      native.filter[hook] = [].concat(filter).map((pattern) => regexSource(pattern, plugin, hook));
+    }
+    native[`${hook}Batch`] = async (json) => {
+      const calls = JSON.parse(json);
+      const replies = await Promise.all(calls.map(async (call) => {
+        try {
+          const value = await invoke(plugin, call);
+          return { value: value == null ? null : value };
+        } catch (err) {
+          return { error: err && err.message ? err.message : String(err) };
+        }
+      }));
+      return JSON.stringify(replies);
+    };
+  }
+  return native;
+}
+
+module.exports.toNative = toNative;
@@ -1,15 +1,81 @@
+use napi::{Env, JsObject};
 use napi_derive::napi;
-use std::sync::Once;
+use nexus_core::bundler::BuildOptions;
+use nexus_core::loader::Loader;
+use nexus_core::plugin::{Plugin, PluginContainer};
+use nexus_core::svelte::SvelteHook;
+use nexus_core::vue::VueHook;
+use nexus_core::ServerOptions;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Once};
+
+mod plugin;
+mod svelte;
+mod vue;
+use plugin::JsPlugin;
+use svelte::JsSvelteCompiler;
+use vue::JsVueCompiler;
 
 static INIT: Once = Once::new();
 
-#[napi]
-pub fn start_server(root: String, port: u16) -> napi::Result<()> {
+fn init_tracing() {
     INIT.call_once(|| {
         tracing_subscriber::fmt()
             .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
             .init();
     });
+}
+
+// Plugins as prepared by `index.js`; see `plugin`.
+fn plugin_container(plugins: Option<Vec<JsObject>>) -> napi::Result<PluginContainer> {
+    let plugins = plugins
+        .unwrap_or_default()
+        .iter()
+        .map(|object| JsPlugin::from_js(object).map(|plugin| Arc::new(plugin) as Arc<dyn Plugin>))
+        .collect::<napi::Result<Vec<_>>>()?;
+    Ok(PluginContainer::new(plugins))
+}
+
+// `{ ".glsl": "text", "src/raw/**": "copy" }`; see `nexus_core::loader`.
+fn loader_rules(loader: Option<HashMap<String, String>>) -> napi::Result<BTreeMap<String, Loader>> {
+    loader
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(key, name)| Ok((key, name.parse().map_err(|e: std::io::Error| napi::Error::from_reason(e.to_string()))?)))
+        .collect()
+}
+
+// The project's Svelte compiler as prepared by `index.js`; see `svelte`.
+fn svelte_hook(svelte: Option<JsObject>) -> napi::Result<Option<SvelteHook>> {
+    svelte.map(|object| JsSvelteCompiler::from_js(&object).map(SvelteHook::new)).transpose()
+}
+
+// The project's Vue compiler as prepared by `index.js`; see `vue`.
+fn vue_hook(vue: Option<JsObject>) -> napi::Result<Option<VueHook>> {
+    vue.map(|object| JsVueCompiler::from_js(&object).map(VueHook::new)).transpose()
+}
+
+#[napi(object)]
+#[derive(Default)]
+pub struct ProjectOptions {
+    pub loader: Option<HashMap<String, String>>,
+    pub mode: Option<String>,
+    pub define: Option<HashMap<String, String>>,
+}
+
+#[napi]
+pub fn start_server(root: String, port: u16, plugins: Option<Vec<JsObject>>, options: Option<ProjectOptions>, svelte: Option<JsObject>, vue: Option<JsObject>) -> napi::Result<()> {
+    init_tracing();
+    let ProjectOptions { loader, mode, define } = options.unwrap_or_default();
+    let defaults = ServerOptions::default();
+    let options = ServerOptions {
+        mode: mode.unwrap_or(defaults.mode),
+        define: define.unwrap_or_default().into_iter().collect(),
+        plugins: plugin_container(plugins)?,
+        loader: loader_rules(loader)?,
+        svelte: svelte_hook(svelte)?,
+        vue: vue_hook(vue)?,
+    };
 
     std::thread::spawn(move || {
         let rt = match tokio::runtime::Builder::new_current_thread()
@@ -21,11 +87,34 @@ pub fn start_server(root: String, port: u16) -> napi::Result<()> {
                     return;
                 }
             };
-            
-        if let Err(e) = rt.block_on(nexus_core::start_server(root, port)) {
+
+        if let Err(e) = rt.block_on(nexus_core::start_server_with_options(root, port, options)) {
             tracing::error!("Server caught error: {}", e);
         }
     });
-    
+
     Ok(())
 }
+
+#[napi(ts_return_type = "Promise<void>")]
+pub fn build(env: Env, root: String, plugins: Option<Vec<JsObject>>, options: Option<ProjectOptions>, svelte: Option<JsObject>, vue: Option<JsObject>) -> napi::Result<JsObject> {
+    init_tracing();
+    let ProjectOptions { loader, mode, define } = options.unwrap_or_default();
+    let defaults = BuildOptions::default();
+    let options = BuildOptions {
+        mode: mode.unwrap_or(defaults.mode),
+        define: define.unwrap_or_default().into_iter().collect(),
+        plugins: plugin_container(plugins)?,
+        loader: loader_rules(loader)?,
+        svelte: svelte_hook(svelte)?,
+        vue: vue_hook(vue)?,
+        ..defaults
+    };
+    // Off the JS thread, which has to stay free to run the plugins.
+    env.spawn_future(async move {
+        nexus_core::bundler::build_with_options(&root, &options)
+            .await
+            .map(|_| ())
+            .map_err(|e| napi::Error::from_reason(e.to_string()))
+    })
+}
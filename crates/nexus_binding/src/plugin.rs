@@ -0,0 +1,279 @@
+use axum::body::Body;
+use axum::extract::Request;
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use napi::bindgen_prelude::Promise;
+use napi::threadsafe_function::{ErrorStrategy, ThreadSafeCallContext, ThreadsafeFunction};
+use napi::{JsFunction, JsObject};
+use nexus_core::plugin::{Batcher, HookResult, HotUpdate, Order, Plugin, TransformOutput};
+use regex::RegexSet;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+// JS plugins.
+//
+// `index.js` turns each plugin from `nexus.config.ts` into an object with
+// `name`, `cacheKey`, `order`, `filter` and, for each hook it implements, a `<hook>Batch`
+// function: it takes a JSON array of calls and resolves to a JSON array of
+// replies, `{ "value": ... }` or `{ "error": "..." }` each. Calls from the
+// pipeline's threads are batched (see `Batcher`), so a build crosses into JS
+// a few times per graph level rather than once per module.
+//
+// Filters are regex sources, matched here against the specifier for
+// `resolveId` and the module id otherwise, so modules a plugin doesn't care
+// about never reach JS.
+//
+// `handleRequest` backs `configure_server`: requests are handed to JS before
+// the dev server's own routes (phase `pre`) and, when those find nothing,
+// again after them (phase `post`). A `null` reply passes the request on.
+
+pub(crate) type JsBatch = ThreadsafeFunction<String, ErrorStrategy::Fatal>;
+
+// Request bodies handed to `handleRequest`, at most.
+const MAX_BODY: usize = 16 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct Reply {
+    #[serde(default)]
+    value: Value,
+    error: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsTransformOutput {
+    code: String,
+    map: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JsResponse {
+    #[serde(default = "ok")]
+    status: u16,
+    #[serde(default)]
+    headers: HashMap<String, String>,
+    #[serde(default)]
+    body: String,
+}
+
+fn ok() -> u16 {
+    200
+}
+
+struct Hook {
+    filter: Option<RegexSet>,
+    batcher: Batcher<Value, std::io::Result<Value>>,
+}
+
+impl Hook {
+    // `None` when filtered out or when the plugin returned nothing.
+    fn call(&self, key: &str, call: Value) -> std::io::Result<Option<Value>> {
+        if self.filter.as_ref().is_some_and(|filter| !filter.is_match(key)) {
+            return Ok(None);
+        }
+        let value = self.batcher.call(call)??;
+        Ok(Some(value).filter(|v| !v.is_null()))
+    }
+}
+
+pub struct JsPlugin {
+    name: String,
+    cache_key: String,
+    order: Order,
+    resolve_id: Option<Hook>,
+    load: Option<Hook>,
+    transform: Option<Hook>,
+    transform_index_html: Option<Hook>,
+    handle_hot_update: Option<Hook>,
+    handle_request: Option<Arc<Hook>>,
+}
+
+impl JsPlugin {
+    pub fn from_js(object: &JsObject) -> napi::Result<Self> {
+        let name: String = object.get("name")?.unwrap_or_else(|| "anonymous".to_string());
+        let cache_key: String = object.get("cacheKey")?.unwrap_or_else(|| name.clone());
+        let order = match object.get::<_, String>("order")?.as_deref() {
+            Some("pre") => Order::Pre,
+            Some("post") => Order::Post,
+            _ => Order::Normal,
+        };
+        let filter: Option<JsObject> = object.get("filter")?;
+        let hook = |key: &str| -> napi::Result<Option<Hook>> {
+            let Some(function) = object.get::<_, JsFunction>(format!("{}Batch", key))? else { return Ok(None) };
+            let patterns: Option<Vec<String>> = match &filter {
+                Some(filter) => filter.get(key)?,
+                None => None,
+            };
+            let filter = patterns
+                .map(RegexSet::new)
+                .transpose()
+                .map_err(|e| napi::Error::from_reason(format!("plugin {}: invalid {} filter: {}", name, key, e)))?;
+            let batch: JsBatch = function.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<String>| Ok(vec![ctx.value]))?;
+            let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+            let batcher = Batcher::new(&format!("{}-{}", name, key), move |calls| run_batch(&runtime, &batch, calls));
+            Ok(Some(Hook { filter, batcher }))
+        };
+        Ok(Self {
+            resolve_id: hook("resolveId")?,
+            load: hook("load")?,
+            transform: hook("transform")?,
+            transform_index_html: hook("transformIndexHtml")?,
+            handle_hot_update: hook("handleHotUpdate")?,
+            handle_request: hook("handleRequest")?.map(Arc::new),
+            name,
+            cache_key,
+            order,
+        })
+    }
+}
+
+pub(crate) fn run_batch(runtime: &tokio::runtime::Runtime, batch: &JsBatch, calls: Vec<Value>) -> Vec<std::io::Result<Value>> {
+    let count = calls.len();
+    let replies = runtime
+        .block_on(async {
+            let replies: Promise<String> = batch.call_async(Value::Array(calls).to_string()).await?;
+            replies.await
+        })
+        .map_err(|e| std::io::Error::other(e.reason))
+        .and_then(|json| serde_json::from_str::<Vec<Reply>>(&json).map_err(std::io::Error::other));
+    match replies {
+        Ok(replies) if replies.len() == count => replies
+            .into_iter()
+            .map(|reply| match reply.error {
+                Some(message) => Err(std::io::Error::other(message)),
+                None => Ok(reply.value),
+            })
+            .collect(),
+        Ok(_) => (0..count).map(|_| Err(std::io::Error::other("expected one reply per call"))).collect(),
+        Err(e) => (0..count).map(|_| Err(std::io::Error::new(e.kind(), e.to_string()))).collect(),
+    }
+}
+
+async fn handle_request(plugin: Arc<str>, hook: Arc<Hook>, request: Request, next: Next) -> Response {
+    let (parts, body) = request.into_parts();
+    let body = match axum::body::to_bytes(body, MAX_BODY).await {
+        Ok(body) => body,
+        Err(e) => return (StatusCode::PAYLOAD_TOO_LARGE, e.to_string()).into_response(),
+    };
+    let headers = parts
+        .headers
+        .iter()
+        .filter_map(|(name, value)| Some((name.to_string(), Value::from(value.to_str().ok()?))))
+        .collect::<serde_json::Map<_, _>>();
+    let js_request = json!({
+        "method": parts.method.as_str(),
+        "url": parts.uri.to_string(),
+        "headers": headers,
+        "body": String::from_utf8_lossy(&body),
+    });
+    if let Some(response) = respond(&plugin, &hook, &js_request, "pre").await {
+        return response;
+    }
+    let response = next.run(Request::from_parts(parts, Body::from(body))).await;
+    if response.status() != StatusCode::NOT_FOUND {
+        return response;
+    }
+    respond(&plugin, &hook, &js_request, "post").await.unwrap_or(response)
+}
+
+async fn respond(plugin: &str, hook: &Arc<Hook>, request: &Value, phase: &str) -> Option<Response> {
+    let call = json!({ "request": request, "phase": phase });
+    let hook = hook.clone();
+    // The batcher blocks until JS replies; keep that off the server's thread.
+    let reply = tokio::task::spawn_blocking(move || hook.call("", call))
+        .await
+        .unwrap_or_else(|e| Err(std::io::Error::other(e)))
+        .and_then(|value| value.map(serde_json::from_value::<JsResponse>).transpose().map_err(std::io::Error::other));
+    let response = match reply {
+        Ok(response) => response?,
+        Err(e) => {
+            let message = format!("[plugin {}] configure_server: {}", plugin, e);
+            tracing::error!("{}", message);
+            return Some((StatusCode::INTERNAL_SERVER_ERROR, message).into_response());
+        }
+    };
+    let mut builder = Response::builder().status(response.status);
+    for (name, value) in &response.headers {
+        builder = builder.header(name, value);
+    }
+    Some(builder.body(Body::from(response.body)).unwrap_or_else(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()))
+}
+
+fn expect_string(value: Value) -> std::io::Result<String> {
+    match value {
+        Value::String(s) => Ok(s),
+        other => Err(std::io::Error::other(format!("expected a string, got {}", other))),
+    }
+}
+
+impl Plugin for JsPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn cache_key(&self) -> String {
+        self.cache_key.clone()
+    }
+
+    fn order(&self) -> Order {
+        self.order
+    }
+
+    fn resolve_id(&self, specifier: &str, importer: &Path) -> HookResult<PathBuf> {
+        let Some(hook) = &self.resolve_id else { return Ok(None) };
+        let call = json!({ "specifier": specifier, "importer": importer.to_string_lossy() });
+        let Some(value) = hook.call(specifier, call)? else { return Ok(None) };
+        // Relative results are relative to the importer's directory.
+        let dir = importer.parent().unwrap_or(importer);
+        let mut path = PathBuf::new();
+        for component in dir.join(expect_string(value)?).components() {
+            match component {
+                Component::ParentDir => {
+                    path.pop();
+                }
+                Component::CurDir => {}
+                component => path.push(component),
+            }
+        }
+        Ok(Some(path))
+    }
+
+    fn load(&self, id: &str, path: &Path) -> HookResult<Vec<u8>> {
+        let Some(hook) = &self.load else { return Ok(None) };
+        let call = json!({ "id": id, "path": path.to_string_lossy() });
+        hook.call(id, call)?.map(|value| expect_string(value).map(String::into_bytes)).transpose()
+    }
+
+    fn transform(&self, code: &str, id: &str) -> HookResult<TransformOutput> {
+        let Some(hook) = &self.transform else { return Ok(None) };
+        let call = json!({ "code": code, "id": id });
+        let Some(value) = hook.call(id, call)? else { return Ok(None) };
+        let output: JsTransformOutput = serde_json::from_value(value).map_err(std::io::Error::other)?;
+        Ok(Some(TransformOutput { code: output.code, map: output.map }))
+    }
+
+    fn transform_index_html(&self, html: &str, path: &str) -> HookResult<String> {
+        let Some(hook) = &self.transform_index_html else { return Ok(None) };
+        let call = json!({ "html": html, "path": path });
+        hook.call(path, call)?.map(expect_string).transpose()
+    }
+
+    fn handle_hot_update(&self, update: &HotUpdate) -> Option<Vec<String>> {
+        let hook = self.handle_hot_update.as_ref()?;
+        let call = json!({ "file": update.file, "modules": update.modules });
+        let modules = hook.call(&update.file, call).and_then(|value| value.map(serde_json::from_value).transpose().map_err(std::io::Error::other));
+        modules.unwrap_or_else(|e| {
+            tracing::error!("[plugin {}] handle_hot_update: {}", self.name, e);
+            None
+        })
+    }
+
+    fn configure_server(&self, app: axum::Router) -> axum::Router {
+        let Some(hook) = self.handle_request.clone() else { return app };
+        let plugin: Arc<str> = self.name.as_str().into();
+        app.layer(axum::middleware::from_fn(move |request: Request, next: Next| handle_request(plugin.clone(), hook.clone(), request, next)))
+    }
+}
@@ -0,0 +1,50 @@
+use napi::threadsafe_function::ThreadSafeCallContext;
+use napi::{JsFunction, JsObject};
+use nexus_core::plugin::Batcher;
+use nexus_core::svelte::{SvelteCompiler, SvelteOutput};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::plugin::{run_batch, JsBatch};
+
+// The Svelte compiler.
+//
+// `index.js` finds `svelte/compiler` in the project and hands in its `name`
+// (`svelte@<version>`) and a `compileBatch` function, batched like a plugin
+// hook: it takes `{ source, id, dev }` calls and replies `{ js, css }`.
+
+#[derive(Deserialize)]
+struct JsOutput {
+    js: String,
+    css: Option<String>,
+}
+
+pub struct JsSvelteCompiler {
+    name: String,
+    batcher: Batcher<Value, std::io::Result<Value>>,
+}
+
+impl JsSvelteCompiler {
+    pub fn from_js(object: &JsObject) -> napi::Result<Self> {
+        let name: String = object.get("name")?.unwrap_or_else(|| "svelte".to_string());
+        let function: JsFunction = object
+            .get("compileBatch")?
+            .ok_or_else(|| napi::Error::from_reason(format!("{}: expected a compileBatch function", name)))?;
+        let batch: JsBatch = function.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<String>| Ok(vec![ctx.value]))?;
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let batcher = Batcher::new("svelte", move |calls| run_batch(&runtime, &batch, calls));
+        Ok(Self { name, batcher })
+    }
+}
+
+impl SvelteCompiler for JsSvelteCompiler {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn compile(&self, source: &str, id: &str, dev: bool) -> std::io::Result<SvelteOutput> {
+        let value = self.batcher.call(json!({ "source": source, "id": id, "dev": dev }))??;
+        let output: JsOutput = serde_json::from_value(value).map_err(std::io::Error::other)?;
+        Ok(SvelteOutput { js: output.js, css: output.css })
+    }
+}
@@ -0,0 +1,55 @@
+use napi::threadsafe_function::ThreadSafeCallContext;
+use napi::{JsFunction, JsObject};
+use nexus_core::plugin::Batcher;
+use nexus_core::vue::{VueCompiler, VueOutput};
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::plugin::{run_batch, JsBatch};
+
+// The Vue compiler.
+//
+// `index.js` finds `vue/compiler-sfc` in the project and hands in its `name`
+// (`vue@<version>`) and a `compileBatch` function, batched like a plugin
+// hook: it takes `{ source, id, dev }` calls and replies
+// `{ js, css, modules, script, template }`, `modules` as `[name, css]` pairs.
+
+#[derive(Deserialize)]
+struct JsOutput {
+    js: String,
+    css: Option<String>,
+    #[serde(default)]
+    modules: Vec<(String, String)>,
+    script: String,
+    template: String,
+}
+
+pub struct JsVueCompiler {
+    name: String,
+    batcher: Batcher<Value, std::io::Result<Value>>,
+}
+
+impl JsVueCompiler {
+    pub fn from_js(object: &JsObject) -> napi::Result<Self> {
+        let name: String = object.get("name")?.unwrap_or_else(|| "vue".to_string());
+        let function: JsFunction = object
+            .get("compileBatch")?
+            .ok_or_else(|| napi::Error::from_reason(format!("{}: expected a compileBatch function", name)))?;
+        let batch: JsBatch = function.create_threadsafe_function(0, |ctx: ThreadSafeCallContext<String>| Ok(vec![ctx.value]))?;
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+        let batcher = Batcher::new("vue", move |calls| run_batch(&runtime, &batch, calls));
+        Ok(Self { name, batcher })
+    }
+}
+
+impl VueCompiler for JsVueCompiler {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn compile(&self, source: &str, id: &str, dev: bool) -> std::io::Result<VueOutput> {
+        let value = self.batcher.call(json!({ "source": source, "id": id, "dev": dev }))??;
+        let output: JsOutput = serde_json::from_value(value).map_err(std::io::Error::other)?;
+        Ok(VueOutput { js: output.js, css: output.css, modules: output.modules, script: output.script, template: output.template })
+    }
+}
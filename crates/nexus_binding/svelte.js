@@ -0,0 +1,54 @@
+/* Nexus Core - Svelte compiler adapter */
+/* Wraps the project's svelte/compiler in what the native binding expects: */
+/* a name and a batch function taking and returning JSON (see src/svelte.rs). */
+
+// `svelte/compiler` as the project resolves it, if installed.
+function projectCompiler(root) {
+  try {
+    return require(require.resolve('svelte/compiler', { paths: [root] }));
+  } catch {
+    return undefined;
+  }
+}
+
+function compileOptions(major, call) {
+  const options = {
+    filename: call.id,
+    dev: call.dev,
+    generate: major >= 5 ? 'client' : 'dom',
+    css: major >= 4 ? 'external' : false,
+    // Scoping classes by file rather than by styles keeps the JS of a
+    // component the same across style edits, and with it the component's state.
+    cssHash: ({ hash, filename }) => `svelte-${hash(filename)}`,
+  };
+  if (major >= 5) {
+    options.hmr = call.dev;
+  }
+  return options;
+}
+
+function svelteCompiler(root, option) {
+  const compiler = option === false ? undefined : option || projectCompiler(root);
+  if (!compiler) {
+    return undefined;
+  }
+  const version = compiler.VERSION || 'unknown';
+  const major = parseInt(version, 10);
+  return {
+    name: `svelte@${version}`,
+    compileBatch: async (json) => {
+      const replies = JSON.parse(json).map((call) => {
+        try {
+          const { js, css } = compiler.compile(call.source, compileOptions(major, call));
+          return { value: { js: js.code, css: css ? css.code : null } };
+        } catch (err) {
+          const frame = err && err.frame ? `\n${err.frame}` : '';
+          return { error: (err && err.message ? err.message : String(err)) + frame };
+        }
+      });
+      return JSON.stringify(replies);
+    },
+  };
+}
+
+module.exports.svelteCompiler = svelteCompiler;
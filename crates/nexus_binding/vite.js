@@ -0,0 +1,341 @@
+/* Nexus Core - Vite plugin compatibility */
+/* Adapts Vite (and Rollup) plugin objects to Nexus plugins (see plugins.js). */
+/* Vite ids are absolute paths, or `\0`-prefixed for virtual modules; Nexus */
+/* ids are paths from the root. Virtual modules live under `/@id/`, as in */
+/* Vite's URLs. */
+
+const fs = require('fs');
+const path = require('path');
+const { Readable } = require('stream');
+
+const VIRTUAL = '/@id/';
+const NULL_BYTE = '__x00__';
+
+function toViteId(root, id) {
+  if (id.startsWith(VIRTUAL)) {
+    const rest = id.slice(VIRTUAL.length);
+    return rest.startsWith(NULL_BYTE) ? '\0' + rest.slice(NULL_BYTE.length) : rest;
+  }
+  return path.join(root, id);
+}
+
+// Where Nexus looks for the module a Vite `resolveId` returned.
+function fromViteId(root, id) {
+  if (id.startsWith('\0')) {
+    return path.join(root, VIRTUAL, NULL_BYTE + id.slice(1));
+  }
+  return path.isAbsolute(id) ? id : path.join(root, VIRTUAL, id);
+}
+
+// Vite hooks may be `{ handler, order }` objects, or for
+// `transformIndexHtml`, the older `{ enforce, transform }`.
+function hookOf(plugin, name) {
+  const hook = plugin[name];
+  if (hook == null) {
+    return null;
+  }
+  return typeof hook === 'function' ? hook : hook.handler || hook.transform;
+}
+
+function flatten(plugins) {
+  return [].concat(plugins).flat(Infinity).filter(Boolean);
+}
+
+function applies(plugin, config, env) {
+  if (plugin.apply == null) {
+    return true;
+  }
+  return typeof plugin.apply === 'function' ? plugin.apply(config, env) : plugin.apply === env.command;
+}
+
+// What Rollup plugins get as `this`, as far as Nexus can provide it.
+function pluginContext(plugin, env, plugins) {
+  const context = {
+    meta: { watchMode: env.command === 'serve', rollupVersion: '4.0.0' },
+    error(err) {
+      throw err instanceof Error ? err : new Error(typeof err === 'string' ? err : err.message);
+    },
+    warn(message) {
+      console.warn(`[${plugin.name}] ${typeof message === 'string' ? message : message.message}`);
+    },
+    addWatchFile() {},
+    getWatchFiles: () => [],
+    getModuleInfo: () => null,
+    emitFile() {
+      context.warn('emitFile is not supported by Nexus');
+      return '';
+    },
+    // Through the other plugins' `resolveId`, then relative paths.
+    async resolve(source, importer, options = {}) {
+      for (const other of plugins) {
+        const resolveId = hookOf(other, 'resolveId');
+        if (!resolveId || (options.skipSelf !== false && other === plugin)) {
+          continue;
+        }
+        const resolved = await resolveId.call(pluginContext(other, env, plugins), source, importer, { isEntry: false, ...options });
+        if (resolved) {
+          return typeof resolved === 'string' ? { id: resolved, external: false } : resolved;
+        }
+      }
+      if (importer && source.startsWith('.')) {
+        return { id: path.resolve(path.dirname(importer), source), external: false };
+      }
+      return null;
+    },
+  };
+  return context;
+}
+
+function serializeTag({ tag, attrs = {}, children }) {
+  const attributes = Object.entries(attrs)
+    .filter(([, value]) => value !== false && value != null)
+    .map(([name, value]) => (value === true ? ` ${name}` : ` ${name}=${JSON.stringify(String(value))}`))
+    .join('');
+  const content = Array.isArray(children) ? children.map(serializeTag).join('') : children || '';
+  return `<${tag}${attributes}>${content}</${tag}>`;
+}
+
+function injectTags(html, tags) {
+  for (const tag of tags) {
+    const markup = serializeTag(tag);
+    const [element, position] = (tag.injectTo || 'head-prepend').split('-');
+    if (position === 'prepend') {
+      const open = html.match(new RegExp(`<${element}[^>]*>`, 'i'));
+      html = open ? html.replace(open[0], `${open[0]}${markup}`) : markup + html;
+    } else {
+      const close = html.search(new RegExp(`</${element}>`, 'i'));
+      html = close === -1 ? html + markup : html.slice(0, close) + markup + html.slice(close);
+    }
+  }
+  return html;
+}
+
+// A connect-style middleware stack, run against plain request objects.
+function middlewareStack() {
+  const stack = [];
+  return {
+    stack,
+    use(route, handle) {
+      if (typeof route === 'function') {
+        [route, handle] = ['/', route];
+      }
+      stack.push({ route, handle });
+      return this;
+    },
+  };
+}
+
+function runMiddlewares(stack, request) {
+  return new Promise((resolve, reject) => {
+    const req = Object.assign(Readable.from(request.body ? [Buffer.from(request.body)] : []), {
+      method: request.method,
+      url: request.url,
+      originalUrl: request.url,
+      headers: request.headers,
+    });
+    const headers = {};
+    const chunks = [];
+    const res = {
+      statusCode: 200,
+      headersSent: false,
+      writableEnded: false,
+      setHeader(name, value) {
+        headers[name.toLowerCase()] = [].concat(value).join(', ');
+        return res;
+      },
+      getHeader: (name) => headers[name.toLowerCase()],
+      removeHeader(name) {
+        delete headers[name.toLowerCase()];
+      },
+      writeHead(status, extra = {}) {
+        res.statusCode = status;
+        for (const [name, value] of Object.entries(extra)) {
+          res.setHeader(name, value);
+        }
+        res.headersSent = true;
+        return res;
+      },
+      write(chunk) {
+        chunks.push(Buffer.from(chunk));
+        return true;
+      },
+      end(chunk) {
+        if (chunk != null && typeof chunk !== 'function') {
+          res.write(chunk);
+        }
+        res.writableEnded = true;
+        resolve({ status: res.statusCode, headers, body: Buffer.concat(chunks).toString() });
+        return res;
+      },
+    };
+    let index = 0;
+    const next = (err) => {
+      if (err) {
+        return reject(err);
+      }
+      const layer = stack[index++];
+      if (!layer) {
+        return resolve(null);
+      }
+      const pathname = request.url.split('?')[0];
+      if (layer.route !== '/' && pathname !== layer.route && !pathname.startsWith(`${layer.route}/`)) {
+        return next();
+      }
+      req.url = layer.route === '/' ? request.url : request.url.slice(layer.route.length) || '/';
+      try {
+        const result = layer.handle(req, res, next);
+        if (result && typeof result.catch === 'function') {
+          result.catch(next);
+        }
+      } catch (e) {
+        next(e);
+      }
+    };
+    next();
+  });
+}
+
+function moduleNode(root, url) {
+  const id = toViteId(root, url);
+  return { id, url, file: id.startsWith('\0') ? null : id, type: url.endsWith('.css') ? 'css' : 'js' };
+}
+
+/**
+ * Adapts Vite plugins (nested arrays and falsy entries allowed, as in
+ * `vite.config`) for `startServer` or `build`. Runs their `config`,
+ * `configResolved` and, for `serve`, `configureServer` hooks.
+ *
+ * Options live in closures Nexus can't see, so the cache key is the
+ * plugin's name and `version` plus `configHash`, a hash of the config file
+ * the plugins were created in.
+ */
+async function fromVite(vitePlugins, { root, command, mode = command === 'build' ? 'production' : 'development', configHash }) {
+  const env = { command, mode };
+  const userConfig = { root, mode };
+  const plugins = flatten(vitePlugins).filter((plugin) => applies(plugin, userConfig, env));
+  for (const plugin of plugins) {
+    const config = hookOf(plugin, 'config');
+    if (config) {
+      await config.call(null, userConfig, env);
+    }
+  }
+  const resolvedConfig = { ...userConfig, root, command, mode, base: '/', isProduction: mode === 'production', env: {}, plugins };
+  for (const plugin of plugins) {
+    const configResolved = hookOf(plugin, 'configResolved');
+    if (configResolved) {
+      await configResolved.call(null, resolvedConfig);
+    }
+  }
+
+  // One dev server, shared by every plugin's `configureServer`.
+  const pre = middlewareStack();
+  const post = middlewareStack();
+  const server = {
+    config: resolvedConfig,
+    middlewares: pre,
+    ws: { send() {}, on() {}, off() {} },
+    watcher: { on() {}, add() {}, unwatch() {} },
+    moduleGraph: {
+      getModuleById: (id) => moduleNode(root, id.startsWith(root) ? id.slice(root.length) : `${VIRTUAL}${id.replace('\0', NULL_BYTE)}`),
+      getModulesByFile: (file) => new Set([moduleNode(root, '/' + path.relative(root, file).split(path.sep).join('/'))]),
+      invalidateModule() {},
+    },
+  };
+  const owners = new Map();
+  if (command === 'serve') {
+    const after = [];
+    for (const plugin of plugins) {
+      const configureServer = hookOf(plugin, 'configureServer');
+      if (!configureServer) {
+        continue;
+      }
+      const count = pre.stack.length;
+      const postHook = await configureServer.call(null, server);
+      if (pre.stack.length > count) {
+        owners.set(plugin, pre.stack.splice(count));
+      }
+      if (typeof postHook === 'function') {
+        after.push([plugin, postHook]);
+      }
+    }
+    // Hooks returned by `configureServer` add middlewares after Nexus's own.
+    server.middlewares = post;
+    for (const [plugin, postHook] of after) {
+      const count = post.stack.length;
+      await postHook();
+      owners.set(plugin, [...(owners.get(plugin) || []), ...post.stack.splice(count).map((layer) => ({ ...layer, post: true }))]);
+    }
+    server.middlewares = pre;
+  }
+
+  return plugins.map((plugin) => adapt(plugin, { root, env, plugins, server, middlewares: owners.get(plugin), configHash }));
+}
+
+function adapt(plugin, { root, env, plugins, server, middlewares, configHash }) {
+  const order = plugin.enforce === 'pre' || plugin.enforce === 'post' ? plugin.enforce : undefined;
+  const name = plugin.name || 'vite-plugin';
+  const cacheKey = [name, plugin.version, configHash].filter(Boolean).join('@');
+  const nexus = { name, cacheKey, order };
+  const context = pluginContext(plugin, env, plugins);
+
+  const resolveId = hookOf(plugin, 'resolveId');
+  if (resolveId) {
+    nexus.resolveId = async (specifier, importer) => {
+      const resolved = await resolveId.call(context, specifier, importer, { isEntry: false, attributes: {} });
+      const id = resolved && (typeof resolved === 'string' ? resolved : !resolved.external && resolved.id);
+      return id ? fromViteId(root, id) : null;
+    };
+  }
+
+  const load = hookOf(plugin, 'load');
+  if (load) {
+    nexus.load = async (id) => {
+      const loaded = await load.call(context, toViteId(root, id));
+      return loaded && typeof loaded === 'object' ? loaded.code : loaded;
+    };
+  }
+
+  const transform = hookOf(plugin, 'transform');
+  if (transform) {
+    nexus.transform = (code, id) => transform.call(context, code, toViteId(root, id));
+  }
+
+  const transformIndexHtml = hookOf(plugin, 'transformIndexHtml');
+  if (transformIndexHtml) {
+    nexus.transformIndexHtml = async (html, pagePath) => {
+      const ctx = { path: pagePath, filename: path.join(root, pagePath), server: env.command === 'serve' ? server : undefined };
+      const result = await transformIndexHtml.call(null, html, ctx);
+      if (result == null) {
+        return null;
+      }
+      if (typeof result === 'string') {
+        return result;
+      }
+      return Array.isArray(result) ? injectTags(html, result) : injectTags(result.html, result.tags || []);
+    };
+  }
+
+  const handleHotUpdate = hookOf(plugin, 'handleHotUpdate');
+  if (handleHotUpdate) {
+    nexus.handleHotUpdate = async ({ file, modules }) => {
+      const filename = path.join(root, file);
+      const ctx = {
+        file: filename,
+        timestamp: Date.now(),
+        modules: modules.map((url) => moduleNode(root, url)),
+        read: () => fs.promises.readFile(filename, 'utf8'),
+        server,
+      };
+      const result = await handleHotUpdate.call(null, ctx);
+      return Array.isArray(result) ? result.map((module) => module.url) : null;
+    };
+  }
+
+  if (middlewares && middlewares.length > 0) {
+    nexus.handleRequest = (request, phase) => runMiddlewares(middlewares.filter((layer) => !!layer.post === (phase === 'post')), request);
+  }
+
+  return nexus;
+}
+
+module.exports = { fromVite, toViteId, fromViteId };
@@ -0,0 +1,112 @@
+/* Nexus Core - Vue compiler adapter */
+/* Wraps the project's vue/compiler-sfc in what the native binding expects: */
+/* a name and a batch function taking and returning JSON (see src/vue.rs). */
+
+const crypto = require('crypto');
+
+// `vue/compiler-sfc` as the project resolves it, if installed.
+function projectCompiler(root) {
+  for (const specifier of ['vue/compiler-sfc', '@vue/compiler-sfc']) {
+    try {
+      return require(require.resolve(specifier, { paths: [root] }));
+    } catch {
+      // Try the next one.
+    }
+  }
+  return undefined;
+}
+
+// Preprocessors (`sass`, `less`, `stylus`) as the project resolves them.
+function projectRequire(root) {
+  return (name) => require(require.resolve(name, { paths: [root] }));
+}
+
+function check(errors) {
+  if (errors && errors.length > 0) {
+    const [err] = errors;
+    throw typeof err === 'string' ? new Error(err) : err;
+  }
+}
+
+// The module binds the component to `__sfc__` and leaves exporting it, and
+// the HMR code that goes before, to the native side. In dev the template
+// stays a separate render function, so an edit to it can re-render in place.
+async function compile(compiler, root, call) {
+  const { descriptor, errors } = compiler.parse(call.source, { filename: call.id, sourceMap: false });
+  check(errors);
+  // Scoping by file keeps the template's output the same across style edits.
+  const id = crypto.createHash('sha256').update(call.id).digest('hex').slice(0, 8);
+  const scoped = descriptor.styles.some((style) => style.scoped);
+  const isProd = !call.dev;
+  const inline = isProd && !!descriptor.scriptSetup;
+  const templateOptions = { id, scoped, slotted: descriptor.slotted, isProd, filename: call.id };
+
+  let js = 'const __sfc__ = {};';
+  let bindings;
+  if (descriptor.script || descriptor.scriptSetup) {
+    const script = compiler.compileScript(descriptor, { id, isProd, inlineTemplate: inline, genDefaultAs: '__sfc__', templateOptions });
+    js = script.content;
+    bindings = script.bindings;
+  }
+  if (descriptor.template && !inline) {
+    const template = compiler.compileTemplate({ ...templateOptions, source: descriptor.template.content, compilerOptions: { bindingMetadata: bindings } });
+    check(template.errors);
+    js += `\n${template.code.replace(/\bexport function render\(/, 'function render(')}\n__sfc__.render = render;`;
+  }
+  if (scoped) {
+    js += `\n__sfc__.__scopeId = ${JSON.stringify(`data-v-${id}`)};`;
+  }
+
+  // Module styles come back preprocessed and scoped; the native side renames
+  // their classes.
+  const css = [];
+  const modules = [];
+  for (const style of descriptor.styles) {
+    const result = await compiler.compileStyleAsync({
+      source: style.content,
+      filename: call.id,
+      id: `data-v-${id}`,
+      scoped: style.scoped,
+      isProd,
+      preprocessLang: style.lang,
+      preprocessCustomRequire: projectRequire(root),
+    });
+    check(result.errors);
+    if (style.module) {
+      modules.push([style.module === true ? '$style' : style.module, result.code]);
+    } else {
+      css.push(result.code);
+    }
+  }
+
+  const blocks = [descriptor.script, descriptor.scriptSetup].filter(Boolean);
+  return {
+    js,
+    css: css.length > 0 ? css.join('\n') : null,
+    modules,
+    script: blocks.map((block) => block.content).join('\n'),
+    template: `${scoped}${descriptor.template ? descriptor.template.content : ''}`,
+  };
+}
+
+function vueCompiler(root, option) {
+  const compiler = option === false ? undefined : option || projectCompiler(root);
+  if (!compiler) {
+    return undefined;
+  }
+  return {
+    name: `vue@${compiler.version || 'unknown'}`,
+    compileBatch: async (json) => {
+      const replies = await Promise.all(JSON.parse(json).map(async (call) => {
+        try {
+          return { value: await compile(compiler, root, call) };
+        } catch (err) {
+          return { error: err && err.message ? err.message : String(err) };
+        }
+      }));
+      return JSON.stringify(replies);
+    },
+  };
+}
+
+module.exports.vueCompiler = vueCompiler;
@@ -0,0 +1,87 @@
+// Graph construction scaling benchmark.
+//
+// Generates a synthetic project (10k modules by default, override with
+// NEXUS_BENCH_MODULES) and runs the production build with 1, 2, 4, .. N worker
+// threads, printing wall time and speedup over the single-threaded run.
+//
+//   cargo bench -p nexus_core --bench graph_scaling
+
+use nexus_core::bundler::{self, BuildOptions};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const MODULES_PER_DIR: usize = 100;
+
+fn generate_project(root: &Path, modules: usize) {
+    let _ = std::fs::remove_dir_all(root);
+    let src = root.join("src");
+    std::fs::create_dir_all(&src).unwrap();
+
+    let dirs = modules.div_ceil(MODULES_PER_DIR);
+    let mut entry = String::new();
+    for d in 0..dirs {
+        let dir = src.join(format!("feature_{}", d));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let count = MODULES_PER_DIR.min(modules - d * MODULES_PER_DIR);
+        let mut index = String::new();
+        for m in 0..count {
+            // Every module imports the previous one in its directory, so the
+            // graph is both wide and deep.
+            let import = if m > 0 { format!("import {{ value_{} }} from './mod_{}';\n", m - 1, m - 1) } else { String::new() };
+            let prev = if m > 0 { format!("value_{}", m - 1) } else { "0".to_string() };
+            let body = format!(
+                "{import}\
+interface Item{m} {{ id: number; label: string; tags: string[] }}\n\
+const items{m}: Item{m}[] = Array.from({{ length: 8 }}, (_, i) => ({{ id: i, label: `item-${{i}}`, tags: ['a', 'b'] }}));\n\
+export function compute_{m}(input: number): number {{\n  return items{m}.reduce((acc, item) => acc + item.id * input + item.tags.length, {prev});\n}}\n\
+export const value_{m} = compute_{m}({m});\n"
+            );
+            std::fs::write(dir.join(format!("mod_{}.ts", m)), body).unwrap();
+            index.push_str(&format!("export {{ value_{} as v{} }} from './mod_{}';\n", m, m, m));
+        }
+        std::fs::write(dir.join("index.ts"), index).unwrap();
+        entry.push_str(&format!("import * as f{} from './feature_{}';\nconsole.log(f{});\n", d, d, d));
+    }
+    std::fs::write(src.join("index.tsx"), entry).unwrap();
+}
+
+fn run(root: &Path, threads: usize) -> Duration {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    // Without the transform cache, or every run after the first would only read it back.
+    let options = BuildOptions { threads: Some(threads), cache: false, ..BuildOptions::default() };
+    let start = Instant::now();
+    runtime.block_on(bundler::build_with_options(root.to_str().unwrap(), &options)).expect("build failed");
+    start.elapsed()
+}
+
+fn main() {
+    let modules: usize = std::env::var("NEXUS_BENCH_MODULES").ok().and_then(|v| v.parse().ok()).unwrap_or(10_000);
+    let root = std::env::temp_dir().join("nexus_bench").join("graph_scaling");
+    generate_project(&root, modules);
+
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut counts = vec![1];
+    while counts.last().unwrap() * 2 <= cores {
+        counts.push(counts.last().unwrap() * 2);
+    }
+    if *counts.last().unwrap() != cores {
+        counts.push(cores);
+    }
+
+    // Warm the OS page cache so the first measurement isn't penalised.
+    run(&root, cores);
+
+    println!("graph_scaling: {} modules, {} cores", modules, cores);
+    let baseline = run(&root, 1);
+    println!("  threads=1   {:>8.2?}  speedup 1.00x", baseline);
+    for &threads in &counts[1..] {
+        let elapsed = run(&root, threads);
+        println!(
+            "  threads={:<3} {:>8.2?}  speedup {:.2}x",
+            threads,
+            elapsed,
+            baseline.as_secs_f64() / elapsed.as_secs_f64()
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use oxc_allocator::Allocator;
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_minifier::{Minifier, MinifierOptions};
+use oxc_parser::Parser;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::io::Write;
+use crate::report::BuildReport;
+
+// Bundle analyzer: per-module and per-chunk sizes, a webpack-compatible
+// stats.json and a self-contained HTML treemap.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sizes {
+    pub raw: usize,
+    pub minified: usize,
+    pub gzip: usize,
+    pub brotli: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleSizes {
+    pub id: String,
+    pub sizes: Sizes,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChunkSizes {
+    /// Output file relative to dist, e.g. `assets/main.js`.
+    pub file: String,
+    pub is_entry: bool,
+    pub sizes: Sizes,
+    pub modules: Vec<ModuleSizes>,
+}
+
+/// One emitted chunk with the code each module contributed to it.
+pub struct ChunkInput {
+    pub file: String,
+    pub is_entry: bool,
+    pub code: String,
+    pub modules: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BundleAnalysis {
+    pub chunks: Vec<ChunkSizes>,
+}
+
+/// Minifies JS with oxc (compress + mangle). Falls back to the input on parse errors.
+pub fn minify(code: &str, path: &str) -> String {
+    let allocator = Allocator::default();
+    let source_type = crate::loader::source_type_of(path);
+    let ret = Parser::new(&allocator, code, source_type).parse();
+    if !ret.errors.is_empty() {
+        return code.to_string();
+    }
+    let mut program = ret.program;
+    let minified = Minifier::new(MinifierOptions::default()).build(&allocator, &mut program);
+    Codegen::new()
+        .with_options(CodegenOptions { minify: true, ..CodegenOptions::default() })
+        .with_symbol_table(minified.symbol_table)
+        .build(&program)
+        .code
+}
+
+fn gzip_size(bytes: &[u8]) -> usize {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+    let _ = encoder.write_all(bytes);
+    encoder.finish().map(|b| b.len()).unwrap_or(bytes.len())
+}
+
+fn brotli_size(bytes: &[u8]) -> usize {
+    let mut out = Vec::new();
+    {
+        let mut writer = brotli::CompressorWriter::new(&mut out, 4096, 11, 22);
+        let _ = writer.write_all(bytes);
+    }
+    out.len()
+}
+
+/// Raw size plus minified, gzip and brotli sizes (compressed sizes are of the minified code).
+pub fn measure(code: &str, path: &str) -> Sizes {
+    let minified = minify(code, path);
+    Sizes {
+        raw: code.len(),
+        minified: minified.len(),
+        gzip: gzip_size(minified.as_bytes()),
+        brotli: brotli_size(minified.as_bytes()),
+    }
+}
+
+// Module ids are virtual paths (`/src/a.ts`); webpack names are relative (`./src/a.ts`).
+fn webpack_name(id: &str) -> String {
+    format!(".{}", id)
+}
+
+impl BundleAnalysis {
+    pub fn new(chunks: Vec<ChunkInput>) -> Self {
+        let chunks = chunks
+            .into_iter()
+            .map(|chunk| ChunkSizes {
+                sizes: measure(&chunk.code, &chunk.file),
+                modules: chunk
+                    .modules
+                    .iter()
+                    .map(|(id, code)| ModuleSizes { id: id.clone(), sizes: measure(code, "module.js") })
+                    .collect(),
+                file: chunk.file,
+                is_entry: chunk.is_entry,
+            })
+            .collect();
+        Self { chunks }
+    }
+
+    /// webpack 5 `stats.json` subset understood by webpack-bundle-analyzer and statoscope.
+    /// Nexus-specific sizes live under `nexusSizes`.
+    pub fn stats_json(&self, report: &BuildReport, time_ms: u128) -> serde_json::Value {
+        let chunk_name = |file: &str| {
+            file.trim_start_matches("assets/").trim_end_matches(".js").to_string()
+        };
+
+        let assets: Vec<_> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                json!({
+                    "name": c.file,
+                    "size": c.sizes.raw,
+                    "chunks": [i],
+                    "chunkNames": [chunk_name(&c.file)],
+                    "emitted": true,
+                    "nexusSizes": c.sizes,
+                })
+            })
+            .collect();
+
+        let mut modules = Vec::new();
+        let mut chunks = Vec::new();
+        for (i, c) in self.chunks.iter().enumerate() {
+            let mut chunk_modules = Vec::new();
+            for m in &c.modules {
+                let info = report.module(&m.id);
+                let reasons: Vec<_> = info
+                    .map(|r| r.importers.as_slice())
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|imp| json!({ "moduleId": imp, "moduleName": webpack_name(imp), "type": "harmony import" }))
+                    .collect();
+                let issuer = info.and_then(|r| r.chain.len().checked_sub(2).map(|p| r.chain[p].clone()));
+                let module = json!({
+                    "id": m.id,
+                    "identifier": m.id,
+                    "name": webpack_name(&m.id),
+                    "size": m.sizes.raw,
+                    "chunks": [i],
+                    "issuer": issuer,
+                    "issuerName": issuer.as_deref().map(webpack_name),
+                    "reasons": reasons,
+                    "usedExports": info.map(|r| r.used_exports.clone()),
+                    "nexusSizes": m.sizes,
+                });
+                chunk_modules.push(module.clone());
+                modules.push(module);
+            }
+            chunks.push(json!({
+                "id": i,
+                "names": [chunk_name(&c.file)],
+                "files": [c.file],
+                "size": c.sizes.raw,
+                "entry": c.is_entry,
+                "initial": c.is_entry || c.file == "assets/vendor.js",
+                "rendered": true,
+                "modules": chunk_modules,
+                "nexusSizes": c.sizes,
+            }));
+        }
+
+        let initial: Vec<usize> = self
+            .chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_entry || c.file == "assets/vendor.js")
+            .map(|(i, _)| i)
+            .collect();
+        let entry_assets: Vec<_> = initial.iter().map(|&i| json!({ "name": self.chunks[i].file })).collect();
+
+        json!({
+            "version": "5.0.0",
+            "time": time_ms as u64,
+            "publicPath": "/",
+            "outputPath": "dist",
+            "assets": assets,
+            "chunks": chunks,
+            "modules": modules,
+            "entrypoints": { "main": { "name": "main", "chunks": initial, "assets": entry_assets } },
+            "errors": [],
+            "warnings": [],
+        })
+    }
+
+    /// A single HTML file (no external requests) rendering a squarified treemap.
+    pub fn treemap_html(&self) -> String {
+        let data = serde_json::to_string(self).unwrap_or_else(|_| "{\"chunks\":[]}".to_string());
+        // Keep `</script>` inside module ids from closing the data block.
+        TREEMAP_TEMPLATE.replace("__NEXUS_DATA__", &data.replace("</", "<\\/"))
+    }
+}
+
+const TREEMAP_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Nexus Bundle Analyzer</title>
+<style>
+  body { margin: 0; font: 12px system-ui, sans-serif; background: #1e1e1e; color: #eee; }
+  header { height: 40px; padding: 0 12px; display: flex; gap: 16px; align-items: center; background: #111; }
+  #map { position: absolute; top: 40px; left: 0; right: 0; bottom: 0; }
+  .node { position: absolute; box-sizing: border-box; border: 1px solid #1e1e1e; overflow: hidden;
+          padding: 2px 4px; white-space: nowrap; text-overflow: ellipsis; }
+  .chunk { background: #333; font-weight: bold; }
+</style>
+</head>
+<body>
+<header>
+  <strong>Nexus Bundle Analyzer</strong>
+  <label>Size:
+    <select id="metric">
+      <option value="raw">Raw</option>
+      <option value="minified">Minified</option>
+      <option value="gzip">Gzip</option>
+      <option value="brotli">Brotli</option>
+    </select>
+  </label>
+  <span id="total"></span>
+</header>
+<div id="map"></div>
+<script>
+const DATA = __NEXUS_DATA__;
+
+function fmt(n) { return n < 1024 ? n + " B" : (n / 1024).toFixed(1) + " KB"; }
+function describe(label, s) {
+  return label + "\nraw: " + fmt(s.raw) + "\nminified: " + fmt(s.minified) +
+    "\ngzip: " + fmt(s.gzip) + "\nbrotli: " + fmt(s.brotli);
+}
+function color(id) {
+  let h = 0;
+  for (const c of id) h = (h * 31 + c.charCodeAt(0)) % 360;
+  return "hsl(" + h + ", 45%, 38%)";
+}
+
+// Squarified treemap (Bruls, Huizing, van Wijk).
+function worst(row, side) {
+  const sum = row.reduce((a, r) => a + r.area, 0);
+  const max = Math.max(...row.map(r => r.area));
+  const min = Math.min(...row.map(r => r.area));
+  return Math.max((side * side * max) / (sum * sum), (sum * sum) / (side * side * min));
+}
+function layoutRow(row, x, y, w, h, out) {
+  const sum = row.reduce((a, r) => a + r.area, 0);
+  if (w >= h) {
+    const rw = sum / h;
+    let cy = y;
+    for (const r of row) { const rh = r.area / rw; out.push({ item: r.item, x, y: cy, w: rw, h: rh }); cy += rh; }
+    return [x + rw, y, w - rw, h];
+  }
+  const rh = sum / w;
+  let cx = x;
+  for (const r of row) { const cw = r.area / rh; out.push({ item: r.item, x: cx, y, w: cw, h: rh }); cx += cw; }
+  return [x, y + rh, w, h - rh];
+}
+function squarify(items, x, y, w, h, out) {
+  items = items.filter(i => i.value > 0).sort((a, b) => b.value - a.value);
+  const total = items.reduce((s, i) => s + i.value, 0);
+  if (!total || w <= 0 || h <= 0) return;
+  const scale = (w * h) / total;
+  const rest = items.map(i => ({ item: i, area: i.value * scale }));
+  let row = [];
+  while (rest.length) {
+    const side = Math.min(w, h);
+    if (!row.length || worst(row.concat([rest[0]]), side) <= worst(row, side)) {
+      row.push(rest.shift());
+    } else {
+      [x, y, w, h] = layoutRow(row, x, y, w, h, out);
+      row = [];
+    }
+  }
+  if (row.length) layoutRow(row, x, y, w, h, out);
+}
+
+function box(r, cls, text, title, bg) {
+  const d = document.createElement("div");
+  d.className = cls;
+  Object.assign(d.style, { left: r.x + "px", top: r.y + "px", width: r.w + "px", height: r.h + "px", background: bg });
+  d.textContent = text;
+  d.title = title;
+  return d;
+}
+
+function render() {
+  const metric = document.getElementById("metric").value;
+  const map = document.getElementById("map");
+  map.innerHTML = "";
+  const chunks = DATA.chunks.map(c => ({ value: c.sizes[metric], chunk: c }));
+  document.getElementById("total").textContent = "Total: " + fmt(chunks.reduce((s, c) => s + c.value, 0));
+
+  const rects = [];
+  squarify(chunks, 0, 0, map.clientWidth, map.clientHeight, rects);
+  for (const r of rects) {
+    const c = r.item.chunk;
+    map.appendChild(box(r, "node chunk", c.file + " (" + fmt(r.item.value) + ")", describe(c.file, c.sizes), "#333"));
+    const inner = [];
+    const modules = c.modules.map(m => ({ value: m.sizes[metric], module: m }));
+    squarify(modules, r.x + 2, r.y + 18, r.w - 4, r.h - 20, inner);
+    for (const m of inner) {
+      const mod = m.item.module;
+      map.appendChild(box(m, "node", mod.id, describe(mod.id, mod.sizes), color(mod.id)));
+    }
+  }
+}
+
+document.getElementById("metric").onchange = render;
+window.onresize = render;
+render();
+</script>
+</body>
+</html>
+"##;
@@ -1,9 +1,24 @@
-use std::collections::{HashSet, VecDeque, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque, HashMap};
+use rayon::prelude::*;
 use std::path::{Path, PathBuf};
+use notify::Watcher;
 use crate::resolver::NexusResolver;
 use crate::compiler;
-use crate::parser::{analyze_module, transform_tree_shake, transform_cjs, ImportInfo};
+use crate::parser::{transform_cjs, ImportInfo};
+use crate::unit::CompilationUnit;
+use crate::define::Defines;
+use crate::import_meta::{self, ImportMetaOutput};
+use crate::cache::{self, CachedModule, TransformCache};
+use crate::shaker::ShakeInfo;
+use crate::exports::ExportTable;
+use crate::report::{self, BuildReport, ModuleReport};
+use crate::analyzer::{BundleAnalysis, ChunkInput};
 use crate::runtime::NEXUS_RUNTIME_JS;
+use crate::wasm;
+use crate::plugin::{ChunkInfo, ModuleInfo, OutputBundle, PluginContainer};
+use crate::loader::{Loader, LoaderRegistry};
+use crate::svelte::SvelteHook;
+use crate::vue::VueHook;
 
 struct BuildNode {
     id: String, // Virtual Path (e.g. /src/utils.ts)
@@ -13,10 +28,26 @@ struct BuildNode {
     imports: HashMap<String, String>, // Import Source -> Resolved Virtual ID
     sync_deps: Vec<String>, // Resolved Virtual IDs
     async_deps: Vec<String>, // Resolved Virtual IDs
+    worker_deps: Vec<String>, // Worker entries it constructs
     css: Option<String>,
     asset: Option<(String, Vec<u8>)>,
-    exports: Vec<String>,
+    // Files referenced through `new URL(..., import.meta.url)` or URL globs.
+    url_assets: Vec<(String, Vec<u8>)>,
+    globs: Vec<String>,
     import_info: Vec<ImportInfo>,
+    shake: ShakeInfo,
+    // Parsed AST, kept alive until emit. `None` for CSS, binary assets and
+    // modules restored from the transform cache.
+    unit: Option<CompilationUnit>,
+    content_hash: u64,
+    // `sideEffects` from the owning package.json, if declared.
+    declared_side_effects: Option<bool>,
+}
+
+impl BuildNode {
+    fn has_side_effects(&self) -> bool {
+        self.declared_side_effects.unwrap_or(true) && self.shake.has_side_effects
+    }
 }
 
 struct Chunk {
@@ -25,362 +56,1142 @@ struct Chunk {
     is_entry: bool,
 }
 
+#[derive(Debug, Clone)]
+pub struct BuildOptions {
+    /// Write `dist/report.json` describing what tree shaking kept and why.
+    pub report: bool,
+    /// Write `dist/stats.json` (webpack format) and `dist/treemap.html`.
+    pub analyze: bool,
+    /// Worker threads for graph construction. `None` uses one per core.
+    pub threads: Option<usize>,
+    /// Reuse transform results from `.nexus/cache` and store new ones there.
+    pub cache: bool,
+    /// Selects `.env.[mode]` and sets `import.meta.env.MODE`.
+    pub mode: String,
+    /// Extra compile-time replacements, e.g. `__APP_VERSION__` -> `"1.2.0"`.
+    pub define: BTreeMap<String, String>,
+    /// Plugins run over every module and output file; see `plugin`.
+    pub plugins: PluginContainer,
+    /// Loaders by extension or glob, on top of the defaults; see `loader`.
+    pub loader: BTreeMap<String, Loader>,
+    /// Compiles `.svelte` modules; see `svelte`.
+    pub svelte: Option<SvelteHook>,
+    /// Compiles `.vue` modules; see `vue`.
+    pub vue: Option<VueHook>,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            report: false,
+            analyze: false,
+            threads: None,
+            cache: true,
+            mode: "production".to_string(),
+            define: BTreeMap::new(),
+            plugins: PluginContainer::default(),
+            loader: BTreeMap::new(),
+            svelte: None,
+            vue: None,
+        }
+    }
+}
+
+pub(crate) const ENTRY_CANDIDATES: &[&str] = &["./src/main.tsx", "./src/index.tsx", "./src/main.js", "./src/index.js"];
+
 pub async fn build(root_dir: &str) -> std::io::Result<()> {
-    tracing::info!("Starting Production Build with Tree Shaking...");
-    let root = Path::new(root_dir);
-    let dist = root.join("dist");
-    let assets_dir = dist.join("assets");
-
-    // 1. Clean & Create dist
-    if dist.exists() {
-        tokio::fs::remove_dir_all(&dist).await?;
+    build_with_options(root_dir, &BuildOptions::default()).await.map(|_| ())
+}
+
+pub async fn build_with_options(root_dir: &str, options: &BuildOptions) -> std::io::Result<BuildReport> {
+    BuildSession::new(root_dir, options).await?.build().await
+}
+
+/// Builds once, then rebuilds whenever files under the root change. Only
+/// changed modules are recompiled and only changed output files rewritten.
+/// Runs until the file watcher stops.
+pub async fn watch(root_dir: &str, options: &BuildOptions) -> std::io::Result<()> {
+    let mut session = BuildSession::new(root_dir, options).await?;
+    session.build().await?;
+
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+    let mut watcher = notify::RecommendedWatcher::new(
+        move |res| {
+            let _ = tx.send(res);
+        },
+        notify::Config::default(),
+    )
+    .map_err(std::io::Error::other)?;
+    watcher.watch(&session.root, notify::RecursiveMode::Recursive).map_err(std::io::Error::other)?;
+    tracing::info!("Watching {} for changes", session.root.display());
+
+    // Our own output, the cache and dependencies don't trigger rebuilds.
+    let ignored = [session.root.join("dist"), session.root.join(".nexus"), session.root.join("node_modules")];
+    let collect = |res: notify::Result<notify::Event>, changed: &mut BTreeSet<PathBuf>| match res {
+        Ok(event) if !event.kind.is_access() => {
+            changed.extend(event.paths.into_iter().filter(|p| !ignored.iter().any(|dir| p.starts_with(dir))));
+        }
+        Ok(_) => {}
+        Err(e) => tracing::error!("Watch error: {:?}", e),
+    };
+
+    while let Some(res) = rx.recv().await {
+        let mut changed = BTreeSet::new();
+        collect(res, &mut changed);
+        // Saving a file often produces a burst of events; handle them as one change.
+        tokio::time::sleep(std::time::Duration::from_millis(30)).await;
+        while let Ok(res) = rx.try_recv() {
+            collect(res, &mut changed);
+        }
+        if changed.is_empty() {
+            continue;
+        }
+
+        let changed: Vec<PathBuf> = changed.into_iter().collect();
+        match session.rebuild(&changed).await {
+            Ok(_) => tracing::info!(
+                "Rebuilt after {} change(s) in {}ms, {} file(s) written",
+                changed.len(),
+                session.started.elapsed().as_millis(),
+                session.rewritten().len()
+            ),
+            Err(e) => tracing::error!("Rebuild failed: {}", e),
+        }
     }
-    tokio::fs::create_dir_all(&assets_dir).await?;
+    Ok(())
+}
 
-    // 2. Resolve Entry
-    let resolver = NexusResolver::new(root);
-    let entry_candidates = vec!["./src/main.tsx", "./src/index.tsx", "./src/main.js", "./src/index.js"];
-    let mut entry_abs = PathBuf::new();
-    let mut found_entry = false;
+/// Output files of a build, written in one go once everything is generated.
+/// A file whose content is unchanged since the last build of the session isn't
+/// rewritten, and files the last build produced but the current one doesn't
+/// are removed.
+#[derive(Default)]
+struct OutputFiles {
+    pending: Vec<(PathBuf, Vec<u8>)>,
+    hashes: HashMap<PathBuf, u64>,
+    rewritten: Vec<PathBuf>,
+}
 
-    for c in entry_candidates {
-        if let Ok(p) = resolver.resolve(root, c) {
-            entry_abs = p;
-            found_entry = true;
-            break;
+impl OutputFiles {
+    fn write(&mut self, path: PathBuf, data: impl Into<Vec<u8>>) {
+        self.pending.push((path, data.into()));
+    }
+
+    // Hands the pending files to `generate_bundle` hooks, keyed by their
+    // path relative to `dist`.
+    fn generate_bundle(&mut self, dist: &Path, plugins: &PluginContainer) -> std::io::Result<()> {
+        if plugins.is_empty() {
+            return Ok(());
         }
+        let mut bundle: OutputBundle = self
+            .pending
+            .drain(..)
+            .map(|(path, data)| (path.strip_prefix(dist).unwrap_or(&path).to_string_lossy().replace('\\', "/"), data))
+            .collect();
+        plugins.generate_bundle(&mut bundle)?;
+        self.pending = bundle.into_iter().map(|(name, data)| (dist.join(name), data)).collect();
+        Ok(())
     }
 
-    if !found_entry {
-        return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Entry point not found"));
+    async fn flush(&mut self) -> std::io::Result<()> {
+        self.rewritten.clear();
+        let pending = std::mem::take(&mut self.pending);
+        let current: HashSet<PathBuf> = pending.iter().map(|(path, _)| path.clone()).collect();
+        for path in self.hashes.keys().filter(|p| !current.contains(*p)).cloned().collect::<Vec<_>>() {
+            self.hashes.remove(&path);
+            match tokio::fs::remove_file(&path).await {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e),
+                _ => {}
+            }
+        }
+        for (path, data) in pending {
+            let hash = cache::content_hash(&data);
+            if self.hashes.get(&path) == Some(&hash) {
+                continue;
+            }
+            if let Some(dir) = path.parent() {
+                tokio::fs::create_dir_all(dir).await?;
+            }
+            tokio::fs::write(&path, &data).await?;
+            self.hashes.insert(path.clone(), hash);
+            self.rewritten.push(path);
+        }
+        Ok(())
     }
+}
 
-    // 3. Build Graph
-    let mut nodes: HashMap<String, BuildNode> = HashMap::new();
-    let mut queue = VecDeque::new();
-    let mut visited_paths = HashSet::new();
+/// A production build whose module graph stays in memory, so later builds
+/// only recompile what changed. `build_with_options` uses one for a single
+/// build, `watch` keeps one for its whole run.
+pub struct BuildSession {
+    root: PathBuf,
+    options: BuildOptions,
+    resolver: NexusResolver,
+    pool: std::sync::Arc<rayon::ThreadPool>,
+    cache: Option<std::sync::Arc<TransformCache>>,
+    defines: std::sync::Arc<Defines>,
+    loaders: std::sync::Arc<LoaderRegistry>,
+    entry_abs: PathBuf,
+    entry_id: String,
+    nodes: HashMap<String, BuildNode>,
+    // Last shaken and linked output per included module, with its `linked_key`.
+    linked: HashMap<String, (u64, (usize, String))>,
+    outputs: OutputFiles,
+    started: std::time::Instant,
+}
 
-    // Canonical ID for entry
-    let entry_str = entry_abs.to_string_lossy().to_string();
-    queue.push_back(entry_abs.clone());
-    visited_paths.insert(entry_str);
+impl BuildSession {
+    /// Cleans `dist`, finds the entry and builds the module graph.
+    pub async fn new(root_dir: &str, options: &BuildOptions) -> std::io::Result<Self> {
+        tracing::info!("Starting Production Build with Tree Shaking...");
+        let started = std::time::Instant::now();
+        let root = Path::new(root_dir);
+        let dist = root.join("dist");
+        let assets_dir = dist.join("assets");
 
-    let normalize_id = |p: &Path| -> String {
-        let s = p.to_string_lossy().to_string();
-        let rel = s.replace(root.to_string_lossy().as_ref(), "").replace("\\", "/");
-        if rel.starts_with('/') { rel } else { format!("/{}", rel) }
-    };
-    
-    let entry_virtual_id = normalize_id(&entry_abs);
+        // 1. Clean & Create dist
+        if dist.exists() {
+            tokio::fs::remove_dir_all(&dist).await?;
+        }
+        tokio::fs::create_dir_all(&assets_dir).await?;
 
-    while let Some(current_path) = queue.pop_front() {
-        let virtual_id = normalize_id(&current_path);
+        // 2. Resolve Entry
+        let resolver = NexusResolver::new(root);
+        let mut entry_abs = PathBuf::new();
+        let mut found_entry = false;
 
-        if nodes.contains_key(&virtual_id) {
-            continue;
+        for c in ENTRY_CANDIDATES {
+            if let Ok(p) = resolver.resolve(root, c) {
+                entry_abs = p;
+                found_entry = true;
+                break;
+            }
         }
 
-        let bytes = tokio::fs::read(&current_path).await?;
-        let ext = current_path.extension().and_then(|s| s.to_str()).unwrap_or("");
-        let is_vendor = current_path.to_string_lossy().contains("node_modules");
-
-        // Compile
-        let compiled = match ext {
-             "css" => {
-                 let text = String::from_utf8_lossy(&bytes);
-                 compiler::compile_css(&text, &virtual_id, true)
-             },
-             "png" | "jpg" | "jpeg" | "gif" | "svg" | "wasm" | "json" => {
-                 compiler::compile_asset(&bytes, &virtual_id, true)
-             },
-             _ => {
-                 let text = String::from_utf8_lossy(&bytes);
-                 compiler::compile(&text, &virtual_id, true)
-             }
+        if !found_entry {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Entry point not found"));
+        }
+
+        // 3. Build Graph
+        // Discovery runs level by level on a rayon pool; see `build_graph`.
+        let pool = std::sync::Arc::new(
+            rayon::ThreadPoolBuilder::new()
+                .num_threads(options.threads.unwrap_or(0))
+                .build()
+                .map_err(std::io::Error::other)?,
+        );
+        let defines = Defines::load(root, &options.mode, &options.define);
+        let loaders = LoaderRegistry::new(&options.loader).with_svelte(options.svelte.clone()).with_vue(options.vue.clone());
+        let cache_config = format!("build:{:x}{}{}", defines.hash(), options.plugins.cache_key(), loaders.cache_key());
+        let mut session = Self {
+            entry_id: normalize_id(root, &entry_abs),
+            entry_abs: entry_abs.clone(),
+            root: root.to_path_buf(),
+            options: options.clone(),
+            resolver,
+            pool,
+            cache: if options.cache { TransformCache::open(root, &cache_config).map(std::sync::Arc::new) } else { None },
+            defines: std::sync::Arc::new(defines),
+            loaders: std::sync::Arc::new(loaders),
+            nodes: HashMap::new(),
+            linked: HashMap::new(),
+            outputs: OutputFiles::default(),
+            started,
         };
+        session.update_graph(vec![entry_abs]).await?;
+        Ok(session)
+    }
 
-        // Extract Deps
-        let mut sync_deps = Vec::new();
-        let mut async_deps = Vec::new();
-        let mut imports_map = HashMap::new();
-        let mut exports = Vec::new();
-        let mut import_info = Vec::new();
-
-        if ext != "css" && !matches!(ext, "png" | "jpg" | "jpeg" | "gif" | "svg" | "wasm") {
-            let (ex, im) = analyze_module(&compiled.code, &virtual_id);
-            exports = ex;
-            import_info = im;
-            
-            for info in &import_info {
-                if let Ok(resolved) = resolver.resolve(&current_path.parent().unwrap(), &info.source) {
-                    let resolved_vid = normalize_id(&resolved);
-                    
-                    imports_map.insert(info.source.clone(), resolved_vid.clone());
-
-                    if info.is_dynamic {
-                        async_deps.push(resolved_vid.clone());
-                    } else {
-                        // is_star (export * or import *) is treated as sync dependency usually
-                        sync_deps.push(resolved_vid.clone());
-                    }
+    /// Output files written or rewritten by the last build.
+    pub fn rewritten(&self) -> &[PathBuf] {
+        &self.outputs.rewritten
+    }
 
-                    let res_str = resolved.to_string_lossy().to_string();
-                    if !visited_paths.contains(&res_str) {
-                        visited_paths.insert(res_str);
-                        queue.push_back(resolved);
-                    }
-                }
+    // Loads `seeds` (replacing them if already in the graph) and whatever new
+    // modules they import.
+    async fn update_graph(&mut self, seeds: Vec<PathBuf>) -> std::io::Result<()> {
+        let mut nodes = std::mem::take(&mut self.nodes);
+        let pool = self.pool.clone();
+        let root = self.root.clone();
+        let resolver = self.resolver.clone();
+        let cache = self.cache.clone();
+        let defines = self.defines.clone();
+        let plugins = self.options.plugins.clone();
+        let loaders = self.loaders.clone();
+        let (nodes, result) = tokio::task::spawn_blocking(move || {
+            let lock_hash = cache.as_ref().map_or(0, |_| cache::lockfile_hash(&root));
+            let cx = GraphContext { root: &root, resolver: &resolver, plugins: &plugins, loaders: &loaders, cache: cache.as_deref(), defines: &defines, lock_hash };
+            let result = pool.install(|| build_graph(cx, &mut nodes, seeds));
+            (nodes, result)
+        })
+        .await
+        .map_err(std::io::Error::other)?;
+        self.nodes = nodes;
+        result
+    }
+
+    /// Updates the graph for changed, created or deleted files and rebuilds.
+    pub async fn rebuild(&mut self, changed: &[PathBuf]) -> std::io::Result<BuildReport> {
+        self.started = std::time::Instant::now();
+        // A file can back several modules, one per import variant.
+        let mut by_path: HashMap<PathBuf, Vec<(&PathBuf, &String)>> = HashMap::new();
+        for node in self.nodes.values() {
+            by_path.entry(file_path(&node.fs_path)).or_default().push((&node.fs_path, &node.id));
+        }
+        let mut seeds = Vec::new();
+        let mut removed = Vec::new();
+        let mut created = false;
+        for path in changed {
+            match (by_path.get(path), path.exists()) {
+                (Some(nodes), true) => seeds.extend(nodes.iter().map(|(fs_path, _)| (*fs_path).clone())),
+                (Some(nodes), false) => removed.extend(nodes.iter().map(|(_, id)| (*id).clone())),
+                (None, true) => created = true,
+                (None, false) => {}
             }
         }
+        for id in &removed {
+            self.nodes.remove(id);
+        }
 
-        nodes.insert(virtual_id.clone(), BuildNode {
-            id: virtual_id,
-            fs_path: current_path,
-            code: compiled.code,
-            is_vendor,
-            imports: imports_map,
-            sync_deps,
-            async_deps,
-            css: compiled.css,
-            asset: compiled.asset,
-            exports,
-            import_info,
-        });
-    }
+        // Globs matching a created or deleted file expand differently now.
+        let changed_ids: Vec<String> = changed.iter().map(|path| normalize_id(&self.root, path)).collect();
+        for node in self.nodes.values() {
+            if node.globs.iter().any(|glob| changed_ids.iter().any(|id| import_meta::glob_match(glob, id))) {
+                seeds.push(node.fs_path.clone());
+            }
+        }
 
-    // 4. Liveness Analysis (Mark & Sweep)
-    let mut used_exports: HashMap<String, HashSet<String>> = HashMap::new();
-    let mut live_modules = VecDeque::new();
-    let mut visited_live = HashSet::new();
-
-    // Start with Entry
-    live_modules.push_back(entry_virtual_id.clone());
-    visited_live.insert(entry_virtual_id.clone());
-
-    while let Some(mid) = live_modules.pop_front() {
-        if let Some(node) = nodes.get(&mid) {
-            // Process Imports
-            for info in &node.import_info {
-                if let Some(resolved_id) = node.imports.get(&info.source) {
-                    // Mark target module as Live
-                    if !visited_live.contains(resolved_id) {
-                        visited_live.insert(resolved_id.clone());
-                        live_modules.push_back(resolved_id.clone());
-                    }
+        // Importers of deleted modules, and after a file was created, modules
+        // with imports that didn't resolve before, get resolved again.
+        for node in self.nodes.values() {
+            let dangling = node.imports.values().any(|target| !self.nodes.contains_key(target));
+            let unresolved = created && node.import_info.iter().any(|i| !node.imports.contains_key(&i.source));
+            if dangling || unresolved {
+                seeds.push(node.fs_path.clone());
+            }
+        }
+        if !self.entry_abs.exists() {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotFound, "Entry point not found"));
+        }
 
-                    // Mark explicitly requested exports as Used
-                    let entry_set = used_exports.entry(resolved_id.clone()).or_default();
-                    for spec in &info.specifiers {
-                        entry_set.insert(spec.clone());
-                    }
-                    
-                    // If import * or export *, mark ALL as used?
-                    // Safe logic: If I `import * as ns`, I might use any key. 
-                    // So we must verify ALL exports.
-                    // Also `export * from 'mod'` usage is propagated later.
-                    // But here, if we encounter `import *`, we optimistically mark all.
-                    if info.is_star && !info.specifiers.is_empty() { 
-                         // `import * as ns` (specifier is `ns`? No, visitor logic) 
-                         // Visitor: `import *` sets `is_star=true` and NO specifiers?
-                         // Re-check parser.rs:
-                         // ImportNamespaceSpecifier -> `is_star = true`. specifiers empty?
-                         // "specifiers.push(s.imported.name())" was logic for ImportSpecifier.
-                         // ImportNamespaceSpecifier logic: `is_star=true`. Specifiers list left empty?
-                         // Yes.
-                         // So if `is_star`, we treat it as "Uses Everything".
-                         if let Some(target) = nodes.get(resolved_id) {
-                             for e in &target.exports {
-                                 entry_set.insert(e.clone());
-                             }
-                         }
-                    }
-                }
+        self.update_graph(seeds).await?;
+        self.prune_unreachable();
+        self.build().await
+    }
+
+    // Drops modules no longer imported from the entry, as if built from scratch.
+    fn prune_unreachable(&mut self) {
+        let mut reachable = HashSet::new();
+        let mut queue = vec![self.entry_id.clone()];
+        while let Some(id) = queue.pop() {
+            if !reachable.insert(id.clone()) {
+                continue;
+            }
+            if let Some(node) = self.nodes.get(&id) {
+                queue.extend(node.imports.values().cloned());
             }
         }
+        self.nodes.retain(|id, _| reachable.contains(id));
+        self.linked.retain(|id, _| reachable.contains(id));
     }
 
-    // Propagate `export *` usage
-    // If A uses `x` from B, and B has `export * from C`.
-    // If B doesn't export `x` directly, `x` might come from C.
-    // So we add `x` to C.used.
-    let mut changed = true;
-    while changed {
-        changed = false;
-        let mut updates: Vec<(String, String)> = Vec::new();
-        
-        for (mid, node) in &nodes {
-            if let Some(used) = used_exports.get(mid) {
-                // Check if any used symbol is NOT in local exports (handled by export *) 
-                // OR simpler: Just propagate ALL used symbols to all `export *` children.
-                // This is over-approximation but safe.
-                for info in &node.import_info {
-                     // export * from ... -> is_star=true, specifiers empty?
-                     // Visitor: `visit_export_all_declaration` -> `is_star=true`, specifiers empty.
-                     if info.is_star && info.specifiers.is_empty() {
-                         if let Some(child_id) = node.imports.get(&info.source) {
-                             // Propagate all used symbols
-                             for sym in used {
-                                 updates.push((child_id.clone(), sym.clone()));
-                             }
-                         }
-                     }
+    /// Runs liveness, chunking and linking over the current graph and writes
+    /// the output. Linking is skipped for modules whose inputs didn't change.
+    pub async fn build(&mut self) -> std::io::Result<BuildReport> {
+        tokio::fs::create_dir_all(self.root.join("dist/assets")).await?;
+        let html_path = self.root.join("index.html");
+        let html_template = if html_path.exists() { Some(tokio::fs::read_to_string(&html_path).await?) } else { None };
+
+        let build_report = self.emit(html_template)?;
+        self.outputs.generate_bundle(&self.root.join("dist"), &self.options.plugins)?;
+        self.outputs.flush().await?;
+
+        tracing::info!(
+            "Build Complete. {} modules bundled, {} pruned.",
+            build_report.modules.len(),
+            build_report.pruned.len()
+        );
+        Ok(build_report)
+    }
+
+    // Everything after graph construction. Output is buffered in `outputs`,
+    // so nothing here awaits while the graph is borrowed.
+    fn emit(&mut self, html_template: Option<String>) -> std::io::Result<BuildReport> {
+        let Self { root, options, pool, cache, entry_id: entry_virtual_id, nodes, linked: linked_memo, outputs, started, .. } = self;
+        let root = root.as_path();
+        let dist = root.join("dist");
+        let assets_dir = dist.join("assets");
+        let entry_virtual_id = entry_virtual_id.clone();
+
+        // 4. Export Resolution
+        let mut export_table = ExportTable::new();
+        for node in nodes.values() {
+            export_table.add_module(&node.id, &node.shake, &node.imports);
+        }
+
+        // A module has side effects when its own statements do or when one of its
+        // static dependencies does: dropping it would drop theirs too.
+        let mut effectful: HashSet<String> = HashSet::new();
+        let mut importers: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut pending: Vec<&str> = Vec::new();
+        for node in nodes.values() {
+            for dep in &node.sync_deps {
+                importers.entry(dep.as_str()).or_default().push(node.id.as_str());
+            }
+            if node.has_side_effects() {
+                pending.push(node.id.as_str());
+            }
+        }
+        while let Some(id) = pending.pop() {
+            if !effectful.insert(id.to_string()) {
+                continue;
+            }
+            for &importer in importers.get(id).into_iter().flatten() {
+                // `sideEffects: false` promises the package can be dropped, imports included.
+                if nodes[importer].declared_side_effects != Some(false) && !effectful.contains(importer) {
+                    pending.push(importer);
                 }
             }
         }
-        
-        for (child, sym) in updates {
-            let entry = used_exports.entry(child).or_default();
-            if entry.insert(sym) {
-                changed = true;
+
+        // 5. Liveness Analysis (Mark & Sweep, symbol level)
+        // A module is included when one of its exports is used by live code or when
+        // it has side effects. Re-running a module whenever its used set grows keeps
+        // this going until nothing changes.
+        let mut used_exports: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut included: HashSet<String> = HashSet::new();
+        // Import sources whose target module is pruned, per importer.
+        let mut dropped_imports: HashMap<String, HashSet<String>> = HashMap::new();
+        let mut worklist = VecDeque::new();
+
+        included.insert(entry_virtual_id.clone());
+        worklist.push_back(entry_virtual_id.clone());
+
+        while let Some(mid) = worklist.pop_front() {
+            let Some(node) = nodes.get(&mid) else { continue };
+            let used = used_exports.get(&mid).cloned().unwrap_or_default();
+            let mut marks: Vec<(String, Vec<String>)> = Vec::new();
+            let mut needed_sources = HashSet::new();
+            let mut dropped = HashSet::new();
+
+            for usage in node.shake.dependency_usage(&used) {
+                let Some(target_id) = node.imports.get(&usage.source) else { continue };
+                let target = nodes.get(target_id);
+                let mut names = usage.names;
+                if usage.star {
+                    // Only follow `export *` into the module that actually provides the
+                    // name, so a barrel doesn't pull in every file it re-exports.
+                    names.retain(|n| export_table.star_provider(&mid, n) == Some(target_id.as_str()));
+                }
+                if usage.namespace {
+                    // `import * as ns` may touch any export, including ones behind `export *`.
+                    names.extend(export_table.all_exports(target_id));
+                }
+
+                let has_side_effects = target.is_none() || effectful.contains(target_id);
+                if !names.is_empty() || usage.namespace || has_side_effects {
+                    needed_sources.insert(usage.source.clone());
+                    marks.push((target_id.clone(), names));
+                } else {
+                    dropped.insert(usage.source.clone());
+                }
+            }
+
+            // Dynamic imports and `require` hand the whole namespace to the caller.
+            for info in node.import_info.iter().filter(|i| i.is_dynamic || i.is_require) {
+                if let Some(target_id) = node.imports.get(&info.source) {
+                    marks.push((target_id.clone(), export_table.all_exports(target_id).into_iter().collect()));
+                }
+            }
+            // Worker entries run for their side effects, like the main entry.
+            for target_id in &node.worker_deps {
+                marks.push((target_id.clone(), Vec::new()));
+            }
+
+            dropped.retain(|src| !needed_sources.contains(src));
+            dropped_imports.insert(mid.clone(), dropped);
+
+            for (target_id, names) in marks {
+                let entry_set = used_exports.entry(target_id.clone()).or_default();
+                let mut grew = false;
+                for name in names {
+                    grew |= entry_set.insert(name);
+                }
+                if included.insert(target_id.clone()) || grew {
+                    worklist.push_back(target_id);
+                }
             }
         }
-    }
 
-    // 5. Partitioning / Chunking
-    let mut module_chunk_map: HashMap<String, String> = HashMap::new(); 
-    let mut chunks: Vec<Chunk> = Vec::new();
-    let mut chunk_queue = VecDeque::new();
+        // 6. Partitioning / Chunking
+        let mut module_chunk_map: HashMap<String, String> = HashMap::new(); 
+        let mut chunks: Vec<Chunk> = Vec::new();
+        let mut chunk_queue = VecDeque::new();
     
-    // Entry Chunk
-    chunk_queue.push_back((entry_virtual_id.clone(), "main.js".to_string(), true));
+        // Entry Chunk
+        chunk_queue.push_back((entry_virtual_id.clone(), "main.js".to_string(), true));
     
-    while let Some((root_id, chunk_name, is_entry)) = chunk_queue.pop_front() {
-        let mut chunk_modules = Vec::new();
-        let mut bfs = VecDeque::new();
-        bfs.push_back(root_id);
+        while let Some((root_id, chunk_name, is_entry)) = chunk_queue.pop_front() {
+            let mut chunk_modules = Vec::new();
+            let mut bfs = VecDeque::new();
+            bfs.push_back(root_id);
         
-        while let Some(curr) = bfs.pop_front() {
-            if module_chunk_map.contains_key(&curr) { continue; }
+            while let Some(curr) = bfs.pop_front() {
+                if module_chunk_map.contains_key(&curr) { continue; }
             
-            module_chunk_map.insert(curr.clone(), chunk_name.clone());
-            chunk_modules.push(curr.clone());
+                module_chunk_map.insert(curr.clone(), chunk_name.clone());
+                chunk_modules.push(curr.clone());
             
-            if let Some(node) = nodes.get(&curr) {
-                for dep in &node.sync_deps {
-                    bfs.push_back(dep.clone());
-                }
-                for async_dep in &node.async_deps {
-                    if !module_chunk_map.contains_key(async_dep) {
-                        let name = format!("chunk-{}.js", async_dep.replace("/", "-").trim_start_matches('-'));
-                        chunk_queue.push_back((async_dep.clone(), name, false));
+                if let Some(node) = nodes.get(&curr) {
+                    for dep in node.sync_deps.iter().filter(|d| included.contains(*d)) {
+                        bfs.push_back(dep.clone());
+                    }
+                    for async_dep in &node.async_deps {
+                        if !module_chunk_map.contains_key(async_dep) {
+                            let name = format!("chunk-{}.js", async_dep.replace("/", "-").trim_start_matches('-'));
+                            chunk_queue.push_back((async_dep.clone(), name, false));
+                        }
                     }
                 }
             }
-        }
         
-        if !chunk_modules.is_empty() {
-             chunks.push(Chunk {
-                 name: chunk_name,
-                 modules: chunk_modules,
-                 is_entry
-             });
+            if !chunk_modules.is_empty() {
+                 chunks.push(Chunk {
+                     name: chunk_name,
+                     modules: chunk_modules,
+                     is_entry
+                 });
+            }
         }
-    }
     
-    // 6. Vendor Extraction
-    let mut vendor_modules = Vec::new();
-    for chunk in &mut chunks {
-        let (vendors, app): (Vec<_>, Vec<_>) = chunk.modules.drain(..).partition(|id| {
-            nodes.get(id).map(|n| n.is_vendor).unwrap_or(false)
-        });
-        chunk.modules = app;
-        for v in vendors {
-            if !vendor_modules.contains(&v) {
-                vendor_modules.push(v);
+        // 7. Vendor Extraction
+        let mut vendor_modules = Vec::new();
+        for chunk in &mut chunks {
+            let (vendors, app): (Vec<_>, Vec<_>) = chunk.modules.drain(..).partition(|id| {
+                nodes.get(id).map(|n| n.is_vendor).unwrap_or(false)
+            });
+            chunk.modules = app;
+            for v in vendors {
+                if !vendor_modules.contains(&v) {
+                    vendor_modules.push(v);
+                }
             }
         }
-    }
 
-    // 7. Build Mapping for Runtime
-    let mut nexus_chunk_map = HashMap::new();
-    for chunk in &chunks {
-        if !chunk.is_entry {
-            for mod_id in &chunk.modules {
-                nexus_chunk_map.insert(mod_id.clone(), format!("/assets/{}", chunk.name));
+        // 8. Build Mapping for Runtime
+        let mut nexus_chunk_map = BTreeMap::new();
+        for chunk in &chunks {
+            if !chunk.is_entry {
+                for mod_id in &chunk.modules {
+                    nexus_chunk_map.insert(mod_id.clone(), format!("/assets/{}", chunk.name));
+                }
             }
         }
-    }
     
-    // 8. Emit Bundles (With Tree Shaking)
-    let mut css_bundle = String::new();
-    
-    for node in nodes.values() {
-        if let Some(css) = &node.css {
+        // 9. Emit Bundles (With Tree Shaking)
+        // CSS follows module order within each chunk, so rules cascade the
+        // same way on every build.
+        let mut css_bundle = String::new();
+        let mut css_order: Vec<&str> = Vec::new();
+        let mut seen_css = HashSet::new();
+        for chunk in &chunks {
+            for mid in &chunk.modules {
+                if included.contains(mid) && seen_css.insert(mid.as_str()) {
+                    css_order.push(mid);
+                }
+            }
+        }
+        let mut rest: Vec<&str> = included.iter().map(String::as_str).filter(|id| !seen_css.contains(id)).collect();
+        rest.sort_unstable();
+        css_order.extend(rest);
+        for css in css_order.iter().filter_map(|id| nodes.get(*id)?.css.as_ref()) {
             css_bundle.push_str(css);
             css_bundle.push('\n');
         }
-        if let Some((name, data)) = &node.asset {
-            tokio::fs::write(dist.join(name), data).await?;
+    
+        for node in nodes.values().filter(|n| included.contains(&n.id)) {
+            if let Some((name, data)) = &node.asset {
+                outputs.write(dist.join(name), data.as_slice());
+            }
+            for (name, data) in &node.url_assets {
+                outputs.write(dist.join(name), data.as_slice());
+            }
         }
-    }
 
-    // Helper to process code
-    let fallback_set = HashSet::new();
-    let process_code = |mid: &str| -> String {
-        if let Some(node) = nodes.get(mid) {
-            let used = used_exports.get(mid).unwrap_or(&fallback_set);
-            
-            // 1. Tree Shake
-            let shaken = transform_tree_shake(&node.code, &node.id, used);
-            
-            // 2. Transform CJS
-            let transformed = transform_cjs(&shaken, &node.id, &node.imports);
-            
-            return format!(
-                "__nexus_register__(\"{}\", function(require, module, exports) {{\n// Using: {:?}\n{}\n}});\n",
-                node.id, used, transformed
-            );
+        // 1. Tree Shake + 2. Link, both on the AST parsed during graph construction
+        // (or parsed now, for modules that came from the cache). Output from the
+        // previous build of the session is reused while its key is unchanged.
+        // Keeps (size after shaking, linked code) per included module.
+        let fallback_set = HashSet::new();
+        let previous = std::mem::take(linked_memo);
+        *linked_memo = pool.install(|| {
+            nodes
+                .par_iter_mut()
+                .filter(|(mid, _)| included.contains(*mid))
+                .map(|(mid, node)| {
+                    let used = used_exports.get(mid).unwrap_or(&fallback_set);
+                    let dropped = dropped_imports.get(mid).unwrap_or(&fallback_set);
+                    let key = linked_key(node, used, dropped);
+                    if let Some((_, hit)) = previous.get(mid).filter(|(k, _)| *k == key) {
+                        return (mid.clone(), (key, hit.clone()));
+                    }
+                    if let Some(hit) = cache.as_ref().and_then(|c| c.get_linked(mid, key)) {
+                        return (mid.clone(), (key, hit));
+                    }
+
+                    let is_js = node.css.is_none() && node.asset.is_none();
+                    let output = if is_js {
+                        // Shaking consumes the AST; a later build parses again.
+                        let mut unit = node.unit.take().unwrap_or_else(|| CompilationUnit::parse(&node.id, node.code.clone()));
+                        unit.shake(used, dropped);
+                        let shaken_size = unit.code().len();
+                        unit.link(&node.imports);
+                        (shaken_size, unit.code())
+                    } else {
+                        (node.code.len(), transform_cjs(&node.code, &node.id, &node.imports))
+                    };
+                    if let Some(cache) = &cache {
+                        cache.put_linked(mid, key, &output);
+                    }
+                    (mid.clone(), (key, output))
+                })
+                .collect()
+        });
+        drop(previous);
+        let linked = &*linked_memo;
+
+        // Helper to process code
+        let process_code = |mid: &str| -> String {
+            if let (Some(node), Some((_, (_, transformed)))) = (nodes.get(mid), linked.get(mid)) {
+                return format!(
+                    "__nexus_register__(\"{}\", function(require, module, exports) {{\n{}\n}});\n",
+                    node.id, transformed
+                );
+            }
+            "".to_string()
+        };
+
+        // `.wasm` modules imported for their exports, which each bundle compiles
+        // before running; see `wasm`. `?init` fetches on its own.
+        let esm_wasm: HashSet<&String> = nodes
+            .values()
+            .filter(|n| included.contains(&n.id))
+            .flat_map(|n| {
+                n.import_info
+                    .iter()
+                    .filter(|info| !wasm::is_wasm_init(&info.source))
+                    .filter_map(|info| n.imports.get(&info.source))
+            })
+            .filter(|id| wasm::is_wasm(id))
+            .collect();
+        let preload = |modules: &[String]| -> String {
+            let ids: Vec<&str> = modules.iter().filter(|m| esm_wasm.contains(m)).map(String::as_str).collect();
+            wasm::preload_text(&ids)
+        };
+        let asset_map: BTreeMap<&String, String> = nodes
+            .values()
+            .filter(|n| included.contains(&n.id) && wasm::is_wasm(&n.id))
+            .filter_map(|n| n.asset.as_ref().map(|(name, _)| (&n.id, format!("/{}", name))))
+            .collect();
+        let asset_map_json = serde_json::to_string(&asset_map).unwrap();
+
+        let mut vendor_code = String::new();
+        vendor_code.push_str(NEXUS_RUNTIME_JS);
+        vendor_code.push('\n');
+        let vendor_preload = preload(&vendor_modules);
+        vendor_code.push_str(&vendor_preload);
+        let mut vendor_emitted = Vec::new();
+        for vid in &vendor_modules {
+            let module_code = process_code(vid);
+            vendor_code.push_str(&module_code);
+            vendor_emitted.push((vid.clone(), module_code));
         }
-        "".to_string()
-    };
+        let render = |code: String, name: &str, is_entry: bool, modules: &[String]| {
+            if options.plugins.is_empty() {
+                return Ok(code);
+            }
+            let chunk = ChunkInfo { name: name.to_string(), is_entry, modules: modules.to_vec() };
+            options.plugins.render_chunk(code, &chunk)
+        };
+        let vendor_code = render(vendor_code, "vendor", false, &vendor_modules)?;
 
-    let mut vendor_code = String::new();
-    vendor_code.push_str(NEXUS_RUNTIME_JS);
-    vendor_code.push('\n');
-    for vid in &vendor_modules {
-        vendor_code.push_str(&process_code(vid));
-    }
+        // Workers run in their own global scope, so every worker entry gets a
+        // self-contained bundle: runtime, each module it reaches, bootstrap.
+        let worker_entries: BTreeSet<&String> = nodes
+            .values()
+            .filter(|n| included.contains(&n.id))
+            .flat_map(|n| n.worker_deps.iter())
+            .filter(|id| included.contains(*id))
+            .collect();
+        let mut worker_map = BTreeMap::new();
+        let mut analyzer_chunks = Vec::new();
+        for worker_id in worker_entries {
+            let mut modules = Vec::new();
+            let mut seen = HashSet::new();
+            let mut bfs = VecDeque::from([worker_id.clone()]);
+            while let Some(curr) = bfs.pop_front() {
+                if !seen.insert(curr.clone()) {
+                    continue;
+                }
+                if let Some(node) = nodes.get(&curr) {
+                    bfs.extend(node.sync_deps.iter().chain(&node.async_deps).filter(|d| included.contains(*d)).cloned());
+                }
+                modules.push(curr);
+            }
+
+            let mut code = format!("{}\n", NEXUS_RUNTIME_JS);
+            if !asset_map.is_empty() {
+                code.push_str(&format!("self.__nexus_asset_map__ = {};\n", asset_map_json));
+            }
+            let worker_preload = preload(&modules);
+            code.push_str(&worker_preload);
+            let mut emitted = Vec::new();
+            for mid in &modules {
+                let module_code = process_code(mid);
+                code.push_str(&module_code);
+                emitted.push((mid.clone(), module_code));
+            }
+            code.push('\n');
+            code.push_str(&wasm::entry_text(worker_id, !worker_preload.is_empty()));
 
-    for chunk in chunks {
-        let mut code = String::new();
-        for mid in &chunk.modules {
-            code.push_str(&process_code(mid));
+            let stem = Path::new(worker_id.as_str()).file_stem().unwrap_or_default().to_string_lossy();
+            let code = render(code, &format!("worker-{}", stem), true, &modules)?;
+            let hash = format!("{:016x}", cache::content_hash(code.as_bytes()));
+            let name = format!("worker-{}-{}.js", stem, &hash[..8]);
+            worker_map.insert(worker_id.clone(), format!("/assets/{}", name));
+            outputs.write(assets_dir.join(&name), code.as_bytes());
+            if options.analyze {
+                analyzer_chunks.push(ChunkInput { file: format!("assets/{}", name), is_entry: true, code, modules: emitted });
+            }
         }
+
+        for chunk in &chunks {
+            let mut code = preload(&chunk.modules);
+            let wait = !code.is_empty() || !vendor_preload.is_empty();
+            let mut emitted = Vec::new();
+            for mid in &chunk.modules {
+                let module_code = process_code(mid);
+                code.push_str(&module_code);
+                emitted.push((mid.clone(), module_code));
+            }
         
-        if chunk.is_entry {
-            if !nexus_chunk_map.is_empty() {
-                let map_json = serde_json::to_string(&nexus_chunk_map).unwrap();
-                code.push_str(&format!("\nwindow.__nexus_chunk_map__ = {};\n", map_json));
+            if chunk.is_entry {
+                if !nexus_chunk_map.is_empty() {
+                    let map_json = serde_json::to_string(&nexus_chunk_map).unwrap();
+                    code.push_str(&format!("\nwindow.__nexus_chunk_map__ = {};\n", map_json));
+                }
+                if !worker_map.is_empty() {
+                    let map_json = serde_json::to_string(&worker_map).unwrap();
+                    code.push_str(&format!("\nwindow.__nexus_worker_map__ = {};\n", map_json));
+                }
+                if !asset_map.is_empty() {
+                    code.push_str(&format!("\nwindow.__nexus_asset_map__ = {};\n", asset_map_json));
+                }
+                code.push('\n');
+                code.push_str(&wasm::entry_text(&entry_virtual_id, wait));
             }
-            code.push_str(&format!("\n__nexus_require__(\"{}\");\n", entry_virtual_id));
-        }
+            let code = render(code, chunk.name.trim_end_matches(".js"), chunk.is_entry, &chunk.modules)?;
         
-        tokio::fs::write(assets_dir.join(&chunk.name), code).await?;
-    }
+            outputs.write(assets_dir.join(&chunk.name), code.as_bytes());
+            if options.analyze {
+                analyzer_chunks.push(ChunkInput {
+                    file: format!("assets/{}", chunk.name),
+                    is_entry: chunk.is_entry,
+                    code,
+                    modules: emitted,
+                });
+            }
+        }
     
-    tokio::fs::write(assets_dir.join("vendor.js"), vendor_code).await?;
-    tokio::fs::write(assets_dir.join("style.css"), css_bundle).await?;
+        outputs.write(assets_dir.join("vendor.js"), vendor_code.as_bytes());
+        if options.analyze {
+            analyzer_chunks.push(ChunkInput {
+                file: "assets/vendor.js".to_string(),
+                is_entry: false,
+                code: vendor_code,
+                modules: vendor_emitted,
+            });
+        }
+        outputs.write(assets_dir.join("style.css"), css_bundle);
     
-    // 9. HTML
-     let html_path = root.join("index.html");
-     let tags = r#"
+        // 10. HTML
+         let tags = r#"
     <link rel="stylesheet" href="/assets/style.css">
     <script src="/assets/vendor.js"></script>
     <script src="/assets/main.js"></script>
 "#;
-    if html_path.exists() {
-        let mut html = tokio::fs::read_to_string(&html_path).await?;
-        if html.contains("</body>") {
-            html = html.replace("</body>", &format!("{}</body>", tags));
+        let html = if let Some(mut html) = html_template {
+            if html.contains("</body>") {
+                html = html.replace("</body>", &format!("{}</body>", tags));
+            } else {
+                html.push_str(tags);
+            }
+            html
         } else {
-            html.push_str(tags);
+            format!(r#"<!DOCTYPE html><html><body>{}</body></html>"#, tags)
+        };
+        outputs.write(dist.join("index.html"), options.plugins.transform_index_html(html, "/index.html")?);
+
+        // 11. Report
+        let mut chunk_of: HashMap<&str, &str> = HashMap::new();
+        for chunk in &chunks {
+            for mid in &chunk.modules {
+                chunk_of.insert(mid, &chunk.name);
+            }
+        }
+        for vid in &vendor_modules {
+            chunk_of.insert(vid, "vendor.js");
+        }
+
+        // Live edges only: the target is included and the import wasn't dropped.
+        let mut live_edges: std::collections::BTreeMap<String, Vec<String>> = Default::default();
+        for mid in &included {
+            let Some(node) = nodes.get(mid) else { continue };
+            let dropped = dropped_imports.get(mid).unwrap_or(&fallback_set);
+            let mut targets: Vec<String> = node
+                .imports
+                .iter()
+                .filter(|(source, target)| included.contains(*target) && !dropped.contains(*source))
+                .map(|(_, target)| target.clone())
+                .collect();
+            targets.sort();
+            targets.dedup();
+            live_edges.insert(mid.clone(), targets);
+        }
+        let chains = report::shortest_chains(&entry_virtual_id, &live_edges);
+
+        let mut build_report = BuildReport { entry: entry_virtual_id.clone(), ..BuildReport::default() };
+        for (mid, node) in nodes.iter() {
+            if !included.contains(mid) {
+                build_report.pruned.push(mid.clone());
+                continue;
+            }
+            let used = used_exports.get(mid).unwrap_or(&fallback_set);
+            let mut used_list: Vec<String> = used.iter().cloned().collect();
+            used_list.sort();
+            let removed_exports = export_table
+                .all_exports(mid)
+                .into_iter()
+                .filter(|e| !used.contains(e))
+                .collect();
+            let mut importers: Vec<String> = live_edges
+                .iter()
+                .filter(|(_, targets)| targets.contains(mid))
+                .map(|(importer, _)| importer.clone())
+                .collect();
+            importers.sort();
+
+            build_report.modules.push(ModuleReport {
+                id: mid.clone(),
+                chunk: chunk_of.get(mid.as_str()).unwrap_or(&"").to_string(),
+                size_before: node.code.len(),
+                size_after: linked.get(mid).map(|(_, (size, _))| *size).unwrap_or(0),
+                used_exports: used_list,
+                removed_exports,
+                importers,
+                chain: chains.get(mid).cloned().unwrap_or_default(),
+            });
+        }
+        build_report.modules.sort_by(|a, b| a.id.cmp(&b.id));
+        build_report.pruned.sort();
+
+        if options.report {
+            outputs.write(dist.join("report.json"), build_report.to_json());
+        }
+        if let Some(cache) = &cache {
+            cache.flush();
+        }
+
+        // 12. Bundle analyzer
+        if options.analyze {
+            let analysis = BundleAnalysis::new(analyzer_chunks);
+            let stats = analysis.stats_json(&build_report, started.elapsed().as_millis());
+            outputs.write(dist.join("stats.json"), serde_json::to_string_pretty(&stats).unwrap_or_default());
+            outputs.write(dist.join("treemap.html"), analysis.treemap_html());
+        }
+
+        Ok(build_report)
+    }
+}
+
+// Paths of import variants end in their query, e.g. `/app/src/logo.svg?raw`.
+fn file_path(path: &Path) -> PathBuf {
+    match path.to_str() {
+        Some(s) => PathBuf::from(compiler::split_query(s).0),
+        None => path.to_path_buf(),
+    }
+}
+
+pub(crate) fn normalize_id(root: &Path, p: &Path) -> String {
+    let s = p.to_string_lossy().to_string();
+    let rel = s.replace(root.to_string_lossy().as_ref(), "").replace("\\", "/");
+    if rel.starts_with('/') { rel } else { format!("/{}", rel) }
+}
+
+// A module that has been read, compiled and parsed but whose imports are not resolved yet.
+struct LoadedModule {
+    id: String,
+    path: PathBuf,
+    compiled: compiler::CompileResult,
+    import_meta: ImportMetaOutput,
+    import_info: Vec<ImportInfo>,
+    shake: ShakeInfo,
+    unit: Option<CompilationUnit>,
+    declared_side_effects: Option<bool>,
+    content_hash: u64,
+    // Still-valid resolutions restored from the cache.
+    cached_resolutions: Option<Vec<(String, PathBuf)>>,
+    // Whether this module should be written back to the cache once resolved.
+    store_in_cache: bool,
+}
+
+// What loading modules needs, shared by the graph's worker threads.
+#[derive(Clone, Copy)]
+struct GraphContext<'a> {
+    root: &'a Path,
+    resolver: &'a NexusResolver,
+    plugins: &'a PluginContainer,
+    loaders: &'a LoaderRegistry,
+    cache: Option<&'a TransformCache>,
+    defines: &'a Defines,
+    // `cache::lockfile_hash`, for validating cached resolutions.
+    lock_hash: u64,
+}
+
+fn load_module(cx: GraphContext, path: PathBuf) -> std::io::Result<LoadedModule> {
+    let GraphContext { root, resolver, plugins, loaders, cache, defines, lock_hash } = cx;
+    let id = normalize_id(root, &path);
+    let file = file_path(&path);
+    let (file_id, query) = compiler::split_query(&id);
+    let bytes = plugins.source(&id, &file)?;
+    let loader = loaders.loader(&id);
+    let content_hash = cache::content_hash(&bytes);
+    let declared_side_effects = resolver.has_side_effects(&file);
+
+    let cache = cache.filter(|_| loader.is_cacheable() && query.is_none());
+    if let Some(cached) = cache.and_then(|c| c.get_module(&id, content_hash)) {
+        let cached_resolutions = cached.resolutions(&file, lock_hash).map(|r| r.to_vec());
+        return Ok(LoadedModule {
+            shake: cached.shake.unwrap_or_else(|| ShakeInfo::opaque(cached.css.is_some())),
+            import_info: cached.imports,
+            compiled: compiler::CompileResult { code: cached.code, sourcemap: cached.sourcemap, css: cached.css, asset: None },
+            import_meta: ImportMetaOutput::default(),
+            unit: None,
+            store_in_cache: cached_resolutions.is_none(),
+            cached_resolutions,
+            declared_side_effects,
+            content_hash,
+            id,
+            path,
+        });
+    }
+
+    // Compile. JS keeps its AST for analysis, shaking and linking.
+    let mut import_meta = ImportMetaOutput::default();
+    let compiled = if query.is_some() { None } else { loaders.compile(loader, &bytes, &id, true)? };
+    let (mut compiled, mut unit) = match compiled {
+        // `?raw`, `?url` and `?inline` compile the same whatever the file.
+        _ if query.is_some() => {
+            let compiled = compiler::compile_variant(&bytes, file_id, query.unwrap(), true);
+            let unit = CompilationUnit::parse(&id, compiled.code.clone());
+            (compiled, Some(unit))
+        },
+        // JSON, SVG and wasm modules import and export like any module.
+        Some(compiled) if loader.is_analyzed() => {
+            let unit = CompilationUnit::parse(&id, compiled.code.clone());
+            (compiled, Some(unit))
+        },
+        Some(compiled) => (compiled, None),
+        None => {
+            let source = String::from_utf8_lossy(&bytes).into_owned();
+            let mut unit = CompilationUnit::parse_as(&id, source, loader.source_type(&id));
+            unit.define(defines);
+            import_meta = unit.expand_import_meta(root, true);
+            (unit.compile_result(), Some(unit))
         }
-         tokio::fs::write(dist.join("index.html"), html).await?;
-    } else {
-        let html = format!(r#"<!DOCTYPE html><html><body>{}</body></html>"#, tags);
-        tokio::fs::write(dist.join("index.html"), html).await?;
+    };
+
+    if plugins.transform(&id, &mut compiled)? && unit.is_some() {
+        unit = Some(CompilationUnit::parse(&id, compiled.code.clone()));
     }
 
-    tracing::info!("Build Complete.");
+    // Extracted CSS has no JS statements but must never be pruned.
+    let shake = unit.as_ref().map(|u| u.shake_info()).unwrap_or_else(|| ShakeInfo::opaque(compiled.css.is_some()));
+    let import_info = unit.as_ref().map(|u| u.imports()).unwrap_or_default();
+
+    Ok(LoadedModule {
+        declared_side_effects,
+        id,
+        path,
+        compiled,
+        import_info,
+        shake,
+        unit,
+        content_hash,
+        cached_resolutions: None,
+        store_in_cache: cache.is_some() && !import_meta.depends_on_files(),
+        import_meta,
+    })
+}
+
+// Cache key for a module's shaken and linked output.
+fn linked_key(node: &BuildNode, used: &HashSet<String>, dropped: &HashSet<String>) -> u64 {
+    let mut used: Vec<&String> = used.iter().collect();
+    let mut dropped: Vec<&String> = dropped.iter().collect();
+    let mut imports: Vec<(&String, &String)> = node.imports.iter().collect();
+    used.sort();
+    dropped.sort();
+    imports.sort();
+    let key = format!("{:016x}|{:?}|{:?}|{:?}", node.content_hash, used, dropped, imports);
+    cache::content_hash(key.as_bytes())
+}
+
+/// Discovers the module graph breadth-first. Each level is read, compiled and
+/// parsed in parallel, then its imports are resolved in one batch grouped by
+/// importing directory, so a specifier shared by sibling files resolves once.
+/// Levels are processed in sorted order, which keeps the graph independent of
+/// thread scheduling.
+///
+/// Starts from `seeds`, which replace their nodes if already present in
+/// `nodes`; modules already in the graph aren't loaded again.
+fn build_graph(cx: GraphContext, nodes: &mut HashMap<String, BuildNode>, mut seeds: Vec<PathBuf>) -> std::io::Result<()> {
+    let GraphContext { root, resolver, plugins, cache, lock_hash, .. } = cx;
+    let mut visited_paths: HashSet<PathBuf> = nodes.values().map(|n| n.fs_path.clone()).collect();
+    visited_paths.extend(seeds.iter().cloned());
+    seeds.sort();
+    seeds.dedup();
+    let mut frontier = seeds;
+
+    while !frontier.is_empty() {
+        let loaded = frontier
+            .par_drain(..)
+            .map(|path| load_module(cx, path))
+            .collect::<std::io::Result<Vec<_>>>()?;
+
+        // Plugins see the importing file, so they resolve per module.
+        let requests: Vec<(PathBuf, String)> = loaded
+            .iter()
+            .filter(|m| m.cached_resolutions.is_none() && !plugins.is_empty())
+            .flat_map(|m| m.import_info.iter().map(|info| (m.path.clone(), info.source.clone())))
+            .collect();
+        let plugin_resolved: HashMap<(PathBuf, String), PathBuf> = requests
+            .into_par_iter()
+            .filter_map(|(importer, specifier)| match plugins.resolve_id(&specifier, &importer) {
+                Ok(Some(target)) => Some(Ok(((importer, specifier), target))),
+                Ok(None) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .collect::<std::io::Result<_>>()?;
+
+        let mut by_dir: BTreeMap<PathBuf, BTreeSet<String>> = BTreeMap::new();
+        for module in loaded.iter().filter(|m| m.cached_resolutions.is_none()) {
+            let dir = module.path.parent().unwrap_or(root).to_path_buf();
+            let specifiers = by_dir.entry(dir).or_default();
+            specifiers.extend(
+                module
+                    .import_info
+                    .iter()
+                    .filter(|info| !plugin_resolved.contains_key(&(module.path.clone(), info.source.clone())))
+                    .map(|info| info.source.clone()),
+            );
+        }
+        let resolved: HashMap<(PathBuf, String), PathBuf> = by_dir
+            .into_par_iter()
+            .flat_map_iter(|(dir, specifiers)| {
+                specifiers.into_iter().filter_map(move |specifier| {
+                    let target = resolver.resolve(&dir, &specifier).ok()?;
+                    Some(((dir.clone(), specifier), target))
+                })
+            })
+            .collect();
+
+        let mut next = Vec::new();
+        for module in loaded {
+            let dir = module.path.parent().unwrap_or(root).to_path_buf();
+            let mut sync_deps = Vec::new();
+            let mut async_deps = Vec::new();
+            let mut worker_deps = Vec::new();
+            let mut imports_map = HashMap::new();
+            let own: HashMap<&str, &PathBuf> = module
+                .cached_resolutions
+                .iter()
+                .flatten()
+                .map(|(specifier, target)| (specifier.as_str(), target))
+                .collect();
+            let mut module_resolutions = Vec::new();
+
+            for info in &module.import_info {
+                let target = match &module.cached_resolutions {
+                    Some(_) => own.get(info.source.as_str()).copied(),
+                    None => plugin_resolved
+                        .get(&(module.path.clone(), info.source.clone()))
+                        .or_else(|| resolved.get(&(dir.clone(), info.source.clone()))),
+                };
+                let Some(target) = target else { continue };
+                module_resolutions.push((info.source.clone(), target.clone()));
+                let target = &compiler::with_query(target, &info.source);
+                let resolved_vid = normalize_id(root, target);
+                imports_map.insert(info.source.clone(), resolved_vid.clone());
+
+                if info.is_worker() {
+                    worker_deps.push(resolved_vid);
+                } else if info.is_dynamic {
+                    async_deps.push(resolved_vid);
+                } else {
+                    // is_star (export * or import *) is treated as sync dependency usually
+                    sync_deps.push(resolved_vid);
+                }
+
+                if visited_paths.insert(target.clone()) {
+                    next.push(target.clone());
+                }
+            }
+
+            if !plugins.is_empty() {
+                plugins.module_parsed(&ModuleInfo {
+                    id: module.id.clone(),
+                    imported_ids: sync_deps.clone(),
+                    dynamically_imported_ids: async_deps.clone(),
+                });
+            }
+
+            if let (Some(cache), true) = (cache, module.store_in_cache) {
+                let mut entry = CachedModule {
+                    code: module.compiled.code.clone(),
+                    sourcemap: module.compiled.sourcemap.clone(),
+                    css: module.compiled.css.clone(),
+                    dependencies: Vec::new(),
+                    imports: module.import_info.clone(),
+                    shake: Some(module.shake.clone()),
+                    resolved: module_resolutions,
+                    resolved_in: 0,
+                };
+                entry.resolved_in = entry.resolution_fingerprint(&module.path, lock_hash);
+                cache.put_module(&module.id, module.content_hash, &entry);
+            }
+
+            nodes.insert(module.id.clone(), BuildNode {
+                is_vendor: module.path.to_string_lossy().contains("node_modules"),
+                id: module.id,
+                fs_path: module.path,
+                code: module.compiled.code,
+                imports: imports_map,
+                sync_deps,
+                async_deps,
+                worker_deps,
+                css: module.compiled.css,
+                asset: module.compiled.asset,
+                url_assets: module.import_meta.assets,
+                globs: module.import_meta.globs,
+                import_info: module.import_info,
+                shake: module.shake,
+                unit: module.unit,
+                content_hash: module.content_hash,
+                declared_side_effects: module.declared_side_effects,
+            });
+        }
+        next.sort();
+        frontier = next;
+    }
     Ok(())
 }
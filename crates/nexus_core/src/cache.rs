@@ -0,0 +1,170 @@
+use nexus_cache::PersistentCache;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use crate::parser::ImportInfo;
+use crate::shaker::ShakeInfo;
+
+// Persistent transform cache (`.nexus/cache` under the project root).
+//
+// Entries are keyed by module id, a hash of the file contents, the compiler
+// version and a hash of the configuration, so a stale entry can never be
+// returned for changed input; it simply stops being looked up. Writing a
+// module's entry removes the one it replaces, so the store holds one entry
+// per module and configuration rather than one per edit.
+//
+// Resolutions depend on more than the importing file: they are only reused
+// while the lockfile and the directories they were looked up in are as they
+// were, so `foo.ts` appearing next to `foo.js` resolves again.
+
+pub const CACHE_DIR: &str = ".nexus/cache";
+
+// Bump when the layout of cached entries or the transforms change.
+const CACHE_FORMAT: u32 = 6;
+
+pub(crate) const LOCKFILES: &[&str] = &["package-lock.json", "pnpm-lock.yaml", "yarn.lock", "bun.lockb"];
+
+/// Everything produced from one module's source that doesn't depend on the
+/// rest of the graph.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CachedModule {
+    pub code: String,
+    pub sourcemap: Option<String>,
+    pub css: Option<String>,
+    /// Import specifiers and whether each is a dynamic `import()` (dev server).
+    pub dependencies: Vec<(String, bool)>,
+    pub imports: Vec<ImportInfo>,
+    pub shake: Option<ShakeInfo>,
+    /// Resolution result per import specifier; unresolvable ones are left out.
+    pub resolved: Vec<(String, PathBuf)>,
+    /// `resolution_fingerprint` when `resolved` was recorded.
+    pub resolved_in: u64,
+}
+
+impl CachedModule {
+    /// Cached resolutions, or `None` if one of the targets has disappeared or
+    /// the directories they were looked up in changed since.
+    pub fn resolutions(&self, importer: &Path, lock_hash: u64) -> Option<&[(String, PathBuf)]> {
+        let valid = self.resolved.iter().all(|(_, p)| p.exists())
+            && self.resolved_in == self.resolution_fingerprint(importer, lock_hash);
+        valid.then_some(self.resolved.as_slice())
+    }
+
+    /// Hash of the listings of every directory resolving this module's imports
+    /// looked into, and of the lockfile for packages.
+    pub fn resolution_fingerprint(&self, importer: &Path, lock_hash: u64) -> u64 {
+        let dir = importer.parent().unwrap_or(importer);
+        let mut dirs = BTreeSet::from([dir.to_path_buf()]);
+        for info in self.imports.iter().filter(|i| i.source.starts_with('.')) {
+            dirs.extend(dir.join(&info.source).parent().map(Path::to_path_buf));
+        }
+        dirs.extend(self.resolved.iter().filter_map(|(_, target)| target.parent().map(Path::to_path_buf)));
+
+        let mut listing = format!("{:016x}", lock_hash);
+        for dir in dirs {
+            let mut names: Vec<_> = std::fs::read_dir(&dir)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|entry| entry.file_name())
+                .collect();
+            names.sort();
+            listing.push_str(&format!("\n{}:{:?}", dir.display(), names));
+        }
+        content_hash(listing.as_bytes())
+    }
+}
+
+pub struct TransformCache {
+    store: PersistentCache,
+    config_hash: u64,
+}
+
+pub fn content_hash(bytes: &[u8]) -> u64 {
+    seahash::hash(bytes)
+}
+
+/// Hash of the project's lockfiles; changes whenever `node_modules` would.
+pub fn lockfile_hash(root: &Path) -> u64 {
+    let mut bytes = Vec::new();
+    for name in LOCKFILES {
+        if let Ok(content) = std::fs::read(root.join(name)) {
+            bytes.extend_from_slice(name.as_bytes());
+            bytes.extend_from_slice(&content);
+        }
+    }
+    content_hash(&bytes)
+}
+
+impl TransformCache {
+    /// Opens the cache for `root`. `config` describes everything besides the
+    /// source that affects output (e.g. dev vs build). Returns `None` and logs
+    /// when the store can't be opened, e.g. while another process holds it.
+    pub fn open(root: &Path, config: &str) -> Option<Self> {
+        let dir = root.join(CACHE_DIR);
+        match PersistentCache::open(&dir.to_string_lossy()) {
+            Ok(store) => Some(Self { store, config_hash: content_hash(config.as_bytes()) }),
+            Err(e) => {
+                tracing::warn!("Transform cache disabled, could not open {}: {}", dir.display(), e);
+                None
+            }
+        }
+    }
+
+    fn key(&self, kind: &str, id: &str, hash: u64) -> String {
+        format!("{}:{:016x}", self.slot(kind, id), hash)
+    }
+
+    // Everything in an entry's key but the hash. Holds the key of the entry
+    // last written for it, under `latest:`.
+    fn slot(&self, kind: &str, id: &str) -> String {
+        format!("{}:{}-{}:{:016x}:{}", kind, env!("CARGO_PKG_VERSION"), CACHE_FORMAT, self.config_hash, id)
+    }
+
+    fn get<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let bytes = self.store.get(key).ok()??;
+        serde_json::from_slice(&bytes).ok()
+    }
+
+    // Stores `value` and drops the entry it replaces, i.e. the one for the
+    // same module written from different content.
+    fn put<T: Serialize>(&self, kind: &str, id: &str, hash: u64, value: &T) {
+        let key = self.key(kind, id, hash);
+        let latest = format!("latest:{}", self.slot(kind, id));
+        let Ok(bytes) = serde_json::to_vec(value) else { return };
+        let result = self.store.put(&key, &bytes).and_then(|_| {
+            let previous = self.store.get(&latest)?;
+            if let Some(previous) = previous.filter(|previous| previous.as_slice() != key.as_bytes()) {
+                self.store.remove(&String::from_utf8_lossy(&previous))?;
+            }
+            self.store.put(&latest, key.as_bytes())
+        });
+        if let Err(e) = result {
+            tracing::warn!("Failed to write cache entry {}: {}", key, e);
+        }
+    }
+
+    pub fn get_module(&self, id: &str, content_hash: u64) -> Option<CachedModule> {
+        self.get(&self.key("module", id, content_hash))
+    }
+
+    pub fn put_module(&self, id: &str, content_hash: u64, module: &CachedModule) {
+        self.put("module", id, content_hash, module);
+    }
+
+    /// Shaken and linked output of a module; `hash` must cover the module's
+    /// content and everything shaking and linking depend on.
+    pub fn get_linked(&self, id: &str, hash: u64) -> Option<(usize, String)> {
+        self.get(&self.key("linked", id, hash))
+    }
+
+    pub fn put_linked(&self, id: &str, hash: u64, linked: &(usize, String)) {
+        self.put("linked", id, hash, linked);
+    }
+
+    pub fn flush(&self) {
+        if let Err(e) = self.store.flush() {
+            tracing::warn!("Failed to flush transform cache: {}", e);
+        }
+    }
+}
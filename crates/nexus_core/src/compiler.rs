@@ -1,8 +1,4 @@
-use oxc_allocator::Allocator;
-use oxc_parser::Parser;
-use oxc_span::SourceType;
-use oxc_codegen::Codegen;
-use std::path::Path;
+use crate::unit::CompilationUnit;
 
 pub struct CompileResult {
     pub code: String,
@@ -14,6 +10,95 @@ pub struct CompileResult {
 
 use lightningcss::stylesheet::{StyleSheet, ParserOptions, PrinterOptions};
 use base64::Engine;
+use std::path::{Path, PathBuf};
+
+// Import query suffixes that choose how a file is imported, whatever its type:
+// `?raw` its text, `?url` the URL of an emitted copy, `?inline` a data URI.
+// SVGs also have `?react`, a component; see `svg`.
+// The query stays part of the module id, so `./logo.svg` and `./logo.svg?raw`
+// are separate modules of the same file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetQuery {
+    Raw,
+    Url,
+    Inline,
+    React,
+}
+
+impl AssetQuery {
+    pub const ALL: [AssetQuery; 4] = [AssetQuery::Raw, AssetQuery::Url, AssetQuery::Inline, AssetQuery::React];
+
+    pub fn of(specifier: &str) -> Option<Self> {
+        let (_, query) = specifier.rsplit_once('?')?;
+        Self::ALL.into_iter().find(|q| q.suffix()[1..] == *query)
+    }
+
+    pub fn suffix(self) -> &'static str {
+        match self {
+            AssetQuery::Raw => "?raw",
+            AssetQuery::Url => "?url",
+            AssetQuery::Inline => "?inline",
+            AssetQuery::React => "?react",
+        }
+    }
+}
+
+/// File part and variant of a module id or path: `/src/a.svg?raw` ->
+/// (`/src/a.svg`, `Raw`). Other queries are left in place.
+pub fn split_query(id: &str) -> (&str, Option<AssetQuery>) {
+    match (id.rsplit_once('?'), AssetQuery::of(id)) {
+        (Some((file, _)), Some(query)) => (file, Some(query)),
+        _ => (id, None),
+    }
+}
+
+/// The resolved `path` of `specifier`, keeping its variant query.
+pub fn with_query(path: &Path, specifier: &str) -> PathBuf {
+    match specifier.rsplit_once('?').filter(|_| AssetQuery::of(specifier).is_some()) {
+        Some((_, query)) => {
+            let mut path = path.as_os_str().to_owned();
+            path.push("?");
+            path.push(query);
+            PathBuf::from(path)
+        }
+        None => path.to_path_buf(),
+    }
+}
+
+/// Content-hashed name under `assets/` for a copy of the file `id`.
+pub fn hashed_asset_name(id: &str, bytes: &[u8]) -> String {
+    let path = Path::new(id);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let hash = format!("{:016x}", crate::cache::content_hash(bytes));
+    match path.extension() {
+        Some(ext) => format!("assets/{}-{}.{}", stem, &hash[..8], ext.to_string_lossy()),
+        None => format!("assets/{}-{}", stem, &hash[..8]),
+    }
+}
+
+/// `bytes` of the file `id` (without its query) imported as `query`.
+pub fn compile_variant(bytes: &[u8], id: &str, query: AssetQuery, is_prod: bool) -> CompileResult {
+    let mut asset = None;
+    let value = match query {
+        AssetQuery::React => return crate::svg::compile_react_variant(bytes, id, is_prod),
+        AssetQuery::Raw => String::from_utf8_lossy(bytes).into_owned(),
+        AssetQuery::Inline => data_uri(bytes, id),
+        AssetQuery::Url if is_prod => {
+            let name = hashed_asset_name(id, bytes);
+            let url = format!("/{}", name);
+            asset = Some((name, bytes.to_vec()));
+            url
+        }
+        AssetQuery::Url => format!("{}?raw", id),
+    };
+    let code = format!("export default {};", serde_json::to_string(&value).unwrap());
+    CompileResult { code, sourcemap: None, css: None, asset }
+}
+
+fn data_uri(bytes: &[u8], filename: &str) -> String {
+    let mime = mime_guess::from_path(filename).first_or_octet_stream();
+    format!("data:{};base64,{}", mime, base64::engine::general_purpose::STANDARD.encode(bytes))
+}
 
 pub fn compile_asset(bytes: &[u8], filename: &str, is_prod: bool) -> CompileResult {
     // 1. JSON
@@ -30,9 +115,7 @@ pub fn compile_asset(bytes: &[u8], filename: &str, is_prod: bool) -> CompileResu
     // 2. Binary / Image
     // Limit: 8KB
     if bytes.len() < 8 * 1024 {
-        let mime = mime_guess::from_path(filename).first_or_octet_stream();
-        let b64 = base64::engine::general_purpose::STANDARD.encode(bytes);
-        let code = format!("export default \"data:{};base64,{}\"", mime, b64);
+        let code = format!("export default \"{}\"", data_uri(bytes, filename));
         return CompileResult { code, sourcemap: None, css: None, asset: None };
     }
     
@@ -44,12 +127,12 @@ pub fn compile_asset(bytes: &[u8], filename: &str, is_prod: bool) -> CompileResu
         // URL for runtime (absolute)
         let code = format!("export default \"/{}\"", out_path);
         
-        return CompileResult {
+        CompileResult {
             code,
             sourcemap: None,
             css: None,
             asset: Some((out_path, bytes.to_vec())),
-        };
+        }
     } else {
         // Dev: Serve Raw
         // We assume filename is a valid URL path (virtual path used by server)
@@ -125,29 +208,6 @@ pub fn compile_css(source: &str, _filename: &str, is_prod: bool) -> CompileResul
 }
 
 pub fn compile(source: &str, filename: &str, _is_prod: bool) -> CompileResult {
-    let allocator = Allocator::default();
-    let source_type = SourceType::from_path(Path::new(filename)).unwrap_or_default();
-    
-    // 1. Parse
-    let ret = Parser::new(&allocator, source, source_type).parse();
-    
-    if !ret.errors.is_empty() {
-         tracing::warn!("Parse errors in {}: {:?}", filename, ret.errors);
-    }
-    
-    let program = ret.program;
-
-    // 2. Transform (TS + JSX) - Disabled for now
-    // oxc v0.54 requires different transformer API
-    // For MVP, we skip transformation
-    
-    // 3. Codegen
-    let ret = Codegen::new().build(&program);
-
-    CompileResult {
-        code: ret.code,
-        sourcemap: ret.map.map(|sm| sm.to_json_string()),
-        css: None,
-        asset: None,
-    }
+    // Types are stripped on parse; JSX is printed as written.
+    CompilationUnit::parse(filename, source.to_string()).compile_result()
 }
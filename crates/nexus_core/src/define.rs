@@ -0,0 +1,315 @@
+use oxc_allocator::{Allocator, Vec as ArenaVec};
+use oxc_ast::ast::*;
+use oxc_ast::AstBuilder;
+use oxc_ast_visit::{walk, walk_mut, Visit, VisitMut};
+use oxc_span::SPAN;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::Path;
+use crate::cache;
+use crate::unit::{parse_expression, parse_statements};
+
+// Compile-time constants.
+//
+// Expressions like `process.env.NODE_ENV` or `import.meta.env.NEXUS_API_URL`
+// are replaced with literal code, then conditions that became constant are
+// folded and the branches they rule out dropped, so development-only code and
+// the imports inside it never reach a production bundle.
+//
+// `import.meta.env` comes from `.env` files. Only variables with a public
+// prefix are exposed; anything else may be a secret meant for the server.
+
+pub const ENV_PREFIXES: &[&str] = &["NEXUS_", "VITE_"];
+
+/// Dotted expression -> replacement code.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Defines {
+    entries: BTreeMap<String, String>,
+}
+
+impl Defines {
+    /// Defines for `mode` with `import.meta.env` read from `root`'s env files.
+    pub fn load(root: &Path, mode: &str, user: &BTreeMap<String, String>) -> Self {
+        Self::new(mode, &load_env(root, mode), user)
+    }
+
+    /// Built-in defines for `mode` and `env`, then `user` defines, which win.
+    pub fn new(mode: &str, env: &BTreeMap<String, String>, user: &BTreeMap<String, String>) -> Self {
+        let prod = mode == "production";
+        let mut meta_env: BTreeMap<String, Value> =
+            env.iter().map(|(k, v)| (k.clone(), Value::String(v.clone()))).collect();
+        meta_env.insert("MODE".to_string(), mode.into());
+        meta_env.insert("BASE_URL".to_string(), "/".into());
+        meta_env.insert("DEV".to_string(), (!prod).into());
+        meta_env.insert("PROD".to_string(), prod.into());
+
+        let mut entries = BTreeMap::new();
+        let node_env = if prod { "production" } else { "development" };
+        entries.insert("process.env.NODE_ENV".to_string(), Value::from(node_env).to_string());
+        for (key, value) in &meta_env {
+            entries.insert(format!("import.meta.env.{}", key), value.to_string());
+        }
+        // Unknown keys read as `undefined` off the whole object.
+        entries.insert("import.meta.env".to_string(), Value::from_iter(meta_env).to_string());
+        entries.extend(user.iter().map(|(k, v)| (k.clone(), v.clone())));
+        Self { entries }
+    }
+
+    pub fn get(&self, expr: &str) -> Option<&str> {
+        self.entries.get(expr).map(String::as_str)
+    }
+
+    /// Changes whenever any replacement does; part of transform cache keys.
+    pub fn hash(&self) -> u64 {
+        let mut bytes = Vec::new();
+        for (key, value) in &self.entries {
+            bytes.extend_from_slice(key.as_bytes());
+            bytes.push(0);
+            bytes.extend_from_slice(value.as_bytes());
+            bytes.push(0);
+        }
+        cache::content_hash(&bytes)
+    }
+}
+
+/// Public variables from `.env`, `.env.local`, `.env.[mode]` and
+/// `.env.[mode].local`, later files winning. Variables already set in the
+/// process environment win over all of them.
+pub fn load_env(root: &Path, mode: &str) -> BTreeMap<String, String> {
+    let mut vars = BTreeMap::new();
+    let files = [".env".to_string(), ".env.local".to_string(), format!(".env.{}", mode), format!(".env.{}.local", mode)];
+    for name in files {
+        if let Ok(text) = std::fs::read_to_string(root.join(name)) {
+            vars.extend(parse_env(&text));
+        }
+    }
+    vars.extend(std::env::vars());
+    vars.retain(|key, _| ENV_PREFIXES.iter().any(|prefix| key.starts_with(prefix)));
+    vars
+}
+
+// `KEY=value` lines; `#` comments, `export ` prefixes and quotes allowed.
+fn parse_env(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            if line.starts_with('#') {
+                return None;
+            }
+            let line = line.strip_prefix("export ").unwrap_or(line);
+            let (key, value) = line.split_once('=')?;
+            let value = value.trim();
+            let value = if let Some(v) = value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+                v.replace("\\n", "\n")
+            } else if let Some(v) = value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')) {
+                v.to_string()
+            } else {
+                value.split(" #").next().unwrap_or_default().trim().to_string()
+            };
+            Some((key.trim().to_string(), value))
+        })
+        .collect()
+}
+
+/// Applies `defines` to `program`, then removes the dead branches.
+pub(crate) fn define_program<'a>(allocator: &'a Allocator, program: &mut Program<'a>, defines: &Defines) {
+    let ast = AstBuilder::new(allocator);
+    let sloppy = program.source_type.is_script();
+    Replacer { allocator, ast, defines, sloppy }.visit_program(program);
+}
+
+struct Replacer<'a, 'b> {
+    allocator: &'a Allocator,
+    ast: AstBuilder<'a>,
+    defines: &'b Defines,
+    // Function declarations in blocks are hoisted as `var`s (Annex B).
+    sloppy: bool,
+}
+
+impl<'a> VisitMut<'a> for Replacer<'a, '_> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        let replacement = expression_path(expr).and_then(|path| self.defines.get(&path));
+        if let Some(replacement) = replacement.and_then(|code| parse_expression(self.allocator, code)) {
+            *expr = replacement;
+            return;
+        }
+        walk_mut::walk_expression(self, expr);
+        fold_expression(self.ast, expr);
+    }
+
+    fn visit_statement(&mut self, stmt: &mut Statement<'a>) {
+        walk_mut::walk_statement(self, stmt);
+        let Statement::IfStatement(if_stmt) = stmt else { return };
+        let Some(truthy) = constant(&if_stmt.test).map(Constant::truthy) else { return };
+        let IfStatement { consequent, alternate, .. } = &mut **if_stmt;
+        let (live, dead) = if truthy {
+            (Some(consequent), alternate.as_ref())
+        } else {
+            (alternate.as_mut(), Some(&*consequent))
+        };
+        // What the dead branch declares for the whole function stays declared,
+        // as `var x;`, in case code outside the branch refers to it.
+        let mut hoisted = HoistedNames { names: Vec::new(), functions: self.sloppy };
+        if let Some(dead) = dead {
+            hoisted.visit_statement(dead);
+        }
+        let mut live = match live {
+            Some(branch) => self.ast.move_statement(branch),
+            None => self.ast.statement_empty(SPAN),
+        };
+        if !hoisted.names.is_empty() {
+            let mut body = parse_statements(self.allocator, &format!("var {};", hoisted.names.join(", ")));
+            match &mut live {
+                Statement::BlockStatement(block) => {
+                    body.extend(block.body.drain(..));
+                    block.body = body;
+                }
+                Statement::EmptyStatement(_) => live = body.pop().unwrap(),
+                _ => {
+                    body.push(live);
+                    live = self.ast.statement_block(SPAN, body);
+                }
+            }
+        }
+        *stmt = live;
+    }
+
+    fn visit_statements(&mut self, stmts: &mut ArenaVec<'a, Statement<'a>>) {
+        walk_mut::walk_statements(self, stmts);
+        stmts.retain(|stmt| !matches!(stmt, Statement::EmptyStatement(_)));
+    }
+}
+
+// Names a statement declares for its enclosing function: `var`s, and in
+// sloppy code function declarations, outside nested functions and classes.
+struct HoistedNames {
+    names: Vec<String>,
+    functions: bool,
+}
+
+impl<'a> Visit<'a> for HoistedNames {
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        match stmt {
+            Statement::FunctionDeclaration(f) => {
+                if self.functions {
+                    self.names.extend(f.id.as_ref().map(|id| id.name.to_string()));
+                }
+            }
+            Statement::ClassDeclaration(_) => {}
+            _ => walk::walk_statement(self, stmt),
+        }
+    }
+
+    fn visit_variable_declaration(&mut self, decl: &VariableDeclaration<'a>) {
+        if decl.kind == VariableDeclarationKind::Var {
+            for declarator in &decl.declarations {
+                self.names.extend(declarator.id.get_binding_identifiers().iter().map(|id| id.name.to_string()));
+            }
+        }
+    }
+
+    // Anything declared in an expression is inside a function of its own.
+    fn visit_expression(&mut self, _expr: &Expression<'a>) {}
+}
+
+// `import.meta.env.MODE`, `process.env["NODE_ENV"]`, `__APP_VERSION__`.
+fn expression_path(expr: &Expression) -> Option<String> {
+    match expr {
+        Expression::Identifier(ident) => Some(ident.name.to_string()),
+        Expression::MetaProperty(meta) => Some(format!("{}.{}", meta.meta.name, meta.property.name)),
+        Expression::StaticMemberExpression(member) => {
+            Some(format!("{}.{}", expression_path(&member.object)?, member.property.name))
+        }
+        Expression::ComputedMemberExpression(member) => match &member.expression {
+            Expression::StringLiteral(key) => Some(format!("{}.{}", expression_path(&member.object)?, key.value)),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Constant<'s> {
+    Bool(bool),
+    Number(f64),
+    Str(&'s str),
+    Null,
+    Undefined,
+}
+
+impl Constant<'_> {
+    fn truthy(self) -> bool {
+        match self {
+            Constant::Bool(b) => b,
+            Constant::Number(n) => n != 0.0 && !n.is_nan(),
+            Constant::Str(s) => !s.is_empty(),
+            Constant::Null | Constant::Undefined => false,
+        }
+    }
+
+    fn is_nullish(self) -> bool {
+        matches!(self, Constant::Null | Constant::Undefined)
+    }
+
+    // `==`, where it doesn't need type coercion.
+    fn loose_equals(self, other: Self) -> Option<bool> {
+        match (self.is_nullish(), other.is_nullish()) {
+            (true, true) => Some(true),
+            (true, false) | (false, true) => Some(false),
+            _ if std::mem::discriminant(&self) == std::mem::discriminant(&other) => Some(self == other),
+            _ => None,
+        }
+    }
+}
+
+fn constant<'s>(expr: &'s Expression) -> Option<Constant<'s>> {
+    match expr {
+        Expression::BooleanLiteral(b) => Some(Constant::Bool(b.value)),
+        Expression::NumericLiteral(n) => Some(Constant::Number(n.value)),
+        Expression::StringLiteral(s) => Some(Constant::Str(s.value.as_str())),
+        Expression::NullLiteral(_) => Some(Constant::Null),
+        Expression::UnaryExpression(u) if u.operator == UnaryOperator::Void => {
+            constant(&u.argument).map(|_| Constant::Undefined)
+        }
+        Expression::ParenthesizedExpression(p) => constant(&p.expression),
+        _ => None,
+    }
+}
+
+// Folds the conditions defines typically leave behind:
+// `"production" !== "production"`, `!true`, `false && x`, `true ? a : b`.
+fn fold_expression<'a>(ast: AstBuilder<'a>, expr: &mut Expression<'a>) {
+    let folded = match expr {
+        Expression::UnaryExpression(u) if u.operator == UnaryOperator::LogicalNot => {
+            constant(&u.argument).map(|c| ast.expression_boolean_literal(SPAN, !c.truthy()))
+        }
+        Expression::BinaryExpression(bin) => {
+            let (Some(left), Some(right)) = (constant(&bin.left), constant(&bin.right)) else { return };
+            let value = match bin.operator {
+                BinaryOperator::StrictEquality => Some(left == right),
+                BinaryOperator::StrictInequality => Some(left != right),
+                BinaryOperator::Equality => left.loose_equals(right),
+                BinaryOperator::Inequality => left.loose_equals(right).map(|eq| !eq),
+                _ => None,
+            };
+            value.map(|v| ast.expression_boolean_literal(SPAN, v))
+        }
+        Expression::LogicalExpression(logical) => {
+            let Some(left) = constant(&logical.left) else { return };
+            let keep_left = match logical.operator {
+                LogicalOperator::And => !left.truthy(),
+                LogicalOperator::Or => left.truthy(),
+                LogicalOperator::Coalesce => !left.is_nullish(),
+            };
+            Some(if keep_left { ast.move_expression(&mut logical.left) } else { ast.move_expression(&mut logical.right) })
+        }
+        Expression::ConditionalExpression(cond) => {
+            let Some(test) = constant(&cond.test).map(Constant::truthy) else { return };
+            Some(if test { ast.move_expression(&mut cond.consequent) } else { ast.move_expression(&mut cond.alternate) })
+        }
+        _ => None,
+    };
+    if let Some(folded) = folded {
+        *expr = folded;
+    }
+}
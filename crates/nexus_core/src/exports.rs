@@ -0,0 +1,121 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use crate::shaker::{ExportBinding, ShakeInfo};
+
+// Export resolution table.
+//
+// Maps every (module, exported name) to the binding that actually provides it,
+// following named re-exports (`export { a as b } from`), imported-then-exported
+// bindings and `export *` chains. Module ids are the bundler's virtual ids.
+
+/// Where an exported name ultimately comes from.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ExportOrigin {
+    /// A binding declared in `module` and exported there as `name`.
+    Local { module: String, name: String },
+    /// The namespace object of `module` (`export * as ns from`, `import * as ns; export { ns }`).
+    Namespace { module: String },
+}
+
+#[derive(Debug, Default)]
+struct ModuleExports {
+    // exported name -> binding, with import sources already resolved to module ids
+    named: HashMap<String, ExportBinding>,
+    stars: Vec<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct ExportTable {
+    modules: HashMap<String, ModuleExports>,
+}
+
+impl ExportTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a module. `imports` maps import sources to module ids; sources
+    /// that failed to resolve are skipped.
+    pub fn add_module(&mut self, id: &str, info: &ShakeInfo, imports: &HashMap<String, String>) {
+        let mut entry = ModuleExports::default();
+        for (exported, binding) in info.export_bindings() {
+            let binding = match binding {
+                ExportBinding::Imported { source, name } => match imports.get(&source) {
+                    Some(target) => ExportBinding::Imported { source: target.clone(), name },
+                    None => continue,
+                },
+                local => local,
+            };
+            entry.named.insert(exported, binding);
+        }
+        for source in info.star_sources() {
+            if let Some(target) = imports.get(source) {
+                entry.stars.push(target.clone());
+            }
+        }
+        self.modules.insert(id.to_string(), entry);
+    }
+
+    /// Resolves `name` exported by `module` to its origin binding.
+    pub fn resolve(&self, module: &str, name: &str) -> Option<ExportOrigin> {
+        self.resolve_inner(module, name, &mut HashSet::new())
+    }
+
+    fn resolve_inner(&self, module: &str, name: &str, seen: &mut HashSet<(String, String)>) -> Option<ExportOrigin> {
+        if !seen.insert((module.to_string(), name.to_string())) {
+            return None;
+        }
+        let Some(exports) = self.modules.get(module) else {
+            // Not analysed (e.g. unparseable vendor code): trust the importer.
+            return Some(ExportOrigin::Local { module: module.to_string(), name: name.to_string() });
+        };
+
+        match exports.named.get(name) {
+            Some(ExportBinding::Local(_)) => {
+                Some(ExportOrigin::Local { module: module.to_string(), name: name.to_string() })
+            }
+            Some(ExportBinding::Imported { source, name: imported }) if imported == "*" => {
+                Some(ExportOrigin::Namespace { module: source.clone() })
+            }
+            Some(ExportBinding::Imported { source, name: imported }) => self.resolve_inner(source, imported, seen),
+            // `export *` never forwards `default`.
+            None if name == "default" => None,
+            None => exports.stars.iter().find_map(|child| self.resolve_inner(child, name, seen)),
+        }
+    }
+
+    /// The `export *` child of `module` that provides `name`, if `module` does
+    /// not export it itself.
+    pub fn star_provider(&self, module: &str, name: &str) -> Option<&str> {
+        let exports = self.modules.get(module)?;
+        if exports.named.contains_key(name) || name == "default" {
+            return None;
+        }
+        exports
+            .stars
+            .iter()
+            .find(|child| self.resolve_inner(child, name, &mut HashSet::new()).is_some())
+            .map(|s| s.as_str())
+    }
+
+    /// Every name visible on the namespace object of `module`.
+    pub fn all_exports(&self, module: &str) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        self.collect_exports(module, &mut names, &mut HashSet::new(), true);
+        names
+    }
+
+    fn collect_exports(&self, module: &str, names: &mut BTreeSet<String>, seen: &mut HashSet<String>, is_root: bool) {
+        if !seen.insert(module.to_string()) {
+            return;
+        }
+        let Some(exports) = self.modules.get(module) else { return };
+        for name in exports.named.keys() {
+            if is_root || name != "default" {
+                names.insert(name.clone());
+            }
+        }
+        for child in &exports.stars {
+            self.collect_exports(child, names, seen, false);
+        }
+    }
+}
@@ -1,7 +1,35 @@
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct ModuleId(pub usize);
 
-#[derive(Clone, Debug)]
+// What a module was compiled from, so a restored graph can tell whether the
+// file changed while the server was down.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Fingerprint {
+    pub mtime_ns: u64,
+    pub hash: u64,
+}
+
+impl Fingerprint {
+    pub fn of(path: &Path, bytes: &[u8]) -> Self {
+        Self { mtime_ns: mtime_ns(path).unwrap_or(0), hash: crate::cache::content_hash(bytes) }
+    }
+}
+
+/// The file the module `id` is compiled from. Ids of import variants carry a
+/// query, e.g. `/src/logo.svg?raw`; see `compiler::AssetQuery`.
+pub fn module_file(root: &Path, id: &str) -> PathBuf {
+    root.join(crate::compiler::split_query(id).0.trim_start_matches('/'))
+}
+
+fn mtime_ns(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(std::time::UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Module {
     pub id: ModuleId,
     pub path: String,
@@ -10,9 +38,15 @@ pub struct Module {
     pub is_vendor: bool,
     pub imports: std::collections::HashMap<String, String>,
     pub version: u64,
+    /// `None` until the module has been compiled from a file.
+    #[serde(default)]
+    pub fingerprint: Option<Fingerprint>,
+    /// `import.meta.glob` patterns, root-relative; see `import_meta`.
+    #[serde(default)]
+    pub globs: Vec<String>,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ModuleGraph {
     pub modules: Vec<Module>,
     pub outgoing_edges: Vec<Vec<ModuleId>>,
@@ -20,6 +54,12 @@ pub struct ModuleGraph {
     pub incoming_edges: Vec<Vec<ModuleId>>,
 }
 
+impl Default for ModuleGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ModuleGraph {
     pub fn new() -> Self {
         Self {
@@ -40,6 +80,8 @@ impl ModuleGraph {
             is_vendor: false,
             imports: std::collections::HashMap::new(),
             version: 1,
+            fingerprint: None,
+            globs: Vec::new(),
         };
         self.modules.push(module);
         self.outgoing_edges.push(Vec::new());
@@ -100,6 +142,36 @@ impl ModuleGraph {
         }
     }
 
+    pub fn set_fingerprint(&mut self, id: ModuleId, fingerprint: Fingerprint) {
+        if let Some(module) = self.modules.get_mut(id.0) {
+            module.fingerprint = Some(fingerprint);
+        }
+    }
+
+    pub fn set_globs(&mut self, id: ModuleId, globs: Vec<String>) {
+        if let Some(module) = self.modules.get_mut(id.0) {
+            module.globs = globs;
+        }
+    }
+
+    /// Modules with a glob matching `path`, whose expansion changes when the
+    /// file is created or deleted.
+    pub fn glob_importers(&self, path: &str) -> Vec<ModuleId> {
+        self.modules
+            .iter()
+            .filter(|m| m.globs.iter().any(|glob| crate::import_meta::glob_match(glob, path)))
+            .map(|m| m.id)
+            .collect()
+    }
+
+    /// Grows whenever a module or edge is added or a module recompiled, so
+    /// a saved copy can tell whether it is behind.
+    pub fn revision(&self) -> u64 {
+        let versions: u64 = self.modules.iter().map(|m| m.version).sum();
+        let edges: usize = self.outgoing_edges.iter().chain(&self.dynamic_edges).map(Vec::len).sum();
+        versions + (self.modules.len() + edges) as u64
+    }
+
     pub fn get_version(&self, id: ModuleId) -> Option<u64> {
         self.modules.get(id.0).map(|m| m.version)
     }
@@ -117,6 +189,83 @@ impl ModuleGraph {
         self.modules.iter().find(|m| m.path == path).map(|m| m.id)
     }
 
+    // Persistence: the dev server writes the graph to `.nexus/graph` on
+    // shutdown and restores it on start. `config` describes everything besides
+    // the sources that affects compiled code (env, defines, plugins, loaders),
+    // as for the transform cache; a graph saved under another one is dropped.
+
+    pub fn save(&self, path: &Path, config: &str) -> std::io::Result<()> {
+        let saved = SavedGraph {
+            format: GRAPH_FORMAT,
+            compiler: env!("CARGO_PKG_VERSION").to_string(),
+            config: crate::cache::content_hash(config.as_bytes()),
+            graph: self.clone(),
+        };
+        let bytes = serde_json::to_vec(&saved)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        // Write then rename so a crash mid-write can't leave a truncated graph.
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, bytes)?;
+        std::fs::rename(&tmp, path)
+    }
+
+    /// Loads a saved graph. Returns `None` if there is none or it was written
+    /// by a different compiler version or under a different `config`.
+    pub fn load(path: &Path, config: &str) -> Option<Self> {
+        let bytes = std::fs::read(path).ok()?;
+        let saved: SavedGraph = match serde_json::from_slice(&bytes) {
+            Ok(saved) => saved,
+            Err(e) => {
+                tracing::warn!("Ignoring unreadable module graph {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        if saved.format != GRAPH_FORMAT || saved.compiler != env!("CARGO_PKG_VERSION") {
+            return None;
+        }
+        if saved.config != crate::cache::content_hash(config.as_bytes()) {
+            tracing::info!("Configuration changed since the last session, rebuilding the module graph");
+            return None;
+        }
+        Some(saved.graph)
+    }
+
+    /// Checks every compiled module against its file under `root` and returns
+    /// the ones that changed or disappeared. Only files whose mtime moved are
+    /// hashed; if the content is the same the new mtime is recorded and the
+    /// module is kept. Stale modules lose their fingerprint, deleted ones also
+    /// lose their code.
+    pub fn validate(&mut self, root: &Path) -> Vec<ModuleId> {
+        let mut stale = Vec::new();
+        for module in &mut self.modules {
+            let Some(fingerprint) = module.fingerprint else { continue };
+            let path = module_file(root, &module.path);
+            let Some(mtime) = mtime_ns(&path) else {
+                module.fingerprint = None;
+                module.source.clear();
+                module.map = None;
+                module.version += 1;
+                stale.push(module.id);
+                continue;
+            };
+            if mtime == fingerprint.mtime_ns {
+                continue;
+            }
+            match std::fs::read(&path) {
+                Ok(bytes) if crate::cache::content_hash(&bytes) == fingerprint.hash => {
+                    module.fingerprint = Some(Fingerprint { mtime_ns: mtime, hash: fingerprint.hash });
+                }
+                _ => {
+                    module.fingerprint = None;
+                    stale.push(module.id);
+                }
+            }
+        }
+        stale
+    }
+
     // Week 5: Linearization (Virtual Chunking)
     // Post-order DFS traversal: visits leaf dependencies first.
     pub fn linearize(&self, root: ModuleId) -> Vec<ModuleId> {
@@ -126,6 +275,29 @@ impl ModuleGraph {
         result
     }
 
+    /// Every module `root` reaches, through dynamic imports too, dependencies
+    /// first. A dev bundle registers all of them, so `import()` finds its
+    /// module without another request.
+    pub fn reachable(&self, root: ModuleId) -> Vec<ModuleId> {
+        let mut visited = std::collections::HashSet::new();
+        let mut result = Vec::new();
+        let mut stack = vec![(root, false)];
+        while let Some((node, done)) = stack.pop() {
+            if done {
+                result.push(node);
+                continue;
+            }
+            if !visited.insert(node) {
+                continue;
+            }
+            stack.push((node, true));
+            let deps = self.outgoing_edges.get(node.0).into_iter().chain(self.dynamic_edges.get(node.0)).flatten();
+            let deps: Vec<ModuleId> = deps.copied().collect();
+            stack.extend(deps.into_iter().rev().filter(|dep| !visited.contains(dep)).map(|dep| (dep, false)));
+        }
+        result
+    }
+
     fn dfs_post_order(&self, node: ModuleId, visited: &mut std::collections::HashSet<ModuleId>, result: &mut Vec<ModuleId>) {
         if visited.contains(&node) {
             return;
@@ -211,3 +383,14 @@ impl ModuleGraph {
         roots.into_iter().collect()
     }
 }
+
+// Bump when the serialized layout of the graph changes.
+const GRAPH_FORMAT: u32 = 3;
+
+#[derive(Serialize, Deserialize)]
+struct SavedGraph {
+    format: u32,
+    compiler: String,
+    config: u64,
+    graph: ModuleGraph,
+}
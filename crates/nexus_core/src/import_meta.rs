@@ -0,0 +1,350 @@
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast_visit::{walk_mut, VisitMut};
+use std::path::Path;
+use crate::compiler;
+use crate::parser::worker_source;
+use crate::unit::{parse_expression, parse_statements};
+
+// `import.meta.glob` and `import.meta.url`.
+//
+// Globs are expanded at compile time into an object of plain `import()`
+// calls (or hoisted `import` declarations when eager), so the graph, linker
+// and chunking see ordinary imports. `new URL('./file', import.meta.url)`
+// becomes the URL the file is served from: its own path in dev, a hashed copy
+// under `assets/` in production. Our output runs as classic scripts, where
+// `import.meta` doesn't exist, so every other `import.meta.url` is replaced
+// with the module's URL as well.
+
+/// What expanding a module's `import.meta` uses besides its own source.
+#[derive(Debug, Default)]
+pub struct ImportMetaOutput {
+    /// Root-relative patterns of the module's globs, e.g. `/src/pages/*.tsx`.
+    /// Files created or deleted under them change the module's output.
+    pub globs: Vec<String>,
+    /// Files referenced by URL, to be written to `dist` (production only).
+    pub assets: Vec<(String, Vec<u8>)>,
+}
+
+impl ImportMetaOutput {
+    /// Output also depends on other files, so it can't be cached by content.
+    pub fn depends_on_files(&self) -> bool {
+        !self.globs.is_empty() || !self.assets.is_empty()
+    }
+}
+
+/// Expands `import.meta` in `program`, the module `id` (`/src/app.ts`) of
+/// the project at `root`.
+pub(crate) fn expand_program<'a>(
+    allocator: &'a Allocator,
+    program: &mut Program<'a>,
+    root: &Path,
+    id: &str,
+    is_prod: bool,
+) -> ImportMetaOutput {
+    let mut expander = Expander {
+        allocator,
+        root,
+        id,
+        is_prod,
+        hoisted: String::new(),
+        glob_count: 0,
+        output: ImportMetaOutput::default(),
+    };
+    expander.visit_program(program);
+    if !expander.hoisted.is_empty() {
+        let mut body = parse_statements(allocator, &expander.hoisted);
+        body.extend(program.body.drain(..));
+        program.body = body;
+    }
+    expander.output
+}
+
+struct Expander<'a, 'c> {
+    allocator: &'a Allocator,
+    root: &'c Path,
+    id: &'c str,
+    is_prod: bool,
+    // Import declarations of eager globs, prepended to the module.
+    hoisted: String,
+    glob_count: usize,
+    output: ImportMetaOutput,
+}
+
+impl<'a> VisitMut<'a> for Expander<'a, '_> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        let replacement = match expr {
+            // The worker's URL is the parser's business; see `parser::worker_source`.
+            Expression::NewExpression(new) if worker_source(new).is_some() => {
+                for arg in new.arguments.iter_mut().skip(1) {
+                    self.visit_argument(arg);
+                }
+                return;
+            }
+            Expression::NewExpression(new) => self.asset_url(new),
+            Expression::CallExpression(call) if is_meta_property(&call.callee, "glob") => self.expand_glob(call),
+            _ if is_meta_property(expr, "url") => {
+                Some(format!("new URL({}, self.location.href).href", quote(self.id)))
+            }
+            _ => None,
+        };
+        match replacement.and_then(|code| parse_expression(self.allocator, &code)) {
+            Some(replacement) => *expr = replacement,
+            None => walk_mut::walk_expression(self, expr),
+        }
+    }
+}
+
+// `import.meta.<property>`
+pub(crate) fn is_meta_property(expr: &Expression, property: &str) -> bool {
+    let Expression::StaticMemberExpression(member) = expr else { return false };
+    let Expression::MetaProperty(meta) = &member.object else { return false };
+    meta.meta.name == "import" && meta.property.name == "meta" && member.property.name == property
+}
+
+fn quote(text: &str) -> String {
+    serde_json::to_string(text).unwrap_or_default()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum GlobAs {
+    Module,
+    Raw,
+    Url,
+}
+
+impl Expander<'_, '_> {
+    fn module_dir(&self) -> &str {
+        self.id.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+    }
+
+    fn fs_path(&self, id: &str) -> std::path::PathBuf {
+        self.root.join(id.trim_start_matches('/'))
+    }
+
+    // Where `id` is served from, emitting it in production.
+    fn url_of(&mut self, id: &str, bytes: Vec<u8>) -> String {
+        if !self.is_prod {
+            return id.to_string();
+        }
+        let name = compiler::hashed_asset_name(id, &bytes);
+        let url = format!("/{}", name);
+        if !self.output.assets.iter().any(|(existing, _)| *existing == name) {
+            self.output.assets.push((name, bytes));
+        }
+        url
+    }
+
+    // `new URL('./logo.png', import.meta.url)`
+    fn asset_url(&mut self, new: &NewExpression) -> Option<String> {
+        let Expression::Identifier(callee) = &new.callee else { return None };
+        if callee.name != "URL" || new.arguments.len() != 2 {
+            return None;
+        }
+        let Some(Expression::StringLiteral(specifier)) = new.arguments[0].as_expression() else { return None };
+        if !new.arguments[1].as_expression().is_some_and(|base| is_meta_property(base, "url")) {
+            return None;
+        }
+        let id = join_id(self.module_dir(), &specifier.value)?;
+        let bytes = std::fs::read(self.fs_path(&id)).ok()?;
+        let url = self.url_of(&id, bytes);
+        Some(format!("new URL({}, self.location.href)", quote(&url)))
+    }
+
+    // `import.meta.glob(patterns, { eager, import, as })`
+    fn expand_glob(&mut self, call: &CallExpression) -> Option<String> {
+        let mut patterns = Vec::new();
+        match call.arguments.first()?.as_expression()? {
+            Expression::StringLiteral(s) => patterns.push(s.value.to_string()),
+            Expression::ArrayExpression(array) => {
+                for element in &array.elements {
+                    let Some(Expression::StringLiteral(s)) = element.as_expression() else { return None };
+                    patterns.push(s.value.to_string());
+                }
+            }
+            _ => return None,
+        }
+
+        let mut eager = false;
+        let mut import_name = None;
+        let mut glob_as = GlobAs::Module;
+        if let Some(Expression::ObjectExpression(options)) = call.arguments.get(1).and_then(|a| a.as_expression()) {
+            for property in &options.properties {
+                let ObjectPropertyKind::ObjectProperty(property) = property else { continue };
+                let Some(key) = property.key.static_name() else { continue };
+                match (key.as_ref(), &property.value) {
+                    ("eager", Expression::BooleanLiteral(b)) => eager = b.value,
+                    ("import", Expression::StringLiteral(s)) => import_name = Some(s.value.to_string()),
+                    ("as" | "query", Expression::StringLiteral(s)) => {
+                        glob_as = match s.value.trim_start_matches('?') {
+                            "raw" => GlobAs::Raw,
+                            "url" => GlobAs::Url,
+                            _ => GlobAs::Module,
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let dir = self.module_dir().to_string();
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for pattern in &patterns {
+            match pattern.strip_prefix('!') {
+                Some(negated) => exclude.push(join_id(&dir, negated)?),
+                None => include.push((join_id(&dir, pattern)?, pattern.starts_with('/'))),
+            }
+        }
+        self.output.globs.extend(include.iter().map(|(p, _)| p.clone()));
+
+        let mut files = Vec::new();
+        for (pattern, _) in &include {
+            collect_matches(self.root, pattern, &mut files);
+        }
+        files.sort();
+        files.dedup();
+        files.retain(|id| id.as_str() != self.id && !exclude.iter().any(|p| glob_match(p, id)));
+
+        let glob_index = self.glob_count;
+        self.glob_count += 1;
+        let mut object = String::from("{\n");
+        for (i, file) in files.iter().enumerate() {
+            let specifier = relative_specifier(&dir, file);
+            let owner = include.iter().find(|(p, _)| glob_match(p, file));
+            let key = if owner.is_some_and(|(_, absolute)| *absolute) { file.clone() } else { specifier.clone() };
+            let value = match glob_as {
+                GlobAs::Raw | GlobAs::Url => {
+                    let bytes = std::fs::read(self.fs_path(file)).ok()?;
+                    let value = match glob_as {
+                        GlobAs::Raw => quote(&String::from_utf8_lossy(&bytes)),
+                        _ => quote(&self.url_of(file, bytes)),
+                    };
+                    if eager { value } else { format!("() => Promise.resolve({})", value) }
+                }
+                GlobAs::Module if eager => {
+                    let local = format!("__nexus_glob_{}_{}", glob_index, i);
+                    let binding = match &import_name {
+                        Some(name) => format!("{{ {} as {} }}", name, local),
+                        None => format!("* as {}", local),
+                    };
+                    self.hoisted.push_str(&format!("import {} from {};\n", binding, quote(&specifier)));
+                    local
+                }
+                GlobAs::Module => match &import_name {
+                    Some(name) => format!("() => import({}).then((m) => m[{}])", quote(&specifier), quote(name)),
+                    None => format!("() => import({})", quote(&specifier)),
+                },
+            };
+            object.push_str(&format!("{}: {},\n", quote(&key), value));
+        }
+        object.push('}');
+        Some(object)
+    }
+}
+
+// Root-relative id of `specifier` written in a module under `dir`.
+fn join_id(dir: &str, specifier: &str) -> Option<String> {
+    if !specifier.starts_with('.') && !specifier.starts_with('/') {
+        return None;
+    }
+    let mut segments: Vec<&str> = if specifier.starts_with('/') {
+        Vec::new()
+    } else {
+        dir.split('/').filter(|s| !s.is_empty()).collect()
+    };
+    for segment in specifier.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop()?;
+            }
+            _ => segments.push(segment),
+        }
+    }
+    Some(format!("/{}", segments.join("/")))
+}
+
+// `./pages/a.tsx` or `../lib/b.ts` from `dir` to `id`.
+fn relative_specifier(dir: &str, id: &str) -> String {
+    let from: Vec<&str> = dir.split('/').filter(|s| !s.is_empty()).collect();
+    let to: Vec<&str> = id.split('/').filter(|s| !s.is_empty()).collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut parts: Vec<&str> = vec![".."; from.len() - common];
+    parts.extend(&to[common..]);
+    let relative = parts.join("/");
+    if relative.starts_with("..") { relative } else { format!("./{}", relative) }
+}
+
+// Walks the part of the tree `pattern` can match, skipping `node_modules`
+// and dot-directories.
+fn collect_matches(root: &Path, pattern: &str, files: &mut Vec<String>) {
+    let base: Vec<&str> = pattern
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .take_while(|s| !s.contains(['*', '?', '{']))
+        .collect();
+    let mut stack = vec![root.join(base.join("/"))];
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else { continue };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let name = entry.file_name();
+            let name = name.to_string_lossy();
+            if path.is_dir() {
+                if name != "node_modules" && !name.starts_with('.') {
+                    stack.push(path);
+                }
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(root) else { continue };
+            let id = format!("/{}", relative.to_string_lossy().replace('\\', "/"));
+            if glob_match(pattern, &id) {
+                files.push(id);
+            }
+        }
+    }
+}
+
+/// Matches a root-relative id against a glob with `*`, `?`, `**` and
+/// `{a,b}` alternatives.
+pub fn glob_match(pattern: &str, id: &str) -> bool {
+    let segments: Vec<&str> = id.split('/').collect();
+    expand_braces(pattern).iter().any(|pattern| {
+        let pattern: Vec<&str> = pattern.split('/').collect();
+        match_segments(&pattern, &segments)
+    })
+}
+
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let (Some(open), Some(close)) = (pattern.find('{'), pattern.find('}')) else {
+        return vec![pattern.to_string()];
+    };
+    if close < open {
+        return vec![pattern.to_string()];
+    }
+    pattern[open + 1..close]
+        .split(',')
+        .flat_map(|alt| expand_braces(&format!("{}{}{}", &pattern[..open], alt, &pattern[close + 1..])))
+        .collect()
+}
+
+fn match_segments(pattern: &[&str], segments: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => segments.is_empty(),
+        Some((&"**", rest)) => (0..=segments.len()).any(|skip| match_segments(rest, &segments[skip..])),
+        Some((first, rest)) => segments
+            .split_first()
+            .is_some_and(|(segment, others)| match_wildcard(first.as_bytes(), segment.as_bytes()) && match_segments(rest, others)),
+    }
+}
+
+fn match_wildcard(pattern: &[u8], text: &[u8]) -> bool {
+    match (pattern.split_first(), text.split_first()) {
+        (None, _) => text.is_empty(),
+        (Some((b'*', rest)), _) => match_wildcard(rest, text) || (!text.is_empty() && match_wildcard(pattern, &text[1..])),
+        (Some((b'?', rest)), Some((_, others))) => match_wildcard(rest, others),
+        (Some((c, rest)), Some((t, others))) => c == t && match_wildcard(rest, others),
+        _ => false,
+    }
+}
@@ -1,5 +1,5 @@
 use axum::{
-    extract::{Path, State},
+    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, Path, State},
     http::{HeaderMap, StatusCode, Uri},
     response::{IntoResponse, Response},
     body::Body,
@@ -7,7 +7,7 @@ use axum::{
     Router,
 };
 use std::{
-    collections::HashMap,
+    collections::BTreeMap,
     net::SocketAddr,
     sync::{Arc, RwLock},
 };
@@ -19,12 +19,34 @@ use graph::*;
 pub mod parser;
 use parser::{extract_dependencies_detailed, transform_cjs};
 pub mod compiler;
-use compiler::{compile, compile_css};
+pub mod define;
+pub mod typescript;
+use define::Defines;
+pub mod import_meta;
+use unit::CompilationUnit;
 pub mod bundler;
+pub mod shaker;
+pub mod exports;
+pub mod report;
+pub mod analyzer;
+pub mod unit;
+pub mod cache;
+use cache::{CachedModule, TransformCache};
 pub mod watcher;
+pub mod prebundle;
+use prebundle::DepsOptimizer;
 pub mod resolver;
 use resolver::NexusResolver;
 pub mod runtime;
+pub mod wasm;
+pub mod svg;
+pub mod vue;
+pub mod svelte;
+pub mod mdx;
+pub mod plugin;
+pub mod loader;
+use plugin::{ModuleInfo, PluginContainer};
+use loader::{Loader, LoaderRegistry};
 
 // --- DATA STRUCTURES ---
 
@@ -34,17 +56,15 @@ struct AppState {
     root_dir: String,
     hmr_tx: tokio::sync::broadcast::Sender<watcher::HmrMessage>,
     resolver: Arc<NexusResolver>,
+    cache: Option<Arc<TransformCache>>,
+    deps: Arc<DepsOptimizer>,
+    defines: Arc<Defines>,
+    plugins: PluginContainer,
+    loaders: Arc<LoaderRegistry>,
 }
 
 // --- MODULE HANDLER ---
 
-async fn handle_module(
-    State(state): State<AppState>,
-    uri: Uri,
-) -> impl IntoResponse {
-    handle_module_logic(state, uri).await
-}
-
 async fn handle_sourcemap(
     State(state): State<AppState>,
     Path(id): Path<usize>,
@@ -71,101 +91,102 @@ fn normalize_path_for_graph(path: &std::path::Path) -> String {
     }
 }
 
-async fn handle_module_logic(state: AppState, uri: Uri) -> Response {
-    let path_str = uri.path();
-    tracing::info!("Intercepted request: {}", path_str);
-
-    // Week 10: Virtual Refresh Runtime
-    if path_str == "/__nexus_react_refresh" {
-        let runtime_path = std::path::Path::new(&state.root_dir).join("node_modules/react-refresh/runtime.js");
-        match tokio::fs::read_to_string(&runtime_path).await {
-            Ok(c) => {
-                let headers = HeaderMap::new();
-                return (StatusCode::OK, headers, c).into_response();
-            },
-            Err(e) => {
-                 tracing::error!("Could not find react-refresh: {}", e);
-                 return (StatusCode::INTERNAL_SERVER_ERROR, "react-refresh not found").into_response();
-            }
-        }
-    }
-
-    // FIX 2: Path Sanitization (Still needed for initial entry point from browser)
-    // Browser requests http://localhost:3000/src/index.tsx
-    // We map this to File System.
-    
-    let mut safe_path = std::path::PathBuf::new();
-    for component in std::path::Path::new(path_str).components() {
-        match component {
-            std::path::Component::Normal(c) => safe_path.push(c),
-            _ => {}
-        }
-    }
-    
-    if safe_path.as_os_str().is_empty() {
-         return (StatusCode::BAD_REQUEST, "Invalid path").into_response();
-    }
-
-    let abs_path = std::path::Path::new(&state.root_dir).join(&safe_path);
-    
-    // Determine if vendor
-    let is_vendor = abs_path.to_string_lossy().contains("node_modules");
+// Packages and their pre-bundled versions are served without compiling.
+fn is_vendor_path(path: &std::path::Path) -> bool {
+    let path = path.to_string_lossy().replace('\\', "/");
+    path.contains("node_modules") || path.contains(prebundle::DEPS_DIR)
+}
 
-    // Week 12: Binary Reading
-    let bytes = match tokio::fs::read(&abs_path).await {
-        Ok(b) => b,
-        Err(e) => return (StatusCode::NOT_FOUND, format!("File not found: {}", e)).into_response(),
-    };
+// A module as compiled for the dev server.
+struct CompiledModule {
+    code: String,
+    sourcemap: Option<String>,
+    /// (specifier, is_dynamic)
+    dependencies: Vec<(String, bool)>,
+    /// `import.meta.glob` patterns.
+    globs: Vec<String>,
+}
 
-    // Week 12: Raw Asset Serving
-    if uri.query() == Some("raw") {
-        let mime = mime_guess::from_path(&abs_path).first_or_octet_stream();
-        let mut headers = HeaderMap::new();
-        headers.insert(axum::http::header::CONTENT_TYPE, mime.as_ref().parse().unwrap());
-        return (StatusCode::OK, headers, bytes).into_response();
-    }
-    
-    // Determine Compiler
+fn compile_module(state: &AppState, path_str: &str, loader: Loader, is_vendor: bool, bytes: &[u8]) -> CompiledModule {
     let compiled_code;
     let sourcemap;
-    
-    // Check extensions
-    // Asset extensions: png, jpg, jpeg, gif, svg, wasm, json
-    // We can use a helper or simplistic check
-    let ext = std::path::Path::new(path_str).extension().and_then(|s| s.to_str()).unwrap_or("");
-    
-    if is_vendor {
+    // JS is parsed once; its dependencies come from the same AST.
+    let mut deps = None;
+    let mut globs = Vec::new();
+
+    if let (file, Some(query)) = compiler::split_query(path_str) {
+        let res = compiler::compile_variant(bytes, file, query, false);
+        compiled_code = res.code;
+        sourcemap = res.sourcemap;
+    } else if is_vendor {
         // Vendor usually JS text
-        compiled_code = String::from_utf8_lossy(&bytes).to_string();
+        compiled_code = String::from_utf8_lossy(bytes).to_string();
         sourcemap = None;
+    } else if let Some(res) = state.loaders.compile(loader, bytes, path_str, false).unwrap_or_else(|e| Some(loader::error_module(path_str, &e))) {
+        compiled_code = res.code;
+        sourcemap = res.sourcemap;
     } else {
-        match ext {
-            "css" => {
-                let text = String::from_utf8_lossy(&bytes);
-                let res = compiler::compile_css(&text, path_str, false);
-                compiled_code = res.code;
-                sourcemap = res.sourcemap;
-            },
-            "png" | "jpg" | "jpeg" | "gif" | "svg" | "wasm" | "json" => {
-                let res = compiler::compile_asset(&bytes, path_str, false);
-                compiled_code = res.code;
-                sourcemap = res.sourcemap;
-            },
-            _ => {
-                // Default JS/TS
-                let text = String::from_utf8_lossy(&bytes);
-                let res = compile(&text, path_str, false);
-                compiled_code = res.code;
-                sourcemap = res.sourcemap;
-            }
-        }
+        let source = String::from_utf8_lossy(bytes).into_owned();
+        let mut unit = CompilationUnit::parse_as(path_str, source, loader.source_type(path_str));
+        unit.define(&state.defines);
+        globs = unit.expand_import_meta(std::path::Path::new(&state.root_dir), false).globs;
+        let res = unit.compile_result();
+        deps = Some(unit.dependencies());
+        compiled_code = res.code;
+        sourcemap = res.sourcemap;
+    }
+
+    // Plugins see the compiled JS; if they change it, its dependencies may change too.
+    let mut compiled = compiler::CompileResult { code: compiled_code, sourcemap, css: None, asset: None };
+    match state.plugins.transform(path_str, &mut compiled) {
+        Ok(true) => deps = None,
+        Ok(false) => {}
+        Err(e) => tracing::error!("{}", e),
     }
+    let compiler::CompileResult { code: compiled_code, sourcemap, .. } = compiled;
 
     // Week 4: Extract Dependencies (from compiled/raw JS)
-    let deps = extract_dependencies_detailed(&compiled_code, path_str);
+    let dependencies = deps.unwrap_or_else(|| extract_dependencies_detailed(&compiled_code, path_str));
+    CompiledModule { code: compiled_code, sourcemap, dependencies, globs }
+}
+
+// Compiles a module (or takes it from the transform cache), records it and its
+// resolved dependencies in the graph and returns the code to serve.
+fn update_module(state: &AppState, path_str: &str, abs_path: &std::path::Path, is_vendor: bool, bytes: &[u8]) -> String {
+    let loader = state.loaders.loader(path_str);
+
+    // Persistent cache: unchanged files skip compiling and dependency scanning.
+    let content_hash = cache::content_hash(bytes);
+    let cache = state.cache.as_deref().filter(|_| loader.is_cacheable());
+    let (compiled_code, sourcemap, deps, globs) = match cache.and_then(|c| c.get_module(path_str, content_hash)) {
+        Some(cached) => (cached.code, cached.sourcemap, cached.dependencies, Vec::new()),
+        None => {
+            let CompiledModule { code, sourcemap: map, dependencies: deps, globs } = compile_module(state, path_str, loader, is_vendor, bytes);
+            // Glob expansions depend on the directory, not just this file.
+            if let Some(cache) = cache.filter(|_| globs.is_empty()) {
+                let entry = CachedModule {
+                    code: code.clone(),
+                    sourcemap: map.clone(),
+                    dependencies: deps.clone(),
+                    ..CachedModule::default()
+                };
+                cache.put_module(path_str, content_hash, &entry);
+            }
+            (code, map, deps, globs)
+        }
+    };
+
+    // A package the app didn't import before gets its bundle first.
+    let missing: Vec<String> = deps.iter().map(|(s, _)| s.clone()).filter(|s| state.deps.is_missing(abs_path, s)).collect();
+    if !missing.is_empty() {
+        let changed = state.deps.refresh(&state.graph, &missing);
+        if !changed.is_empty() {
+            let _ = state.hmr_tx.send(watcher::HmrMessage { paths: changed });
+        }
+    }
 
     let mut final_content;
-    let module_id;
+    let mut module_info = ModuleInfo { id: path_str.to_string(), ..ModuleInfo::default() };
 
     {
         let mut graph = state.graph.write().unwrap();
@@ -181,7 +202,6 @@ async fn handle_module_logic(state: AppState, uri: Uri) -> Response {
         } else {
             graph.add_module(path_str, "")
         };
-        module_id = current_id;
         
         // Append SourceMap URL if present
         if sourcemap.is_some() {
@@ -191,7 +211,9 @@ async fn handle_module_logic(state: AppState, uri: Uri) -> Response {
         }
         
         // Week 10: Append React Refresh Footer
-        if !is_vendor && (path_str.ends_with(".tsx") || path_str.ends_with(".jsx")) {
+        // SVG modules render components too, unless imported as text or a URL.
+        let svg_component = loader == Loader::Svg && matches!(compiler::split_query(path_str).1, None | Some(compiler::AssetQuery::React));
+        if !is_vendor && (loader.is_jsx() || svg_component) {
              final_content.push_str(r#"
 if (module.hot) {
   window.$RefreshReg$ = (prev, id) => {
@@ -215,20 +237,29 @@ if (module.hot) {
         // Update Graph
         graph.update_compiled(current_id, &final_content, sourcemap);
         graph.mark_vendor(current_id, is_vendor);
+        graph.set_fingerprint(current_id, Fingerprint::of(abs_path, bytes));
+        graph.set_globs(current_id, globs);
         
         // Resolve Dependencies
         let mut resolved_imports = std::collections::HashMap::new();
         
         for (dep_spec, is_dynamic) in deps {
             // Week 9: Use Resolver
-            match state.resolver.resolve(&abs_path, &dep_spec) {
+            // Plugins go first; pre-bundled packages point at their bundle in `.nexus/deps`.
+            let resolution = match (state.plugins.resolve_id(&dep_spec, abs_path), state.deps.get(&dep_spec)) {
+                (Ok(Some(path)), _) => Ok(path),
+                (Err(e), _) => Err(e),
+                (Ok(None), Some(bundle_id)) => Ok(prebundle::bundle_path(std::path::Path::new(&state.root_dir), &bundle_id)),
+                (Ok(None), None) => state.resolver.resolve(abs_path, &dep_spec),
+            };
+            match resolution {
                 Ok(resolved_abs_path) => {
                      // Convert absolute fs path to "virtual" graph path (URI)
                      // If it's inside root_dir, make relative to root.
                      // If outside (e.g. symlink?), we might have issues.
                      // Generally assume inside root or node_modules inside root.
                      
-                     let normalized_abs = resolved_abs_path.to_string_lossy(); // normalize slashes?
+                     let resolved_abs_path = compiler::with_query(&resolved_abs_path, &dep_spec);
                      
                      // Create a graph key.
                      // If inside root, strip root prefix.
@@ -240,15 +271,30 @@ if (module.hot) {
                      };
                      
                      resolved_imports.insert(dep_spec.clone(), graph_key.clone());
+                     let is_worker = parser::worker_constructor(&dep_spec).is_some();
                      
                      let dep_id = if let Some(id) = graph.find_by_path(&graph_key) {
                          id
+                     } else if state.deps.get(&dep_spec).is_some() {
+                         // Bundles are ready to serve as-is.
+                         let bundle = std::fs::read_to_string(&resolved_abs_path).unwrap_or_default();
+                         let id = graph.add_module(&graph_key, &bundle);
+                         graph.mark_vendor(id, true);
+                         id
                      } else {
                          // Add missing module with empty source (placeholder)
                          graph.add_module(&graph_key, "")
                      };
                      
-                     let _ = graph.add_dependency(current_id, dep_id, is_dynamic);
+                     // A worker entry roots its own bundle; no edge, so edits
+                     // to it reload the page instead of hot-updating it.
+                     if !is_worker {
+                         let _ = graph.add_dependency(current_id, dep_id, is_dynamic);
+                         match is_dynamic {
+                             true => module_info.dynamically_imported_ids.push(graph_key),
+                             false => module_info.imported_ids.push(graph_key),
+                         }
+                     }
                 }
                 Err(e) => {
                     tracing::error!("Failed to resolve import '{}' from '{}': {}", dep_spec, path_str, e);
@@ -262,6 +308,71 @@ if (module.hot) {
         let count = graph.modules.len();
         tracing::info!("Graph Node compile update. Total Nodes: {}", count);
     }
+    state.plugins.module_parsed(&module_info);
+
+    final_content
+}
+
+async fn handle_module_logic(state: AppState, uri: Uri) -> Response {
+    let path_str = uri.path();
+    tracing::info!("Intercepted request: {}", path_str);
+
+    // Week 10: Virtual Refresh Runtime
+    if path_str == "/__nexus_react_refresh" {
+        let runtime_path = std::path::Path::new(&state.root_dir).join("node_modules/react-refresh/runtime.js");
+        match tokio::fs::read_to_string(&runtime_path).await {
+            Ok(c) => {
+                let headers = HeaderMap::new();
+                return (StatusCode::OK, headers, c).into_response();
+            },
+            Err(e) => {
+                 tracing::error!("Could not find react-refresh: {}", e);
+                 return (StatusCode::INTERNAL_SERVER_ERROR, "react-refresh not found").into_response();
+            }
+        }
+    }
+
+    // FIX 2: Path Sanitization (Still needed for initial entry point from browser)
+    // Browser requests http://localhost:3000/src/index.tsx
+    // We map this to File System.
+    
+    let mut safe_path = std::path::PathBuf::new();
+    for component in std::path::Path::new(path_str).components() {
+        if let std::path::Component::Normal(c) = component {
+            safe_path.push(c);
+        }
+    }
+    
+    if safe_path.as_os_str().is_empty() {
+         return (StatusCode::BAD_REQUEST, "Invalid path").into_response();
+    }
+
+    let abs_path = std::path::Path::new(&state.root_dir).join(&safe_path);
+    
+    // Determine if vendor
+    let is_vendor = is_vendor_path(&abs_path);
+
+    // Week 12: Binary Reading
+    // Plugins may load the module instead; the raw file is always the file.
+    let bytes = match uri.query() {
+        Some("raw") => tokio::fs::read(&abs_path).await,
+        _ => state.plugins.source(path_str, &abs_path),
+    };
+    let bytes = match bytes {
+        Ok(b) => b,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (StatusCode::NOT_FOUND, format!("File not found: {}", e)).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+
+    // Week 12: Raw Asset Serving
+    if uri.query() == Some("raw") {
+        let mime = mime_guess::from_path(&abs_path).first_or_octet_stream();
+        let mut headers = HeaderMap::new();
+        headers.insert(axum::http::header::CONTENT_TYPE, mime.as_ref().parse().unwrap());
+        return (StatusCode::OK, headers, bytes).into_response();
+    }
+    
+    let final_content = update_module(&state, path_str, &abs_path, is_vendor, &bytes);
 
     // FIX 3: Header Consistency
     let mut headers = HeaderMap::new();
@@ -271,6 +382,29 @@ if (module.hot) {
     (StatusCode::OK, headers, final_content).into_response()
 }
 
+// Pages go through `transform_index_html` hooks; `/` and directories
+// serve their `index.html`.
+async fn handle_html(state: AppState, uri: Uri) -> Response {
+    let mut path = uri.path().to_string();
+    if path.ends_with('/') {
+        path.push_str("index.html");
+    }
+    if path.contains("..") {
+        return (StatusCode::BAD_REQUEST, "Invalid path").into_response();
+    }
+    let file = std::path::Path::new(&state.root_dir).join(path.trim_start_matches('/'));
+    let html = match tokio::fs::read_to_string(&file).await {
+        Ok(html) => html,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return (StatusCode::NOT_FOUND, format!("File not found: {}", e)).into_response(),
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    let html = match state.plugins.transform_index_html(html, &path) {
+        Ok(html) => html,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+    };
+    ([(axum::http::header::CONTENT_TYPE, "text/html; charset=utf-8")], html).into_response()
+}
+
 // --- CHUNK HANDLER ---
 
 async fn handle_chunk(
@@ -295,6 +429,12 @@ async fn handle_chunk(
     
     let decoded_entry = urlencoding::decode(entry_path).unwrap_or(std::borrow::Cow::Borrowed(entry_path));
     
+    // Read before taking the graph lock, which can't be held across an await.
+    let rr_path = std::path::Path::new(&state.root_dir).join("node_modules/react-refresh/runtime.js");
+    let rr_code = tokio::fs::read_to_string(&rr_path).await.ok();
+
+    // Only the entry was requested; its imports are placeholders until now.
+    compile_reachable(&state, &decoded_entry);
     let graph = state.graph.read().unwrap();
     let entry_id_opt = graph.find_by_path(&decoded_entry);
     
@@ -303,8 +443,8 @@ async fn handle_chunk(
     }
     let entry_id = entry_id_opt.unwrap();
 
-    // 2. Linearize Graph (DFS/BFS Topo Sort)
-    let modules = graph.linearize(entry_id);
+    // 2. Linearize Graph (DFS/BFS Topo Sort), lazily imported modules included
+    let modules = graph.reachable(entry_id);
     
     // 3. Runtime Kernel (Week 7)
     use runtime::NEXUS_RUNTIME_JS;
@@ -314,8 +454,7 @@ async fn handle_chunk(
     chunk.push('\n');
     
     // Week 10: Inject React Refresh Runtime
-    let rr_path = std::path::Path::new(&state.root_dir).join("node_modules/react-refresh/runtime.js");
-    if let Ok(rr_code) = tokio::fs::read_to_string(&rr_path).await {
+    if let Some(rr_code) = rr_code {
          chunk.push_str(&format!(
              "__nexus_register__(\"/__nexus_react_refresh\", function(require, module, exports) {{\n{}\n}});\n",
              rr_code
@@ -384,6 +523,8 @@ async fn handle_chunk(
                     return;
                 }
             }
+        } else if (msg.type === 'full-reload') {
+            window.location.reload();
         }
     };
     console.log("[HMR] Connected");
@@ -392,7 +533,9 @@ async fn handle_chunk(
     chunk.push('\n');
 
     // 4. Wrap Modules
-    for module_id in modules {
+    let wasm_modules = dev_wasm_modules(&graph, &modules);
+    chunk.push_str(&wasm::preload_text(&wasm_modules));
+    for module_id in &modules {
         if let Some(module) = graph.modules.get(module_id.0) {
              // 4. A. Transform Imports (Week 7 + 9)
              let wrapped_source = transform_cjs(&module.source, &module.path, &module.imports);
@@ -408,40 +551,267 @@ async fn handle_chunk(
     }
 
     // 5. Bootstrap
-    chunk.push_str(&format!("__nexus_require__(\"{}\");\n", decoded_entry));
+    chunk.push_str(&wasm::entry_text(&decoded_entry, !wasm_modules.is_empty()));
+
+    let mut headers = HeaderMap::new();
+    headers.insert("Content-Type", "application/javascript".parse().unwrap());
+    (StatusCode::OK, headers, chunk).into_response()
+}
+
+// --- WORKER HANDLER ---
+
+// URL: /_nexus/worker?entry=/src/worker.ts
+// Workers can't share the page's registry, so each gets its own bundle.
+async fn handle_worker(
+    State(state): State<AppState>,
+    uri: Uri,
+) -> impl IntoResponse {
+    let entry_path = uri
+        .query()
+        .unwrap_or("")
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("entry="))
+        .unwrap_or("");
+    if entry_path.is_empty() {
+        return (StatusCode::BAD_REQUEST, "Missing entry param").into_response();
+    }
+    let decoded_entry = urlencoding::decode(entry_path).unwrap_or(std::borrow::Cow::Borrowed(entry_path)).into_owned();
+    if state.graph.read().unwrap().find_by_path(&decoded_entry).is_none() {
+        return (StatusCode::NOT_FOUND, format!("Worker entry not found: {}", decoded_entry)).into_response();
+    }
+
+    // The page never requested the worker's modules.
+    compile_reachable(&state, &decoded_entry);
+
+    let graph = state.graph.read().unwrap();
+    let entry_id = graph.find_by_path(&decoded_entry).unwrap();
+    let mut chunk = String::new();
+    chunk.push_str(runtime::NEXUS_RUNTIME_JS);
+    chunk.push('\n');
+    let modules = graph.reachable(entry_id);
+    let wasm_modules = dev_wasm_modules(&graph, &modules);
+    chunk.push_str(&wasm::preload_text(&wasm_modules));
+    for module_id in modules {
+        if let Some(module) = graph.modules.get(module_id.0) {
+            let wrapped_source = transform_cjs(&module.source, &module.path, &module.imports);
+            chunk.push_str(&format!(
+                "__nexus_register__(\"{}\", function(require, module, exports) {{\n{}\n}});\n",
+                module.path, wrapped_source
+            ));
+        }
+    }
+    chunk.push_str(&wasm::entry_text(&decoded_entry, !wasm_modules.is_empty()));
 
     let mut headers = HeaderMap::new();
     headers.insert("Content-Type", "application/javascript".parse().unwrap());
     (StatusCode::OK, headers, chunk).into_response()
 }
 
+// Compiles the modules `entry` reaches that only have a placeholder so far,
+// until no import leads to a new one.
+fn compile_reachable(state: &AppState, entry: &str) {
+    loop {
+        let pending: Vec<String> = {
+            let graph = state.graph.read().unwrap();
+            let Some(entry_id) = graph.find_by_path(entry) else { return };
+            graph
+                .reachable(entry_id)
+                .into_iter()
+                .filter_map(|id| graph.modules.get(id.0))
+                .filter(|m| m.fingerprint.is_none() && !m.is_vendor)
+                .map(|m| m.path.clone())
+                .collect()
+        };
+        let mut compiled = false;
+        for path_str in pending {
+            let abs_path = graph::module_file(std::path::Path::new(&state.root_dir), &path_str);
+            let Ok(bytes) = state.plugins.source(&path_str, &abs_path) else { continue };
+            update_module(state, &path_str, &abs_path, is_vendor_path(&abs_path), &bytes);
+            compiled = true;
+        }
+        if !compiled {
+            break;
+        }
+    }
+}
+
+// `.wasm` modules in a dev bundle. All of them are compiled up front, even
+// those only imported with `?init`.
+fn dev_wasm_modules<'g>(graph: &'g ModuleGraph, modules: &[ModuleId]) -> Vec<&'g str> {
+    modules
+        .iter()
+        .filter_map(|id| graph.modules.get(id.0))
+        .map(|m| m.path.as_str())
+        .filter(|path| wasm::is_wasm(path))
+        .collect()
+}
+
 // --- WEBSOCKET HANDLER ---
 
+// Each page's HMR client connects here and gets every `HmrMessage` as
+// `{"type":"update","paths":[...]}`, or `{"type":"full-reload"}` if it fell
+// behind.
 async fn handle_ws(
-    State(_state): State<AppState>,
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
 ) -> impl IntoResponse {
-    // TODO: Implement WebSocket upgrade for HMR
-    // For now, return 501 Not Implemented to unblock binary compilation
-    (StatusCode::NOT_IMPLEMENTED, "WebSocket HMR not yet implemented")
+    let updates = state.hmr_tx.subscribe();
+    ws.on_upgrade(move |socket| send_updates(socket, updates))
+}
+
+async fn send_updates(mut socket: WebSocket, mut updates: tokio::sync::broadcast::Receiver<watcher::HmrMessage>) {
+    loop {
+        tokio::select! {
+            update = updates.recv() => {
+                let message = match update {
+                    Ok(update) => serde_json::json!({ "type": "update", "paths": update.paths }),
+                    // Updates were missed, so the page can't catch up in place.
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => serde_json::json!({ "type": "full-reload" }),
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => return,
+                };
+                let text = message.to_string();
+                if socket.send(Message::Text(text)).await.is_err() {
+                    return;
+                }
+            }
+            incoming = socket.recv() => {
+                // The client only listens; this notices it leaving.
+                if !matches!(incoming, Some(Ok(_))) {
+                    return;
+                }
+            }
+        }
+    }
 }
 
 // --- SERVER ---
 
+pub const GRAPH_FILE: &str = ".nexus/graph";
+
+// How often the module graph is saved if it changed. A graceful shutdown
+// saves it once more.
+const GRAPH_SAVE_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+// What a saved graph was built under. Resolutions into `node_modules` go
+// stale when a lockfile changes, so its hash is part of it.
+fn graph_config(root: &std::path::Path, defines: &Defines, plugins: &PluginContainer, loaders: &LoaderRegistry) -> String {
+    format!("dev:{:x}:{:016x}{}{}", defines.hash(), cache::lockfile_hash(root), plugins.cache_key(), loaders.cache_key())
+}
+
+// Saves the graph whenever it changed since the last save, so a server that
+// is killed rather than stopped loses little.
+async fn save_graph_periodically(state: AppState, graph_file: std::path::PathBuf, mut saved: u64) {
+    let mut interval = tokio::time::interval(GRAPH_SAVE_INTERVAL);
+    interval.tick().await;
+    loop {
+        interval.tick().await;
+        let state = state.clone();
+        let file = graph_file.clone();
+        let result = tokio::task::spawn_blocking(move || {
+            let graph = state.graph.read().unwrap();
+            let revision = graph.revision();
+            if revision == saved {
+                return Ok(revision);
+            }
+            let config = graph_config(std::path::Path::new(&state.root_dir), &state.defines, &state.plugins, &state.loaders);
+            graph.save(&file, &config).map(|_| revision)
+        })
+        .await;
+        match result {
+            Ok(Ok(revision)) => saved = revision,
+            Ok(Err(e)) => tracing::warn!("Failed to save module graph to {}: {}", graph_file.display(), e),
+            Err(_) => return,
+        }
+    }
+}
+
+// Recompiles modules that changed while the server was down. Deleted ones have
+// already been emptied by `ModuleGraph::validate`.
+fn recompile_stale(state: &AppState, stale: &[ModuleId]) {
+    let modules: Vec<String> = {
+        let graph = state.graph.read().unwrap();
+        stale.iter().filter_map(|id| graph.modules.get(id.0).map(|m| m.path.clone())).collect()
+    };
+    for path_str in modules {
+        let abs_path = graph::module_file(std::path::Path::new(&state.root_dir), &path_str);
+        let Ok(bytes) = state.plugins.source(&path_str, &abs_path) else { continue };
+        let is_vendor = is_vendor_path(&abs_path);
+        update_module(state, &path_str, &abs_path, is_vendor, &bytes);
+    }
+}
+
+/// Project configuration for the dev server.
+#[derive(Debug, Clone)]
+pub struct ServerOptions {
+    /// Selects `.env.[mode]` and sets `import.meta.env.MODE`.
+    pub mode: String,
+    /// Extra compile-time replacements, e.g. `__APP_VERSION__` -> `"1.2.0"`.
+    pub define: BTreeMap<String, String>,
+    /// Plugins run over every module; see `plugin`.
+    pub plugins: PluginContainer,
+    /// Loaders by extension or glob, on top of the defaults; see `loader`.
+    pub loader: BTreeMap<String, Loader>,
+    /// Compiles `.svelte` modules; see `svelte`.
+    pub svelte: Option<svelte::SvelteHook>,
+    /// Compiles `.vue` modules; see `vue`.
+    pub vue: Option<vue::VueHook>,
+}
+
+impl Default for ServerOptions {
+    fn default() -> Self {
+        Self {
+            mode: "development".to_string(),
+            define: BTreeMap::new(),
+            plugins: PluginContainer::default(),
+            loader: BTreeMap::new(),
+            svelte: None,
+            vue: None,
+        }
+    }
+}
+
 pub async fn start_server(root: String, port: u16) -> Result<(), std::io::Error> {
+    start_server_with_options(root, port, ServerOptions::default()).await
+}
+
+pub async fn start_server_with_options(root: String, port: u16, options: ServerOptions) -> Result<(), std::io::Error> {
+    let ServerOptions { mode, define, plugins, loader, svelte, vue } = options;
+    let loaders = Arc::new(LoaderRegistry::new(&loader).with_svelte(svelte).with_vue(vue));
     // Week 6: Start Watcher Channel
     let (tx, _) = tokio::sync::broadcast::channel(100);
     
     // Init Resolver
     let resolver = Arc::new(NexusResolver::new(std::path::Path::new(&root)));
     
+    // Pre-bundle dependencies before anything imports them.
+    let deps = Arc::new(DepsOptimizer::new(std::path::Path::new(&root), resolver.clone()));
+    let defines = Arc::new(Defines::load(std::path::Path::new(&root), &mode, &define));
+
     // Spawn Watcher
     let watcher_tx = tx.clone();
-    let graph = Arc::new(RwLock::new(ModuleGraph::new()));
+    // Restore the graph from the last session; modules whose files changed
+    // meanwhile are recompiled below.
+    let graph_file = std::path::Path::new(&root).join(GRAPH_FILE);
+    let config = graph_config(std::path::Path::new(&root), &defines, &plugins, &loaders);
+    let (restored, stale) = match ModuleGraph::load(&graph_file, &config) {
+        Some(mut graph) => {
+            let stale = graph.validate(std::path::Path::new(&root));
+            tracing::info!("Restored module graph: {} modules, {} changed", graph.modules.len(), stale.len());
+            (graph, stale)
+        }
+        None => (ModuleGraph::new(), Vec::new()),
+    };
+    // What is on disk already, unless stale modules get recompiled.
+    let saved_revision = restored.revision();
+    let graph = Arc::new(RwLock::new(restored));
     
     let watcher_graph = graph.clone();
     let watcher_root = root.clone();
     let server_root = root.clone();
+    let watcher_defines = defines.clone();
     let watcher_resolver = resolver.clone(); // If watcher needs compilation, it needs resolver too?
+    let watcher_plugins = plugins.clone();
+    let watcher_loaders = loaders.clone();
     // Watcher logic: "Compile on change".
     // compilation doesn't need resolver.
     // BUT graph updating needs resolution to find deps.
@@ -449,20 +819,30 @@ pub async fn start_server(root: String, port: u16) -> Result<(), std::io::Error>
     // Yes.
     
     tokio::spawn(async move {
-        watcher::start_watcher(watcher_root, watcher_graph, watcher_tx, watcher_resolver).await;
+        watcher::start_watcher(watcher_root, watcher_graph, watcher_tx, watcher_resolver, watcher_defines, watcher_plugins, watcher_loaders).await;
     });
+    tokio::spawn(watcher::watch_lockfiles(root.clone(), graph.clone(), tx.clone(), deps.clone()));
 
     let state = AppState {
         graph,
         root_dir: server_root.clone(),
         hmr_tx: tx,
         resolver,
+        cache: TransformCache::open(std::path::Path::new(&root), &config).map(Arc::new),
+        deps: deps.clone(),
+        defines,
+        plugins: plugins.clone(),
+        loaders,
     };
+    recompile_stale(&state, &stale);
+    let saved_state = state.clone();
+    tokio::spawn(save_graph_periodically(state.clone(), graph_file.clone(), saved_revision));
 
     let serve_dir = ServeDir::new(&root);
 
+    let service_state = state.clone();
     let service = tower::service_fn(move |req: axum::extract::Request| {
-        let state = state.clone();
+        let state = service_state.clone();
         let serve_dir = serve_dir.clone();
         
         async move {
@@ -480,12 +860,19 @@ pub async fn start_server(root: String, port: u16) -> Result<(), std::io::Error>
             // All other modules are INSIDE bundle. Browser never requests them individually!
             // So `handle_module` is ONLY for the entry point (or HMR updates if we fetch individually).
             
-            if path.ends_with(".ts") || path.ends_with(".tsx") || path.ends_with(".js") || path.ends_with(".jsx") {
+            // Components are modules too, and fetched again on hot updates.
+            if state.loaders.get(path).is_some_and(|loader| loader.is_script() || matches!(loader, Loader::Vue | Loader::Svelte | Loader::Mdx)) {
                 let response = handle_module_logic(state, uri).await;
-                Ok::<_, std::io::Error>(response.into_response())
+                Ok::<_, std::convert::Infallible>(response.into_response())
             } else if path.starts_with("/_nexus/chunk") {
                  let response = handle_chunk(State(state), uri).await;
-                 Ok::<_, std::io::Error>(response.into_response())
+                 Ok::<_, std::convert::Infallible>(response.into_response())
+            } else if path.starts_with("/_nexus/worker") {
+                 let response = handle_worker(State(state), uri).await;
+                 Ok::<_, std::convert::Infallible>(response.into_response())
+            } else if !state.plugins.is_empty() && (path.ends_with('/') || path.ends_with(".html")) {
+                let response = handle_html(state, uri).await;
+                Ok::<_, std::convert::Infallible>(response)
             } else {
                 let res = serve_dir.oneshot(req).await;
                 match res {
@@ -502,11 +889,23 @@ pub async fn start_server(root: String, port: u16) -> Result<(), std::io::Error>
         .fallback_service(service)
         .layer(TraceLayer::new_for_http())
         .with_state(state);
+    let app = plugins.configure_server(app);
 
 
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
     
     tracing::info!("starting server on {}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app.into_make_service()).await
+    axum::serve(listener, app.into_make_service())
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await?;
+
+    let config = graph_config(std::path::Path::new(&root), &saved_state.defines, &saved_state.plugins, &saved_state.loaders);
+    let graph = saved_state.graph.read().unwrap();
+    if let Err(e) = graph.save(&graph_file, &config) {
+        tracing::warn!("Failed to save module graph to {}: {}", graph_file.display(), e);
+    }
+    Ok(())
 }
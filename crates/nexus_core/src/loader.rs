@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::str::FromStr;
+use base64::Engine;
+use oxc_span::SourceType;
+use crate::compiler::{self, AssetQuery, CompileResult};
+use crate::import_meta::glob_match;
+use crate::svelte::{self, SvelteHook};
+use crate::vue::{self, VueHook};
+use crate::{mdx, svg, wasm};
+
+// Loaders.
+//
+// A module's `Loader` decides how its file becomes JS. The dev server, its
+// watcher and `build` all look it up in one `LoaderRegistry`, by extension
+// (`.glsl`) or by glob over the module id (`/src/legacy/**`). A project's own
+// rules come first, globs before extensions; the defaults cover the file
+// types Nexus has always handled.
+//
+// Files without a loader are of no interest to the watcher. Imported anyway,
+// they are parsed as JS.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Loader {
+    Js,
+    Jsx,
+    Ts,
+    Tsx,
+    Css,
+    /// `export default` the parsed JSON.
+    Json,
+    /// `export default` the file's text.
+    Text,
+    /// `export default` its bytes in base64.
+    Base64,
+    /// `export default` a `data:` URL.
+    DataUrl,
+    /// `export default` the URL of a content-hashed copy.
+    File,
+    /// `export default` the URL of a copy under its own name.
+    Copy,
+    /// Nothing: the module is empty.
+    Empty,
+    /// Images: inlined as a `data:` URL when small, a file otherwise.
+    Asset,
+    Svg,
+    Wasm,
+    /// Vue single-file components.
+    Vue,
+    /// Svelte components, by the project's compiler; see `svelte`.
+    Svelte,
+    /// MDX and Markdown documents, as React components; see `mdx`.
+    Mdx,
+}
+
+impl Loader {
+    pub const ALL: [Loader; 18] = [
+        Loader::Js, Loader::Jsx, Loader::Ts, Loader::Tsx, Loader::Css, Loader::Json, Loader::Text, Loader::Base64,
+        Loader::DataUrl, Loader::File, Loader::Copy, Loader::Empty, Loader::Asset, Loader::Svg, Loader::Wasm, Loader::Vue,
+        Loader::Svelte, Loader::Mdx,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Loader::Js => "js",
+            Loader::Jsx => "jsx",
+            Loader::Ts => "ts",
+            Loader::Tsx => "tsx",
+            Loader::Css => "css",
+            Loader::Json => "json",
+            Loader::Text => "text",
+            Loader::Base64 => "base64",
+            Loader::DataUrl => "dataurl",
+            Loader::File => "file",
+            Loader::Copy => "copy",
+            Loader::Empty => "empty",
+            Loader::Asset => "asset",
+            Loader::Svg => "svg",
+            Loader::Wasm => "wasm",
+            Loader::Vue => "vue",
+            Loader::Svelte => "svelte",
+            Loader::Mdx => "mdx",
+        }
+    }
+
+    /// JS or TS source, compiled by `CompilationUnit`.
+    pub fn is_script(self) -> bool {
+        matches!(self, Loader::Js | Loader::Jsx | Loader::Ts | Loader::Tsx)
+    }
+
+    /// Whether React Refresh registers the module's components.
+    pub fn is_jsx(self) -> bool {
+        matches!(self, Loader::Jsx | Loader::Tsx | Loader::Mdx)
+    }
+
+    /// What to parse a script module `id` as. Its extension decides when it
+    /// agrees with the loader, so `.cjs` stays a script and `.d.ts` a
+    /// definition file.
+    pub fn source_type(self, id: &str) -> SourceType {
+        let from_path = SourceType::from_path(id).ok();
+        match self {
+            Loader::Js | Loader::Jsx => from_path.filter(|t| t.is_javascript()).unwrap_or(SourceType::mjs().with_jsx(true)),
+            Loader::Ts => from_path.filter(|t| t.is_typescript() && !t.is_jsx()).unwrap_or(SourceType::ts()),
+            Loader::Tsx => from_path.filter(|t| t.is_typescript() && t.is_jsx()).unwrap_or(SourceType::tsx()),
+            // Compiled components keep the types of a `lang="ts"` script.
+            Loader::Vue => SourceType::ts(),
+            // Documents compile to JSX, and may embed some.
+            Loader::Mdx => SourceType::tsx(),
+            _ => from_path.unwrap_or_default(),
+        }
+    }
+
+    /// Whether the compiled module can go to the transform cache. Binary
+    /// files are cheap to compile and not worth storing twice, and files
+    /// emitted next to the bundle aren't cached at all.
+    pub fn is_cacheable(self) -> bool {
+        !matches!(self, Loader::Base64 | Loader::DataUrl | Loader::File | Loader::Copy | Loader::Asset | Loader::Svg | Loader::Wasm)
+    }
+
+    /// Whether `build` parses the compiled module, for modules with imports
+    /// or named exports worth shaking. The rest export a single value.
+    pub fn is_analyzed(self) -> bool {
+        matches!(self, Loader::Json | Loader::Svg | Loader::Wasm | Loader::Vue | Loader::Svelte | Loader::Mdx) || self.is_script()
+    }
+
+    /// Compiles module `id`, or `None` for scripts, which need a
+    /// `CompilationUnit`.
+    pub fn compile(self, bytes: &[u8], id: &str, is_prod: bool) -> Option<CompileResult> {
+        let export = |value: String| {
+            let code = format!("export default {};", value);
+            CompileResult { code, sourcemap: None, css: None, asset: None }
+        };
+        let compiled = match self {
+            Loader::Js | Loader::Jsx | Loader::Ts | Loader::Tsx => return None,
+            Loader::Css => compiler::compile_css(&String::from_utf8_lossy(bytes), id, is_prod),
+            Loader::Json => export(String::from_utf8_lossy(bytes).into_owned()),
+            Loader::Text => compiler::compile_variant(bytes, id, AssetQuery::Raw, is_prod),
+            Loader::Base64 => export(serde_json::to_string(&base64::engine::general_purpose::STANDARD.encode(bytes)).unwrap()),
+            Loader::DataUrl => compiler::compile_variant(bytes, id, AssetQuery::Inline, is_prod),
+            Loader::File => compiler::compile_variant(bytes, id, AssetQuery::Url, is_prod),
+            Loader::Copy if is_prod => {
+                let name = format!("assets/{}", Path::new(id).file_name().unwrap_or_default().to_string_lossy());
+                let mut compiled = export(serde_json::to_string(&format!("/{}", name)).unwrap());
+                compiled.asset = Some((name, bytes.to_vec()));
+                compiled
+            }
+            Loader::Copy => compiler::compile_variant(bytes, id, AssetQuery::Url, is_prod),
+            Loader::Empty => export("undefined".to_string()),
+            Loader::Asset => compiler::compile_asset(bytes, id, is_prod),
+            Loader::Svg => svg::compile_svg(bytes, id, is_prod),
+            Loader::Wasm => wasm::compile_wasm(bytes, id, is_prod),
+            Loader::Vue => vue::compile_vue(None, bytes, id, is_prod).unwrap_or_else(|e| error_module(id, &e)),
+            // Only a registry knows the project's compiler.
+            Loader::Svelte => error_module(id, &svelte::missing_compiler()),
+            Loader::Mdx => mdx::compile_mdx(bytes, id, is_prod),
+        };
+        Some(compiled)
+    }
+}
+
+impl FromStr for Loader {
+    type Err = std::io::Error;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter().find(|loader| loader.name() == name).ok_or_else(|| {
+            let names = Self::ALL.map(Loader::name).join(", ");
+            std::io::Error::new(std::io::ErrorKind::InvalidInput, format!("unknown loader `{}`, expected one of {}", name, names))
+        })
+    }
+}
+
+const DEFAULTS: &[(&str, Loader)] = &[
+    ("js", Loader::Js),
+    ("mjs", Loader::Js),
+    ("cjs", Loader::Js),
+    ("jsx", Loader::Jsx),
+    ("ts", Loader::Ts),
+    ("mts", Loader::Ts),
+    ("cts", Loader::Ts),
+    ("tsx", Loader::Tsx),
+    ("css", Loader::Css),
+    ("json", Loader::Json),
+    ("svg", Loader::Svg),
+    ("wasm", Loader::Wasm),
+    ("png", Loader::Asset),
+    ("jpg", Loader::Asset),
+    ("jpeg", Loader::Asset),
+    ("gif", Loader::Asset),
+    ("vue", Loader::Vue),
+    ("svelte", Loader::Svelte),
+    ("mdx", Loader::Mdx),
+    ("md", Loader::Mdx),
+];
+
+/// A module throwing `error` when run, served in dev in place of one that
+/// failed to compile.
+pub fn error_module(id: &str, error: &std::io::Error) -> CompileResult {
+    tracing::error!("Failed to compile {}: {}", id, error);
+    let message = serde_json::to_string(&format!("[Nexus] {}", error)).unwrap();
+    CompileResult { code: format!("throw new Error({});", message), sourcemap: None, css: None, asset: None }
+}
+
+/// What to parse module `id` as when nothing says otherwise: by the default
+/// loader of its extension, so compiled `.vue` modules parse as TS.
+pub fn source_type_of(id: &str) -> SourceType {
+    match LoaderRegistry::default().get(id) {
+        Some(loader) => loader.source_type(id),
+        None => SourceType::from_path(id).unwrap_or_default(),
+    }
+}
+
+/// Loaders by extension or glob, and the compilers some of them need.
+/// Cheap to clone.
+#[derive(Debug, Clone, Default)]
+pub struct LoaderRegistry {
+    globs: Vec<(String, Loader)>,
+    extensions: BTreeMap<String, Loader>,
+    svelte: Option<SvelteHook>,
+    vue: Option<VueHook>,
+}
+
+impl LoaderRegistry {
+    /// The defaults plus `rules`: keys with a `*` or `/` are globs over
+    /// module ids, others extensions, with or without the dot. A glob
+    /// without a `/` (`*.glsl`) matches file names in any directory.
+    pub fn new(rules: &BTreeMap<String, Loader>) -> Self {
+        let mut registry = Self::default();
+        for (key, &loader) in rules {
+            if key.contains('*') || key.contains('/') {
+                // Ids start with `/`, globs written from the root may not.
+                let glob = if !key.contains('/') {
+                    format!("/**/{}", key)
+                } else if key.starts_with('/') {
+                    key.clone()
+                } else {
+                    format!("/{}", key)
+                };
+                registry.globs.push((glob, loader));
+            } else {
+                registry.extensions.insert(key.trim_start_matches('.').to_string(), loader);
+            }
+        }
+        registry
+    }
+
+    /// The loader of module `id`, ignoring any `?raw`-style query.
+    pub fn get(&self, id: &str) -> Option<Loader> {
+        let file = compiler::split_query(id).0;
+        if let Some(&(_, loader)) = self.globs.iter().find(|(glob, _)| glob_match(glob, file)) {
+            return Some(loader);
+        }
+        let ext = Path::new(file).extension()?.to_str()?;
+        self.extensions.get(ext).copied().or_else(|| DEFAULTS.iter().find(|(e, _)| *e == ext).map(|&(_, loader)| loader))
+    }
+
+    /// Compiles `.svelte` modules with `svelte`.
+    pub fn with_svelte(mut self, svelte: Option<SvelteHook>) -> Self {
+        self.svelte = svelte;
+        self
+    }
+
+    /// Compiles `.vue` modules with `vue` rather than Nexus' own compiler.
+    pub fn with_vue(mut self, vue: Option<VueHook>) -> Self {
+        self.vue = vue;
+        self
+    }
+
+    /// `Loader::compile`, with this registry's compilers.
+    pub fn compile(&self, loader: Loader, bytes: &[u8], id: &str, is_prod: bool) -> std::io::Result<Option<CompileResult>> {
+        match loader {
+            Loader::Svelte => match &self.svelte {
+                Some(hook) => svelte::compile_svelte(hook, bytes, id, is_prod).map(Some),
+                None => Err(svelte::missing_compiler()),
+            },
+            Loader::Vue => vue::compile_vue(self.vue.as_ref(), bytes, id, is_prod).map(Some),
+            _ => Ok(loader.compile(bytes, id, is_prod)),
+        }
+    }
+
+    /// Like `get`, falling back to JS.
+    pub fn loader(&self, id: &str) -> Loader {
+        self.get(id).unwrap_or(Loader::Js)
+    }
+
+    /// Appended to the transform cache configuration, since output depends
+    /// on the rules and compilers. Empty without any.
+    pub fn cache_key(&self) -> String {
+        let globs = self.globs.iter().map(|(glob, loader)| (glob.as_str(), loader));
+        let rules: String = globs.chain(self.extensions.iter().map(|(ext, loader)| (ext.as_str(), loader))).map(|(key, loader)| format!(":{}={}", key, loader.name())).collect();
+        let compilers = [self.svelte.as_ref().map(SvelteHook::name), self.vue.as_ref().map(VueHook::name)];
+        compilers.into_iter().flatten().fold(rules, |key, name| format!("{}:{}", key, name))
+    }
+}
@@ -0,0 +1,1093 @@
+use std::collections::{BTreeSet, HashMap};
+use serde_json::{json, Map, Value};
+use crate::compiler::{self, CompileResult};
+use crate::loader::Loader;
+use crate::svg::decode_entities;
+use crate::unit::CompilationUnit;
+
+// MDX and Markdown.
+//
+// A `.mdx` or `.md` file becomes a JSX module exporting
+//
+// - `frontmatter`: the YAML between the leading `---` lines, as data;
+// - `toc`: its headings, `{ depth, text, id }` each, `id` being the slug the
+//   heading element gets;
+// - a default `MDXContent` component rendering the document. Markdown
+//   elements render through `props.components`, so `<MDXContent components={{
+//   h1: Title }} />` restyles them.
+//
+// In `.mdx`, top-level `import`/`export` statements are kept as they are and
+// JSX and `{expressions}` pass through into the output, so the module goes
+// through `CompilationUnit` as TSX: imports become graph edges and the JSX is
+// handled like any `.tsx` module's. `export default` names a layout the
+// content is wrapped in. Plain `.md` has none of that: HTML blocks in it
+// render as HTML, inline tags as text.
+//
+// The Markdown is CommonMark's everyday subset plus GFM tables, strikethrough
+// and task lists. Reference-style links and the finer points of emphasis
+// aren't supported.
+
+pub fn compile_mdx(bytes: &[u8], id: &str, _is_prod: bool) -> CompileResult {
+    let source = String::from_utf8_lossy(bytes).replace("\r\n", "\n");
+    let is_mdx = !compiler::split_query(id).0.ends_with(".md");
+    let (frontmatter, body) = split_frontmatter(&source);
+    let lines: Vec<String> = body.lines().map(expand_tabs).collect();
+
+    let mut doc = Document { is_mdx, esm: Vec::new(), toc: Vec::new(), slugs: HashMap::new(), components: BTreeSet::new(), layout: false };
+    let content = doc.blocks(&lines, true, false);
+
+    let mut code = String::new();
+    for esm in &doc.esm {
+        code.push_str(esm);
+        code.push('\n');
+    }
+    code.push_str(&format!("export const frontmatter = {};\n", frontmatter));
+    code.push_str(&format!("export const toc = {};\n", Value::Array(doc.toc)));
+    let defaults: Vec<String> = doc.components.iter().map(|tag| format!("{}: \"{}\", ", tag, tag)).collect();
+    code.push_str(&format!(
+        "function _createMdxContent(props) {{\n  const _components = {{ {}...props.components }};\n  return <>{}</>;\n}}\n",
+        defaults.concat(),
+        content
+    ));
+    let render = if doc.layout {
+        "<MDXLayout {...props}><_createMdxContent {...props} /></MDXLayout>"
+    } else {
+        "_createMdxContent(props)"
+    };
+    code.push_str(&format!("export default function MDXContent(props = {{}}) {{\n  return {};\n}}\n", render));
+
+    CompilationUnit::parse_as(id, code, Loader::Mdx.source_type(id)).compile_result()
+}
+
+fn expand_tabs(line: &str) -> String {
+    let tabs = line.len() - line.trim_start_matches('\t').len();
+    format!("{}{}", "    ".repeat(tabs), &line[tabs..])
+}
+
+fn indent_of(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+fn json(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+fn run_len(text: &str, c: char) -> usize {
+    text.len() - text.trim_start_matches(c).len()
+}
+
+// Blocks.
+
+struct Document {
+    is_mdx: bool,
+    esm: Vec<String>,
+    toc: Vec<Value>,
+    slugs: HashMap<String, usize>,
+    // Markdown elements used, rendered through `_components`.
+    components: BTreeSet<&'static str>,
+    layout: bool,
+}
+
+struct Marker {
+    ordered: bool,
+    start: u64,
+    delimiter: char,
+    // Where the item's content starts.
+    offset: usize,
+    empty: bool,
+}
+
+// A list item's marker, `- `, `* `, `1. ` or `1) `.
+fn list_marker(line: &str) -> Option<Marker> {
+    let indent = indent_of(line);
+    let rest = &line[indent..];
+    let (ordered, start, delimiter, len) = match rest.chars().next()? {
+        c @ ('-' | '*' | '+') => (false, 1, c, 1),
+        _ => {
+            let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+            let delimiter = rest[digits..].chars().next().filter(|&d| d == '.' || d == ')')?;
+            if digits == 0 || digits > 9 {
+                return None;
+            }
+            (true, rest[..digits].parse().ok()?, delimiter, digits + 1)
+        }
+    };
+    let after = &rest[len..];
+    if !after.is_empty() && !after.starts_with(' ') {
+        return None;
+    }
+    let empty = after.trim().is_empty();
+    let spaces = if empty || indent_of(after) > 4 { 1 } else { indent_of(after) };
+    Some(Marker { ordered, start, delimiter, offset: indent + len + spaces, empty })
+}
+
+fn fence_open(line: &str) -> Option<(char, usize, &str)> {
+    let text = line.trim_start();
+    let c = text.chars().next().filter(|&c| c == '`' || c == '~')?;
+    let n = run_len(text, c);
+    let info = text[n..].trim();
+    (n >= 3 && !(c == '`' && info.contains('`'))).then_some((c, n, info))
+}
+
+fn atx_heading(text: &str) -> Option<(usize, &str)> {
+    let level = run_len(text, '#');
+    let rest = &text[level..];
+    if !(1..=6).contains(&level) || !(rest.is_empty() || rest.starts_with(' ')) {
+        return None;
+    }
+    let rest = rest.trim();
+    // A closing run of `#`s goes, unless it's part of the text.
+    let content = rest.trim_end_matches('#');
+    let content = if content.is_empty() || content.ends_with(' ') { content.trim_end() } else { rest };
+    Some((level, content))
+}
+
+fn is_hr(text: &str) -> bool {
+    let chars: Vec<char> = text.chars().filter(|c| *c != ' ').collect();
+    chars.len() >= 3 && matches!(chars[0], '-' | '*' | '_') && chars.iter().all(|&c| c == chars[0])
+}
+
+// Whether `line` starts a block that ends a paragraph.
+fn interrupts(line: &str) -> bool {
+    let text = line.trim();
+    fence_open(line).is_some()
+        || atx_heading(text).is_some()
+        || is_hr(text)
+        || text.starts_with('>')
+        || list_marker(line).is_some_and(|m| !m.empty && (!m.ordered || m.start == 1))
+}
+
+fn table_cells(line: &str) -> Vec<String> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = if line.ends_with('|') && !line.ends_with("\\|") { &line[..line.len() - 1] } else { line };
+    let mut cells = Vec::new();
+    let mut cell = String::new();
+    let mut in_code = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&'|') => cell.push(chars.next().unwrap()),
+            '`' => {
+                in_code = !in_code;
+                cell.push(c);
+            }
+            '|' if !in_code => cells.push(std::mem::take(&mut cell).trim().to_string()),
+            _ => cell.push(c),
+        }
+    }
+    cells.push(cell.trim().to_string());
+    cells
+}
+
+fn table_alignments(line: &str) -> Option<Vec<Option<&'static str>>> {
+    if !line.contains('-') || !line.chars().all(|c| matches!(c, '|' | '-' | ':' | ' ')) {
+        return None;
+    }
+    table_cells(line)
+        .iter()
+        .map(|cell| {
+            let dashes = cell.trim_matches(':');
+            if dashes.is_empty() || !dashes.chars().all(|c| c == '-') {
+                return None;
+            }
+            Some(match (cell.starts_with(':'), cell.ends_with(':')) {
+                (true, true) => Some("center"),
+                (false, true) => Some("right"),
+                (true, false) => Some("left"),
+                (false, false) => None,
+            })
+        })
+        .collect()
+}
+
+// The first blank line from `start`, or the end.
+fn block_end(lines: &[String], start: usize) -> usize {
+    (start..lines.len()).find(|&i| lines[i].trim().is_empty()).unwrap_or(lines.len())
+}
+
+fn is_esm(text: &str) -> bool {
+    ["import", "export"].iter().any(|keyword| {
+        text.strip_prefix(keyword).is_some_and(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '{' || c == '*'))
+    })
+}
+
+impl Document {
+    fn element(&mut self, tag: &'static str, attrs: &str, children: &str) -> String {
+        self.components.insert(tag);
+        if children.is_empty() {
+            format!("<_components.{}{} />", tag, attrs)
+        } else {
+            format!("<_components.{}{}>{}</_components.{}>", tag, attrs, children, tag)
+        }
+    }
+
+    // `lines` as JSX. `tight` list items render paragraphs without `<p>`.
+    fn blocks(&mut self, lines: &[String], top_level: bool, tight: bool) -> String {
+        let mut out = String::new();
+        let mut i = 0;
+        while i < lines.len() {
+            let line = lines[i].as_str();
+            let text = line.trim();
+            if text.is_empty() {
+                i += 1;
+                continue;
+            }
+
+            if top_level && self.is_mdx && indent_of(line) == 0 && is_esm(text) {
+                let end = block_end(lines, i);
+                let esm = lines[i..end].join("\n");
+                // `export default` names the layout.
+                let esm = match esm.strip_prefix("export default ") {
+                    Some(layout) => {
+                        self.layout = true;
+                        format!("const MDXLayout = {}", layout)
+                    }
+                    None => esm,
+                };
+                self.esm.push(esm);
+                i = end;
+                continue;
+            }
+
+            // Indentation is for JSX in MDX, not code.
+            if !self.is_mdx && indent_of(line) >= 4 {
+                let mut end = i;
+                while end < lines.len() && (lines[end].trim().is_empty() || indent_of(&lines[end]) >= 4) {
+                    end += 1;
+                }
+                while lines[end - 1].trim().is_empty() {
+                    end -= 1;
+                }
+                let code: Vec<&str> = lines[i..end].iter().map(|l| l.get(4..).unwrap_or("")).collect();
+                out.push_str(&self.code_block(&(code.join("\n") + "\n"), ""));
+                i = end;
+                continue;
+            }
+
+            if let Some((fence, len, info)) = fence_open(line) {
+                let indent = indent_of(line);
+                let close = (i + 1..lines.len()).find(|&j| {
+                    let t = lines[j].trim();
+                    t.starts_with(fence) && run_len(t, fence) >= len && t.trim_start_matches(fence).is_empty()
+                });
+                let end = close.unwrap_or(lines.len());
+                let code: String = lines[i + 1..end].iter().map(|l| format!("{}\n", &l[indent_of(l).min(indent)..])).collect();
+                out.push_str(&self.code_block(&code, info.split_whitespace().next().unwrap_or("")));
+                i = end + 1;
+                continue;
+            }
+
+            if let Some((level, content)) = atx_heading(text) {
+                out.push_str(&self.heading(level, content));
+                i += 1;
+                continue;
+            }
+
+            if is_hr(text) {
+                out.push_str(&self.element("hr", "", ""));
+                i += 1;
+                continue;
+            }
+
+            if text.starts_with('>') {
+                let mut quoted = Vec::new();
+                while i < lines.len() {
+                    let t = lines[i].trim_start();
+                    if let Some(rest) = t.strip_prefix('>') {
+                        quoted.push(rest.strip_prefix(' ').unwrap_or(rest).to_string());
+                    } else if !t.is_empty() && quoted.last().is_some_and(|l: &String| !l.trim().is_empty()) && !interrupts(t) {
+                        // A lazy continuation of the quoted paragraph.
+                        quoted.push(t.to_string());
+                    } else {
+                        break;
+                    }
+                    i += 1;
+                }
+                let inner = self.blocks(&quoted, false, false);
+                out.push_str(&self.element("blockquote", "", &inner));
+                continue;
+            }
+
+            if let Some(marker) = list_marker(line) {
+                i = self.list(lines, i, marker, &mut out);
+                continue;
+            }
+
+            if line.contains('|') && i + 1 < lines.len() {
+                if let Some(alignments) = table_alignments(lines[i + 1].trim()).filter(|a| a.len() == table_cells(line).len()) {
+                    i = self.table(lines, i, &alignments, &mut out);
+                    continue;
+                }
+            }
+
+            // A paragraph, or a setext heading.
+            let mut end = i + 1;
+            let mut setext = None;
+            while end < lines.len() {
+                let t = lines[end].trim();
+                if t.is_empty() {
+                    break;
+                }
+                if t.chars().all(|c| c == '=') {
+                    setext = Some(1);
+                } else if t.chars().all(|c| c == '-') {
+                    setext = Some(2);
+                } else if interrupts(&lines[end]) {
+                    break;
+                }
+                if setext.is_some() {
+                    break;
+                }
+                end += 1;
+            }
+            let paragraph: Vec<&str> = lines[i..end].iter().map(|l| l.trim_start()).collect();
+            let paragraph = paragraph.join("\n");
+            let paragraph = paragraph.trim_end();
+            match setext {
+                Some(level) => {
+                    out.push_str(&self.heading(level, paragraph));
+                    i = end + 1;
+                }
+                None => {
+                    out.push_str(&self.paragraph(paragraph, tight));
+                    i = end;
+                }
+            }
+        }
+        out
+    }
+
+    fn paragraph(&mut self, text: &str, tight: bool) -> String {
+        if text.starts_with("<!--") {
+            return String::new();
+        }
+        if self.is_mdx {
+            // Block-level JSX and expressions aren't wrapped in `<p>`.
+            if text.starts_with(['<', '{']) && text.ends_with(['>', '}']) {
+                let nodes = parse_inline(text, true);
+                return self.render(&nodes, true);
+            }
+        } else if text.starts_with('<') && text[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/') {
+            return format!("<div dangerouslySetInnerHTML={{{{ __html: {} }}}} />", json(text));
+        }
+        let nodes = parse_inline(text, self.is_mdx);
+        let inline = self.render(&nodes, false);
+        if tight {
+            inline
+        } else {
+            self.element("p", "", &inline)
+        }
+    }
+
+    fn heading(&mut self, level: usize, content: &str) -> String {
+        let nodes = parse_inline(content, self.is_mdx);
+        let text = plain_text(&nodes);
+        let id = self.slug(&text);
+        self.toc.push(json!({ "depth": level, "text": text, "id": id }));
+        let inline = self.render(&nodes, false);
+        let tag = ["h1", "h2", "h3", "h4", "h5", "h6"][level - 1];
+        self.element(tag, &format!(" id={{{}}}", json(&id)), &inline)
+    }
+
+    // Lowercase words joined by `-`, numbered when taken, as GitHub does.
+    fn slug(&mut self, text: &str) -> String {
+        let base: String = text
+            .trim()
+            .to_lowercase()
+            .chars()
+            .filter_map(|c| match c {
+                c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+                c if c.is_whitespace() => Some('-'),
+                _ => None,
+            })
+            .collect();
+        let count = self.slugs.entry(base.clone()).or_insert(0);
+        let slug = if *count == 0 { base } else { format!("{}-{}", base, count) };
+        *count += 1;
+        slug
+    }
+
+    fn code_block(&mut self, code: &str, lang: &str) -> String {
+        let attrs = if lang.is_empty() { String::new() } else { format!(" className={{{}}}", json(&format!("language-{}", lang))) };
+        let code = self.element("code", &attrs, &format!("{{{}}}", json(code)));
+        self.element("pre", "", &code)
+    }
+
+    // The list starting at `lines[start]`; returns the line after it.
+    fn list(&mut self, lines: &[String], start: usize, first: Marker, out: &mut String) -> usize {
+        let mut items = Vec::new();
+        let mut loose = false;
+        let mut i = start;
+        while let Some(marker) = lines.get(i).and_then(|l| list_marker(l)).filter(|m| m.ordered == first.ordered && m.delimiter == first.delimiter) {
+            if is_hr(lines[i].trim()) {
+                break;
+            }
+            let mut item = vec![lines[i].get(marker.offset..).unwrap_or("").to_string()];
+            i += 1;
+            while i < lines.len() {
+                let line = &lines[i];
+                if line.trim().is_empty() {
+                    // Blank lines stay in the item when indented content follows.
+                    match (i..lines.len()).find(|&j| !lines[j].trim().is_empty()) {
+                        Some(next) if indent_of(&lines[next]) >= marker.offset => {
+                            item.extend((i..next).map(|_| String::new()));
+                            i = next;
+                            continue;
+                        }
+                        _ => break,
+                    }
+                }
+                if indent_of(line) >= marker.offset {
+                    item.push(line[marker.offset..].to_string());
+                } else if item.last().is_some_and(|l| !l.trim().is_empty()) && !interrupts(line) && list_marker(line).is_none() {
+                    item.push(line.trim_start().to_string());
+                } else {
+                    break;
+                }
+                i += 1;
+            }
+            loose |= item.iter().any(|l| l.is_empty());
+            items.push(item);
+
+            let next = (i..lines.len()).find(|&j| !lines[j].trim().is_empty());
+            match next {
+                Some(next) if next > i && list_marker(&lines[next]).is_some_and(|m| m.ordered == first.ordered && m.delimiter == first.delimiter) => {
+                    loose = true;
+                    i = next;
+                }
+                _ => {}
+            }
+        }
+
+        let mut children = String::new();
+        for mut item in items {
+            // GFM task lists.
+            let mut checkbox = String::new();
+            let first_line = item[0].clone();
+            for (prefix, checked) in [("[ ] ", false), ("[x] ", true), ("[X] ", true)] {
+                if let Some(rest) = first_line.strip_prefix(prefix) {
+                    checkbox = self.element("input", &format!(" type=\"checkbox\" checked={{{}}} disabled", checked), "");
+                    item[0] = rest.to_string();
+                }
+            }
+            let content = self.blocks(&item, false, !loose);
+            children.push_str(&self.element("li", "", &(checkbox + &content)));
+        }
+        let (tag, attrs) = match first {
+            Marker { ordered: true, start: 1, .. } => ("ol", String::new()),
+            Marker { ordered: true, start, .. } => ("ol", format!(" start={{{}}}", start)),
+            _ => ("ul", String::new()),
+        };
+        out.push_str(&self.element(tag, &attrs, &children));
+        i
+    }
+
+    // The table starting at `lines[start]`; returns the line after it.
+    fn table(&mut self, lines: &[String], start: usize, alignments: &[Option<&'static str>], out: &mut String) -> usize {
+        let row = |doc: &mut Self, line: &str, tag: &'static str| {
+            let mut cells = table_cells(line);
+            cells.resize(alignments.len(), String::new());
+            let cells: String = cells
+                .iter()
+                .zip(alignments)
+                .map(|(cell, align)| {
+                    let attrs = align.map(|a| format!(" style={{{{ textAlign: \"{}\" }}}}", a)).unwrap_or_default();
+                    let nodes = parse_inline(cell, doc.is_mdx);
+                    let inline = doc.render(&nodes, false);
+                    doc.element(tag, &attrs, &inline)
+                })
+                .collect();
+            doc.element("tr", "", &cells)
+        };
+        let head = row(self, &lines[start], "th");
+        let mut body = String::new();
+        let mut i = start + 2;
+        while i < lines.len() && !lines[i].trim().is_empty() && !interrupts(&lines[i]) {
+            body.push_str(&row(self, &lines[i], "td"));
+            i += 1;
+        }
+        let mut table = self.element("thead", "", &head);
+        if !body.is_empty() {
+            table.push_str(&self.element("tbody", "", &body));
+        }
+        out.push_str(&self.element("table", "", &table));
+        i
+    }
+
+    // Inline nodes as JSX. In `flow`, text between JSX tags that is only
+    // indentation goes, as it would in JSX.
+    fn render(&mut self, nodes: &[Inline], flow: bool) -> String {
+        let mut out = String::new();
+        for node in nodes {
+            match node {
+                Inline::Text(text) if flow && text.contains('\n') && text.trim().is_empty() => {}
+                Inline::Text(text) => out.push_str(&format!("{{{}}}", json(text))),
+                Inline::Code(code) => out.push_str(&self.element("code", "", &format!("{{{}}}", json(code)))),
+                Inline::Emphasis(tag, children) => {
+                    let inner = self.render(children, false);
+                    out.push_str(&self.element(tag, "", &inner));
+                }
+                Inline::Link { href, title, children } => {
+                    let mut attrs = format!(" href={{{}}}", json(href));
+                    if let Some(title) = title {
+                        attrs.push_str(&format!(" title={{{}}}", json(title)));
+                    }
+                    let inner = self.render(children, false);
+                    out.push_str(&self.element("a", &attrs, &inner));
+                }
+                Inline::Image { src, alt, title } => {
+                    let mut attrs = format!(" src={{{}}} alt={{{}}}", json(src), json(alt));
+                    if let Some(title) = title {
+                        attrs.push_str(&format!(" title={{{}}}", json(title)));
+                    }
+                    out.push_str(&self.element("img", &attrs, ""));
+                }
+                Inline::Break => out.push_str(&self.element("br", "", "")),
+                Inline::Raw(raw) => out.push_str(raw),
+            }
+        }
+        out
+    }
+}
+
+// Inlines.
+
+enum Inline {
+    Text(String),
+    Code(String),
+    // `em`, `strong` or `del`.
+    Emphasis(&'static str, Vec<Inline>),
+    Link { href: String, title: Option<String>, children: Vec<Inline> },
+    Image { src: String, alt: String, title: Option<String> },
+    Break,
+    // JSX tags and expressions, as they are.
+    Raw(String),
+}
+
+fn plain_text(nodes: &[Inline]) -> String {
+    nodes
+        .iter()
+        .map(|node| match node {
+            Inline::Text(text) | Inline::Code(text) => text.clone(),
+            Inline::Emphasis(_, children) | Inline::Link { children, .. } => plain_text(children),
+            Inline::Image { alt, .. } => alt.clone(),
+            Inline::Break => " ".to_string(),
+            Inline::Raw(_) => String::new(),
+        })
+        .collect()
+}
+
+fn parse_inline(text: &str, is_mdx: bool) -> Vec<Inline> {
+    let mut nodes = Vec::new();
+    let mut buf = String::new();
+    let flush = |buf: &mut String, nodes: &mut Vec<Inline>| {
+        if !buf.is_empty() {
+            nodes.push(Inline::Text(decode_entities(buf)));
+            buf.clear();
+        }
+    };
+    let mut i = 0;
+    while let Some(c) = text[i..].chars().next() {
+        let rest = &text[i..];
+        match c {
+            '\\' => match rest[1..].chars().next() {
+                Some('\n') => {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Break);
+                    i += 2;
+                    continue;
+                }
+                Some(next) if next.is_ascii_punctuation() => {
+                    buf.push(next);
+                    i += 2;
+                    continue;
+                }
+                _ => {}
+            },
+            '`' => {
+                if let Some((code, len)) = code_span(rest) {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Code(code));
+                    i += len;
+                } else {
+                    let n = run_len(rest, '`');
+                    buf.push_str(&rest[..n]);
+                    i += n;
+                }
+                continue;
+            }
+            '!' if rest[1..].starts_with('[') => {
+                if let Some((label, src, title, len)) = link(&rest[1..]) {
+                    flush(&mut buf, &mut nodes);
+                    let alt = plain_text(&parse_inline(label, is_mdx));
+                    nodes.push(Inline::Image { src, alt, title });
+                    i += 1 + len;
+                    continue;
+                }
+            }
+            '[' => {
+                if let Some((label, href, title, len)) = link(rest) {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Link { href, title, children: parse_inline(label, is_mdx) });
+                    i += len;
+                    continue;
+                }
+            }
+            '<' => {
+                if let Some((label, href, len)) = autolink(rest) {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Link { href, title: None, children: vec![Inline::Text(label.to_string())] });
+                    i += len;
+                    continue;
+                }
+                if let Some(len) = jsx_tag(rest).filter(|_| is_mdx) {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Raw(rest[..len].to_string()));
+                    i += len;
+                    continue;
+                }
+            }
+            '{' if is_mdx => {
+                if let Some(len) = balanced(rest) {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Raw(rest[..len].to_string()));
+                    i += len;
+                    continue;
+                }
+            }
+            '*' | '_' | '~' => {
+                if let Some((tag, inner, len)) = emphasis(text, i) {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Emphasis(tag, parse_inline(inner, is_mdx)));
+                    i += len;
+                } else {
+                    let n = run_len(rest, c);
+                    buf.push_str(&rest[..n]);
+                    i += n;
+                }
+                continue;
+            }
+            '\n' => {
+                let hard = buf.ends_with("  ");
+                buf.truncate(buf.trim_end_matches(' ').len());
+                if hard {
+                    flush(&mut buf, &mut nodes);
+                    nodes.push(Inline::Break);
+                } else {
+                    buf.push('\n');
+                }
+                i += 1 + indent_of(&text[i + 1..]);
+                continue;
+            }
+            _ => {}
+        }
+        buf.push(c);
+        i += c.len_utf8();
+    }
+    flush(&mut buf, &mut nodes);
+    nodes
+}
+
+// A code span at the start of `text`: its content and length.
+fn code_span(text: &str) -> Option<(String, usize)> {
+    let n = run_len(text, '`');
+    let mut pos = n;
+    while let Some(found) = text[pos..].find('`') {
+        let start = pos + found;
+        let m = run_len(&text[start..], '`');
+        if m == n {
+            let code = text[n..start].replace('\n', " ");
+            let code = match code.strip_prefix(' ').and_then(|c| c.strip_suffix(' ')) {
+                Some(inner) if !code.trim().is_empty() => inner.to_string(),
+                _ => code,
+            };
+            return Some((code, start + m));
+        }
+        pos = start + m;
+    }
+    None
+}
+
+// `[label](destination "title")` at the start of `text`.
+fn link(text: &str) -> Option<(&str, String, Option<String>, usize)> {
+    let mut depth = 0;
+    let mut close = None;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' => {
+                chars.next();
+            }
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    close = Some(i);
+                    break;
+                }
+            }
+            _ => {}
+        }
+    }
+    let close = close?;
+    let label = &text[1..close];
+    let rest = text[close + 1..].strip_prefix('(')?;
+    let dest_start = rest.len() - rest.trim_start().len();
+    let after_dest = &rest[dest_start..];
+    let (href, mut pos) = if let Some(angled) = after_dest.strip_prefix('<') {
+        let end = angled.find('>')?;
+        (angled[..end].to_string(), dest_start + end + 2)
+    } else {
+        let mut parens = 0;
+        let end = after_dest
+            .char_indices()
+            .find(|&(_, c)| {
+                match c {
+                    '(' => parens += 1,
+                    ')' if parens == 0 => return true,
+                    ')' => parens -= 1,
+                    _ => {}
+                }
+                c.is_whitespace()
+            })
+            .map_or(after_dest.len(), |(i, _)| i);
+        (after_dest[..end].to_string(), dest_start + end)
+    };
+    pos += rest[pos..].len() - rest[pos..].trim_start().len();
+    let mut title = None;
+    if let Some(quote @ ('"' | '\'' | '(')) = rest[pos..].chars().next() {
+        let closing = if quote == '(' { ')' } else { quote };
+        let end = rest[pos + 1..].find(closing)?;
+        title = Some(decode_entities(&rest[pos + 1..pos + 1 + end]));
+        pos += end + 2;
+        pos += rest[pos..].len() - rest[pos..].trim_start().len();
+    }
+    rest[pos..].starts_with(')').then(|| (label, decode_entities(&href), title, close + 2 + pos + 1))
+}
+
+// `<https://...>` or `<someone@example.com>`: its label, URL and length.
+fn autolink(text: &str) -> Option<(&str, String, usize)> {
+    let end = text.find('>')?;
+    let inner = &text[1..end];
+    if inner.is_empty() || inner.contains(|c: char| c.is_whitespace() || c == '<') {
+        return None;
+    }
+    let scheme = inner.split(':').next().filter(|s| s.len() < inner.len());
+    if scheme.is_some_and(|s| (2..=32).contains(&s.len()) && s.starts_with(|c: char| c.is_ascii_alphabetic()) && s.chars().all(|c| c.is_ascii_alphanumeric() || "+.-".contains(c))) {
+        return Some((inner, inner.to_string(), end + 1));
+    }
+    (inner.contains('@') && !inner.contains(['/', '"', '{'])).then(|| (inner, format!("mailto:{}", inner), end + 1))
+}
+
+// The length of the JSX tag starting `text`, `<Chart data={[1, 2]} />`.
+fn jsx_tag(text: &str) -> Option<usize> {
+    if !text[1..].starts_with(|c: char| c.is_ascii_alphabetic() || c == '/' || c == '>') {
+        return None;
+    }
+    let mut quote = None;
+    let mut i = 1;
+    while let Some(c) = text[i..].chars().next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '{') => {
+                i += balanced(&text[i..])?;
+                continue;
+            }
+            (None, '>') => return Some(i + 1),
+            _ => {}
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+// The length of the `{...}` starting `text`, strings in it skipped.
+fn balanced(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quote = None;
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'' | '`') => quote = Some(c),
+            (None, '{') => depth += 1,
+            (None, '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Emphasis opening at `text[start..]`: its tag, content and length. A
+// closing run must follow something other than whitespace, and `_` doesn't
+// open or close inside words.
+fn emphasis(text: &str, start: usize) -> Option<(&'static str, &str, usize)> {
+    let rest = &text[start..];
+    let c = rest.chars().next()?;
+    let n = run_len(rest, c);
+    if rest[n..].starts_with(char::is_whitespace) || rest.len() == n {
+        return None;
+    }
+    if c == '_' && text[..start].ends_with(char::is_alphanumeric) {
+        return None;
+    }
+    let (tag, width) = match (c, n) {
+        ('~', 1 | 2) => ("del", n),
+        ('~', _) => return None,
+        (_, 1) => ("em", 1),
+        _ => ("strong", 2),
+    };
+    let mut j = start + n;
+    while let Some(ch) = text[j..].chars().next() {
+        let r = &text[j..];
+        if ch == '`' {
+            if let Some((_, len)) = code_span(r) {
+                j += len;
+                continue;
+            }
+        }
+        if ch == '\\' {
+            j += 1 + r[1..].chars().next().map_or(0, char::len_utf8);
+            continue;
+        }
+        if ch != c {
+            j += ch.len_utf8();
+            continue;
+        }
+        let m = run_len(r, c);
+        let closes = !(text[..j].ends_with(char::is_whitespace) || c == '_' && r[m..].starts_with(char::is_alphanumeric));
+        let fits = match tag {
+            "em" => m % 2 == 1,
+            "strong" => m >= 2,
+            _ => m == width,
+        };
+        if closes && fits && j + m - width > start + width {
+            return Some((tag, &text[start + width..j + m - width], j + m - start));
+        }
+        j += m;
+    }
+    None
+}
+
+// Frontmatter.
+//
+// YAML as frontmatter uses it: maps and lists nested by indentation, flow
+// `[lists]` and `{maps}`, `|` and `>` block scalars, and plain scalars typed
+// as YAML 1.2's core schema types them. Anchors, tags and multi-document
+// streams aren't supported.
+
+fn split_frontmatter(source: &str) -> (Value, &str) {
+    let empty = Value::Object(Map::new());
+    let Some(rest) = source.strip_prefix("---\n") else { return (empty, source) };
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        if matches!(line.trim_end(), "---" | "...") {
+            let data = Yaml { lines: rest[..offset].lines().collect(), pos: 0 }.block(0);
+            let data = if data.is_null() { empty } else { data };
+            return (data, &rest[offset + line.len()..]);
+        }
+        offset += line.len();
+    }
+    (empty, source)
+}
+
+struct Yaml<'s> {
+    lines: Vec<&'s str>,
+    pos: usize,
+}
+
+fn is_seq_item(text: &str) -> bool {
+    text == "-" || text.starts_with("- ")
+}
+
+// `key: value`, the value possibly empty.
+fn split_entry(text: &str) -> Option<(String, &str)> {
+    if let Some(quote @ ('"' | '\'')) = text.chars().next() {
+        let end = text[1..].find(quote)? + 1;
+        let value = text[end + 1..].trim_start().strip_prefix(':')?;
+        return Some((text[1..end].to_string(), value.trim()));
+    }
+    let colon = text.find(": ").or_else(|| text.strip_suffix(':').map(str::len))?;
+    Some((text[..colon].trim().to_string(), text[colon + 1..].trim()))
+}
+
+// `text` split at top-level commas.
+fn split_flow(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '[' | '{') => depth += 1,
+            (None, ']' | '}') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(text[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(text[start..].trim());
+    parts.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+fn scalar(text: &str) -> Value {
+    let text = text.trim();
+    if let Some(quoted) = text.strip_prefix('"') {
+        let end = quoted.char_indices().find(|&(i, c)| c == '"' && !quoted[..i].ends_with('\\')).map_or(text.len(), |(i, _)| i + 2);
+        return serde_json::from_str(&text[..end]).unwrap_or_else(|_| Value::String(text.trim_matches('"').to_string()));
+    }
+    if let Some(quoted) = text.strip_prefix('\'') {
+        let end = quoted.find('\'').unwrap_or(quoted.len());
+        let mut value = quoted[..end].to_string();
+        // `''` escapes a quote.
+        let mut rest = &quoted[end..];
+        while let Some(more) = rest.strip_prefix("''") {
+            let end = more.find('\'').unwrap_or(more.len());
+            value.push('\'');
+            value.push_str(&more[..end]);
+            rest = &more[end..];
+        }
+        return Value::String(value);
+    }
+    // A comment ends a plain scalar.
+    let text = text.find(" #").map_or(text, |i| text[..i].trim_end());
+    if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+        return Value::Array(split_flow(inner).into_iter().map(scalar).collect());
+    }
+    if let Some(inner) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+        let entries = split_flow(inner).into_iter().filter_map(split_entry).map(|(key, value)| (key, scalar(value)));
+        return Value::Object(entries.collect());
+    }
+    match text {
+        "" | "~" | "null" | "Null" | "NULL" => return Value::Null,
+        "true" | "True" | "TRUE" => return Value::Bool(true),
+        "false" | "False" | "FALSE" => return Value::Bool(false),
+        _ => {}
+    }
+    if let Ok(n) = text.parse::<i64>() {
+        return Value::from(n);
+    }
+    let numeric = text.chars().all(|c| c.is_ascii_digit() || "+-.eE".contains(c)) && text.contains(|c: char| c.is_ascii_digit());
+    match text.parse::<f64>() {
+        Ok(n) if numeric => Value::from(n),
+        _ => Value::String(text.to_string()),
+    }
+}
+
+impl<'s> Yaml<'s> {
+    // The next line with content: its indentation and text.
+    fn peek(&mut self) -> Option<(usize, &'s str)> {
+        while let Some(line) = self.lines.get(self.pos) {
+            let text = line.trim();
+            if !text.is_empty() && !text.starts_with('#') {
+                return Some((indent_of(line), text));
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    fn block(&mut self, indent: usize) -> Value {
+        match self.peek() {
+            Some((i, text)) if i >= indent && is_seq_item(text) => self.sequence(i),
+            Some((i, _)) if i >= indent => self.mapping(i, None),
+            _ => Value::Null,
+        }
+    }
+
+    fn sequence(&mut self, indent: usize) -> Value {
+        let mut items = Vec::new();
+        while let Some((_, text)) = self.peek().filter(|&(i, text)| i == indent && is_seq_item(text)) {
+            self.pos += 1;
+            let item = text[1..].trim_start();
+            if item.is_empty() {
+                items.push(self.block(indent + 1));
+            } else if split_entry(item).is_some() && !item.starts_with(['[', '{']) {
+                // `- key: value` starts a map indented like `key`.
+                items.push(self.mapping(indent + text.len() - item.len(), Some(item)));
+            } else {
+                items.push(scalar(item));
+            }
+        }
+        Value::Array(items)
+    }
+
+    // The map at `indent`; `first` is its first entry when already read.
+    fn mapping(&mut self, indent: usize, mut first: Option<&'s str>) -> Value {
+        let mut map = Map::new();
+        loop {
+            let text = match first.take() {
+                Some(text) => text,
+                None => match self.peek() {
+                    Some((i, text)) if i == indent && !is_seq_item(text) => {
+                        self.pos += 1;
+                        text
+                    }
+                    _ => break,
+                },
+            };
+            let Some((key, value)) = split_entry(text) else { continue };
+            let value = match value {
+                "" => match self.peek() {
+                    Some((i, text)) if i > indent || (i == indent && is_seq_item(text)) => self.block(i),
+                    _ => Value::Null,
+                },
+                indicator if indicator.starts_with(['|', '>']) => self.block_scalar(indent, indicator),
+                value => scalar(value),
+            };
+            map.insert(key, value);
+        }
+        Value::Object(map)
+    }
+
+    fn block_scalar(&mut self, indent: usize, indicator: &str) -> Value {
+        let mut lines = Vec::new();
+        while let Some(line) = self.lines.get(self.pos).filter(|l| l.trim().is_empty() || indent_of(l) > indent) {
+            lines.push(*line);
+            self.pos += 1;
+        }
+        while lines.last().is_some_and(|l| l.trim().is_empty()) {
+            lines.pop();
+        }
+        let content_indent = lines.iter().filter(|l| !l.trim().is_empty()).map(|l| indent_of(l)).min().unwrap_or(0);
+        let lines: Vec<&str> = lines.iter().map(|l| l.get(content_indent..).unwrap_or("")).collect();
+        let mut text = if indicator.starts_with('|') {
+            lines.join("\n")
+        } else {
+            // Folded: lines join with spaces, blank ones break them.
+            let mut folded = String::new();
+            for line in lines {
+                if line.is_empty() {
+                    folded.push('\n');
+                } else {
+                    if !folded.is_empty() && !folded.ends_with('\n') {
+                        folded.push(' ');
+                    }
+                    folded.push_str(line);
+                }
+            }
+            folded
+        };
+        if !indicator.contains('-') && !text.is_empty() {
+            text.push('\n');
+        }
+        Value::String(text)
+    }
+}
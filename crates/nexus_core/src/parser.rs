@@ -1,17 +1,35 @@
 use oxc_allocator::Allocator;
 use oxc_parser::Parser;
-use oxc_span::SourceType;
-use oxc_ast::ast::{ModuleDeclaration, ImportDeclarationSpecifier}; // Adjust based on exact structure if needed
+use oxc_ast::ast::ImportDeclarationSpecifier;
+use oxc_ast::{AstBuilder, NONE};
+use oxc_span::{Span, SPAN};
 
-
-use oxc_ast_visit::Visit;
+use oxc_ast_visit::{walk_mut, Visit, VisitMut};
 use oxc_ast::ast::*;
+use crate::import_meta::is_meta_property;
+use crate::wasm::is_wasm_init;
+use crate::unit::{parse_expression, parse_statements};
+use serde::{Deserialize, Serialize};
 
 struct DependencyVisitor {
     deps: Vec<(String, bool)>, 
 }
 
 impl<'a> Visit<'a> for DependencyVisitor {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if let Some(source) = required_source(call) {
+            self.deps.push((source.to_string(), false));
+        }
+        oxc_ast_visit::walk::walk_call_expression(self, call);
+    }
+
+    fn visit_new_expression(&mut self, new: &NewExpression<'a>) {
+        if let Some((source, _)) = worker_source(new) {
+            self.deps.push((source, false));
+        }
+        oxc_ast_visit::walk::walk_new_expression(self, new);
+    }
+
     fn visit_import_expression(&mut self, expr: &ImportExpression<'a>) {
         if let Expression::StringLiteral(s) = &expr.source {
             self.deps.push((s.value.to_string(), true));
@@ -45,24 +63,117 @@ pub fn extract_dependencies(source: &str, path: &str) -> Vec<String> {
 
 pub fn extract_dependencies_detailed(source: &str, path: &str) -> Vec<(String, bool)> {
     let allocator = Allocator::default();
-    let source_type = SourceType::from_path(path).unwrap_or_default();
+    let source_type = crate::loader::source_type_of(path);
     let ret = Parser::new(&allocator, source, source_type).parse();
     
     if !ret.errors.is_empty() {
         tracing::warn!("Parsing errors in {}: {:?}", path, ret.errors);
     }
     
+    dependencies_of(&ret.program)
+}
+
+pub(crate) fn dependencies_of(program: &Program) -> Vec<(String, bool)> {
     let mut visitor = DependencyVisitor { deps: Vec::new() };
-    visitor.visit_program(&ret.program);
+    visitor.visit_program(program);
     visitor.deps
 }
 
+// `require("x")` with a literal argument. Calls inside `try` are included too;
+// if they don't resolve they are left alone and fail at runtime, where the
+// surrounding `catch` handles them.
+fn required_source<'s>(call: &'s CallExpression) -> Option<&'s str> {
+    match (&call.callee, call.arguments.as_slice()) {
+        (Expression::Identifier(callee), [Argument::StringLiteral(s)]) if callee.name == "require" => Some(s.value.as_str()),
+        _ => None,
+    }
+}
+
+// Workers are imported with a `?worker` (or `?sharedworker`) query. A worker
+// constructed from a URL, `new Worker(new URL("./w.ts", import.meta.url))`, is
+// recorded as an import of `./w.ts?worker` too. Worker imports point at a
+// separate entry: the importer gets the URL of its bundle, never its exports.
+const WORKER_QUERIES: [(&str, &str); 2] = [("?worker", "Worker"), ("?sharedworker", "SharedWorker")];
+
+/// `Worker` or `SharedWorker` if `source` imports a worker.
+pub fn worker_constructor(source: &str) -> Option<&'static str> {
+    WORKER_QUERIES.iter().find(|(query, _)| source.ends_with(query)).map(|(_, constructor)| *constructor)
+}
+
+// Worker import source and span of the `new URL(...)` argument.
+pub(crate) fn worker_source(new: &NewExpression) -> Option<(String, Span)> {
+    let Expression::Identifier(callee) = &new.callee else { return None };
+    let (query, _) = WORKER_QUERIES.iter().find(|(_, constructor)| callee.name == *constructor)?;
+    let Some(Expression::NewExpression(url)) = new.arguments.first()?.as_expression() else { return None };
+    let Expression::Identifier(url_callee) = &url.callee else { return None };
+    if url_callee.name != "URL" || url.arguments.len() != 2 {
+        return None;
+    }
+    let Some(Expression::StringLiteral(specifier)) = url.arguments[0].as_expression() else { return None };
+    if !url.arguments[1].as_expression().is_some_and(|base| is_meta_property(base, "url")) {
+        return None;
+    }
+    Some((format!("{}{}", specifier.value, query), url.span))
+}
+
+struct CommonJsVisitor {
+    found: bool,
+}
+
+impl<'a> Visit<'a> for CommonJsVisitor {
+    fn visit_identifier_reference(&mut self, ident: &IdentifierReference<'a>) {
+        self.found |= matches!(ident.name.as_str(), "require" | "module" | "exports");
+    }
+}
+
+/// A module is CommonJS when it has no ESM syntax and refers to `require`,
+/// `module` or `exports`.
+pub(crate) fn is_commonjs(program: &Program) -> bool {
+    if has_module_syntax(program) {
+        return false;
+    }
+    let mut visitor = CommonJsVisitor { found: false };
+    visitor.visit_program(program);
+    visitor.found
+}
+
+// Marks converted ESM so CommonJS consumers (and Babel-style interop helpers)
+// read `default` from the exports object instead of using it as the default.
+const ES_MODULE_FLAG: &str = "Object.defineProperty(exports, \"__esModule\", { value: true });";
+
+fn has_module_syntax(program: &Program) -> bool {
+    program.body.iter().any(|stmt| stmt.as_module_declaration().is_some())
+}
+
 struct DynamicImportRewriter<'b> {
     imports: &'b std::collections::HashMap<String, String>,
     replacements: Vec<(u32, u32, String)>,
 }
 
 impl<'a, 'b> Visit<'a> for DynamicImportRewriter<'b> {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if let (Some(source), Some(Argument::StringLiteral(s))) = (required_source(call), call.arguments.first()) {
+            if let Some(resolved) = self.imports.get(source) {
+                self.replacements.push((s.span.start, s.span.end, format!("\"{}\"", resolved)));
+            }
+        }
+        oxc_ast_visit::walk::walk_call_expression(self, call);
+    }
+
+    fn visit_new_expression(&mut self, new: &NewExpression<'a>) {
+        match worker_source(new) {
+            Some((source, url_span)) => {
+                if let Some(resolved) = self.imports.get(&source) {
+                    self.replacements.push((url_span.start, url_span.end, worker_url_text(resolved)));
+                }
+                for arg in new.arguments.iter().skip(1) {
+                    self.visit_argument(arg);
+                }
+            }
+            None => oxc_ast_visit::walk::walk_new_expression(self, new),
+        }
+    }
+
     fn visit_import_expression(&mut self, expr: &ImportExpression<'a>) {
          if let Expression::StringLiteral(s) = &expr.source {
              let source_val = s.value.as_str();
@@ -83,7 +194,7 @@ impl<'a, 'b> Visit<'a> for DynamicImportRewriter<'b> {
 
 pub fn transform_cjs(source: &str, path: &str, imports: &std::collections::HashMap<String, String>) -> String {
     let allocator = Allocator::default();
-    let source_type = SourceType::from_path(path).unwrap_or_default();
+    let source_type = crate::loader::source_type_of(path);
     let ret = Parser::new(&allocator, source, source_type).parse();
     
     if !ret.errors.is_empty() {
@@ -106,113 +217,60 @@ pub fn transform_cjs(source: &str, path: &str, imports: &std::collections::HashM
         match stmt {
             oxc_ast::ast::Statement::ImportDeclaration(import_decl) => {
                  // import "pkg" -> require("pkg")
-                 let start = import_decl.span.start;
-                 let end = import_decl.span.end;
-                 let source_val = import_decl.source.value.as_str();
-                 
-                 let resolved = imports.get(source_val).cloned().unwrap_or_else(|| source_val.to_string());
-                 
-                 if let Some(specifiers) = &import_decl.specifiers {
-                     if specifiers.is_empty() {
-                         replacements.push((start, end, format!("require(\"{}\");", resolved)));
-                     } else {
-                         let mut decls = Vec::new();
-                         for spec in specifiers {
-                             match spec {
-                                 ImportDeclarationSpecifier::ImportDefaultSpecifier(s) => {
-                                     let local = s.local.name.as_str();
-                                     decls.push(format!("const {} = require(\"{}\").default;", local, resolved));
-                                 }
-                                 ImportDeclarationSpecifier::ImportNamespaceSpecifier(s) => {
-                                      let local = s.local.name.as_str();
-                                      decls.push(format!("const {} = require(\"{}\");", local, resolved));
-                                 }
-                                 ImportDeclarationSpecifier::ImportSpecifier(s) => {
-                                      let local = s.local.name.as_str();
-                                      let imported = s.imported.name().as_str();
-                                      decls.push(format!("const {} = require(\"{}\").{};", local, resolved, imported));
-                                 }
-                             }
-                         }
-                         replacements.push((start, end, decls.join("\n")));
-                     }
-                 }
+                 let resolved = resolve_source(imports, &import_decl.source.value);
+                 let text = import_text(import_decl, &resolved);
+                 replacements.push((import_decl.span.start, import_decl.span.end, text));
             }
             oxc_ast::ast::Statement::ExportDefaultDeclaration(export_default) => {
                 let start = export_default.span.start;
-                
                 match &export_default.declaration {
-                     // oxc_ast::ast::ExportDefaultDeclarationKind::Expression(expr) => {
-                     //      replacements.push((start, expr.span.start, "exports.default = ".to_string()));
-                     // }
-                     _ => {
-                           match &export_default.declaration {
-                               oxc_ast::ast::ExportDefaultDeclarationKind::FunctionDeclaration(f) => {
-                                   replacements.push((start, f.span.start, "exports.default = ".to_string()));
-                               }
-                               oxc_ast::ast::ExportDefaultDeclarationKind::ClassDeclaration(c) => {
-                                   replacements.push((start, c.span.start, "exports.default = ".to_string()));
-                               }
-                               _ => {}
-                           }
-                     }
+                    oxc_ast::ast::ExportDefaultDeclarationKind::FunctionDeclaration(f) => {
+                        replacements.push((start, f.span.start, "exports.default = ".to_string()));
+                    }
+                    oxc_ast::ast::ExportDefaultDeclarationKind::ClassDeclaration(c) => {
+                        replacements.push((start, c.span.start, "exports.default = ".to_string()));
+                    }
+                    // export default "/assets/logo.png";
+                    kind => {
+                        if let Some(expr) = kind.as_expression() {
+                            replacements.push((start, oxc_span::GetSpan::span(expr).start, "exports.default = ".to_string()));
+                        }
+                    }
                 }
             }
+            oxc_ast::ast::Statement::ExportAllDeclaration(export_all) => {
+                 let resolved = resolve_source(imports, &export_all.source.value);
+                 replacements.push((export_all.span.start, export_all.span.end, reexport_all_text(export_all, &resolved)));
+            }
+            oxc_ast::ast::Statement::ExportNamedDeclaration(export_named) if export_named.source.is_some() => {
+                 let resolved = resolve_source(imports, &export_named.source.as_ref().unwrap().value);
+                 replacements.push((export_named.span.start, export_named.span.end, reexport_named_text(export_named, &resolved)));
+            }
             oxc_ast::ast::Statement::ExportNamedDeclaration(export_named) => {
                 let start = export_named.span.start;
                 
                 if let Some(decl) = &export_named.declaration {
-                    let mut names = Vec::new();
-                    let mut decl_start = start;
-                    
-                    match decl {
-                        oxc_ast::ast::Declaration::VariableDeclaration(var_decl) => {
-                            decl_start = var_decl.span.start;
-                            for d in &var_decl.declarations {
-                                 if let oxc_ast::ast::BindingPatternKind::BindingIdentifier(id) = &d.id.kind {
-                                     names.push(id.name.as_str().to_string());
-                                 }
-                            }
-                        }
-                        oxc_ast::ast::Declaration::FunctionDeclaration(f) => {
-                            decl_start = f.span.start;
-                            if let Some(id) = &f.id {
-                                names.push(id.name.as_str().to_string());
-                            }
-                        }
-                        oxc_ast::ast::Declaration::ClassDeclaration(c) => {
-                            decl_start = c.span.start;
-                            if let Some(id) = &c.id {
-                                names.push(id.name.as_str().to_string());
-                            }
-                        }
-                        _ => {}
-                    }
-                    
+                    let names = declared_names(decl);
                     if !names.is_empty() {
+                        let decl_start = oxc_span::GetSpan::span(decl).start;
                         replacements.push((start, decl_start, "".to_string()));
-                        let defines: Vec<String> = names.into_iter().map(|name| {
-                            format!("Object.defineProperty(exports, \"{}\", {{ enumerable: true, get: function() {{ return {}; }} }});", name, name)
-                        }).collect();
+                        let defines: Vec<String> = names.iter().map(|name| define_export_text(name, name)).collect();
                         replacements.push((export_named.span.end, export_named.span.end, format!("\n{}", defines.join("\n"))));
                     }
-                    
                 } else if !export_named.specifiers.is_empty() {
-                     let mut defines = Vec::new();
-                     for spec in &export_named.specifiers {
-                         let exported = spec.exported.name().as_str();
-                         let local = spec.local.name().as_str();
-                         defines.push(format!("Object.defineProperty(exports, \"{}\", {{ enumerable: true, get: function() {{ return {}; }} }});", exported, local));
-                     }
-                     replacements.push((start, export_named.span.end, defines.join("\n")));
+                     replacements.push((start, export_named.span.end, local_exports_text(export_named)));
                 }
             }
             _ => {}
         }
     }
 
+    if has_module_syntax(&program) {
+        replacements.push((0, 0, format!("{}\n", ES_MODULE_FLAG)));
+    }
+
     // Apply
-    replacements.sort_by(|a, b| b.0.cmp(&a.0));
+    replacements.sort_by_key(|r| std::cmp::Reverse(r.0));
     
     let mut result = source.to_string();
     for (start, end, text) in replacements {
@@ -222,13 +280,277 @@ pub fn transform_cjs(source: &str, path: &str, imports: &std::collections::HashM
     result
 }
 
+fn resolve_source(imports: &std::collections::HashMap<String, String>, source: &str) -> String {
+    imports.get(source).cloned().unwrap_or_else(|| source.to_string())
+}
+
+// Text of the CommonJS statements replacing ESM syntax. Shared by the
+// text-splicing `transform_cjs` (dev server) and the AST linker (build).
+
+fn import_text(decl: &ImportDeclaration, resolved: &str) -> String {
+    let specifiers = decl.specifiers.as_ref().map(|s| s.as_slice());
+    if is_wasm_init(&decl.source.value) {
+        return wasm_init_text(specifiers, resolved);
+    }
+    match worker_constructor(&decl.source.value) {
+        Some(constructor) => worker_import_text(specifiers, constructor, resolved),
+        None => require_text(specifiers, resolved),
+    }
+}
+
+// import init from "./m.wasm?init" -> instantiates on call, resolving to the instance.
+fn wasm_init_text(specifiers: Option<&[ImportDeclarationSpecifier]>, resolved: &str) -> String {
+    let mut decls = Vec::new();
+    for spec in specifiers.unwrap_or_default() {
+        if let ImportDeclarationSpecifier::ImportDefaultSpecifier(s) = spec {
+            decls.push(format!(
+                "const {} = function(imports) {{ return __nexus_wasm__.instantiate(\"{}\", imports); }};",
+                s.local.name, resolved
+            ));
+        }
+    }
+    decls.join("\n")
+}
+
+// `__nexus_worker__` maps a worker entry to the URL of its bundle.
+fn worker_url_text(resolved: &str) -> String {
+    format!("__nexus_worker__(\"{}\")", resolved)
+}
+
+// import MyWorker from "./w.ts?worker" -> a constructor taking the options.
+fn worker_import_text(specifiers: Option<&[ImportDeclarationSpecifier]>, constructor: &str, resolved: &str) -> String {
+    let mut decls = Vec::new();
+    for spec in specifiers.unwrap_or_default() {
+        if let ImportDeclarationSpecifier::ImportDefaultSpecifier(s) = spec {
+            decls.push(format!(
+                "const {} = function(options) {{ return new {}({}, options); }};",
+                s.local.name,
+                constructor,
+                worker_url_text(resolved)
+            ));
+        }
+    }
+    decls.join("\n")
+}
+
+// ESM importers of a CommonJS module see `module.exports` as its default
+// export and, in a namespace, next to its properties. The exports object is
+// left alone: it may be a string, or frozen.
+const INTEROP_DEFAULT: &str = "(function(m) { return m && m.__esModule ? m.default : m; })";
+const INTEROP_NAMESPACE: &str = "(function(m) { if (m && m.__esModule) return m; const ns = { default: m }; if (m && (typeof m === \"object\" || typeof m === \"function\")) for (const k in m) if (k !== \"default\") ns[k] = m[k]; return ns; })";
+
+fn require_text(specifiers: Option<&[ImportDeclarationSpecifier]>, resolved: &str) -> String {
+    let specifiers = specifiers.unwrap_or_default();
+    if specifiers.is_empty() {
+        return format!("require(\"{}\");", resolved);
+    }
+    let mut decls = Vec::new();
+    for spec in specifiers {
+        match spec {
+            ImportDeclarationSpecifier::ImportDefaultSpecifier(s) => {
+                decls.push(format!("const {} = {}(require(\"{}\"));", s.local.name, INTEROP_DEFAULT, resolved));
+            }
+            ImportDeclarationSpecifier::ImportNamespaceSpecifier(s) => {
+                decls.push(format!("const {} = {}(require(\"{}\"));", s.local.name, INTEROP_NAMESPACE, resolved));
+            }
+            ImportDeclarationSpecifier::ImportSpecifier(s) => {
+                decls.push(format!("const {} = require(\"{}\").{};", s.local.name, resolved, s.imported.name()));
+            }
+        }
+    }
+    decls.join("\n")
+}
 
-#[derive(Debug, Clone)]
+fn define_export_text(exported: &str, value: &str) -> String {
+    format!("Object.defineProperty(exports, \"{}\", {{ enumerable: true, get: function() {{ return {}; }} }});", exported, value)
+}
+
+// export * from "pkg" / export * as ns from "pkg"
+fn reexport_all_text(export_all: &ExportAllDeclaration, resolved: &str) -> String {
+    if let Some(exported) = &export_all.exported {
+        let tmp = format!("__nexus_reexport_{}__", export_all.span.start);
+        format!("const {} = require(\"{}\");\n{}", tmp, resolved, define_export_text(&exported.name(), &tmp))
+    } else {
+        // Configurable so that explicit local exports defined later still win.
+        format!(
+            "(function(m) {{ for (const k in m) {{ if (k !== \"default\" && !(k in exports)) Object.defineProperty(exports, k, {{ enumerable: true, configurable: true, get: function() {{ return m[k]; }} }}); }} }})(require(\"{}\"));",
+            resolved
+        )
+    }
+}
+
+// export { a as b } from "pkg"
+fn reexport_named_text(export_named: &ExportNamedDeclaration, resolved: &str) -> String {
+    let tmp = format!("__nexus_reexport_{}__", export_named.span.start);
+    let mut lines = vec![format!("const {} = require(\"{}\");", tmp, resolved)];
+    for spec in &export_named.specifiers {
+        let value = match spec.local.name().as_str() {
+            "default" => format!("{}({})", INTEROP_DEFAULT, tmp),
+            local => format!("{}.{}", tmp, local),
+        };
+        lines.push(define_export_text(&spec.exported.name(), &value));
+    }
+    lines.join("\n")
+}
+
+// export { a, b as c }
+fn local_exports_text(export_named: &ExportNamedDeclaration) -> String {
+    let defines: Vec<String> = export_named
+        .specifiers
+        .iter()
+        .map(|spec| define_export_text(&spec.exported.name(), &spec.local.name()))
+        .collect();
+    defines.join("\n")
+}
+
+fn declared_names(decl: &Declaration) -> Vec<String> {
+    let mut names = Vec::new();
+    match decl {
+        Declaration::VariableDeclaration(var_decl) => {
+            for d in &var_decl.declarations {
+                if let BindingPatternKind::BindingIdentifier(id) = &d.id.kind {
+                    names.push(id.name.to_string());
+                }
+            }
+        }
+        Declaration::FunctionDeclaration(f) => names.extend(f.id.as_ref().map(|id| id.name.to_string())),
+        Declaration::ClassDeclaration(c) => names.extend(c.id.as_ref().map(|id| id.name.to_string())),
+        _ => {}
+    }
+    names
+}
+
+struct DynamicImportLinker<'a, 'b> {
+    allocator: &'a Allocator,
+    ast: AstBuilder<'a>,
+    imports: &'b std::collections::HashMap<String, String>,
+}
+
+impl<'a> VisitMut<'a> for DynamicImportLinker<'a, '_> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        walk_mut::walk_expression(self, expr);
+        if let Expression::NewExpression(new) = expr {
+            let resolved = worker_source(new).and_then(|(source, _)| self.imports.get(&source));
+            if let Some(url) = resolved.and_then(|resolved| parse_expression(self.allocator, &worker_url_text(resolved))) {
+                new.arguments[0] = Argument::from(url);
+            }
+            return;
+        }
+        if let Expression::CallExpression(call) = expr {
+            let resolved = required_source(call).and_then(|source| self.imports.get(source));
+            if let Some(resolved) = resolved {
+                let arg = Argument::from(self.ast.expression_string_literal(SPAN, self.ast.atom(resolved), None));
+                call.arguments = self.ast.vec1(arg);
+            }
+            return;
+        }
+        let Expression::ImportExpression(import) = expr else { return };
+        let Expression::StringLiteral(s) = &import.source else { return };
+        let resolved = resolve_source(self.imports, &s.value);
+        let callee = self.ast.expression_identifier(SPAN, "__nexus_import__");
+        let arg = Argument::from(self.ast.expression_string_literal(SPAN, self.ast.atom(&resolved), None));
+        *expr = self.ast.expression_call(import.span, callee, NONE, self.ast.vec1(arg), false);
+    }
+}
+
+// `exports.default = <value>;`
+fn assign_default<'a>(allocator: &'a Allocator, value: Expression<'a>) -> Statement<'a> {
+    let mut stmts = parse_statements(allocator, "exports.default = 0;");
+    let mut stmt = stmts.pop().unwrap();
+    if let Statement::ExpressionStatement(s) = &mut stmt {
+        if let Expression::AssignmentExpression(assign) = &mut s.expression {
+            assign.right = value;
+        }
+    }
+    stmt
+}
+
+/// AST counterpart of `transform_cjs`: rewrites `program` in place for the
+/// production build, where each module's AST is still alive after shaking.
+pub(crate) fn link_program<'a>(
+    allocator: &'a Allocator,
+    program: &mut Program<'a>,
+    imports: &std::collections::HashMap<String, String>,
+) {
+    let ast = AstBuilder::new(allocator);
+    DynamicImportLinker { allocator, ast, imports }.visit_program(program);
+
+    let es_module = has_module_syntax(program);
+    let body = std::mem::replace(&mut program.body, ast.vec());
+    if es_module {
+        program.body.extend(parse_statements(allocator, ES_MODULE_FLAG));
+    }
+    for stmt in body {
+        match stmt {
+            Statement::ImportDeclaration(decl) => {
+                let resolved = resolve_source(imports, &decl.source.value);
+                program.body.extend(parse_statements(allocator, &import_text(&decl, &resolved)));
+            }
+            Statement::ExportDefaultDeclaration(decl)
+                if matches!(
+                    decl.declaration,
+                    ExportDefaultDeclarationKind::FunctionDeclaration(_) | ExportDefaultDeclarationKind::ClassDeclaration(_)
+                ) =>
+            {
+                let value = match decl.unbox().declaration {
+                    ExportDefaultDeclarationKind::FunctionDeclaration(mut f) => {
+                        f.r#type = FunctionType::FunctionExpression;
+                        Expression::FunctionExpression(f)
+                    }
+                    ExportDefaultDeclarationKind::ClassDeclaration(mut c) => {
+                        c.r#type = ClassType::ClassExpression;
+                        Expression::ClassExpression(c)
+                    }
+                    _ => unreachable!(),
+                };
+                program.body.push(assign_default(allocator, value));
+            }
+            Statement::ExportDefaultDeclaration(decl) if decl.declaration.is_expression() => {
+                let value = decl.unbox().declaration.into_expression();
+                program.body.push(assign_default(allocator, value));
+            }
+            Statement::ExportAllDeclaration(decl) => {
+                let resolved = resolve_source(imports, &decl.source.value);
+                program.body.extend(parse_statements(allocator, &reexport_all_text(&decl, &resolved)));
+            }
+            Statement::ExportNamedDeclaration(decl) if decl.source.is_some() => {
+                let resolved = resolve_source(imports, &decl.source.as_ref().unwrap().value);
+                program.body.extend(parse_statements(allocator, &reexport_named_text(&decl, &resolved)));
+            }
+            Statement::ExportNamedDeclaration(mut decl) => {
+                let names = decl.declaration.as_ref().map(declared_names).unwrap_or_default();
+                if !names.is_empty() {
+                    program.body.push(Statement::from(decl.declaration.take().unwrap()));
+                    let defines: Vec<String> = names.iter().map(|name| define_export_text(name, name)).collect();
+                    program.body.extend(parse_statements(allocator, &defines.join("\n")));
+                } else if decl.declaration.is_none() && !decl.specifiers.is_empty() {
+                    program.body.extend(parse_statements(allocator, &local_exports_text(&decl)));
+                } else {
+                    program.body.push(Statement::ExportNamedDeclaration(decl));
+                }
+            }
+            other => program.body.push(other),
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ImportInfo {
     pub source: String,
     pub specifiers: Vec<String>,
     pub is_dynamic: bool,
     pub is_star: bool,
+    /// `require("...")`; the caller may use any export.
+    #[serde(default)]
+    pub is_require: bool,
+}
+
+impl ImportInfo {
+    /// Imports a worker entry; see `worker_constructor`.
+    pub fn is_worker(&self) -> bool {
+        worker_constructor(&self.source).is_some()
+    }
 }
 
 struct AnalysisVisitor {
@@ -237,6 +559,32 @@ struct AnalysisVisitor {
 }
 
 impl<'a> Visit<'a> for AnalysisVisitor {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if let Some(source) = required_source(call) {
+            self.imports.push(ImportInfo {
+                source: source.to_string(),
+                specifiers: Vec::new(),
+                is_dynamic: false,
+                is_star: false,
+                is_require: true,
+            });
+        }
+        oxc_ast_visit::walk::walk_call_expression(self, call);
+    }
+
+    fn visit_new_expression(&mut self, new: &NewExpression<'a>) {
+        if let Some((source, _)) = worker_source(new) {
+            self.imports.push(ImportInfo {
+                source,
+                specifiers: Vec::new(),
+                is_dynamic: false,
+                is_star: false,
+                is_require: false,
+            });
+        }
+        oxc_ast_visit::walk::walk_new_expression(self, new);
+    }
+
     fn visit_import_expression(&mut self, expr: &ImportExpression<'a>) {
         if let Expression::StringLiteral(s) = &expr.source {
             self.imports.push(ImportInfo {
@@ -244,6 +592,7 @@ impl<'a> Visit<'a> for AnalysisVisitor {
                 specifiers: Vec::new(),
                 is_dynamic: true,
                 is_star: false,
+                is_require: false,
             });
         }
         self.visit_expression(&expr.source);
@@ -275,6 +624,7 @@ impl<'a> Visit<'a> for AnalysisVisitor {
              specifiers,
              is_dynamic: false,
              is_star,
+             is_require: false,
          });
     }
 
@@ -289,12 +639,15 @@ impl<'a> Visit<'a> for AnalysisVisitor {
             specifiers: Vec::new(),
             is_dynamic: false,
             is_star: true,
+            is_require: false,
         });
         // We can't list specific exports here without resolving.
     }
 
-    fn visit_export_default_declaration(&mut self, _decl: &ExportDefaultDeclaration<'a>) {
+    fn visit_export_default_declaration(&mut self, decl: &ExportDefaultDeclaration<'a>) {
         self.exports.push("default".to_string());
+        // Exported code may still `require()` or `import()`.
+        oxc_ast_visit::walk::walk_export_default_declaration(self, decl);
     }
 
     fn visit_export_named_declaration(&mut self, decl: &ExportNamedDeclaration<'a>) {
@@ -311,6 +664,7 @@ impl<'a> Visit<'a> for AnalysisVisitor {
                 specifiers: specs,
                 is_dynamic: false,
                 is_star: false,
+                is_require: false,
             });
         } else {
             // Regular export: export const x = 1; or export { x };
@@ -339,13 +693,16 @@ impl<'a> Visit<'a> for AnalysisVisitor {
             for spec in &decl.specifiers {
                 self.exports.push(spec.exported.name().to_string());
             }
+            if let Some(d) = &decl.declaration {
+                self.visit_declaration(d);
+            }
         }
     }
 }
 
 pub fn analyze_module(source: &str, path: &str) -> (Vec<String>, Vec<ImportInfo>) {
     let allocator = Allocator::default();
-    let source_type = SourceType::from_path(path).unwrap_or_default();
+    let source_type = crate::loader::source_type_of(path);
     let ret = Parser::new(&allocator, source, source_type).parse();
     
     if !ret.errors.is_empty() {
@@ -357,101 +714,12 @@ pub fn analyze_module(source: &str, path: &str) -> (Vec<String>, Vec<ImportInfo>
     (visitor.exports, visitor.imports)
 }
 
+pub(crate) fn imports_of(program: &Program) -> Vec<ImportInfo> {
+    let mut visitor = AnalysisVisitor { exports: Vec::new(), imports: Vec::new() };
+    visitor.visit_program(program);
+    visitor.imports
+}
+
 pub fn transform_tree_shake(source: &str, path: &str, used_exports: &std::collections::HashSet<String>) -> String {
-    let allocator = Allocator::default();
-    let source_type = SourceType::from_path(path).unwrap_or_default();
-    let ret = Parser::new(&allocator, source, source_type).parse();
-    
-    if !ret.errors.is_empty() {
-        return source.to_string();
-    }
-    
-    let mut replacements: Vec<(u32, u32, String)> = Vec::new();
-    let program = ret.program;
-    
-    for stmt in program.body {
-        match stmt {
-            oxc_ast::ast::Statement::ExportDefaultDeclaration(d) => {
-                if !used_exports.contains("default") {
-                    // Remove entire statement
-                     replacements.push((d.span.start, d.span.end, "".to_string()));
-                }
-            }
-            oxc_ast::ast::Statement::ExportNamedDeclaration(d) => {
-                if let Some(declaration) = &d.declaration {
-                    // export const x = 1;
-                    let mut keep = false;
-                    
-                    match declaration {
-                         oxc_ast::ast::Declaration::VariableDeclaration(var_decl) => {
-                             for decl in &var_decl.declarations {
-                                 if let oxc_ast::ast::BindingPatternKind::BindingIdentifier(id) = &decl.id.kind {
-                                     if used_exports.contains(id.name.as_str()) {
-                                         keep = true;
-                                     }
-                                 }
-                             }
-                         }
-                         oxc_ast::ast::Declaration::FunctionDeclaration(f) => {
-                             if let Some(id) = &f.id {
-                                  if used_exports.contains(id.name.as_str()) {
-                                      keep = true;
-                                  }
-                             }
-                         }
-                         oxc_ast::ast::Declaration::ClassDeclaration(c) => {
-                             if let Some(id) = &c.id {
-                                  if used_exports.contains(id.name.as_str()) {
-                                      keep = true;
-                                  }
-                             }
-                         }
-                         _ => {}
-                    }
-                    
-                    if !keep {
-                        // Safely remove
-                        replacements.push((d.span.start, d.span.end, "".to_string()));
-                    }
-                } else if !d.specifiers.is_empty() {
-                    // export { x, y }
-                    let mut kept_specs = Vec::new();
-                    for spec in &d.specifiers {
-                        let exported = spec.exported.name().as_str();
-                        if used_exports.contains(exported) {
-                            let local = spec.local.name().as_str();
-                            if local == exported {
-                                kept_specs.push(local.to_string());
-                            } else {
-                                kept_specs.push(format!("{} as {}", local, exported));
-                            }
-                        }
-                    }
-                    
-                    if kept_specs.is_empty() {
-                        replacements.push((d.span.start, d.span.end, "".to_string()));
-                    } else {
-                        // Rewrite
-                        let new_stmt = if let Some(src) = &d.source {
-                            format!("export {{ {} }} from \"{}\";", kept_specs.join(", "), src.value.as_str())
-                        } else {
-                            format!("export {{ {} }};", kept_specs.join(", "))
-                        };
-                        replacements.push((d.span.start, d.span.end, new_stmt));
-                    }
-                }
-            }
-            _ => {}
-        }
-    }
-    
-    // Sort reverse
-    replacements.sort_by(|a, b| b.0.cmp(&a.0));
-    
-    let mut result = source.to_string();
-    for (start, end, text) in replacements {
-        result.replace_range(start as usize..end as usize, &text);
-    }
-    
-    result
+    crate::shaker::shake(source, path, used_exports, &std::collections::HashSet::new())
 }
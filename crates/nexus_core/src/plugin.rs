@@ -0,0 +1,325 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use crate::compiler::CompileResult;
+use crate::import_meta::glob_match;
+
+// Plugin API.
+//
+// A `Plugin` hooks into the pipeline with Rollup-style hooks. The dev server,
+// its watcher and `build` all go through one `PluginContainer`, which runs
+// the hooks of every plugin in order: first `Order::Pre` plugins, then
+// `Normal`, then `Post`, each group in registration order.
+//
+// The built-in compilers sit between the `Pre` and the other plugins, as in
+// Vite: `transform` of a `Pre` plugin gets the module's source (and is where
+// a new file type is turned into JS), the others get the compiled JS.
+//
+// Hooks returning `Ok(None)` leave the module to the next plugin or the
+// built-in behaviour. Errors name the plugin; `build` fails on them, the dev
+// server logs them and carries on without the plugin's result.
+
+pub type HookResult<T> = std::io::Result<Option<T>>;
+
+/// When a plugin's hooks run relative to the built-in compilers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Order {
+    Pre,
+    #[default]
+    Normal,
+    Post,
+}
+
+/// Globs over module ids (or, for `resolve_id`, specifiers) a hook runs for.
+/// An empty `include` matches everything.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl Filter {
+    pub fn include(globs: &[&str]) -> Self {
+        Self { include: globs.iter().map(|g| g.to_string()).collect(), exclude: Vec::new() }
+    }
+
+    pub fn matches(&self, id: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|g| glob_match(g, id)))
+            && !self.exclude.iter().any(|g| glob_match(g, id))
+    }
+}
+
+/// Per-hook filters, declared up front so the container can skip a plugin
+/// without calling it.
+#[derive(Debug, Clone, Default)]
+pub struct HookFilters {
+    pub resolve_id: Filter,
+    pub load: Filter,
+    pub transform: Filter,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransformOutput {
+    pub code: String,
+    /// Replaces the module's sourcemap. Without one the module has none.
+    pub map: Option<String>,
+}
+
+/// A module once its imports are resolved.
+#[derive(Debug, Clone, Default)]
+pub struct ModuleInfo {
+    pub id: String,
+    pub imported_ids: Vec<String>,
+    pub dynamically_imported_ids: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ChunkInfo {
+    /// Without hash or extension, e.g. `main`, `vendor` or `worker-decode`.
+    pub name: String,
+    pub is_entry: bool,
+    pub modules: Vec<String>,
+}
+
+/// Output files of a build, by path relative to `dist`.
+pub type OutputBundle = BTreeMap<String, Vec<u8>>;
+
+/// A file changed under the dev server.
+#[derive(Debug, Clone, Default)]
+pub struct HotUpdate {
+    pub file: String,
+    /// Modules about to be updated in the browser.
+    pub modules: Vec<String>,
+}
+
+#[allow(unused_variables)]
+pub trait Plugin: Send + Sync {
+    fn name(&self) -> &str;
+
+    /// Identifies what the plugin outputs, e.g. its name, version and
+    /// options. Cached transforms made under another key are discarded.
+    fn cache_key(&self) -> String {
+        self.name().to_string()
+    }
+
+    fn order(&self) -> Order {
+        Order::Normal
+    }
+
+    fn filters(&self) -> HookFilters {
+        HookFilters::default()
+    }
+
+    /// The file `specifier` imported from `importer` refers to. The path
+    /// doesn't have to exist if `load` provides its content.
+    fn resolve_id(&self, specifier: &str, importer: &Path) -> HookResult<PathBuf> {
+        Ok(None)
+    }
+
+    /// The content of module `id`, instead of reading `path`.
+    fn load(&self, id: &str, path: &Path) -> HookResult<Vec<u8>> {
+        Ok(None)
+    }
+
+    fn transform(&self, code: &str, id: &str) -> HookResult<TransformOutput> {
+        Ok(None)
+    }
+
+    fn module_parsed(&self, module: &ModuleInfo) {}
+
+    /// Build only: rewrites a chunk's code before it is written.
+    fn render_chunk(&self, code: &str, chunk: &ChunkInfo) -> HookResult<String> {
+        Ok(None)
+    }
+
+    /// Build only: sees, and may change, add or remove, every output file.
+    fn generate_bundle(&self, bundle: &mut OutputBundle) -> std::io::Result<()> {
+        Ok(())
+    }
+
+    /// Rewrites an HTML page, `path` being its URL path, e.g. `/index.html`.
+    /// In `build`, after the bundle's tags are added.
+    fn transform_index_html(&self, html: &str, path: &str) -> HookResult<String> {
+        Ok(None)
+    }
+
+    /// Dev server: the modules to update for a change. An empty list sends
+    /// no update at all.
+    fn handle_hot_update(&self, update: &HotUpdate) -> Option<Vec<String>> {
+        None
+    }
+
+    /// Dev server: adds routes or middleware.
+    fn configure_server(&self, app: axum::Router) -> axum::Router {
+        app
+    }
+}
+
+/// Runs the hooks of a set of plugins. Cheap to clone.
+#[derive(Clone, Default)]
+pub struct PluginContainer {
+    plugins: Vec<Arc<dyn Plugin>>,
+}
+
+impl std::fmt::Debug for PluginContainer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.plugins.iter().map(|p| p.name())).finish()
+    }
+}
+
+fn plugin_error(plugin: &dyn Plugin, hook: &str, e: std::io::Error) -> std::io::Error {
+    std::io::Error::new(e.kind(), format!("[plugin {}] {}: {}", plugin.name(), hook, e))
+}
+
+impl PluginContainer {
+    pub fn new(mut plugins: Vec<Arc<dyn Plugin>>) -> Self {
+        plugins.sort_by_key(|p| p.order());
+        Self { plugins }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.plugins.is_empty()
+    }
+
+    /// Appended to the transform cache configuration, since output depends
+    /// on the plugins. Empty without plugins.
+    pub fn cache_key(&self) -> String {
+        self.plugins.iter().map(|p| format!(":{}", p.cache_key())).collect()
+    }
+
+    pub fn resolve_id(&self, specifier: &str, importer: &Path) -> HookResult<PathBuf> {
+        for plugin in self.plugins.iter().filter(|p| p.filters().resolve_id.matches(specifier)) {
+            if let Some(path) = plugin.resolve_id(specifier, importer).map_err(|e| plugin_error(&**plugin, "resolve_id", e))? {
+                return Ok(Some(path));
+            }
+        }
+        Ok(None)
+    }
+
+    pub fn load(&self, id: &str, path: &Path) -> HookResult<Vec<u8>> {
+        for plugin in self.plugins.iter().filter(|p| p.filters().load.matches(id)) {
+            if let Some(bytes) = plugin.load(id, path).map_err(|e| plugin_error(&**plugin, "load", e))? {
+                return Ok(Some(bytes));
+            }
+        }
+        Ok(None)
+    }
+
+    /// What the built-in compilers get for module `id`: loaded by a plugin
+    /// or read from `path`, then transformed by `Pre` plugins.
+    pub fn source(&self, id: &str, path: &Path) -> std::io::Result<Vec<u8>> {
+        let mut bytes = match self.load(id, path)? {
+            Some(bytes) => bytes,
+            None => std::fs::read(path)?,
+        };
+        for plugin in self.transformers(id, |order| order == Order::Pre) {
+            // Binary files have no text to transform.
+            let Ok(code) = std::str::from_utf8(&bytes) else { break };
+            if let Some(out) = plugin.transform(code, id).map_err(|e| plugin_error(&**plugin, "transform", e))? {
+                bytes = out.code.into_bytes();
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Runs the `Normal` and `Post` transforms over compiled JS. Returns
+    /// whether the code changed, in which case it needs parsing again.
+    pub fn transform(&self, id: &str, compiled: &mut CompileResult) -> std::io::Result<bool> {
+        let mut changed = false;
+        for plugin in self.transformers(id, |order| order != Order::Pre) {
+            if let Some(out) = plugin.transform(&compiled.code, id).map_err(|e| plugin_error(&**plugin, "transform", e))? {
+                compiled.code = out.code;
+                compiled.sourcemap = out.map;
+                changed = true;
+            }
+        }
+        Ok(changed)
+    }
+
+    fn transformers<'a>(&'a self, id: &'a str, order: impl Fn(Order) -> bool + 'a) -> impl Iterator<Item = &'a Arc<dyn Plugin>> + 'a {
+        self.plugins.iter().filter(move |p| order(p.order()) && p.filters().transform.matches(id))
+    }
+
+    pub fn module_parsed(&self, module: &ModuleInfo) {
+        for plugin in &self.plugins {
+            plugin.module_parsed(module);
+        }
+    }
+
+    pub fn render_chunk(&self, mut code: String, chunk: &ChunkInfo) -> std::io::Result<String> {
+        for plugin in &self.plugins {
+            if let Some(rendered) = plugin.render_chunk(&code, chunk).map_err(|e| plugin_error(&**plugin, "render_chunk", e))? {
+                code = rendered;
+            }
+        }
+        Ok(code)
+    }
+
+    pub fn generate_bundle(&self, bundle: &mut OutputBundle) -> std::io::Result<()> {
+        for plugin in &self.plugins {
+            plugin.generate_bundle(bundle).map_err(|e| plugin_error(&**plugin, "generate_bundle", e))?;
+        }
+        Ok(())
+    }
+
+    pub fn transform_index_html(&self, mut html: String, path: &str) -> std::io::Result<String> {
+        for plugin in &self.plugins {
+            if let Some(transformed) = plugin.transform_index_html(&html, path).map_err(|e| plugin_error(&**plugin, "transform_index_html", e))? {
+                html = transformed;
+            }
+        }
+        Ok(html)
+    }
+
+    /// Each plugin sees the list the previous one returned.
+    pub fn handle_hot_update(&self, mut update: HotUpdate) -> Vec<String> {
+        for plugin in &self.plugins {
+            if let Some(modules) = plugin.handle_hot_update(&update) {
+                update.modules = modules;
+            }
+        }
+        update.modules
+    }
+
+    pub fn configure_server(&self, app: axum::Router) -> axum::Router {
+        self.plugins.iter().fold(app, |app, plugin| plugin.configure_server(app))
+    }
+}
+
+// Calls taken from the queue at most at once; see `Batcher`.
+const MAX_BATCH: usize = 256;
+
+/// Funnels calls made from many threads into batches, for hooks that are
+/// expensive to cross into, like a JS plugin's. One thread runs the batches;
+/// calls arriving while it does make up the next one, so batches grow with
+/// parallelism and a lone call isn't kept waiting.
+pub struct Batcher<Req, Res> {
+    tx: crossbeam_channel::Sender<(Req, std::sync::mpsc::Sender<Res>)>,
+}
+
+impl<Req: Send + 'static, Res: Send + 'static> Batcher<Req, Res> {
+    /// `run` gets each batch and returns one result per request, in order.
+    pub fn new(name: &str, mut run: impl FnMut(Vec<Req>) -> Vec<Res> + Send + 'static) -> Self {
+        let (tx, rx) = crossbeam_channel::unbounded::<(Req, std::sync::mpsc::Sender<Res>)>();
+        std::thread::Builder::new()
+            .name(format!("nexus-batch-{}", name))
+            .spawn(move || {
+                // Ends once the batcher, and with it the sender, is dropped.
+                while let Ok(first) = rx.recv() {
+                    let (requests, replies): (Vec<_>, Vec<_>) = std::iter::once(first).chain(rx.try_iter().take(MAX_BATCH - 1)).unzip();
+                    for (reply, result) in replies.into_iter().zip(run(requests)) {
+                        let _ = reply.send(result);
+                    }
+                }
+            })
+            .expect("failed to spawn batch thread");
+        Self { tx }
+    }
+
+    /// Blocks until the batch holding `request` has run.
+    pub fn call(&self, request: Req) -> std::io::Result<Res> {
+        let (reply, result) = std::sync::mpsc::channel();
+        self.tx.send((request, reply)).map_err(|_| std::io::Error::other("batch thread stopped"))?;
+        result.recv().map_err(|_| std::io::Error::other("batch returned no result for the call"))
+    }
+}
@@ -0,0 +1,289 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
+use crate::bundler::{normalize_id, ENTRY_CANDIDATES};
+use crate::cache;
+use crate::define::Defines;
+use crate::graph::ModuleGraph;
+use crate::parser::transform_cjs;
+use crate::resolver::NexusResolver;
+use crate::unit::CompilationUnit;
+
+// Dependency pre-bundling for the dev server.
+//
+// Every package the app imports by bare specifier is bundled into one module
+// under `.nexus/deps`, with its CommonJS `require`s and internal ESM imports
+// resolved inside the bundle. Packages that import each other keep pointing at
+// each other's bundle, so a package like React exists once. Bundles are reused
+// until the lockfile changes or the app starts importing a new package; the
+// dev server's `DepsOptimizer` notices both while it runs.
+
+pub const DEPS_DIR: &str = ".nexus/deps";
+const METADATA_FILE: &str = "_metadata.json";
+
+/// Bare specifier -> graph id of its bundle (`/.nexus/deps/react.js`).
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepsManifest {
+    pub lock_hash: u64,
+    pub deps: BTreeMap<String, String>,
+}
+
+impl DepsManifest {
+    pub fn get(&self, specifier: &str) -> Option<&str> {
+        self.deps.get(specifier).map(String::as_str)
+    }
+}
+
+fn lock_hash(root: &Path) -> u64 {
+    let key = format!("{}:{:016x}", env!("CARGO_PKG_VERSION"), cache::lockfile_hash(root));
+    cache::content_hash(key.as_bytes())
+}
+
+fn is_bare(specifier: &str) -> bool {
+    !specifier.starts_with('.') && !specifier.starts_with('/') && !specifier.contains(':')
+}
+
+fn is_script(path: &Path) -> bool {
+    matches!(path.extension().and_then(|e| e.to_str()), Some("js" | "jsx" | "ts" | "tsx" | "mjs" | "cjs"))
+}
+
+/// Walks the app's own modules from the entry and collects the bare
+/// specifiers that resolve into `node_modules`.
+pub fn scan_dependencies(root: &Path, resolver: &NexusResolver) -> BTreeSet<String> {
+    let mut found = BTreeSet::new();
+    let Some(entry) = ENTRY_CANDIDATES.iter().find_map(|c| resolver.resolve(root, c).ok()) else {
+        return found;
+    };
+    let mut visited = HashSet::from([entry.clone()]);
+    let mut queue = VecDeque::from([entry]);
+
+    while let Some(path) = queue.pop_front() {
+        let Ok(source) = std::fs::read_to_string(&path) else { continue };
+        let unit = CompilationUnit::parse(&normalize_id(root, &path), source);
+        for (specifier, _) in unit.dependencies() {
+            let Ok(target) = resolver.resolve(&path, &specifier) else { continue };
+            if target.to_string_lossy().contains("node_modules") {
+                if is_bare(&specifier) {
+                    found.insert(specifier);
+                }
+            } else if is_script(&target) && visited.insert(target.clone()) {
+                queue.push_back(target);
+            }
+        }
+    }
+    found
+}
+
+// `react.js`; a specifier that needs escaping also gets a hash of itself, so
+// `a/b` and `a_b` can't end up in the same file.
+fn bundle_file_name(specifier: &str) -> String {
+    let escaped: String = specifier
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '@') { c } else { '_' })
+        .collect();
+    if escaped == specifier {
+        format!("{}.js", escaped)
+    } else {
+        format!("{}-{:08x}.js", escaped, cache::content_hash(specifier.as_bytes()) as u32)
+    }
+}
+
+/// Scans the app and makes sure every dependency has an up-to-date bundle,
+/// rebuilding all of them when the lockfile changed or a new one appeared.
+pub fn optimize(root: &Path, resolver: &NexusResolver) -> std::io::Result<DepsManifest> {
+    let dir = root.join(DEPS_DIR);
+    let specifiers = scan_dependencies(root, resolver);
+    let lock_hash = lock_hash(root);
+
+    let previous: Option<DepsManifest> = std::fs::read(dir.join(METADATA_FILE))
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok());
+    if let Some(previous) = previous {
+        let complete = specifiers.iter().all(|s| previous.deps.contains_key(s))
+            && previous.deps.values().all(|id| bundle_path(root, id).exists());
+        if previous.lock_hash == lock_hash && complete {
+            return Ok(previous);
+        }
+    }
+
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    let manifest = DepsManifest {
+        lock_hash,
+        deps: specifiers
+            .iter()
+            .map(|s| (s.clone(), format!("/{}/{}", DEPS_DIR, bundle_file_name(s))))
+            .collect(),
+    };
+    for specifier in &specifiers {
+        let code = bundle_dependency(root, resolver, specifier, &manifest)?;
+        std::fs::write(dir.join(bundle_file_name(specifier)), code)?;
+    }
+    std::fs::write(dir.join(METADATA_FILE), serde_json::to_vec_pretty(&manifest)?)?;
+    tracing::info!("Pre-bundled {} dependencies into {}", specifiers.len(), DEPS_DIR);
+    Ok(manifest)
+}
+
+/// Bundles `specifier` and everything it imports into one CommonJS module.
+/// Imports of other pre-bundled packages are left to the dev runtime.
+pub fn bundle_dependency(
+    root: &Path,
+    resolver: &NexusResolver,
+    specifier: &str,
+    manifest: &DepsManifest,
+) -> std::io::Result<String> {
+    let entry = resolver.resolve(root, specifier)?;
+    let entry_id = normalize_id(root, &entry);
+    // Packages branch on NODE_ENV to pick their development build. The app's
+    // env files are its own business and stay out of bundles.
+    let defines = Defines::new("development", &BTreeMap::new(), &BTreeMap::new());
+    let mut modules = Vec::new();
+    let mut visited = HashSet::from([entry.clone()]);
+    let mut queue = VecDeque::from([entry]);
+
+    while let Some(path) = queue.pop_front() {
+        let id = normalize_id(root, &path);
+        let source = std::fs::read_to_string(&path)?;
+        if path.extension().is_some_and(|e| e == "json") {
+            modules.push((id, format!("module.exports = {};", source.trim()), HashMap::new()));
+            continue;
+        }
+
+        let mut unit = CompilationUnit::parse(&id, source.clone());
+        unit.define(&defines);
+        let source = if unit.has_errors() { source } else { unit.code() };
+        let mut imports = HashMap::new();
+        for (spec, _) in unit.dependencies() {
+            if let Some(external) = manifest.get(&spec).filter(|_| spec != specifier) {
+                imports.insert(spec, external.to_string());
+                continue;
+            }
+            let Ok(target) = resolver.resolve(&path, &spec) else { continue };
+            imports.insert(spec, normalize_id(root, &target));
+            if visited.insert(target.clone()) {
+                queue.push_back(target);
+            }
+        }
+        modules.push((id.clone(), transform_cjs(&source, &id, &imports), imports));
+    }
+
+    let mut out = format!("// Pre-bundled by nexus: {}\nvar __nexus_dep_modules__ = {{\n", specifier);
+    for (id, code, imports) in &modules {
+        let imports: BTreeMap<&String, &String> = imports.iter().collect();
+        out.push_str(&format!(
+            "{}: [function(require, module, exports) {{\n{}\n}}, {}],\n",
+            serde_json::to_string(id)?,
+            code,
+            serde_json::to_string(&imports)?
+        ));
+    }
+    out.push_str("};\n");
+    out.push_str(&DEP_LOADER.replace("__ENTRY__", &serde_json::to_string(&entry_id)?));
+    Ok(out)
+}
+
+// Runs inside the module wrapper of the dev runtime: `require` and `module`
+// are the runtime's. Inner modules get a `require` that maps their specifiers
+// to bundle-internal ids and falls back to the runtime for everything else.
+// CommonJS exports are given a `default` so `import React from 'react'` works.
+const DEP_LOADER: &str = r#"var __nexus_dep_cache__ = {};
+function __nexus_dep_require__(id) {
+  var cached = __nexus_dep_cache__[id];
+  if (cached) return cached.exports;
+  var entry = __nexus_dep_modules__[id];
+  if (!entry) return require(id);
+  var m = { id: id, exports: {} };
+  __nexus_dep_cache__[id] = m;
+  entry[0](function(spec) { return __nexus_dep_require__(entry[1][spec] || spec); }, m, m.exports);
+  return m.exports;
+}
+var __nexus_dep__ = __nexus_dep_require__(__ENTRY__);
+module.exports = __nexus_dep__ && __nexus_dep__.__esModule
+  ? __nexus_dep__
+  : Object.assign({ __esModule: true, default: __nexus_dep__ }, __nexus_dep__);
+"#;
+
+/// The dev server's pre-bundled dependencies, kept up to date while it runs.
+pub struct DepsOptimizer {
+    root: PathBuf,
+    resolver: Arc<NexusResolver>,
+    manifest: RwLock<Arc<DepsManifest>>,
+    // Packages a re-run still had no bundle for (e.g. only imported by code
+    // the scan can't see); not retried on every request.
+    missed: Mutex<HashSet<String>>,
+}
+
+impl DepsOptimizer {
+    /// Runs `optimize` for the first time. If it fails, packages are served
+    /// unbundled.
+    pub fn new(root: &Path, resolver: Arc<NexusResolver>) -> Self {
+        let manifest = Self::run(root, &resolver).unwrap_or_default();
+        Self { root: root.to_path_buf(), resolver, manifest: RwLock::new(Arc::new(manifest)), missed: Mutex::default() }
+    }
+
+    fn run(root: &Path, resolver: &NexusResolver) -> Option<DepsManifest> {
+        optimize(root, resolver)
+            .map_err(|e| tracing::warn!("Dependency pre-bundling failed, serving packages unbundled: {}", e))
+            .ok()
+    }
+
+    pub fn manifest(&self) -> Arc<DepsManifest> {
+        self.manifest.read().unwrap().clone()
+    }
+
+    pub fn get(&self, specifier: &str) -> Option<String> {
+        self.manifest().get(specifier).map(str::to_string)
+    }
+
+    /// Whether `specifier`, imported from `importer`, is a package that has
+    /// no bundle yet.
+    pub fn is_missing(&self, importer: &Path, specifier: &str) -> bool {
+        is_bare(specifier)
+            && self.get(specifier).is_none()
+            && !self.missed.lock().unwrap().contains(specifier)
+            && self.resolver.resolve(importer, specifier).is_ok_and(|target| target.to_string_lossy().contains("node_modules"))
+    }
+
+    /// Whether `path` is one of the project's lockfiles.
+    pub fn is_lockfile(&self, path: &Path) -> bool {
+        path.parent() == Some(self.root.as_path())
+            && path.file_name().and_then(|name| name.to_str()).is_some_and(|name| cache::LOCKFILES.contains(&name))
+    }
+
+    /// Runs `optimize` again, for the `wanted` packages or after a lockfile
+    /// change, and updates the bundles already in `graph`. Returns the ones
+    /// whose code changed: pages using them have to reload.
+    pub fn refresh(&self, graph: &RwLock<ModuleGraph>, wanted: &[String]) -> Vec<String> {
+        // Held throughout, so concurrent requests wait for one run.
+        let mut manifest = self.manifest.write().unwrap();
+        let Some(next) = Self::run(&self.root, &self.resolver) else { return Vec::new() };
+        self.missed.lock().unwrap().extend(wanted.iter().filter(|s| !next.deps.contains_key(*s)).cloned());
+        if next == **manifest {
+            return Vec::new();
+        }
+        *manifest = Arc::new(next);
+        drop(manifest);
+
+        let prefix = format!("/{}/", DEPS_DIR);
+        let mut graph = graph.write().unwrap();
+        let bundles: Vec<_> = graph.modules.iter().filter(|m| m.path.starts_with(&prefix)).map(|m| (m.id, m.path.clone())).collect();
+        let mut changed = Vec::new();
+        for (id, path) in bundles {
+            let code = std::fs::read_to_string(bundle_path(&self.root, &path)).unwrap_or_default();
+            if graph.modules[id.0].source != code {
+                graph.update_compiled(id, &code, None);
+                changed.push(path);
+            }
+        }
+        changed
+    }
+}
+
+/// Filesystem path of a pre-bundled dependency's graph id.
+pub fn bundle_path(root: &Path, id: &str) -> PathBuf {
+    root.join(id.trim_start_matches('/'))
+}
@@ -0,0 +1,67 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+// Tree-shaking report: what ended up in the bundle, how much shaking removed
+// and which import chain made each module live.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModuleReport {
+    pub id: String,
+    pub chunk: String,
+    /// Compiled size before tree shaking, in bytes.
+    pub size_before: usize,
+    /// Size after tree shaking, in bytes.
+    pub size_after: usize,
+    pub used_exports: Vec<String>,
+    pub removed_exports: Vec<String>,
+    /// Live modules that import this one.
+    pub importers: Vec<String>,
+    /// Shortest import path from the entry, ending with this module.
+    pub chain: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BuildReport {
+    pub entry: String,
+    pub modules: Vec<ModuleReport>,
+    /// Modules found in the graph but left out of the bundle.
+    pub pruned: Vec<String>,
+}
+
+impl BuildReport {
+    pub fn module(&self, id: &str) -> Option<&ModuleReport> {
+        self.modules.iter().find(|m| m.id == id)
+    }
+
+    /// Why is `id` included? Returns the shortest import path from the entry,
+    /// or `None` if the module is not in the bundle.
+    pub fn why(&self, id: &str) -> Option<&[String]> {
+        self.module(id).map(|m| m.chain.as_slice())
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+/// BFS over live import edges; `edges` must only contain live targets.
+pub fn shortest_chains(entry: &str, edges: &BTreeMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
+    let mut chains: HashMap<String, Vec<String>> = HashMap::new();
+    let mut queue = VecDeque::new();
+    chains.insert(entry.to_string(), vec![entry.to_string()]);
+    queue.push_back(entry.to_string());
+
+    while let Some(current) = queue.pop_front() {
+        let chain = chains[&current].clone();
+        for dep in edges.get(&current).into_iter().flatten() {
+            if chains.contains_key(dep) {
+                continue;
+            }
+            let mut next = chain.clone();
+            next.push(dep.clone());
+            chains.insert(dep.clone(), next);
+            queue.push_back(dep.clone());
+        }
+    }
+    chains
+}
@@ -1,14 +1,67 @@
-use oxc_resolver::{ResolveOptions, Resolver, Resolution};
+use oxc_resolver::{ResolveOptions, Resolver};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+
+/// The `sideEffects` field of a package.json.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SideEffects {
+    Flag(bool),
+    Globs(Vec<String>),
+}
+
+impl SideEffects {
+    /// Whether `file` (inside `package_dir`) may have side effects.
+    pub fn matches(&self, package_dir: &Path, file: &Path) -> bool {
+        match self {
+            SideEffects::Flag(flag) => *flag,
+            SideEffects::Globs(globs) => {
+                let rel = file.strip_prefix(package_dir).unwrap_or(file);
+                let rel = rel.to_string_lossy().replace('\\', "/");
+                globs.iter().any(|g| {
+                    let g = g.trim_start_matches("./");
+                    // Patterns without a slash match the file name anywhere (webpack semantics).
+                    if g.contains('/') {
+                        glob_match(g, &rel)
+                    } else {
+                        glob_match(&format!("**/{}", g), &rel)
+                    }
+                })
+            }
+        }
+    }
+}
+
+// Minimal glob matcher: `*` and `?` stay inside one path segment, `**` spans segments.
+fn glob_match(pattern: &str, path: &str) -> bool {
+    fn go(p: &[u8], s: &[u8]) -> bool {
+        match p.first() {
+            None => s.is_empty(),
+            Some(b'*') if p.get(1) == Some(&b'*') => {
+                let rest = p[2..].strip_prefix(b"/").unwrap_or(&p[2..]);
+                (0..=s.len()).any(|i| (i == 0 || s[i - 1] == b'/') && go(rest, &s[i..]))
+            }
+            Some(b'*') => (0..=s.len())
+                .take_while(|&i| i == 0 || s[i - 1] != b'/')
+                .any(|i| go(&p[1..], &s[i..])),
+            Some(b'?') => !s.is_empty() && s[0] != b'/' && go(&p[1..], &s[1..]),
+            Some(c) => s.first() == Some(c) && go(&p[1..], &s[1..]),
+        }
+    }
+    go(pattern.as_bytes(), path.as_bytes())
+}
+
+// Directory -> nearest package.json directory and its `sideEffects` field.
+type PackageCache = Arc<RwLock<HashMap<PathBuf, Option<(PathBuf, Option<SideEffects>)>>>>;
 
 #[derive(Clone)]
 pub struct NexusResolver {
     resolver: Arc<Resolver>,
+    package_cache: PackageCache,
 }
 
 impl NexusResolver {
-    pub fn new(project_root: &Path) -> Self {
+    pub fn new(_project_root: &Path) -> Self {
         let options = ResolveOptions {
              extensions: vec![
                 ".ts".into(),
@@ -29,7 +82,45 @@ impl NexusResolver {
         
         Self {
             resolver: Arc::new(resolver),
+            package_cache: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Finds the nearest package.json above `file` and returns its directory
+    /// and `sideEffects` field.
+    pub fn package_side_effects(&self, file: &Path) -> Option<(PathBuf, Option<SideEffects>)> {
+        let dir = file.parent()?.to_path_buf();
+        if let Some(cached) = self.package_cache.read().unwrap().get(&dir) {
+            return cached.clone();
         }
+
+        let mut found = None;
+        for ancestor in dir.ancestors() {
+            let manifest = ancestor.join("package.json");
+            if let Ok(text) = std::fs::read_to_string(&manifest) {
+                let json: serde_json::Value = serde_json::from_str(&text).unwrap_or_default();
+                let side_effects = match json.get("sideEffects") {
+                    Some(serde_json::Value::Bool(b)) => Some(SideEffects::Flag(*b)),
+                    Some(serde_json::Value::String(s)) => Some(SideEffects::Globs(vec![s.clone()])),
+                    Some(serde_json::Value::Array(items)) => Some(SideEffects::Globs(
+                        items.iter().filter_map(|v| v.as_str().map(String::from)).collect(),
+                    )),
+                    _ => None,
+                };
+                found = Some((ancestor.to_path_buf(), side_effects));
+                break;
+            }
+        }
+
+        self.package_cache.write().unwrap().insert(dir, found.clone());
+        found
+    }
+
+    /// `Some(false)` if the owning package declares `file` free of side effects,
+    /// `Some(true)` if it declares it effectful, `None` if it says nothing.
+    pub fn has_side_effects(&self, file: &Path) -> Option<bool> {
+        let (package_dir, side_effects) = self.package_side_effects(file)?;
+        side_effects.map(|s| s.matches(&package_dir, file))
     }
 
     pub fn resolve(&self, from: &Path, import: &str) -> std::io::Result<PathBuf> {
@@ -43,7 +134,7 @@ impl NexusResolver {
         };
 
         match self.resolver.resolve(dir, import) {
-            Ok(Resolution { path, .. }) => Ok(path),
+            Ok(resolution) => Ok(resolution.into_path_buf()),
             Err(e) => {
                  // Convert oxc error to io error for simplicity or handle gracefully
                  Err(std::io::Error::new(std::io::ErrorKind::NotFound, format!("Resolution failed: {}", e)))
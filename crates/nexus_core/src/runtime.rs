@@ -53,19 +53,32 @@ pub const NEXUS_RUNTIME_JS: &str = r#"
         module.exports            // exports
       );
     } catch (err) {
-      delete global.__nexus_cache__[id];
+      // Only a module that failed leaves the cache, so it runs again on the next require.
+      delete global.__nexus_cache__[module.id];
       throw err;
     }
 
     return module.exports;
   };
 
+  // `import()` resolves to a namespace; a CommonJS module's has its
+  // `module.exports` as the default export. Static imports get the same from
+  // the linker.
+  function namespace(e) {
+    if (e && e.__esModule) return e;
+    const ns = { default: e };
+    if (e && (typeof e === "object" || typeof e === "function")) {
+      for (const k in e) if (k !== "default") ns[k] = e[k];
+    }
+    return ns;
+  }
+
   // 5. Async Import
   global.__nexus_chunk_map__ = global.__nexus_chunk_map__ || {};
   
   global.__nexus_import__ = function(id) {
     if (global.__nexus_modules__[id]) {
-      return Promise.resolve(global.__nexus_require__(id));
+      return Promise.resolve(namespace(global.__nexus_require__(id)));
     }
 
     let url = id;
@@ -83,12 +96,61 @@ pub const NEXUS_RUNTIME_JS: &str = r#"
       })
       .then(code => {
           (0, eval)(code);
+          return global.__nexus_wasm__.ready();
+      })
+      .then(() => {
           if (!global.__nexus_modules__[id]) {
               throw new Error("[Nexus] Async chunk loaded but module not registered: " + id);
           }
-          return global.__nexus_require__(id);
+          return namespace(global.__nexus_require__(id));
       });
   };
-})(typeof window !== 'undefined' ? window : this);
+
+  // 6. Workers
+  // Production builds map each worker entry to its bundle; the dev server
+  // bundles worker entries on request.
+  global.__nexus_worker__ = function(id) {
+    const map = global.__nexus_worker_map__;
+    return (map && map[id]) || "/_nexus/worker?entry=" + encodeURIComponent(id);
+  };
+
+  // 7. WebAssembly
+  // Bundles compile the `.wasm` modules they import for their exports before
+  // running, so module factories can instantiate them synchronously.
+  global.__nexus_wasm__ = global.__nexus_wasm__ || (function() {
+    const compiled = {};
+    let pending = Promise.resolve();
+
+    function url(id) {
+      const map = global.__nexus_asset_map__;
+      return (map && map[id]) || id;
+    }
+
+    return {
+      url: url,
+      preload: function(ids) {
+        const loads = ids.map(function(id) {
+          return WebAssembly.compileStreaming(fetch(url(id))).then(function(mod) { compiled[id] = mod; });
+        });
+        pending = Promise.all([pending].concat(loads));
+      },
+      ready: function() {
+        return pending;
+      },
+      // `?init`
+      instantiate: function(id, imports) {
+        return WebAssembly.instantiateStreaming(fetch(url(id)), imports || {}).then(function(result) {
+          return result.instance;
+        });
+      },
+      instance: function(id, imports) {
+        if (!compiled[id]) {
+          throw new Error("[Nexus] WebAssembly module not loaded: " + id);
+        }
+        return new WebAssembly.Instance(compiled[id], imports).exports;
+      }
+    };
+  })();
+})(typeof self !== 'undefined' ? self : this);
 "#;
 
@@ -0,0 +1,596 @@
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast::AstBuilder;
+use oxc_ast_visit::Visit;
+use oxc_parser::Parser;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use crate::unit::{parse_statements, CompilationUnit};
+
+// Statement-level dead code elimination.
+//
+// Every top-level statement records the names it declares, the names it
+// references and whether evaluating it can have side effects. Liveness starts
+// from impure statements and used exports and follows references until nothing
+// changes; everything else is dropped.
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum StmtKind {
+    // import ... from "source" (bindings: local -> imported, "*" for namespace)
+    Import { source: String, bindings: Vec<(String, String)> },
+    // export { a as b } from "source" / export * from "source"
+    ReExport { source: String, names: Vec<(String, String)>, star: bool },
+    Local,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StmtInfo {
+    kind: StmtKind,
+    declares: Vec<String>,
+    // (exported name, local binding)
+    exports: Vec<(String, Option<String>)>,
+    references: HashSet<String>,
+    has_side_effects: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ShakeInfo {
+    stmts: Vec<StmtInfo>,
+    /// True if any top-level statement other than an import has side effects.
+    pub has_side_effects: bool,
+}
+
+/// The binding behind an exported name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExportBinding {
+    /// Declared in this module.
+    Local(String),
+    /// Forwarded from another module; `name` is `"*"` for its namespace object.
+    Imported { source: String, name: String },
+}
+
+/// What a module needs from one of its static dependencies.
+#[derive(Debug, Clone)]
+pub struct ImportUsage {
+    pub source: String,
+    pub names: Vec<String>,
+    pub namespace: bool,
+    /// Names were forwarded through `export *` and may not exist in the target.
+    pub star: bool,
+}
+
+struct ReferenceCollector {
+    names: HashSet<String>,
+}
+
+impl<'a> Visit<'a> for ReferenceCollector {
+    fn visit_identifier_reference(&mut self, ident: &IdentifierReference<'a>) {
+        self.names.insert(ident.name.to_string());
+    }
+}
+
+fn collect_references(f: impl FnOnce(&mut ReferenceCollector)) -> HashSet<String> {
+    let mut collector = ReferenceCollector { names: HashSet::new() };
+    f(&mut collector);
+    collector.names
+}
+
+// --- PURITY ---
+
+pub fn is_pure_expression(expr: &Expression) -> bool {
+    match expr {
+        Expression::BooleanLiteral(_)
+        | Expression::NullLiteral(_)
+        | Expression::NumericLiteral(_)
+        | Expression::BigIntLiteral(_)
+        | Expression::RegExpLiteral(_)
+        | Expression::StringLiteral(_)
+        | Expression::Identifier(_)
+        | Expression::MetaProperty(_)
+        | Expression::FunctionExpression(_)
+        | Expression::ArrowFunctionExpression(_)
+        | Expression::JSXElement(_)
+        | Expression::JSXFragment(_) => true,
+        Expression::ClassExpression(c) => is_pure_class(c),
+        Expression::TemplateLiteral(t) => t.expressions.iter().all(is_pure_expression),
+        Expression::ArrayExpression(a) => a.elements.iter().all(|el| match el {
+            ArrayExpressionElement::SpreadElement(_) => false,
+            ArrayExpressionElement::Elision(_) => true,
+            _ => el.as_expression().is_some_and(is_pure_expression),
+        }),
+        Expression::ObjectExpression(o) => o.properties.iter().all(|p| match p {
+            ObjectPropertyKind::ObjectProperty(p) => is_pure_key(&p.key) && is_pure_expression(&p.value),
+            ObjectPropertyKind::SpreadProperty(_) => false,
+        }),
+        Expression::UnaryExpression(u) => u.operator != UnaryOperator::Delete && is_pure_expression(&u.argument),
+        Expression::BinaryExpression(b) => is_pure_expression(&b.left) && is_pure_expression(&b.right),
+        Expression::LogicalExpression(l) => is_pure_expression(&l.left) && is_pure_expression(&l.right),
+        Expression::ConditionalExpression(c) => {
+            is_pure_expression(&c.test) && is_pure_expression(&c.consequent) && is_pure_expression(&c.alternate)
+        }
+        Expression::SequenceExpression(s) => s.expressions.iter().all(is_pure_expression),
+        Expression::ParenthesizedExpression(p) => is_pure_expression(&p.expression),
+        Expression::TSAsExpression(e) => is_pure_expression(&e.expression),
+        Expression::TSSatisfiesExpression(e) => is_pure_expression(&e.expression),
+        Expression::TSNonNullExpression(e) => is_pure_expression(&e.expression),
+        Expression::TSTypeAssertion(e) => is_pure_expression(&e.expression),
+        // Only calls annotated with /*#__PURE__*/ are assumed side-effect free.
+        Expression::CallExpression(c) => c.pure && c.arguments.iter().all(is_pure_argument),
+        Expression::NewExpression(n) => n.pure && n.arguments.iter().all(is_pure_argument),
+        _ => false,
+    }
+}
+
+fn is_pure_argument(arg: &Argument) -> bool {
+    match arg {
+        Argument::SpreadElement(_) => false,
+        _ => arg.as_expression().is_some_and(is_pure_expression),
+    }
+}
+
+fn is_pure_key(key: &PropertyKey) -> bool {
+    match key {
+        PropertyKey::StaticIdentifier(_) | PropertyKey::PrivateIdentifier(_) => true,
+        _ => key.as_expression().is_some_and(is_pure_expression),
+    }
+}
+
+fn is_pure_class(class: &Class) -> bool {
+    if !class.decorators.is_empty() {
+        return false;
+    }
+    if let Some(super_class) = &class.super_class {
+        if !is_pure_expression(super_class) {
+            return false;
+        }
+    }
+    class.body.body.iter().all(|element| match element {
+        ClassElement::StaticBlock(_) => false,
+        ClassElement::MethodDefinition(m) => m.decorators.is_empty() && is_pure_key(&m.key),
+        ClassElement::PropertyDefinition(p) => {
+            p.decorators.is_empty()
+                && is_pure_key(&p.key)
+                && (!p.r#static || p.value.as_ref().is_none_or(is_pure_expression))
+        }
+        ClassElement::AccessorProperty(p) => {
+            p.decorators.is_empty()
+                && is_pure_key(&p.key)
+                && (!p.r#static || p.value.as_ref().is_none_or(is_pure_expression))
+        }
+        ClassElement::TSIndexSignature(_) => true,
+    })
+}
+
+// --- ANALYSIS ---
+
+fn analyze_declaration(decl: &Declaration) -> (Vec<String>, HashSet<String>, bool) {
+    match decl {
+        Declaration::VariableDeclaration(var_decl) => {
+            let mut declares = Vec::new();
+            let mut pure = true;
+            for d in &var_decl.declarations {
+                for id in d.id.get_binding_identifiers() {
+                    declares.push(id.name.to_string());
+                }
+                // Destructuring may run getters or iterators.
+                if !matches!(d.id.kind, BindingPatternKind::BindingIdentifier(_)) {
+                    pure = false;
+                }
+                if let Some(init) = &d.init {
+                    pure &= is_pure_expression(init);
+                }
+            }
+            (declares, collect_references(|c| c.visit_declaration(decl)), !pure)
+        }
+        Declaration::FunctionDeclaration(f) => {
+            let declares = f.id.iter().map(|id| id.name.to_string()).collect();
+            (declares, collect_references(|c| c.visit_declaration(decl)), false)
+        }
+        Declaration::ClassDeclaration(c) => {
+            let declares = c.id.iter().map(|id| id.name.to_string()).collect();
+            (declares, collect_references(|v| v.visit_declaration(decl)), !is_pure_class(c))
+        }
+        Declaration::TSTypeAliasDeclaration(t) => (vec![t.id.name.to_string()], HashSet::new(), false),
+        Declaration::TSInterfaceDeclaration(t) => (vec![t.id.name.to_string()], HashSet::new(), false),
+        Declaration::TSEnumDeclaration(e) => {
+            (vec![e.id.name.to_string()], collect_references(|c| c.visit_declaration(decl)), false)
+        }
+        _ => (Vec::new(), collect_references(|c| c.visit_declaration(decl)), true),
+    }
+}
+
+fn analyze_statement(stmt: &Statement) -> StmtInfo {
+    let local = |declares, references, has_side_effects| StmtInfo {
+        kind: StmtKind::Local,
+        declares,
+        exports: Vec::new(),
+        references,
+        has_side_effects,
+    };
+
+    match stmt {
+        Statement::ImportDeclaration(import_decl) => {
+            let mut bindings = Vec::new();
+            if let Some(specifiers) = &import_decl.specifiers {
+                for spec in specifiers {
+                    match spec {
+                        ImportDeclarationSpecifier::ImportDefaultSpecifier(s) => {
+                            bindings.push((s.local.name.to_string(), "default".to_string()));
+                        }
+                        ImportDeclarationSpecifier::ImportNamespaceSpecifier(s) => {
+                            bindings.push((s.local.name.to_string(), "*".to_string()));
+                        }
+                        ImportDeclarationSpecifier::ImportSpecifier(s) => {
+                            bindings.push((s.local.name.to_string(), s.imported.name().to_string()));
+                        }
+                    }
+                }
+            }
+            StmtInfo {
+                declares: bindings.iter().map(|(l, _)| l.clone()).collect(),
+                has_side_effects: bindings.is_empty(),
+                kind: StmtKind::Import { source: import_decl.source.value.to_string(), bindings },
+                exports: Vec::new(),
+                references: HashSet::new(),
+            }
+        }
+        Statement::ExportAllDeclaration(export_all) => {
+            let (names, star) = match &export_all.exported {
+                Some(exported) => (vec![("*".to_string(), exported.name().to_string())], false),
+                None => (Vec::new(), true),
+            };
+            StmtInfo {
+                exports: names.iter().map(|(_, e)| (e.clone(), None)).collect(),
+                kind: StmtKind::ReExport { source: export_all.source.value.to_string(), names, star },
+                declares: Vec::new(),
+                references: HashSet::new(),
+                has_side_effects: false,
+            }
+        }
+        Statement::ExportNamedDeclaration(export_named) => {
+            if let Some(source) = &export_named.source {
+                let names: Vec<(String, String)> = export_named
+                    .specifiers
+                    .iter()
+                    .map(|s| (s.local.name().to_string(), s.exported.name().to_string()))
+                    .collect();
+                StmtInfo {
+                    exports: names.iter().map(|(_, e)| (e.clone(), None)).collect(),
+                    kind: StmtKind::ReExport { source: source.value.to_string(), names, star: false },
+                    declares: Vec::new(),
+                    references: HashSet::new(),
+                    has_side_effects: false,
+                }
+            } else if let Some(decl) = &export_named.declaration {
+                let (declares, references, has_side_effects) = analyze_declaration(decl);
+                let mut info = local(declares, references, has_side_effects);
+                info.exports = info.declares.iter().map(|n| (n.clone(), Some(n.clone()))).collect();
+                info
+            } else {
+                // `export { a as b }`: references are added per used specifier.
+                let mut info = local(Vec::new(), HashSet::new(), false);
+                info.exports = export_named
+                    .specifiers
+                    .iter()
+                    .map(|s| (s.exported.name().to_string(), Some(s.local.name().to_string())))
+                    .collect();
+                info
+            }
+        }
+        Statement::ExportDefaultDeclaration(export_default) => {
+            let references =
+                collect_references(|c| c.visit_export_default_declaration_kind(&export_default.declaration));
+            let (declares, has_side_effects) = match &export_default.declaration {
+                ExportDefaultDeclarationKind::FunctionDeclaration(f) => {
+                    (f.id.iter().map(|id| id.name.to_string()).collect(), false)
+                }
+                ExportDefaultDeclarationKind::ClassDeclaration(c) => {
+                    (c.id.iter().map(|id| id.name.to_string()).collect(), !is_pure_class(c))
+                }
+                ExportDefaultDeclarationKind::TSInterfaceDeclaration(_) => (Vec::new(), false),
+                kind => (Vec::new(), !is_pure_expression(kind.to_expression())),
+            };
+            let mut info = local(declares, references, has_side_effects);
+            info.exports = vec![("default".to_string(), info.declares.first().cloned())];
+            info
+        }
+        Statement::VariableDeclaration(_)
+        | Statement::FunctionDeclaration(_)
+        | Statement::ClassDeclaration(_)
+        | Statement::TSTypeAliasDeclaration(_)
+        | Statement::TSInterfaceDeclaration(_)
+        | Statement::TSEnumDeclaration(_) => {
+            let (declares, references, has_side_effects) = analyze_declaration(stmt.as_declaration().unwrap());
+            local(declares, references, has_side_effects)
+        }
+        Statement::EmptyStatement(_) => local(Vec::new(), HashSet::new(), false),
+        _ => local(Vec::new(), collect_references(|c| c.visit_statement(stmt)), true),
+    }
+}
+
+pub(crate) fn analyze_program(program: &Program) -> ShakeInfo {
+    let stmts: Vec<StmtInfo> = program.body.iter().map(analyze_statement).collect();
+    let has_side_effects = stmts
+        .iter()
+        .any(|s| matches!(s.kind, StmtKind::Local) && s.has_side_effects);
+    ShakeInfo { stmts, has_side_effects }
+}
+
+pub fn analyze(source: &str, path: &str) -> ShakeInfo {
+    let allocator = Allocator::default();
+    let source_type = crate::loader::source_type_of(path);
+    let ret = Parser::new(&allocator, source, source_type).parse();
+
+    if !ret.errors.is_empty() {
+        // Unparseable code is kept as-is, so treat it as effectful.
+        return ShakeInfo { stmts: Vec::new(), has_side_effects: true };
+    }
+    analyze_program(&ret.program)
+}
+
+impl ShakeInfo {
+    /// Info for modules without JS statements (CSS, binary assets).
+    pub fn opaque(has_side_effects: bool) -> Self {
+        Self { stmts: Vec::new(), has_side_effects }
+    }
+
+    /// Marks live statements for the given used exports and returns them with
+    /// the set of live top-level names.
+    fn live(&self, used_exports: &HashSet<String>) -> (Vec<bool>, HashSet<String>) {
+        let mut declared_by: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (i, stmt) in self.stmts.iter().enumerate() {
+            for name in &stmt.declares {
+                declared_by.entry(name.as_str()).or_default().push(i);
+            }
+        }
+
+        let mut live = vec![false; self.stmts.len()];
+        let mut names: HashSet<String> = HashSet::new();
+        let mut pending: Vec<String> = Vec::new();
+        let mut stack: Vec<usize> = Vec::new();
+
+        for (i, stmt) in self.stmts.iter().enumerate() {
+            let root = match &stmt.kind {
+                StmtKind::Import { .. } => stmt.has_side_effects,
+                StmtKind::ReExport { .. } => false,
+                StmtKind::Local => {
+                    stmt.has_side_effects || stmt.exports.iter().any(|(e, _)| used_exports.contains(e))
+                }
+            };
+            if root {
+                live[i] = true;
+                stack.push(i);
+            }
+            for (exported, local) in &stmt.exports {
+                if let Some(local) = local {
+                    if used_exports.contains(exported) {
+                        pending.push(local.clone());
+                    }
+                }
+            }
+        }
+
+        loop {
+            while let Some(i) = stack.pop() {
+                pending.extend(self.stmts[i].references.iter().cloned());
+            }
+            if pending.is_empty() {
+                break;
+            }
+            for name in pending.drain(..) {
+                if !names.insert(name.clone()) {
+                    continue;
+                }
+                for &i in declared_by.get(name.as_str()).into_iter().flatten() {
+                    if !live[i] {
+                        live[i] = true;
+                        stack.push(i);
+                    }
+                }
+            }
+        }
+
+        (live, names)
+    }
+
+    /// Every exported name except those behind `export *`, with its binding.
+    pub fn export_bindings(&self) -> Vec<(String, ExportBinding)> {
+        let imported: HashMap<&str, (&str, &str)> = self
+            .stmts
+            .iter()
+            .filter_map(|s| match &s.kind {
+                StmtKind::Import { source, bindings } => Some((source, bindings)),
+                _ => None,
+            })
+            .flat_map(|(source, bindings)| {
+                bindings.iter().map(move |(l, i)| (l.as_str(), (source.as_str(), i.as_str())))
+            })
+            .collect();
+
+        let mut result = Vec::new();
+        for stmt in &self.stmts {
+            match &stmt.kind {
+                StmtKind::ReExport { source, names, .. } => {
+                    for (name, exported) in names {
+                        result.push((
+                            exported.clone(),
+                            ExportBinding::Imported { source: source.clone(), name: name.clone() },
+                        ));
+                    }
+                }
+                StmtKind::Local => {
+                    for (exported, local) in &stmt.exports {
+                        let binding = match local.as_deref().and_then(|l| imported.get(l)) {
+                            // `import { a } from "x"; export { a }`
+                            Some((source, name)) => {
+                                ExportBinding::Imported { source: source.to_string(), name: name.to_string() }
+                            }
+                            None => ExportBinding::Local(local.clone().unwrap_or_else(|| exported.clone())),
+                        };
+                        result.push((exported.clone(), binding));
+                    }
+                }
+                StmtKind::Import { .. } => {}
+            }
+        }
+        result
+    }
+
+    /// Sources of `export * from` statements.
+    pub fn star_sources(&self) -> Vec<&str> {
+        self.stmts
+            .iter()
+            .filter_map(|s| match &s.kind {
+                StmtKind::ReExport { source, star: true, .. } => Some(source.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Lists, per static dependency, the imported names that survive shaking.
+    pub fn dependency_usage(&self, used_exports: &HashSet<String>) -> Vec<ImportUsage> {
+        let (_, names) = self.live(used_exports);
+        let local_exports: HashSet<&str> = self
+            .stmts
+            .iter()
+            .filter(|s| !matches!(s.kind, StmtKind::ReExport { star: true, .. }))
+            .flat_map(|s| s.exports.iter().map(|(e, _)| e.as_str()))
+            .collect();
+
+        let mut usages = Vec::new();
+        for stmt in &self.stmts {
+            match &stmt.kind {
+                StmtKind::Import { source, bindings } => {
+                    let mut usage =
+                        ImportUsage { source: source.clone(), names: Vec::new(), namespace: false, star: false };
+                    for (local, imported) in bindings {
+                        if names.contains(local) {
+                            if imported == "*" {
+                                usage.namespace = true;
+                            } else {
+                                usage.names.push(imported.clone());
+                            }
+                        }
+                    }
+                    usages.push(usage);
+                }
+                StmtKind::ReExport { source, names: specs, star } => {
+                    let mut usage =
+                        ImportUsage { source: source.clone(), names: Vec::new(), namespace: false, star: *star };
+                    for (imported, exported) in specs {
+                        if used_exports.contains(exported) {
+                            if imported == "*" {
+                                usage.namespace = true;
+                            } else {
+                                usage.names.push(imported.clone());
+                            }
+                        }
+                    }
+                    if *star {
+                        // Anything not provided locally may come through `export *`.
+                        for used in used_exports {
+                            if used != "default" && !local_exports.contains(used.as_str()) {
+                                usage.names.push(used.clone());
+                            }
+                        }
+                    }
+                    usages.push(usage);
+                }
+                StmtKind::Local => {}
+            }
+        }
+        usages
+    }
+}
+
+// --- TRANSFORM ---
+
+/// Removes statements that are unreachable from side effects and used
+/// exports. Imports and re-exports of `dropped_sources` are removed entirely
+/// because the bundler will not emit those modules.
+pub fn shake(
+    source: &str,
+    path: &str,
+    used_exports: &HashSet<String>,
+    dropped_sources: &HashSet<String>,
+) -> String {
+    let mut unit = CompilationUnit::parse(path, source.to_string());
+    if unit.has_errors() {
+        return source.to_string();
+    }
+    unit.shake(used_exports, dropped_sources);
+    unit.code()
+}
+
+/// In-place version of `shake` on an already parsed program.
+pub(crate) fn shake_program<'a>(
+    allocator: &'a Allocator,
+    program: &mut Program<'a>,
+    used_exports: &HashSet<String>,
+    dropped_sources: &HashSet<String>,
+) {
+    let info = analyze_program(program);
+    let (live, names) = info.live(used_exports);
+    let body = std::mem::replace(&mut program.body, AstBuilder::new(allocator).vec());
+
+    for ((stmt, meta), is_live) in body.into_iter().zip(&info.stmts).zip(live) {
+        let kept = match (&meta.kind, stmt) {
+            (StmtKind::Import { source, .. }, _) if dropped_sources.contains(source) => None,
+            (StmtKind::Import { bindings, .. }, Statement::ImportDeclaration(mut decl)) if !bindings.is_empty() => {
+                if let Some(specifiers) = &mut decl.specifiers {
+                    specifiers.retain(|s| names.contains(s.local().name.as_str()));
+                    if specifiers.is_empty() {
+                        // Keep the module's side effects without the bindings.
+                        decl.specifiers = None;
+                    }
+                }
+                Some(Statement::ImportDeclaration(decl))
+            }
+            (StmtKind::ReExport { source, .. }, _) if dropped_sources.contains(source) => None,
+            (StmtKind::ReExport { source, .. }, Statement::ExportNamedDeclaration(mut decl)) => {
+                decl.specifiers.retain(|s| used_exports.contains(s.exported.name().as_str()));
+                if decl.specifiers.is_empty() {
+                    // Keep the module's side effects (if any) without the bindings.
+                    program.body.extend(parse_statements(allocator, &format!("import \"{}\";", source)));
+                    None
+                } else {
+                    Some(Statement::ExportNamedDeclaration(decl))
+                }
+            }
+            (StmtKind::Local, _) if !is_live => None,
+            (StmtKind::Local, Statement::ExportNamedDeclaration(mut decl)) => {
+                if decl.declaration.is_some() {
+                    if meta.exports.iter().any(|(e, _)| used_exports.contains(e)) {
+                        Some(Statement::ExportNamedDeclaration(decl))
+                    } else {
+                        // Still referenced locally: keep the declaration, drop `export`.
+                        decl.declaration.take().map(Statement::from)
+                    }
+                } else {
+                    decl.specifiers.retain(|s| used_exports.contains(s.exported.name().as_str()));
+                    (!decl.specifiers.is_empty()).then_some(Statement::ExportNamedDeclaration(decl))
+                }
+            }
+            (StmtKind::Local, Statement::ExportDefaultDeclaration(decl))
+                if !used_exports.contains("default") && default_has_name(&decl.declaration) =>
+            {
+                match decl.unbox().declaration {
+                    ExportDefaultDeclarationKind::FunctionDeclaration(f) => Some(Statement::FunctionDeclaration(f)),
+                    ExportDefaultDeclarationKind::ClassDeclaration(c) => Some(Statement::ClassDeclaration(c)),
+                    _ => unreachable!(),
+                }
+            }
+            // Anonymous defaults with side effects stay as they are.
+            (_, stmt) => Some(stmt),
+        };
+        program.body.extend(kept);
+    }
+}
+
+fn default_has_name(kind: &ExportDefaultDeclarationKind) -> bool {
+    match kind {
+        ExportDefaultDeclarationKind::FunctionDeclaration(f) => f.id.is_some(),
+        ExportDefaultDeclarationKind::ClassDeclaration(c) => c.id.is_some(),
+        _ => false,
+    }
+}
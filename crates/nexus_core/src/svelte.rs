@@ -0,0 +1,139 @@
+use std::path::Path;
+use std::sync::Arc;
+use crate::cache::content_hash;
+use crate::compiler::{self, CompileResult};
+use crate::loader::Loader;
+use crate::typescript;
+use crate::unit::CompilationUnit;
+use crate::vue::parse_tag;
+
+// Svelte components.
+//
+// The Svelte compiler is JS, so Nexus doesn't carry one: a `SvelteCompiler`
+// comes with the project options, and the napi binding hands in one calling
+// the project's own `svelte/compiler`. Around that call:
+//
+// - `<script lang="ts">` blocks have their types stripped first, like any
+//   `.ts` module, but keep their imports, which the markup may use;
+// - the component's CSS comes back on its own and goes through
+//   `compile_css`: injected in dev, into `style.css` in builds;
+// - in dev, the compiler's HMR code (Svelte 5) gets a stand-in for
+//   `import.meta.hot`. An update swaps the code of mounted components in
+//   place, keeping their props; one that leaves the JS as it was, such as a
+//   style edit, doesn't touch them, so they keep their state too.
+
+/// Compiles Svelte components; see the module docs.
+pub trait SvelteCompiler: Send + Sync {
+    /// The compiler and its version, part of the transform cache key.
+    fn name(&self) -> &str;
+
+    /// Compiles component `source` of module `id`. `dev` asks for a
+    /// development build with HMR code.
+    fn compile(&self, source: &str, id: &str, dev: bool) -> std::io::Result<SvelteOutput>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SvelteOutput {
+    pub js: String,
+    /// The component's styles, already scoped.
+    pub css: Option<String>,
+}
+
+/// A shared `SvelteCompiler`. Cheap to clone.
+#[derive(Clone)]
+pub struct SvelteHook(Arc<dyn SvelteCompiler>);
+
+impl SvelteHook {
+    pub fn new(compiler: impl SvelteCompiler + 'static) -> Self {
+        Self(Arc::new(compiler))
+    }
+
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+impl std::fmt::Debug for SvelteHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("SvelteHook").field(&self.0.name()).finish()
+    }
+}
+
+pub(crate) fn missing_compiler() -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::NotFound, "no Svelte compiler; is `svelte` installed in the project?")
+}
+
+pub fn compile_svelte(hook: &SvelteHook, bytes: &[u8], id: &str, is_prod: bool) -> std::io::Result<CompileResult> {
+    let source = preprocess(&String::from_utf8_lossy(bytes), id);
+    let output = hook.0.compile(&source, id, !is_prod).map_err(|e| std::io::Error::new(e.kind(), format!("[{}] {}: {}", hook.name(), id, e)))?;
+    let styles = output.css.filter(|css| !css.trim().is_empty()).map(|css| compiler::compile_css(&css, id, is_prod));
+
+    let mut code = if is_prod { output.js } else { hot_code(id, &output.js) };
+    if let Some(styles) = styles.as_ref().filter(|_| !is_prod) {
+        code.push('\n');
+        code.push_str(&styles.code);
+    }
+
+    let mut compiled = CompilationUnit::parse_as(id, code, Loader::Svelte.source_type(id)).compile_result();
+    compiled.css = styles.and_then(|styles| styles.css);
+    Ok(compiled)
+}
+
+// `source` with the types stripped from its `<script lang="ts">` blocks.
+// Imports stay, the markup may be what uses them.
+fn preprocess(source: &str, id: &str) -> String {
+    let file = compiler::split_query(id).0;
+    let ts_id = Path::new(file).with_extension("ts").to_string_lossy().into_owned();
+    let mut out = String::with_capacity(source.len());
+    let mut rest = source;
+    while let Some(start) = rest.find("<script") {
+        let tag = &rest[start + 1..];
+        let Some((name, attrs, self_closing, len)) = parse_tag(tag).filter(|(name, ..)| name == "script") else {
+            out.push_str(&rest[..start + 1]);
+            rest = tag;
+            continue;
+        };
+        let body = &tag[len..];
+        let end = if self_closing { 0 } else { body.find(&format!("</{}", name)).unwrap_or(body.len()) };
+        out.push_str(&rest[..start + 1 + len]);
+        let is_ts = attrs.iter().any(|(name, value)| name == "lang" && matches!(value.as_deref(), Some("ts" | "typescript")));
+        if is_ts && !body[..end].trim().is_empty() {
+            out.push('\n');
+            out.push_str(&typescript::strip_types(&body[..end], &ts_id));
+        } else {
+            out.push_str(&body[..end]);
+        }
+        rest = &body[end..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// Svelte's HMR code accepts updates through `import.meta.hot.accept(cb)`,
+// and expects `cb` to run with the next version of the module, as in Vite.
+// Here the next version calls it, unless its JS is the same; either way the
+// callback of the version that mounted the components stays on record.
+fn hot_code(id: &str, js: &str) -> String {
+    if !js.contains("import.meta.hot") {
+        return js.to_string();
+    }
+    let hash = format!("{:08x}", content_hash(js.as_bytes()) as u32);
+    let id = serde_json::to_string(id).unwrap();
+    format!(
+        r#"const __nexus_records__ = window.__NEXUS_SVELTE__ || (window.__NEXUS_SVELTE__ = {{}});
+const __nexus_previous__ = __nexus_records__[{id}];
+const __nexus_hot__ = module.hot && {{
+  data: {{}},
+  accept(callback) {{
+    module.hot.accept();
+    if (!__nexus_previous__ || __nexus_previous__.js !== "{hash}") __nexus_records__[{id}] = {{ js: "{hash}", accept: callback }};
+  }},
+  dispose() {{}},
+  invalidate() {{ window.location.reload(); }},
+}};
+{js}
+if (__nexus_previous__ && __nexus_previous__.js !== "{hash}" && __nexus_previous__.accept) __nexus_previous__.accept(module.exports);
+"#,
+        js = js.replace("import.meta.hot", "__nexus_hot__"),
+    )
+}
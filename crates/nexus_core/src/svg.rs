@@ -0,0 +1,301 @@
+use crate::compiler::{self, CompileResult};
+
+// SVGs as React components.
+//
+// `import { ReactComponent as Icon } from './icon.svg'` or
+// `import Icon from './icon.svg?react'` gives a component rendering the file's
+// markup, so it can be styled and sized through props. The plain default
+// import is still the file's URL. There is no JSX transform in the pipeline,
+// so the markup compiles straight to `React.createElement` calls.
+//
+// Production builds also optimise the markup: editor metadata goes, and
+// groups that don't change rendering are collapsed into their children.
+
+const COMPONENT: &str = "SvgComponent";
+
+/// A `.svg` module: its URL as the default export, the component as `ReactComponent`.
+pub fn compile_svg(bytes: &[u8], id: &str, is_prod: bool) -> CompileResult {
+    let mut compiled = compiler::compile_asset(bytes, id, is_prod);
+    if let Some(component) = component_code(bytes, id, is_prod) {
+        compiled.code = format!(
+            "{};\n{}export {{ {} as ReactComponent }};\n",
+            compiled.code.trim_end().trim_end_matches(';'),
+            component,
+            COMPONENT
+        );
+    }
+    compiled
+}
+
+/// `?react`: the component as the default export.
+pub fn compile_react_variant(bytes: &[u8], id: &str, is_prod: bool) -> CompileResult {
+    let code = match component_code(bytes, id, is_prod) {
+        Some(component) => format!("{}export default {};\n", component, COMPONENT),
+        None => "export default function SvgComponent() { return null; }\n".to_string(),
+    };
+    CompileResult { code, sourcemap: None, css: None, asset: None }
+}
+
+fn component_code(bytes: &[u8], id: &str, optimize: bool) -> Option<String> {
+    let Some(mut root) = parse(&String::from_utf8_lossy(bytes)) else {
+        tracing::error!("Invalid SVG: {}", id);
+        return None;
+    };
+    if optimize {
+        optimize_element(&mut root);
+    }
+    Some(format!(
+        "import * as React from \"react\";\nfunction {}(props) {{\n\treturn {};\n}}\n",
+        COMPONENT,
+        element_code(&root, true)
+    ))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    children: Vec<Node>,
+}
+
+// Just enough XML for SVG files: elements, attributes, text and CDATA.
+// Declarations, doctypes, processing instructions and comments are skipped.
+// Returns the `<svg>` root element.
+fn parse(source: &str) -> Option<Element> {
+    let mut stack: Vec<Element> = vec![Element { name: String::new(), attrs: Vec::new(), children: Vec::new() }];
+    let mut rest = source;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = &after[after.find("-->")? + 3..];
+        } else if let Some(after) = rest.strip_prefix("<![CDATA[") {
+            let end = after.find("]]>")?;
+            stack.last_mut()?.children.push(Node::Text(after[..end].to_string()));
+            rest = &after[end + 3..];
+        } else if let Some(after) = rest.strip_prefix("<?") {
+            rest = &after[after.find("?>")? + 2..];
+        } else if rest.starts_with("<!") {
+            // `<!DOCTYPE svg [ ... ]>`
+            let end = match (rest.find('['), rest.find('>')) {
+                (Some(open), Some(close)) if open < close => rest.find("]>")? + 2,
+                (_, close) => close? + 1,
+            };
+            rest = &rest[end..];
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>')?;
+            let element = stack.pop()?;
+            if element.name != after[..end].trim() {
+                return None;
+            }
+            stack.last_mut()?.children.push(Node::Element(element));
+            rest = &after[end + 1..];
+        } else if let Some(after) = rest.strip_prefix('<') {
+            let (element, self_closing, len) = parse_tag(after)?;
+            if self_closing {
+                stack.last_mut()?.children.push(Node::Element(element));
+            } else {
+                stack.push(element);
+            }
+            rest = &after[len..];
+        } else {
+            let end = rest.find('<').unwrap_or(rest.len());
+            let text = decode_entities(&rest[..end]);
+            if !text.trim().is_empty() {
+                stack.last_mut()?.children.push(Node::Text(text));
+            }
+            rest = &rest[end..];
+        }
+    }
+    if stack.len() != 1 {
+        return None;
+    }
+    stack.pop()?.children.into_iter().find_map(|node| match node {
+        Node::Element(element) if element.name == "svg" => Some(element),
+        _ => None,
+    })
+}
+
+// `name attr="value" ...>` after the `<`: the element, whether it closes
+// itself and the length up to and including the `>`.
+fn parse_tag(tag: &str) -> Option<(Element, bool, usize)> {
+    let name_end = tag.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+    let mut element = Element { name: tag[..name_end].to_string(), attrs: Vec::new(), children: Vec::new() };
+    let mut rest = &tag[name_end..];
+    loop {
+        let trimmed = rest.trim_start();
+        if let Some(after) = trimmed.strip_prefix("/>") {
+            return Some((element, true, tag.len() - after.len()));
+        }
+        if let Some(after) = trimmed.strip_prefix('>') {
+            return Some((element, false, tag.len() - after.len()));
+        }
+        let eq = trimmed.find('=')?;
+        let name = trimmed[..eq].trim().to_string();
+        let value = trimmed[eq + 1..].trim_start();
+        let quote = value.chars().next().filter(|c| *c == '"' || *c == '\'')?;
+        let close = value[1..].find(quote)?;
+        element.attrs.push((name, decode_entities(&value[1..close + 1])));
+        rest = &value[close + 2..];
+    }
+}
+
+pub(crate) fn decode_entities(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let decoded = rest.find(';').and_then(|end| {
+            let c = match &rest[1..end] {
+                "amp" => '&',
+                "lt" => '<',
+                "gt" => '>',
+                "quot" => '"',
+                "apos" => '\'',
+                entity => {
+                    let code = match entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X")) {
+                        Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                        None => entity.strip_prefix('#')?.parse().ok()?,
+                    };
+                    char::from_u32(code)?
+                }
+            };
+            Some((c, end))
+        });
+        match decoded {
+            Some((c, end)) => {
+                out.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+// Attributes only editors read; `xlink:href` and `xml:space` still matter.
+fn is_editor_attr(name: &str) -> bool {
+    match name.split_once(':') {
+        Some(("xmlns", prefix)) => prefix != "xlink",
+        Some((prefix, _)) => prefix != "xlink" && prefix != "xml",
+        None => false,
+    }
+}
+
+// Moving these onto a child would change the box they are relative to.
+const BOX_RELATIVE: &[&str] = &["clip-path", "mask", "filter"];
+
+fn optimize_element(element: &mut Element) {
+    element.attrs.retain(|(name, _)| !is_editor_attr(name));
+    let children = std::mem::take(&mut element.children);
+    for child in children {
+        let Node::Element(mut child) = child else {
+            element.children.push(child);
+            continue;
+        };
+        // `<metadata>`, `<sodipodi:namedview>` and the like.
+        if child.name == "metadata" || child.name.contains(':') {
+            continue;
+        }
+        optimize_element(&mut child);
+        if (child.name == "g" || child.name == "defs") && child.children.is_empty() {
+            continue;
+        }
+        if child.name == "g" && child.attrs.is_empty() {
+            element.children.append(&mut child.children);
+            continue;
+        }
+        if child.name == "g" && child.children.len() == 1 {
+            if let Node::Element(only) = &mut child.children[0] {
+                let disjoint = child.attrs.iter().all(|(name, _)| only.attrs.iter().all(|(other, _)| other != name));
+                let box_relative = child.attrs.iter().any(|(name, _)| BOX_RELATIVE.contains(&name.as_str()));
+                if disjoint && !box_relative {
+                    let mut only = std::mem::replace(only, Element { name: String::new(), attrs: Vec::new(), children: Vec::new() });
+                    only.attrs.splice(0..0, child.attrs);
+                    element.children.push(Node::Element(only));
+                    continue;
+                }
+            }
+        }
+        element.children.push(Node::Element(child));
+    }
+}
+
+fn element_code(element: &Element, is_root: bool) -> String {
+    let props: Vec<String> = element
+        .attrs
+        .iter()
+        .filter(|(name, _)| !(name == "xmlns" || name.starts_with("xmlns:")) || is_root)
+        .map(|(name, value)| {
+            let prop = prop_name(name);
+            let value = if prop == "style" { style_object(value) } else { json(value) };
+            format!("{}: {}", json(&prop), value)
+        })
+        .collect();
+    let props = format!("{{ {} }}", props.join(", "));
+    // Props passed to the component go on the root, after the file's own.
+    let props = if is_root { format!("Object.assign({}, props)", props) } else { props };
+    let mut args = vec![json(&element.name), props];
+    for child in &element.children {
+        args.push(match child {
+            Node::Element(child) => element_code(child, false),
+            Node::Text(text) => json(text),
+        });
+    }
+    format!("React.createElement({})", args.join(", "))
+}
+
+// SVG attribute -> React prop: `stroke-width` -> `strokeWidth`,
+// `xlink:href` -> `xlinkHref`, `class` -> `className`.
+fn prop_name(name: &str) -> String {
+    match name {
+        "class" => return "className".to_string(),
+        "for" => return "htmlFor".to_string(),
+        _ if name.starts_with("data-") || name.starts_with("aria-") => return name.to_string(),
+        _ => {}
+    }
+    camel_case(name, &['-', ':'])
+}
+
+fn camel_case(name: &str, separators: &[char]) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if separators.contains(&c) {
+            upper = !out.is_empty();
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+// `fill: red; stroke-width: 2` -> `{ "fill": "red", "strokeWidth": "2" }`
+fn style_object(style: &str) -> String {
+    let entries: Vec<String> = style
+        .split(';')
+        .filter_map(|decl| decl.split_once(':'))
+        .map(|(property, value)| {
+            let property = property.trim();
+            let property = if property.starts_with("--") { property.to_string() } else { camel_case(property, &['-']) };
+            format!("{}: {}", json(&property), json(value.trim()))
+        })
+        .collect();
+    format!("{{ {} }}", entries.join(", "))
+}
+
+fn json(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
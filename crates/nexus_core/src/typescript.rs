@@ -0,0 +1,527 @@
+use oxc_allocator::{Allocator, Vec as ArenaVec};
+use oxc_ast::ast::*;
+use oxc_ast::AstBuilder;
+use oxc_ast_visit::{walk, walk_mut, Visit, VisitMut};
+use oxc_codegen::Codegen;
+use oxc_syntax::scope::ScopeFlags;
+use std::collections::HashSet;
+use crate::unit::{parse_expression, parse_statements};
+
+// TypeScript to JavaScript.
+//
+// Types are erased from the AST: annotations, type-only declarations and
+// imports, `as`/`satisfies`/`!` and class modifiers. The little TS with a
+// runtime meaning is lowered: enums become objects, constructor parameter
+// properties assignments to `this`. Nothing is type checked.
+//
+// Like `tsc`, imported names only types use are dropped, as they may name
+// only types; an import nothing uses stays, for its side effects. Code whose
+// other uses live elsewhere, like a Svelte component's markup, keeps them all
+// (`keep_imports`) and must say `import type` for types.
+
+/// Strips the types from `program` in place.
+pub(crate) fn strip_program<'a>(allocator: &'a Allocator, program: &mut Program<'a>, keep_imports: bool) {
+    let mut types = TopLevelNames::default();
+    for stmt in &program.body {
+        types.add(stmt);
+    }
+    let mut type_references = TypeReferences::default();
+    if !keep_imports {
+        type_references.visit_program(program);
+    }
+    let ast = AstBuilder::new(allocator);
+    Stripper { allocator, ast, types: types.types.difference(&types.values).cloned().collect() }.visit_program(program);
+    if !keep_imports {
+        elide_type_imports(ast, program, &type_references.0);
+    }
+}
+
+/// `source`, a TS module `id`, as JS with its imports kept.
+pub fn strip_types(source: &str, id: &str) -> String {
+    let allocator = Allocator::default();
+    let source_type = oxc_span::SourceType::from_path(id).unwrap_or_default().with_typescript(true);
+    let ret = oxc_parser::Parser::new(&allocator, source, source_type).parse();
+    if !ret.errors.is_empty() {
+        tracing::warn!("Parse errors in {}: {:?}", id, ret.errors);
+        return source.to_string();
+    }
+    let mut program = ret.program;
+    strip_program(&allocator, &mut program, true);
+    Codegen::new().build(&program).code
+}
+
+// Top-level names declared as types and as values; an `export { Name }` of
+// one that is only a type goes.
+#[derive(Default)]
+struct TopLevelNames {
+    types: HashSet<String>,
+    values: HashSet<String>,
+}
+
+impl TopLevelNames {
+    fn add(&mut self, stmt: &Statement) {
+        match stmt {
+            Statement::ImportDeclaration(import) => {
+                for specifier in import.specifiers.iter().flatten() {
+                    let type_only = import.import_kind.is_type()
+                        || matches!(specifier, ImportDeclarationSpecifier::ImportSpecifier(s) if s.import_kind.is_type());
+                    let names = if type_only { &mut self.types } else { &mut self.values };
+                    names.insert(specifier.local().name.to_string());
+                }
+            }
+            Statement::ExportNamedDeclaration(export) => {
+                if let Some(decl) = &export.declaration {
+                    self.add_declaration(decl);
+                }
+            }
+            _ => {
+                if let Some(decl) = stmt.as_declaration() {
+                    self.add_declaration(decl);
+                }
+            }
+        }
+    }
+
+    fn add_declaration(&mut self, decl: &Declaration) {
+        match decl {
+            Declaration::TSTypeAliasDeclaration(t) => {
+                self.types.insert(t.id.name.to_string());
+            }
+            Declaration::TSInterfaceDeclaration(t) => {
+                self.types.insert(t.id.name.to_string());
+            }
+            Declaration::TSEnumDeclaration(e) if !e.declare => {
+                self.values.insert(e.id.name.to_string());
+            }
+            Declaration::VariableDeclaration(v) if !v.declare => {
+                for declarator in &v.declarations {
+                    self.values.extend(declarator.id.get_binding_identifiers().iter().map(|id| id.name.to_string()));
+                }
+            }
+            Declaration::FunctionDeclaration(f) if !f.declare => {
+                self.values.extend(f.id.as_ref().map(|id| id.name.to_string()));
+            }
+            Declaration::ClassDeclaration(c) if !c.declare => {
+                self.values.extend(c.id.as_ref().map(|id| id.name.to_string()));
+            }
+            _ => {}
+        }
+    }
+}
+
+struct Stripper<'a> {
+    allocator: &'a Allocator,
+    ast: AstBuilder<'a>,
+    // Top-level names that are only types.
+    types: HashSet<String>,
+}
+
+impl<'a> Stripper<'a> {
+    // What `stmt` becomes once its types are gone: itself, nothing, or for an
+    // enum the statements building its object.
+    fn strip_statement(&self, stmt: Statement<'a>, out: &mut ArenaVec<'a, Statement<'a>>) {
+        match stmt {
+            Statement::ImportDeclaration(mut import) => {
+                if import.import_kind.is_type() {
+                    return;
+                }
+                if let Some(specifiers) = &mut import.specifiers {
+                    let before = specifiers.len();
+                    specifiers.retain(|s| !matches!(s, ImportDeclarationSpecifier::ImportSpecifier(s) if s.import_kind.is_type()));
+                    if before > 0 && specifiers.is_empty() {
+                        return;
+                    }
+                }
+                out.push(Statement::ImportDeclaration(import));
+            }
+            Statement::ExportNamedDeclaration(mut export) => {
+                if export.export_kind.is_type() {
+                    return;
+                }
+                if let Some(decl) = export.declaration.take() {
+                    if let Declaration::TSEnumDeclaration(mut e) = decl {
+                        if !e.declare {
+                            out.extend(self.enum_statements(&mut e, true));
+                        }
+                        return;
+                    }
+                    if is_type_only(&decl) {
+                        return;
+                    }
+                    export.declaration = Some(decl);
+                } else {
+                    let before = export.specifiers.len();
+                    let local_types = export.source.is_none();
+                    let is_type = |s: &ExportSpecifier| {
+                        s.export_kind.is_type() || (local_types && self.types.contains(s.local.name().as_str()))
+                    };
+                    export.specifiers.retain(|s| !is_type(s));
+                    if before > 0 && export.specifiers.is_empty() {
+                        return;
+                    }
+                }
+                out.push(Statement::ExportNamedDeclaration(export));
+            }
+            Statement::ExportAllDeclaration(export) if export.export_kind.is_type() => {}
+            Statement::ExportDefaultDeclaration(export) => match &export.declaration {
+                ExportDefaultDeclarationKind::TSInterfaceDeclaration(_) => {}
+                ExportDefaultDeclarationKind::FunctionDeclaration(f) if f.body.is_none() => {}
+                _ => out.push(Statement::ExportDefaultDeclaration(export)),
+            },
+            Statement::TSExportAssignment(_) | Statement::TSNamespaceExportDeclaration(_) => {}
+            Statement::TSEnumDeclaration(mut e) => {
+                if !e.declare {
+                    out.extend(self.enum_statements(&mut e, false));
+                }
+            }
+            stmt => match stmt.as_declaration() {
+                Some(decl) if is_type_only(decl) => {}
+                _ => out.push(stmt),
+            },
+        }
+    }
+
+    // `var E = function(E) { E[E["A"] = 0] = "A"; ...; return E; }(E || {});`,
+    // what `tsc` emits for `enum E { A }`. Members may refer to earlier ones
+    // by bare name.
+    fn enum_statements(&self, decl: &mut TSEnumDeclaration<'a>, export: bool) -> ArenaVec<'a, Statement<'a>> {
+        let name = decl.id.name.to_string();
+        let mut members = Vec::new();
+        let mut body = String::new();
+        let mut next = Some(0.0);
+        for member in &mut decl.members {
+            let key = match &member.id {
+                TSEnumMemberName::Identifier(id) => id.name.to_string(),
+                TSEnumMemberName::String(s) => s.value.to_string(),
+            };
+            let quoted = serde_json::to_string(&key).unwrap();
+            let (value, is_string) = match &mut member.initializer {
+                Some(init) => {
+                    MemberReferences { allocator: self.allocator, enum_name: &name, members: &members }.visit_expression(init);
+                    next = match init {
+                        Expression::NumericLiteral(n) => Some(n.value + 1.0),
+                        _ => None,
+                    };
+                    let mut codegen = Codegen::new();
+                    codegen.print_expression(init);
+                    (codegen.into_source_text(), matches!(init, Expression::StringLiteral(_) | Expression::TemplateLiteral(_)))
+                }
+                None => {
+                    let value = match next {
+                        Some(n) => n.to_string(),
+                        None => format!("{}[{}] + 1", name, serde_json::to_string(members.last().unwrap_or(&key)).unwrap()),
+                    };
+                    next = next.map(|n| n + 1.0);
+                    (value, false)
+                }
+            };
+            if is_string {
+                body.push_str(&format!("{name}[{quoted}] = {value};\n"));
+            } else {
+                body.push_str(&format!("{name}[{name}[{quoted}] = {value}] = {quoted};\n"));
+            }
+            members.push(key);
+        }
+        let export = if export { "export " } else { "" };
+        let text = format!("{export}var {name} = function({name}) {{\n{body}return {name};\n}}({name} || {{}});");
+        parse_statements(self.allocator, &text)
+    }
+
+    // `constructor(private x)` assigns `this.x = x` before the body, after
+    // `super()` in a derived class.
+    fn lower_parameter_properties(&self, class: &mut Class<'a>) {
+        let derived = class.super_class.is_some();
+        for element in &mut class.body.body {
+            let ClassElement::MethodDefinition(method) = element else { continue };
+            if method.kind != MethodDefinitionKind::Constructor {
+                continue;
+            }
+            let mut names = Vec::new();
+            for param in &mut method.value.params.items {
+                if param.accessibility.is_some() || param.readonly || param.r#override {
+                    param.accessibility = None;
+                    param.readonly = false;
+                    param.r#override = false;
+                    if let BindingPatternKind::BindingIdentifier(id) = &param.pattern.kind {
+                        names.push(id.name.to_string());
+                    }
+                }
+            }
+            let Some(body) = &mut method.value.body else { continue };
+            if names.is_empty() {
+                continue;
+            }
+            let text: String = names.iter().map(|name| format!("this.{name} = {name};")).collect();
+            let assignments = parse_statements(self.allocator, &text);
+            let at = if derived {
+                body.statements.iter().position(is_super_call).map_or(0, |i| i + 1)
+            } else {
+                0
+            };
+            let rest: Vec<_> = body.statements.drain(at..).collect();
+            body.statements.extend(assignments);
+            body.statements.extend(rest);
+        }
+    }
+}
+
+fn is_super_call(stmt: &Statement) -> bool {
+    matches!(stmt, Statement::ExpressionStatement(s) if matches!(&s.expression, Expression::CallExpression(call) if call.callee.is_super()))
+}
+
+// Declarations that only exist for the type checker.
+fn is_type_only(decl: &Declaration) -> bool {
+    match decl {
+        Declaration::TSTypeAliasDeclaration(_) | Declaration::TSInterfaceDeclaration(_) => true,
+        Declaration::TSModuleDeclaration(m) => m.declare || m.body.is_none(),
+        Declaration::TSImportEqualsDeclaration(i) => i.import_kind.is_type(),
+        Declaration::TSEnumDeclaration(e) => e.declare,
+        Declaration::VariableDeclaration(v) => v.declare,
+        // Overloads have no body.
+        Declaration::FunctionDeclaration(f) => f.declare || f.body.is_none(),
+        Declaration::ClassDeclaration(c) => c.declare,
+    }
+}
+
+impl<'a> VisitMut<'a> for Stripper<'a> {
+    fn visit_statements(&mut self, stmts: &mut ArenaVec<'a, Statement<'a>>) {
+        walk_mut::walk_statements(self, stmts);
+        let old = std::mem::replace(stmts, self.ast.vec());
+        for stmt in old {
+            self.strip_statement(stmt, stmts);
+        }
+    }
+
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        loop {
+            let inner = match expr {
+                Expression::TSAsExpression(e) => self.ast.move_expression(&mut e.expression),
+                Expression::TSSatisfiesExpression(e) => self.ast.move_expression(&mut e.expression),
+                Expression::TSNonNullExpression(e) => self.ast.move_expression(&mut e.expression),
+                Expression::TSTypeAssertion(e) => self.ast.move_expression(&mut e.expression),
+                Expression::TSInstantiationExpression(e) => self.ast.move_expression(&mut e.expression),
+                _ => break,
+            };
+            *expr = inner;
+        }
+        walk_mut::walk_expression(self, expr);
+    }
+
+    fn visit_chain_element(&mut self, element: &mut ChainElement<'a>) {
+        if let ChainElement::TSNonNullExpression(e) = element {
+            let mut inner = self.ast.move_expression(&mut e.expression);
+            self.visit_expression(&mut inner);
+            match inner.into_chain_element() {
+                Some(unwrapped) => *element = unwrapped,
+                None => unreachable!("`!` in a chain follows a member or call"),
+            }
+            return;
+        }
+        walk_mut::walk_chain_element(self, element);
+    }
+
+    fn visit_simple_assignment_target(&mut self, target: &mut SimpleAssignmentTarget<'a>) {
+        let inner = match target {
+            SimpleAssignmentTarget::TSAsExpression(e) => Some(self.ast.move_expression(&mut e.expression)),
+            SimpleAssignmentTarget::TSSatisfiesExpression(e) => Some(self.ast.move_expression(&mut e.expression)),
+            SimpleAssignmentTarget::TSNonNullExpression(e) => Some(self.ast.move_expression(&mut e.expression)),
+            SimpleAssignmentTarget::TSTypeAssertion(e) => Some(self.ast.move_expression(&mut e.expression)),
+            _ => None,
+        };
+        if let Some(mut inner) = inner {
+            self.visit_expression(&mut inner);
+            *target = match inner {
+                Expression::Identifier(id) => SimpleAssignmentTarget::AssignmentTargetIdentifier(id),
+                inner if inner.is_member_expression() => SimpleAssignmentTarget::from(inner.into_member_expression()),
+                _ => unreachable!("the parser only accepts identifiers and members as assignment targets"),
+            };
+            return;
+        }
+        walk_mut::walk_simple_assignment_target(self, target);
+    }
+
+    fn visit_binding_pattern(&mut self, pattern: &mut BindingPattern<'a>) {
+        pattern.type_annotation = None;
+        pattern.optional = false;
+        walk_mut::walk_binding_pattern(self, pattern);
+    }
+
+    fn visit_variable_declarator(&mut self, declarator: &mut VariableDeclarator<'a>) {
+        declarator.definite = false;
+        walk_mut::walk_variable_declarator(self, declarator);
+    }
+
+    fn visit_function(&mut self, func: &mut Function<'a>, flags: ScopeFlags) {
+        func.type_parameters = None;
+        func.this_param = None;
+        func.return_type = None;
+        walk_mut::walk_function(self, func, flags);
+    }
+
+    fn visit_arrow_function_expression(&mut self, arrow: &mut ArrowFunctionExpression<'a>) {
+        arrow.type_parameters = None;
+        arrow.return_type = None;
+        walk_mut::walk_arrow_function_expression(self, arrow);
+    }
+
+    fn visit_class(&mut self, class: &mut Class<'a>) {
+        class.type_parameters = None;
+        class.super_type_parameters = None;
+        class.implements = None;
+        class.r#abstract = false;
+        self.lower_parameter_properties(class);
+        class.body.body.retain(|element| match element {
+            ClassElement::TSIndexSignature(_) => false,
+            ClassElement::MethodDefinition(m) => m.r#type == MethodDefinitionType::MethodDefinition && m.value.body.is_some(),
+            ClassElement::PropertyDefinition(p) => p.r#type == PropertyDefinitionType::PropertyDefinition && !p.declare,
+            ClassElement::AccessorProperty(a) => a.r#type == AccessorPropertyType::AccessorProperty,
+            ClassElement::StaticBlock(_) => true,
+        });
+        for element in &mut class.body.body {
+            match element {
+                ClassElement::MethodDefinition(m) => {
+                    m.accessibility = None;
+                    m.optional = false;
+                    m.r#override = false;
+                }
+                ClassElement::PropertyDefinition(p) => {
+                    p.accessibility = None;
+                    p.type_annotation = None;
+                    p.optional = false;
+                    p.definite = false;
+                    p.readonly = false;
+                    p.r#override = false;
+                }
+                ClassElement::AccessorProperty(a) => {
+                    a.type_annotation = None;
+                    a.definite = false;
+                }
+                _ => {}
+            }
+        }
+        walk_mut::walk_class(self, class);
+    }
+
+    fn visit_call_expression(&mut self, call: &mut CallExpression<'a>) {
+        call.type_parameters = None;
+        walk_mut::walk_call_expression(self, call);
+    }
+
+    fn visit_new_expression(&mut self, new: &mut NewExpression<'a>) {
+        new.type_parameters = None;
+        walk_mut::walk_new_expression(self, new);
+    }
+
+    fn visit_tagged_template_expression(&mut self, tagged: &mut TaggedTemplateExpression<'a>) {
+        tagged.type_parameters = None;
+        walk_mut::walk_tagged_template_expression(self, tagged);
+    }
+
+    fn visit_jsx_opening_element(&mut self, element: &mut JSXOpeningElement<'a>) {
+        element.type_parameters = None;
+        walk_mut::walk_jsx_opening_element(self, element);
+    }
+}
+
+// Rewrites bare references to earlier members in an enum initializer.
+struct MemberReferences<'a, 'b> {
+    allocator: &'a Allocator,
+    enum_name: &'b str,
+    members: &'b [String],
+}
+
+impl<'a> VisitMut<'a> for MemberReferences<'a, '_> {
+    fn visit_expression(&mut self, expr: &mut Expression<'a>) {
+        if let Expression::Identifier(id) = expr {
+            if self.members.iter().any(|m| m == id.name.as_str()) {
+                let text = format!("{}[{}]", self.enum_name, serde_json::to_string(id.name.as_str()).unwrap());
+                if let Some(member) = parse_expression(self.allocator, &text) {
+                    *expr = member;
+                }
+                return;
+            }
+        }
+        walk_mut::walk_expression(self, expr);
+    }
+}
+
+// Names types refer to, by their leftmost part: `A` of `A.B<C>`.
+#[derive(Default)]
+struct TypeReferences(HashSet<String>);
+
+impl<'a> Visit<'a> for TypeReferences {
+    fn visit_ts_type_name(&mut self, name: &TSTypeName<'a>) {
+        let mut name = name;
+        while let TSTypeName::QualifiedName(qualified) = name {
+            name = &qualified.left;
+        }
+        if let TSTypeName::IdentifierReference(id) = name {
+            self.0.insert(id.name.to_string());
+        }
+    }
+
+    fn visit_ts_interface_heritage(&mut self, heritage: &TSInterfaceHeritage<'a>) {
+        if let Expression::Identifier(id) = &heritage.expression {
+            self.0.insert(id.name.to_string());
+        }
+        walk::walk_ts_interface_heritage(self, heritage);
+    }
+}
+
+// Imported names types used and nothing uses now: the name goes, or if all
+// of them do, the whole import.
+fn elide_type_imports<'a>(ast: AstBuilder<'a>, program: &mut Program<'a>, type_references: &HashSet<String>) {
+    let mut used = UsedNames::default();
+    used.visit_program(program);
+    let body = std::mem::replace(&mut program.body, ast.vec());
+    for mut stmt in body {
+        if let Statement::ImportDeclaration(import) = &mut stmt {
+            if let Some(specifiers) = &mut import.specifiers {
+                let before = specifiers.len();
+                specifiers.retain(|s| {
+                    let name = s.local().name.as_str();
+                    used.is_used(name) || !type_references.contains(name)
+                });
+                if before > 0 && specifiers.is_empty() {
+                    continue;
+                }
+            }
+        }
+        program.body.push(stmt);
+    }
+}
+
+#[derive(Default)]
+struct UsedNames {
+    names: HashSet<String>,
+    // Classic JSX compiles to `React.createElement`.
+    jsx: bool,
+}
+
+impl UsedNames {
+    fn is_used(&self, name: &str) -> bool {
+        self.names.contains(name) || (self.jsx && name == "React")
+    }
+}
+
+impl<'a> Visit<'a> for UsedNames {
+    fn visit_identifier_reference(&mut self, id: &IdentifierReference<'a>) {
+        self.names.insert(id.name.to_string());
+    }
+
+    fn visit_jsx_opening_element(&mut self, element: &JSXOpeningElement<'a>) {
+        self.jsx = true;
+        if let JSXElementName::Identifier(id) = &element.name {
+            // `<foo>` is an HTML tag, but `<Foo>` a component.
+            if id.name.starts_with(|c: char| c.is_ascii_uppercase()) {
+                self.names.insert(id.name.to_string());
+            }
+        }
+        walk::walk_jsx_opening_element(self, element);
+    }
+
+    fn visit_jsx_fragment(&mut self, fragment: &JSXFragment<'a>) {
+        self.jsx = true;
+        walk::walk_jsx_fragment(self, fragment);
+    }
+}
@@ -0,0 +1,193 @@
+use oxc_allocator::{Allocator, Vec as ArenaVec};
+use oxc_ast::ast::{Expression, Program, Statement};
+use oxc_ast_visit::VisitMut;
+use oxc_codegen::{Codegen, CodegenOptions};
+use oxc_parser::Parser;
+use oxc_span::{SourceType, Span, SPAN};
+use self_cell::self_cell;
+use std::collections::{HashMap, HashSet};
+use crate::compiler::CompileResult;
+use crate::define::{self, Defines};
+use crate::import_meta::{self, ImportMetaOutput};
+use crate::parser::{self, ImportInfo};
+use crate::shaker::{self, ShakeInfo};
+use crate::typescript;
+
+// Per-module compilation unit.
+//
+// Owns the source text and the oxc arena together with the AST parsed from
+// them, so a module is parsed exactly once: dependency extraction, export
+// analysis, tree shaking and CommonJS linking all work on the same `Program`.
+// TypeScript has its types stripped right after parsing; see `typescript`.
+// Shaking and linking edit the AST in place and text comes out of codegen.
+
+struct UnitOwner {
+    allocator: Allocator,
+    source: String,
+    source_type: SourceType,
+}
+
+self_cell!(
+    struct UnitCell {
+        owner: UnitOwner,
+
+        #[not_covariant]
+        dependent: Program,
+    }
+);
+
+pub struct CompilationUnit {
+    id: String,
+    cell: UnitCell,
+    has_errors: bool,
+    is_commonjs: bool,
+}
+
+// SAFETY: every pointer inside the AST points into the arena or the source
+// text owned by the same cell. The unit is moved between threads as a whole
+// and never shared, so nothing can observe the arena from two threads.
+unsafe impl Send for CompilationUnit {}
+
+impl CompilationUnit {
+    pub fn parse(id: &str, source: String) -> Self {
+        Self::parse_as(id, source, crate::loader::source_type_of(id))
+    }
+
+    /// Parses `source` as `source_type` whatever `id`'s extension, for
+    /// files a `Loader` maps to JS or TS.
+    pub fn parse_as(id: &str, source: String, source_type: SourceType) -> Self {
+        let owner = UnitOwner {
+            allocator: Allocator::default(),
+            source,
+            source_type,
+        };
+        let mut has_errors = false;
+        let cell = UnitCell::new(owner, |owner| {
+            let ret = Parser::new(&owner.allocator, &owner.source, owner.source_type).parse();
+            let mut program = ret.program;
+            if !ret.errors.is_empty() {
+                tracing::warn!("Parse errors in {}: {:?}", id, ret.errors);
+                has_errors = true;
+            } else if owner.source_type.is_typescript() {
+                typescript::strip_program(&owner.allocator, &mut program, false);
+            }
+            program
+        });
+        let is_commonjs = cell.with_dependent(|_, program| parser::is_commonjs(program));
+        Self { id: id.to_string(), cell, has_errors, is_commonjs }
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.has_errors
+    }
+
+    /// Prints the current AST.
+    pub fn code(&self) -> String {
+        self.cell.with_dependent(|_, program| Codegen::new().build(program).code)
+    }
+
+    /// The code with a sourcemap back to the source of `id`.
+    pub fn compile_result(&self) -> CompileResult {
+        let options = CodegenOptions { source_map_path: Some(self.id.clone().into()), ..CodegenOptions::default() };
+        let ret = self.cell.with_dependent(|_, program| Codegen::new().with_options(options).build(program));
+        CompileResult {
+            code: ret.code,
+            sourcemap: ret.map.map(|sm| sm.to_json_string()),
+            css: None,
+            asset: None,
+        }
+    }
+
+    /// Import specifiers with whether each one is a dynamic `import()`.
+    pub fn dependencies(&self) -> Vec<(String, bool)> {
+        self.cell.with_dependent(|_, program| parser::dependencies_of(program))
+    }
+
+    /// No ESM syntax, but uses `require`, `module` or `exports`.
+    pub fn is_commonjs(&self) -> bool {
+        self.is_commonjs
+    }
+
+    pub fn imports(&self) -> Vec<ImportInfo> {
+        if self.has_errors {
+            return Vec::new();
+        }
+        self.cell.with_dependent(|_, program| parser::imports_of(program))
+    }
+
+    pub fn shake_info(&self) -> ShakeInfo {
+        if self.has_errors || self.is_commonjs {
+            // Unparseable code is kept as-is, and CommonJS exports can't be
+            // known statically, so treat both as effectful.
+            return ShakeInfo::opaque(true);
+        }
+        self.cell.with_dependent(|_, program| shaker::analyze_program(program))
+    }
+
+    /// Replaces compile-time constants and drops dead branches; see `define`.
+    pub fn define(&mut self, defines: &Defines) {
+        if self.has_errors {
+            return;
+        }
+        self.cell.with_dependent_mut(|owner, program| define::define_program(&owner.allocator, program, defines));
+    }
+
+    /// Expands `import.meta.glob` and `import.meta.url`; see `import_meta`.
+    pub fn expand_import_meta(&mut self, root: &std::path::Path, is_prod: bool) -> ImportMetaOutput {
+        if self.has_errors {
+            return ImportMetaOutput::default();
+        }
+        let id = self.id.clone();
+        self.cell.with_dependent_mut(|owner, program| {
+            import_meta::expand_program(&owner.allocator, program, root, &id, is_prod)
+        })
+    }
+
+    /// Tree shakes the AST in place; see `shaker::shake`.
+    pub fn shake(&mut self, used_exports: &HashSet<String>, dropped_sources: &HashSet<String>) {
+        if self.has_errors || self.is_commonjs {
+            return;
+        }
+        self.cell.with_dependent_mut(|owner, program| {
+            shaker::shake_program(&owner.allocator, program, used_exports, dropped_sources)
+        });
+    }
+
+    /// Rewrites ESM syntax to the runtime's CommonJS shape; see `parser::transform_cjs`.
+    pub fn link(&mut self, imports: &HashMap<String, String>) {
+        if self.has_errors {
+            return;
+        }
+        self.cell.with_dependent_mut(|owner, program| parser::link_program(&owner.allocator, program, imports));
+    }
+}
+
+struct ClearSpans;
+
+impl<'a> VisitMut<'a> for ClearSpans {
+    fn visit_span(&mut self, span: &mut Span) {
+        *span = SPAN;
+    }
+}
+
+/// Parses generated code into statements living in `allocator`, for splicing
+/// into another program. Spans are cleared so the statements can't pick up the
+/// host program's comments during codegen.
+pub(crate) fn parse_statements<'a>(allocator: &'a Allocator, text: &str) -> ArenaVec<'a, Statement<'a>> {
+    let text = allocator.alloc_str(text);
+    let mut program = Parser::new(allocator, text, SourceType::mjs()).parse().program;
+    ClearSpans.visit_program(&mut program);
+    program.body
+}
+
+/// Expression counterpart of `parse_statements`.
+pub(crate) fn parse_expression<'a>(allocator: &'a Allocator, text: &str) -> Option<Expression<'a>> {
+    let text = allocator.alloc_str(text);
+    let mut expr = Parser::new(allocator, text, SourceType::mjs()).parse_expression().ok()?;
+    ClearSpans.visit_expression(&mut expr);
+    Some(expr)
+}
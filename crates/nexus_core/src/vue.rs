@@ -0,0 +1,1518 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::Path;
+use std::sync::Arc;
+use lightningcss::css_modules::{self, CssModuleReference};
+use lightningcss::stylesheet::{ParserOptions, PrinterOptions, StyleSheet};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast_visit::{walk, Visit};
+use oxc_parser::Parser;
+use oxc_span::{GetSpan, SourceType, Span};
+use crate::cache::content_hash;
+use crate::compiler::{self, CompileResult};
+use crate::loader::Loader;
+use crate::svg::decode_entities;
+use crate::unit::CompilationUnit;
+
+// Vue single-file components.
+//
+// A `.vue` file compiles to a module binding the component to `__sfc__`, and
+// its styles. A `VueCompiler` in the project options does that: the napi
+// binding hands in one calling the project's own `vue/compiler-sfc`, which
+// also runs `lang="scss"`, `less`, ... through the preprocessor the project
+// has installed. Without one, Nexus compiles the component itself.
+//
+// It splits the file into its `<script>`, `<script setup>`, `<template>` and
+// `<style>` blocks. The scripts become the component's options object,
+// `<script setup>` its `setup()` returning every top-level binding, and the
+// template a render function of `h()` calls reaching those bindings through
+// `_ctx`. A `scoped` style gets a `[data-v-<hash>]` attribute selector on
+// each rule; the component gets the same `__scopeId`, so Vue puts the
+// attribute on the elements it renders. This template compiler handles the
+// directives in everyday use (`v-if`, `v-for`, `v-bind`, `v-on`, `v-model`,
+// `v-show`, `v-slot`, ...) but none of Vue's compile-time optimisations: each
+// render diffs the whole tree. Styles in other languages fail the module.
+//
+// Either way:
+//
+// - `<style module>` blocks are CSS modules: their classes get names of their
+//   own, which the component reaches through `$style`, or the name the
+//   attribute gives (`<style module="classes">`);
+// - the module as a whole goes through `CompilationUnit` like any TS module,
+//   and the CSS through `compile_css`: injected in dev, into `style.css` in
+//   builds;
+// - in dev, the component registers with Vue's HMR runtime. When an edit runs
+//   the module again, the hashes of its blocks tell what changed: a new
+//   script reloads the component, a new template re-renders it in place,
+//   keeping its state, and a new style is left to the style injector.
+
+/// Compiles Vue single-file components; see the module docs.
+pub trait VueCompiler: Send + Sync {
+    /// The compiler and its version, part of the transform cache key.
+    fn name(&self) -> &str;
+
+    /// Compiles component `source` of module `id`. `dev` asks for a
+    /// development build, with the render function kept apart for HMR.
+    fn compile(&self, source: &str, id: &str, dev: bool) -> std::io::Result<VueOutput>;
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VueOutput {
+    /// The component's module, binding the component to `__sfc__` rather
+    /// than exporting it.
+    pub js: String,
+    /// The component's styles, preprocessed and scoped.
+    pub css: Option<String>,
+    /// The `<style module>` blocks, preprocessed and scoped, by the name the
+    /// component reaches them under (`$style` by default).
+    pub modules: Vec<(String, String)>,
+    /// The sources of the script blocks and of the template, telling hot
+    /// updates what an edit changed.
+    pub script: String,
+    pub template: String,
+}
+
+/// A shared `VueCompiler`. Cheap to clone.
+#[derive(Clone)]
+pub struct VueHook(Arc<dyn VueCompiler>);
+
+impl VueHook {
+    pub fn new(compiler: impl VueCompiler + 'static) -> Self {
+        Self(Arc::new(compiler))
+    }
+
+    pub fn name(&self) -> &str {
+        self.0.name()
+    }
+}
+
+impl std::fmt::Debug for VueHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("VueHook").field(&self.0.name()).finish()
+    }
+}
+
+const MACROS: &[&str] = &["defineProps", "withDefaults", "defineEmits", "defineExpose", "defineOptions"];
+
+// Globals template expressions may use as they are.
+const GLOBALS: &[&str] = &[
+    "Infinity", "undefined", "NaN", "isFinite", "isNaN", "parseFloat", "parseInt", "decodeURI", "decodeURIComponent",
+    "encodeURI", "encodeURIComponent", "Math", "Number", "Date", "Array", "Object", "Boolean", "String", "RegExp", "Map",
+    "Set", "JSON", "Intl", "BigInt", "console", "Error", "Symbol",
+];
+
+const BUILTIN_COMPONENTS: &[&str] = &["Transition", "TransitionGroup", "KeepAlive", "Teleport", "Suspense"];
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source", "track", "wbr",
+];
+
+// Modifiers `withModifiers` knows; any other `v-on` modifier is a key.
+const EVENT_MODIFIERS: &[&str] = &["stop", "prevent", "self", "ctrl", "shift", "alt", "meta", "exact", "left", "middle", "right"];
+
+/// Compiles `.vue` module `id` with `hook`, or without one with Nexus' own
+/// compiler; see the module docs.
+pub fn compile_vue(hook: Option<&VueHook>, bytes: &[u8], id: &str, is_prod: bool) -> std::io::Result<CompileResult> {
+    let source = String::from_utf8_lossy(bytes);
+    let output = match hook {
+        Some(hook) => hook.0.compile(&source, id, !is_prod).map_err(|e| std::io::Error::new(e.kind(), format!("[{}] {}: {}", hook.name(), id, e)))?,
+        None => compile_sfc(&source, id).map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", id, e)))?,
+    };
+
+    let mut code = output.js;
+    code.push('\n');
+    let mut css = output.css.unwrap_or_default();
+    let mut script = output.script;
+    if !output.modules.is_empty() {
+        let mut modules = Vec::new();
+        for (name, source) in &output.modules {
+            let (module_css, classes) = css_module(source, id, name).map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", id, e)))?;
+            css.push_str(&module_css);
+            css.push('\n');
+            modules.push(format!("{}: {}", json(name), classes));
+        }
+        let modules = format!("__sfc__.__cssModules = {{ {} }};\n", modules.join(", "));
+        code.push_str(&modules);
+        // The component reads its classes once; new ones need a reload.
+        script.push_str(&modules);
+    }
+    let styles = (!css.trim().is_empty()).then(|| compiler::compile_css(&css, id, is_prod));
+
+    if !is_prod {
+        code.push_str(&hmr_code(id, &short_hash(id), &short_hash(&script), &short_hash(&output.template)));
+    }
+    if let Some(styles) = styles.as_ref().filter(|_| !is_prod) {
+        code.push_str(&styles.code);
+        code.push('\n');
+    }
+    code.push_str("export default __sfc__;\n");
+
+    let mut compiled = CompilationUnit::parse_as(id, code, Loader::Vue.source_type(id)).compile_result();
+    compiled.css = styles.and_then(|styles| styles.css);
+    Ok(compiled)
+}
+
+// Nexus' own compiler.
+fn compile_sfc(source: &str, id: &str) -> std::io::Result<VueOutput> {
+    let sfc = split(source);
+    let scope_id = format!("data-v-{}", short_hash(id));
+    let is_ts = [&sfc.script, &sfc.script_setup].into_iter().flatten().any(|block| matches!(block.attr("lang"), Some("ts" | "tsx")));
+    let source_type = if is_ts { SourceType::ts() } else { SourceType::mjs() };
+    if let Some(lang) = sfc.styles.iter().filter_map(|style| style.attr("lang")).find(|lang| *lang != "css") {
+        let message = format!("<style lang=\"{}\"> needs the project's `vue/compiler-sfc` and a preprocessor", lang);
+        return Err(std::io::Error::new(std::io::ErrorKind::Unsupported, message));
+    }
+
+    let script = compile_script(&sfc, id, source_type);
+    let mut template = Template { bindings: &script.bindings, source_type, helpers: BTreeSet::new(), locals: Vec::new(), errors: Vec::new() };
+    let render = sfc.template.as_ref().map(|block| template.render(&block.content));
+    if !template.errors.is_empty() {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, template.errors.join("; ")));
+    }
+
+    let mut helpers = template.helpers;
+    helpers.extend(script.helpers);
+    let mut js = String::new();
+    if !helpers.is_empty() {
+        let names: Vec<String> = helpers.iter().map(|name| format!("{} as _{}", name, name)).collect();
+        js.push_str(&format!("import {{ {} }} from \"vue\";\n", names.join(", ")));
+    }
+    js.push_str(&script.code);
+    if let Some(render) = &render {
+        js.push_str(&format!("function render(_ctx, _cache) {{\n\treturn {};\n}}\n__sfc__.render = render;\n", render));
+    }
+    let scoped = sfc.styles.iter().any(|style| style.attr("scoped").is_some());
+    if scoped {
+        js.push_str(&format!("__sfc__.__scopeId = {};\n", json(&scope_id)));
+    }
+
+    let mut css = String::new();
+    let mut modules = Vec::new();
+    for style in &sfc.styles {
+        let content = match style.attr("scoped") {
+            Some(_) => scope_css(&style.content, &scope_id),
+            None => style.content.clone(),
+        };
+        match style.attr("module") {
+            Some(name) => modules.push((if name.is_empty() { "$style" } else { name }.to_string(), content)),
+            None => {
+                css.push_str(&content);
+                css.push('\n');
+            }
+        }
+    }
+
+    let script_source = [&sfc.script, &sfc.script_setup].into_iter().flatten().map(|block| block.content.as_str()).collect::<String>();
+    Ok(VueOutput {
+        js,
+        css: (!css.is_empty()).then_some(css),
+        modules,
+        script: script_source,
+        template: format!("{}{}", scoped, sfc.template.as_ref().map_or("", |block| &block.content)),
+    })
+}
+
+fn short_hash(text: &str) -> String {
+    format!("{:08x}", content_hash(text.as_bytes()) as u32)
+}
+
+// Registers the component with Vue's HMR runtime or, when the module runs
+// again, updates it as the changed blocks require.
+fn hmr_code(id: &str, hash: &str, script: &str, template: &str) -> String {
+    format!(
+        r#"__sfc__.__hmrId = "{hash}";
+__sfc__.__file = {file};
+if (module.hot) {{
+  const records = window.__NEXUS_VUE__ || (window.__NEXUS_VUE__ = {{}});
+  const previous = records[__sfc__.__hmrId];
+  records[__sfc__.__hmrId] = {{ script: "{script}", template: "{template}" }};
+  const runtime = window.__VUE_HMR_RUNTIME__;
+  if (runtime) {{
+    if (!previous) runtime.createRecord(__sfc__.__hmrId, __sfc__);
+    else if (previous.script !== "{script}") runtime.reload(__sfc__.__hmrId, __sfc__);
+    else if (previous.template !== "{template}") runtime.rerender(__sfc__.__hmrId, __sfc__.render);
+  }}
+  module.hot.accept();
+}}
+"#,
+        file = json(id),
+    )
+}
+
+fn json(value: &str) -> String {
+    serde_json::to_string(value).unwrap()
+}
+
+// CSS modules.
+
+// The CSS of `<style module="name">` block `source` with its classes, ids and
+// keyframes renamed, and the JS object mapping the old names to the new.
+// Renames depend on the module id and `name` only, so style edits keep them.
+fn css_module(source: &str, id: &str, name: &str) -> std::io::Result<(String, String)> {
+    let invalid = |message: String| std::io::Error::new(std::io::ErrorKind::InvalidData, format!("<style module>: {}", message));
+    let options = ParserOptions {
+        filename: format!("{}?{}", compiler::split_query(id).0, name),
+        css_modules: Some(css_modules::Config::default()),
+        ..ParserOptions::default()
+    };
+    let sheet = StyleSheet::parse(source, options).map_err(|e| invalid(e.to_string()))?;
+    let result = sheet.to_css(PrinterOptions::default()).map_err(|e| invalid(e.to_string()))?;
+
+    let exports: BTreeMap<_, _> = result.exports.unwrap_or_default().into_iter().collect();
+    let mut classes = Vec::new();
+    for (local, export) in exports {
+        let mut names = vec![export.name];
+        for reference in export.composes {
+            match reference {
+                CssModuleReference::Local { name } | CssModuleReference::Global { name } => names.push(name),
+                CssModuleReference::Dependency { specifier, .. } => return Err(invalid(format!("can't compose from {}", specifier))),
+            }
+        }
+        classes.push(format!("{}: {}", json(&local), json(&names.join(" "))));
+    }
+    Ok((result.code, format!("{{ {} }}", classes.join(", "))))
+}
+
+// Blocks.
+
+type Attr = (String, Option<String>);
+
+struct Block {
+    attrs: Vec<Attr>,
+    content: String,
+}
+
+impl Block {
+    // `Some("")` for an attribute without a value.
+    fn attr(&self, name: &str) -> Option<&str> {
+        attr(&self.attrs, name)
+    }
+}
+
+fn attr<'s>(attrs: &'s [Attr], name: &str) -> Option<&'s str> {
+    attrs.iter().find(|(n, _)| n == name).map(|(_, value)| value.as_deref().unwrap_or(""))
+}
+
+#[derive(Default)]
+struct Sfc {
+    script: Option<Block>,
+    script_setup: Option<Block>,
+    template: Option<Block>,
+    styles: Vec<Block>,
+}
+
+// The top-level blocks of `source`. Custom blocks are skipped.
+fn split(source: &str) -> Sfc {
+    let mut sfc = Sfc::default();
+    let mut rest = source;
+    while let Some(start) = rest.find('<') {
+        rest = &rest[start..];
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+        let Some((name, attrs, self_closing, len)) = parse_tag(&rest[1..]) else {
+            rest = &rest[1..];
+            continue;
+        };
+        rest = &rest[1 + len..];
+        if self_closing {
+            continue;
+        }
+        let end = if name == "template" { template_end(rest) } else { rest.find(&format!("</{}", name)) };
+        let Some(end) = end else { break };
+        let block = Block { attrs, content: rest[..end].to_string() };
+        rest = rest[end..].find('>').map_or("", |close| &rest[end + close + 1..]);
+        match name.as_str() {
+            "script" if block.attr("setup").is_some() => sfc.script_setup = Some(block),
+            "script" => sfc.script = Some(block),
+            "template" => sfc.template = Some(block),
+            "style" => sfc.styles.push(block),
+            _ => {}
+        }
+    }
+    sfc
+}
+
+// Where the `<template>` whose content starts `rest` closes; templates nest.
+fn template_end(rest: &str) -> Option<usize> {
+    let mut depth = 1;
+    let mut pos = 0;
+    while let Some(next) = rest[pos..].find("<template").map(|i| pos + i).into_iter().chain(rest[pos..].find("</template").map(|i| pos + i)).min() {
+        if rest[next..].starts_with("</template") {
+            depth -= 1;
+            if depth == 0 {
+                return Some(next);
+            }
+        } else if rest[next + "<template".len()..].starts_with(|c: char| c.is_whitespace() || c == '>') {
+            depth += 1;
+        }
+        pos = next + 1;
+    }
+    None
+}
+
+// `name attrs...>` after the `<`: the tag name, its attributes, whether it
+// closes itself and the length up to and including the `>`. Unlike SVG
+// files, HTML has attributes without values and unquoted ones.
+pub(crate) fn parse_tag(tag: &str) -> Option<(String, Vec<Attr>, bool, usize)> {
+    let name_end = tag.find(|c: char| c.is_whitespace() || c == '>' || c == '/')?;
+    if name_end == 0 {
+        return None;
+    }
+    let mut attrs = Vec::new();
+    let mut rest = &tag[name_end..];
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("/>") {
+            return Some((tag[..name_end].to_string(), attrs, true, tag.len() - after.len()));
+        }
+        if let Some(after) = rest.strip_prefix('>') {
+            return Some((tag[..name_end].to_string(), attrs, false, tag.len() - after.len()));
+        }
+        // A stray `/` is skipped like whitespace.
+        if let Some(after) = rest.strip_prefix('/') {
+            rest = after;
+            continue;
+        }
+        let name_len = rest.find(|c: char| c.is_whitespace() || c == '=' || c == '>' || c == '/').unwrap_or(rest.len());
+        if name_len == 0 {
+            return None;
+        }
+        let name = rest[..name_len].to_string();
+        rest = &rest[name_len..];
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            attrs.push((name, None));
+            continue;
+        };
+        let value = value.trim_start();
+        match value.chars().next()? {
+            quote @ ('"' | '\'') => {
+                let close = value[1..].find(quote)?;
+                attrs.push((name, Some(decode_entities(&value[1..close + 1]))));
+                rest = &value[close + 2..];
+            }
+            _ => {
+                let end = value.find(|c: char| c.is_whitespace() || c == '>').unwrap_or(value.len());
+                attrs.push((name, Some(decode_entities(&value[..end]))));
+                rest = &value[end..];
+            }
+        }
+    }
+}
+
+// Scripts.
+
+struct Script {
+    // Imports, the plain `<script>`, and `const __sfc__ = { ... }`.
+    code: String,
+    // What `setup()` returns, so the template can use it.
+    bindings: BTreeSet<String>,
+    helpers: Vec<&'static str>,
+}
+
+fn compile_script(sfc: &Sfc, id: &str, source_type: SourceType) -> Script {
+    let mut code = String::new();
+    let mut options = Vec::new();
+    let name = Path::new(compiler::split_query(id).0).file_stem().unwrap_or_default().to_string_lossy().into_owned();
+
+    if let Some(script) = &sfc.script {
+        let (body, has_default) = rewrite_default_export(&script.content, id, source_type);
+        code.push_str(&body);
+        code.push('\n');
+        if has_default {
+            options.push("...__default__".to_string());
+        }
+    }
+    options.push(format!("__name: {}", json(&name)));
+
+    let mut script = Script { code: String::new(), bindings: BTreeSet::new(), helpers: Vec::new() };
+    if let Some(setup) = &sfc.script_setup {
+        let setup = compile_setup(&setup.content, id, source_type, &mut script, &mut options);
+        options.push(setup);
+    }
+    script.code.push_str(&code);
+    script.code.push_str(&format!("const __sfc__ = {{\n{}\n}};\n", options.join(",\n")));
+    script
+}
+
+// The plain `<script>`, with `export default` turned into `const __default__ =`.
+fn rewrite_default_export(source: &str, id: &str, source_type: SourceType) -> (String, bool) {
+    let allocator = Allocator::default();
+    let ret = Parser::new(&allocator, source, source_type).parse();
+    if !ret.errors.is_empty() {
+        tracing::error!("Parse errors in <script> of {}: {:?}", id, ret.errors);
+    }
+    for stmt in &ret.program.body {
+        if let Statement::ExportDefaultDeclaration(export) = stmt {
+            let start = export.span.start as usize;
+            let value = export.declaration.span().start as usize;
+            return (format!("{}const __default__ = {}", &source[..start], &source[value..]), true);
+        }
+    }
+    (source.to_string(), false)
+}
+
+// Hoists the imports of `<script setup>` into `script`, collects its
+// bindings, turns its macros into `options` and returns the `setup()`
+// method running the rest.
+fn compile_setup(source: &str, id: &str, source_type: SourceType, script: &mut Script, options: &mut Vec<String>) -> String {
+    let allocator = Allocator::default();
+    let ret = Parser::new(&allocator, source, source_type).parse();
+    if !ret.errors.is_empty() {
+        tracing::error!("Parse errors in <script setup> of {}: {:?}", id, ret.errors);
+    }
+    let program = &ret.program;
+    let text = |span: Span| &source[span.start as usize..span.end as usize];
+    let types = local_types(program);
+
+    let mut edits: Vec<(Span, String)> = Vec::new();
+    let mut is_async = false;
+    for stmt in &program.body {
+        match stmt {
+            Statement::ImportDeclaration(import) => {
+                script.code.push_str(text(import.span));
+                script.code.push('\n');
+                edits.push((import.span, String::new()));
+                if !import.import_kind.is_type() {
+                    for specifier in import.specifiers.iter().flatten() {
+                        if !matches!(specifier, ImportDeclarationSpecifier::ImportSpecifier(s) if s.import_kind.is_type()) {
+                            script.bindings.insert(specifier.local().name.to_string());
+                        }
+                    }
+                }
+                continue;
+            }
+            Statement::ExpressionStatement(expr) => {
+                if let Some((name, call)) = macro_call(&expr.expression) {
+                    edits.push((call.span, expand_macro(name, call, &text, &types, script, options)));
+                }
+            }
+            Statement::VariableDeclaration(decl) => {
+                for declarator in &decl.declarations {
+                    script.bindings.extend(bound_names(|v| v.visit_binding_pattern(&declarator.id)));
+                    if let Some((name, call)) = declarator.init.as_ref().and_then(macro_call) {
+                        edits.push((call.span, expand_macro(name, call, &text, &types, script, options)));
+                    }
+                }
+            }
+            Statement::FunctionDeclaration(function) => script.bindings.extend(function.id.as_ref().map(|id| id.name.to_string())),
+            Statement::ClassDeclaration(class) => script.bindings.extend(class.id.as_ref().map(|id| id.name.to_string())),
+            Statement::TSEnumDeclaration(decl) => {
+                script.bindings.insert(decl.id.name.to_string());
+            }
+            _ => {}
+        }
+        let mut awaits = TopLevelAwait(false);
+        awaits.visit_statement(stmt);
+        is_async |= awaits.0;
+    }
+
+    edits.sort_by_key(|(span, _)| span.start);
+    let mut body = String::new();
+    let mut last = 0;
+    for (span, replacement) in edits {
+        body.push_str(&source[last..span.start as usize]);
+        body.push_str(&replacement);
+        last = span.end as usize;
+    }
+    body.push_str(&source[last..]);
+    let bindings: Vec<&str> = script.bindings.iter().map(String::as_str).collect();
+    format!(
+        "{}setup(__props, {{ emit: __emit, expose: __expose }}) {{\n{}\nreturn {{ {} }};\n}}",
+        if is_async { "async " } else { "" },
+        body.trim(),
+        bindings.join(", ")
+    )
+}
+
+fn macro_call<'b, 'a>(expr: &'b Expression<'a>) -> Option<(&'b str, &'b CallExpression<'a>)> {
+    let Expression::CallExpression(call) = expr.get_inner_expression() else { return None };
+    let Expression::Identifier(callee) = &call.callee else { return None };
+    MACROS.contains(&callee.name.as_str()).then_some((callee.name.as_str(), &**call))
+}
+
+// The code replacing a macro call, adding to the component's options.
+fn expand_macro<'s>(
+    name: &str,
+    call: &CallExpression,
+    text: &impl Fn(Span) -> &'s str,
+    types: &HashMap<&str, &[TSSignature]>,
+    script: &mut Script,
+    options: &mut Vec<String>,
+) -> String {
+    let arg = |i: usize| call.arguments.get(i).map(|arg| text(arg.span()));
+    match name {
+        "defineProps" => {
+            options.push(format!("props: {}", props_option(call, text, types)));
+            "__props".to_string()
+        }
+        "withDefaults" => {
+            let props = match call.arguments.first().and_then(|arg| arg.as_expression()).and_then(macro_call) {
+                Some(("defineProps", define)) => props_option(define, text, types),
+                _ => "{}".to_string(),
+            };
+            script.helpers.push("mergeDefaults");
+            options.push(format!("props: _mergeDefaults({}, {})", props, arg(1).unwrap_or("{}")));
+            "__props".to_string()
+        }
+        "defineEmits" => {
+            let emits = match arg(0) {
+                Some(emits) => emits.to_string(),
+                None => {
+                    let signatures = call.type_parameters.as_ref().and_then(|t| t.params.first()).and_then(|ty| type_members(ty, types));
+                    let names: Vec<String> = signatures.unwrap_or_default().iter().flat_map(event_names).map(|name| json(&name)).collect();
+                    format!("[{}]", names.join(", "))
+                }
+            };
+            options.push(format!("emits: {}", emits));
+            "__emit".to_string()
+        }
+        "defineExpose" => format!("__expose({})", arg(0).unwrap_or("")),
+        _ => {
+            options.extend(arg(0).map(|value| format!("...({})", value)));
+            String::new()
+        }
+    }
+}
+
+// Runtime props from `defineProps({ ... })` or `defineProps<{ ... }>()`.
+fn props_option<'s>(call: &CallExpression, text: &impl Fn(Span) -> &'s str, types: &HashMap<&str, &[TSSignature]>) -> String {
+    if let Some(arg) = call.arguments.first() {
+        return text(arg.span()).to_string();
+    }
+    let signatures = call.type_parameters.as_ref().and_then(|t| t.params.first()).and_then(|ty| type_members(ty, types));
+    let props: Vec<String> = signatures
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|signature| {
+            let (key, optional, ty) = match signature {
+                TSSignature::TSPropertySignature(prop) => {
+                    let ty = prop.type_annotation.as_ref().map_or("null".to_string(), |t| runtime_type(&t.type_annotation));
+                    (&prop.key, prop.optional, ty)
+                }
+                TSSignature::TSMethodSignature(method) => (&method.key, method.optional, "Function".to_string()),
+                _ => return None,
+            };
+            Some(format!("{}: {{ type: {}, required: {} }}", json(&key.static_name()?), ty, !optional))
+        })
+        .collect();
+    format!("{{ {} }}", props.join(", "))
+}
+
+// The members of an object type, inline or a local interface or alias.
+fn type_members<'b, 'a>(ty: &'b TSType<'a>, types: &HashMap<&str, &'b [TSSignature<'a>]>) -> Option<&'b [TSSignature<'a>]> {
+    match ty {
+        TSType::TSTypeLiteral(literal) => Some(&literal.members),
+        TSType::TSTypeReference(reference) => match &reference.type_name {
+            TSTypeName::IdentifierReference(name) => types.get(name.name.as_str()).copied(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn local_types<'b, 'a>(program: &'b Program<'a>) -> HashMap<&'b str, &'b [TSSignature<'a>]> {
+    let mut types = HashMap::new();
+    for stmt in &program.body {
+        match stmt {
+            Statement::TSInterfaceDeclaration(decl) => {
+                types.insert(decl.id.name.as_str(), &decl.body.body[..]);
+            }
+            Statement::TSTypeAliasDeclaration(decl) => {
+                if let TSType::TSTypeLiteral(literal) = &decl.type_annotation {
+                    types.insert(decl.id.name.as_str(), &literal.members[..]);
+                }
+            }
+            _ => {}
+        }
+    }
+    types
+}
+
+// The constructor Vue checks a prop against, `null` for any.
+fn runtime_type(ty: &TSType) -> String {
+    let name = match ty {
+        TSType::TSStringKeyword(_) | TSType::TSTemplateLiteralType(_) => "String",
+        TSType::TSNumberKeyword(_) => "Number",
+        TSType::TSBooleanKeyword(_) => "Boolean",
+        TSType::TSFunctionType(_) => "Function",
+        TSType::TSArrayType(_) | TSType::TSTupleType(_) => "Array",
+        TSType::TSTypeLiteral(_) => "Object",
+        TSType::TSLiteralType(literal) => match &literal.literal {
+            TSLiteral::StringLiteral(_) => "String",
+            TSLiteral::NumericLiteral(_) => "Number",
+            TSLiteral::BooleanLiteral(_) => "Boolean",
+            _ => "null",
+        },
+        TSType::TSTypeReference(reference) => match &reference.type_name {
+            TSTypeName::IdentifierReference(name) => match name.name.as_str() {
+                "Array" | "ReadonlyArray" => "Array",
+                "Record" | "Partial" | "Required" | "Readonly" | "Object" => "Object",
+                "Function" => "Function",
+                "Date" => "Date",
+                "Map" => "Map",
+                "Set" => "Set",
+                "Promise" => "Promise",
+                _ => "null",
+            },
+            _ => "null",
+        },
+        TSType::TSUnionType(union) => {
+            let mut names: Vec<String> = Vec::new();
+            for ty in &union.types {
+                if matches!(ty, TSType::TSNullKeyword(_) | TSType::TSUndefinedKeyword(_)) {
+                    continue;
+                }
+                let name = runtime_type(ty);
+                if name == "null" {
+                    return name;
+                }
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            return match names.len() {
+                0 => "null".to_string(),
+                1 => names.pop().unwrap(),
+                _ => format!("[{}]", names.join(", ")),
+            };
+        }
+        _ => "null",
+    };
+    name.to_string()
+}
+
+// `(e: 'change', id: number): void` or `change: [id: number]`.
+fn event_names(signature: &TSSignature) -> Vec<String> {
+    match signature {
+        TSSignature::TSPropertySignature(prop) => prop.key.static_name().map(|name| name.to_string()).into_iter().collect(),
+        TSSignature::TSCallSignatureDeclaration(call) => {
+            let ty = call.params.items.first().and_then(|param| param.pattern.type_annotation.as_ref());
+            let literals = match ty.map(|t| &t.type_annotation) {
+                Some(TSType::TSUnionType(union)) => union.types.iter().collect(),
+                Some(ty) => vec![ty],
+                None => Vec::new(),
+            };
+            literals
+                .into_iter()
+                .filter_map(|ty| match ty {
+                    TSType::TSLiteralType(literal) => match &literal.literal {
+                        TSLiteral::StringLiteral(s) => Some(s.value.to_string()),
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+// Whether a statement awaits outside any function, making `setup()` async.
+struct TopLevelAwait(bool);
+
+impl<'a> Visit<'a> for TopLevelAwait {
+    fn visit_await_expression(&mut self, _: &AwaitExpression<'a>) {
+        self.0 = true;
+    }
+
+    fn visit_function_body(&mut self, _: &FunctionBody<'a>) {}
+}
+
+// Template expressions.
+
+// Free identifiers of an expression, with whether each is an object
+// shorthand, and the names it binds itself: arrow parameters and the like.
+#[derive(Default)]
+struct Identifiers {
+    references: Vec<(Span, bool)>,
+    bound: HashSet<String>,
+}
+
+impl<'a> Visit<'a> for Identifiers {
+    fn visit_identifier_reference(&mut self, ident: &IdentifierReference<'a>) {
+        self.references.push((ident.span, false));
+    }
+
+    fn visit_binding_identifier(&mut self, ident: &BindingIdentifier<'a>) {
+        self.bound.insert(ident.name.to_string());
+    }
+
+    fn visit_object_property(&mut self, prop: &ObjectProperty<'a>) {
+        if let (true, Expression::Identifier(ident)) = (prop.shorthand, &prop.value) {
+            self.references.push((ident.span, true));
+            return;
+        }
+        walk::walk_object_property(self, prop);
+    }
+}
+
+fn bound_names(visit: impl FnOnce(&mut Identifiers)) -> HashSet<String> {
+    let mut identifiers = Identifiers::default();
+    visit(&mut identifiers);
+    identifiers.bound
+}
+
+enum Handler {
+    // `onSubmit`, `form.submit`: the function itself.
+    Path,
+    // `() => done()`
+    Function,
+    // `count++`
+    Inline,
+    // `a++; b++`
+    Statements,
+}
+
+fn classify_handler(source: &str, source_type: SourceType) -> Handler {
+    let allocator = Allocator::default();
+    match Parser::new(&allocator, source, source_type).parse_expression() {
+        Ok(expr) => match expr.get_inner_expression() {
+            Expression::Identifier(_) | Expression::StaticMemberExpression(_) | Expression::ComputedMemberExpression(_) => Handler::Path,
+            Expression::ArrowFunctionExpression(_) | Expression::FunctionExpression(_) => Handler::Function,
+            _ => Handler::Inline,
+        },
+        Err(_) => Handler::Statements,
+    }
+}
+
+// `source` with its free identifiers read from `_ctx`, the component
+// instance, unless `locals` or the expression itself bind them or they are
+// globals templates may use.
+fn prefix_identifiers(source: &str, locals: &[String], source_type: SourceType) -> Result<String, String> {
+    let allocator = Allocator::default();
+    let expr = Parser::new(&allocator, source, source_type)
+        .parse_expression()
+        .map_err(|errors| errors.first().map(|e| e.to_string()).unwrap_or_default())?;
+    let mut identifiers = Identifiers::default();
+    identifiers.visit_expression(&expr);
+    identifiers.references.sort_by_key(|(span, _)| span.start);
+
+    let mut out = String::with_capacity(source.len());
+    let mut last = 0;
+    for (span, shorthand) in identifiers.references {
+        let (start, end) = (span.start as usize, span.end as usize);
+        let name = &source[start..end];
+        if locals.iter().any(|local| local == name) || identifiers.bound.contains(name) || GLOBALS.contains(&name) {
+            continue;
+        }
+        out.push_str(&source[last..start]);
+        if shorthand {
+            out.push_str(&format!("{}: ", name));
+        }
+        out.push_str("_ctx.");
+        out.push_str(name);
+        last = end;
+    }
+    out.push_str(&source[last..]);
+    Ok(out)
+}
+
+// Templates.
+
+#[derive(Debug, Clone)]
+enum Node {
+    Element(Element),
+    Text(String),
+}
+
+#[derive(Debug, Clone)]
+struct Element {
+    tag: String,
+    attrs: Vec<Attr>,
+    children: Vec<Node>,
+}
+
+// Elements and text; comments are dropped. Unclosed elements close with
+// their parent, stray closing tags are ignored.
+fn parse_template(source: &str) -> Vec<Node> {
+    let mut stack = vec![Element { tag: String::new(), attrs: Vec::new(), children: Vec::new() }];
+    let mut rest = source;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+        } else if let Some(after) = rest.strip_prefix("</") {
+            let end = after.find('>').unwrap_or(after.len());
+            let name = after[..end].trim();
+            if let Some(depth) = stack.iter().rposition(|element| element.tag == name).filter(|depth| *depth > 0) {
+                while stack.len() > depth {
+                    let element = stack.pop().unwrap();
+                    stack.last_mut().unwrap().children.push(Node::Element(element));
+                }
+            }
+            rest = after.get(end + 1..).unwrap_or("");
+        } else if let Some((tag, attrs, self_closing, len)) = rest.strip_prefix('<').and_then(parse_tag) {
+            let element = Element { tag, attrs, children: Vec::new() };
+            if self_closing || VOID_ELEMENTS.contains(&element.tag.as_str()) {
+                stack.last_mut().unwrap().children.push(Node::Element(element));
+            } else {
+                stack.push(element);
+            }
+            rest = &rest[1 + len..];
+        } else {
+            // Text runs to the next tag, skipping over interpolations, which
+            // may compare with `<`.
+            let mut end = 0;
+            loop {
+                let tag = rest[end + 1..].find('<').map_or(rest.len(), |i| end + 1 + i);
+                match rest[end..tag].find("{{").map(|i| end + i) {
+                    Some(open) if rest[open..tag].find("}}").is_none() => match rest[open..].find("}}") {
+                        Some(close) => end = open + close,
+                        None => break end = rest.len(),
+                    },
+                    _ => break end = tag,
+                }
+            }
+            let parent = stack.last_mut().unwrap();
+            match parent.children.last_mut() {
+                Some(Node::Text(text)) => text.push_str(&rest[..end]),
+                _ => parent.children.push(Node::Text(rest[..end].to_string())),
+            }
+            rest = &rest[end..];
+        }
+    }
+    while stack.len() > 1 {
+        let element = stack.pop().unwrap();
+        stack.last_mut().unwrap().children.push(Node::Element(element));
+    }
+    stack.pop().unwrap().children
+}
+
+// `v-on:click.prevent`, `@click`, `:title`, `#header`, `v-model.trim`.
+struct Directive<'s> {
+    name: &'s str,
+    arg: Option<&'s str>,
+    dynamic: bool,
+    modifiers: Vec<&'s str>,
+}
+
+impl<'s> Directive<'s> {
+    fn parse(attr: &'s str) -> Option<Self> {
+        let (name, rest) = if let Some(rest) = attr.strip_prefix(':') {
+            ("bind", rest)
+        } else if let Some(rest) = attr.strip_prefix('@') {
+            ("on", rest)
+        } else if let Some(rest) = attr.strip_prefix('#') {
+            ("slot", rest)
+        } else {
+            let rest = attr.strip_prefix("v-")?;
+            match rest.find([':', '.']) {
+                Some(i) if rest[i..].starts_with(':') => (&rest[..i], &rest[i + 1..]),
+                Some(i) => (&rest[..i], &rest[i..]),
+                None => (rest, ""),
+            }
+        };
+        let (arg, dynamic, modifiers) = match rest.strip_prefix('[').and_then(|r| r.find(']').map(|end| (r, end))) {
+            Some((r, end)) => (Some(&r[..end]), true, &r[end + 1..]),
+            None => {
+                let end = rest.find('.').unwrap_or(rest.len());
+                (Some(&rest[..end]).filter(|arg| !arg.is_empty()), false, &rest[end..])
+            }
+        };
+        let modifiers = modifiers.split('.').filter(|m| !m.is_empty()).collect();
+        Some(Self { name, arg, dynamic, modifiers })
+    }
+}
+
+fn is_component(tag: &str) -> bool {
+    tag == "component" || tag.contains('-') || tag.starts_with(|c: char| c.is_ascii_uppercase())
+}
+
+fn camelize(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    let mut upper = false;
+    for c in name.chars() {
+        if c == '-' {
+            upper = true;
+        } else if upper {
+            out.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+// Whitespace as Vue condenses it: runs become one space, and whitespace
+// alone goes at either end of its parent or when it spans lines.
+fn condense(text: &str, at_edge: bool) -> Option<String> {
+    if text.trim().is_empty() {
+        return (!at_edge && !text.contains('\n')).then(|| " ".to_string());
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut space = false;
+    for c in text.chars() {
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space {
+            out.push(' ');
+            space = false;
+        }
+        out.push(c);
+    }
+    if space {
+        out.push(' ');
+    }
+    if text.starts_with(char::is_whitespace) {
+        out.insert(0, ' ');
+    }
+    Some(out)
+}
+
+struct Template<'b> {
+    bindings: &'b BTreeSet<String>,
+    source_type: SourceType,
+    // Imported from "vue" as `_<name>`.
+    helpers: BTreeSet<&'static str>,
+    // Names bound by `v-for` and slot props around the current node.
+    locals: Vec<String>,
+    errors: Vec<String>,
+}
+
+impl Template<'_> {
+    fn render(&mut self, source: &str) -> String {
+        let nodes = parse_template(source);
+        let roots = self.children(&nodes);
+        match roots.as_slice() {
+            [] => "null".to_string(),
+            [root] => root.clone(),
+            _ => format!("{}({}, null, [{}])", self.helper("h"), self.helper("Fragment"), roots.join(", ")),
+        }
+    }
+
+    fn helper(&mut self, name: &'static str) -> String {
+        self.helpers.insert(name);
+        format!("_{}", name)
+    }
+
+    fn expression(&mut self, source: &str) -> String {
+        match prefix_identifiers(source.trim(), &self.locals, self.source_type) {
+            Ok(code) => format!("({})", code),
+            Err(e) => {
+                self.errors.push(format!("`{}`: {}", source.trim(), e));
+                "undefined".to_string()
+            }
+        }
+    }
+
+    // Runs `f` with the names `params` binds in scope.
+    fn scoped(&mut self, params: &str, f: impl FnOnce(&mut Self) -> String) -> String {
+        let allocator = Allocator::default();
+        let source = format!("({}) => 0", params);
+        let names = match Parser::new(&allocator, &source, self.source_type).parse_expression() {
+            Ok(expr) => bound_names(|v| v.visit_expression(&expr)),
+            Err(_) => {
+                self.errors.push(format!("invalid parameters `{}`", params));
+                HashSet::new()
+            }
+        };
+        let depth = self.locals.len();
+        self.locals.extend(names);
+        let code = f(self);
+        self.locals.truncate(depth);
+        code
+    }
+
+    fn children(&mut self, nodes: &[Node]) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < nodes.len() {
+            match &nodes[i] {
+                Node::Text(text) => {
+                    if let Some(text) = condense(text, i == 0 || i + 1 == nodes.len()) {
+                        out.push(self.text(&text));
+                    }
+                    i += 1;
+                }
+                Node::Element(element) if attr(&element.attrs, "v-if").is_some() => {
+                    // The `v-else-if` and `v-else` siblings, whitespace between.
+                    let mut branches = vec![element];
+                    i += 1;
+                    let mut next = i;
+                    while let Some(node) = nodes.get(next) {
+                        match node {
+                            Node::Text(text) if text.trim().is_empty() => next += 1,
+                            Node::Element(branch) if attr(&branch.attrs, "v-else-if").is_some() || attr(&branch.attrs, "v-else").is_some() => {
+                                branches.push(branch);
+                                next += 1;
+                                i = next;
+                                if attr(&branch.attrs, "v-else").is_some() {
+                                    break;
+                                }
+                            }
+                            _ => break,
+                        }
+                    }
+                    out.push(self.conditional(&branches));
+                }
+                Node::Element(element) => {
+                    out.push(self.element(element));
+                    i += 1;
+                }
+            }
+        }
+        out
+    }
+
+    fn text(&mut self, text: &str) -> String {
+        let mut parts = Vec::new();
+        let mut rest = text;
+        while let Some((start, end)) = rest.find("{{").and_then(|start| Some((start, start + rest[start..].find("}}")?))) {
+            if start > 0 {
+                parts.push(json(&decode_entities(&rest[..start])));
+            }
+            let expr = self.expression(&rest[start + 2..end]);
+            parts.push(format!("{}{}", self.helper("toDisplayString"), expr));
+            rest = &rest[end + 2..];
+        }
+        if !rest.is_empty() || parts.is_empty() {
+            parts.push(json(&decode_entities(rest)));
+        }
+        parts.join(" + ")
+    }
+
+    fn conditional(&mut self, branches: &[&Element]) -> String {
+        let mut code = format!("{}(\"v-if\", true)", self.helper("createCommentVNode"));
+        for element in branches.iter().rev() {
+            let branch = self.element(element);
+            code = match attr(&element.attrs, "v-if").or_else(|| attr(&element.attrs, "v-else-if")) {
+                Some(condition) => format!("{} ? {} : {}", self.expression(condition), branch, code),
+                None => branch,
+            };
+        }
+        code
+    }
+
+    fn element(&mut self, element: &Element) -> String {
+        let Some(value) = attr(&element.attrs, "v-for") else { return self.element_body(element) };
+        // `item in items`, `(item, index) of items`
+        let split = [" in ", " of "].iter().filter_map(|sep| value.find(sep).map(|i| (i, sep.len()))).min();
+        let Some((i, len)) = split else {
+            self.errors.push(format!("invalid v-for `{}`", value));
+            return "null".to_string();
+        };
+        let alias = value[..i].trim();
+        let params = alias.strip_prefix('(').and_then(|a| a.strip_suffix(')')).unwrap_or(alias).to_string();
+        let source = self.expression(&value[i + len..]);
+        let body = self.scoped(&params, |this| this.element_body(element));
+        format!("{}({}, null, {}({}, ({}) => {}))", self.helper("h"), self.helper("Fragment"), self.helper("renderList"), source, params, body)
+    }
+
+    fn element_body(&mut self, element: &Element) -> String {
+        let h = self.helper("h");
+        let tag = element.tag.as_str();
+        if tag == "slot" {
+            return self.slot_outlet(element);
+        }
+        if tag == "template" {
+            let children = self.children(&element.children);
+            return format!("{}({}, null, [{}])", h, self.helper("Fragment"), children.join(", "));
+        }
+        let component = is_component(tag);
+        let ty = if component { self.component_type(element) } else { json(tag) };
+        let (props, directives) = self.props(element, component);
+        let children = if component {
+            self.slots(element)
+        } else {
+            let children = self.children(&element.children);
+            (!children.is_empty()).then(|| format!("[{}]", children.join(", ")))
+        };
+        let code = match (props, children) {
+            (None, None) => format!("{}({})", h, ty),
+            (props, None) => format!("{}({}, {})", h, ty, props.unwrap()),
+            (props, Some(children)) => format!("{}({}, {}, {})", h, ty, props.as_deref().unwrap_or("null"), children),
+        };
+        if directives.is_empty() {
+            return code;
+        }
+        format!("{}({}, [{}])", self.helper("withDirectives"), code, directives.join(", "))
+    }
+
+    fn component_type(&mut self, element: &Element) -> String {
+        let tag = element.tag.as_str();
+        if tag == "component" {
+            let is = match (attr(&element.attrs, ":is").or_else(|| attr(&element.attrs, "v-bind:is")), attr(&element.attrs, "is")) {
+                (Some(dynamic), _) => self.expression(dynamic),
+                (None, is) => json(is.unwrap_or("")),
+            };
+            return format!("{}({})", self.helper("resolveDynamicComponent"), is);
+        }
+        let pascal = capitalize(&camelize(tag));
+        if let Some(builtin) = BUILTIN_COMPONENTS.iter().find(|builtin| **builtin == pascal) {
+            return self.helper(builtin);
+        }
+        if self.bindings.contains(tag) {
+            return format!("_ctx.{}", tag);
+        }
+        if self.bindings.contains(&pascal) {
+            return format!("_ctx.{}", pascal);
+        }
+        format!("{}({})", self.helper("resolveComponent"), json(tag))
+    }
+
+    // Props and `withDirectives` entries of an element or component.
+    fn props(&mut self, element: &Element, component: bool) -> (Option<String>, Vec<String>) {
+        let mut entries = Vec::new();
+        let mut class = Vec::new();
+        let mut style = Vec::new();
+        let mut directives = Vec::new();
+        // Handlers of the same event run in turn.
+        let mut listeners: Vec<(String, Vec<String>)> = Vec::new();
+        for (name, value) in &element.attrs {
+            let value = value.as_deref().unwrap_or("");
+            let Some(directive) = Directive::parse(name) else {
+                match name.as_str() {
+                    "class" => class.push(json(value)),
+                    "style" => style.push(json(value)),
+                    "is" if element.tag == "component" => {}
+                    _ => entries.push(format!("{}: {}", json(name), json(value))),
+                }
+                continue;
+            };
+            match directive.name {
+                "if" | "else-if" | "else" | "for" | "slot" | "once" | "pre" | "cloak" | "memo" => {}
+                "bind" => {
+                    let expr = self.expression(value);
+                    match directive.arg {
+                        None => entries.push(format!("...{}", expr)),
+                        Some("class") => class.push(expr),
+                        Some("style") => style.push(expr),
+                        Some("is") if element.tag == "component" => {}
+                        Some(arg) if directive.dynamic => entries.push(format!("[{}]: {}", self.expression(arg), expr)),
+                        Some(arg) if directive.modifiers.contains(&"camel") => entries.push(format!("{}: {}", json(&camelize(arg)), expr)),
+                        Some(arg) => entries.push(format!("{}: {}", json(arg), expr)),
+                    }
+                }
+                "on" => {
+                    let (key, handler) = self.listener(&directive, value);
+                    match listeners.iter_mut().find(|(k, _)| *k == key) {
+                        Some((_, handlers)) => handlers.push(handler),
+                        None => listeners.push((key, vec![handler])),
+                    }
+                }
+                "model" => self.model(element, &directive, value, component, &mut entries, &mut directives),
+                "show" => {
+                    let expr = self.expression(value);
+                    directives.push(format!("[{}, {}]", self.helper("vShow"), expr));
+                }
+                "html" => entries.push(format!("innerHTML: {}", self.expression(value))),
+                "text" => {
+                    let expr = self.expression(value);
+                    entries.push(format!("textContent: {}{}", self.helper("toDisplayString"), expr));
+                }
+                _ => {
+                    let custom = self.custom_directive(&directive, value);
+                    directives.push(custom);
+                }
+            }
+        }
+        let merged = [("class".to_string(), class), ("style".to_string(), style)].into_iter().chain(listeners);
+        for (key, mut values) in merged {
+            match values.len() {
+                0 => {}
+                1 => entries.push(format!("{}: {}", key, values.pop().unwrap())),
+                _ => entries.push(format!("{}: [{}]", key, values.join(", "))),
+            }
+        }
+        let props = (!entries.is_empty()).then(|| format!("{{ {} }}", entries.join(", ")));
+        (props, directives)
+    }
+
+    // The key and handler of a `v-on`.
+    fn listener(&mut self, directive: &Directive, value: &str) -> (String, String) {
+        let value = value.trim();
+        let mut handler = if value.is_empty() {
+            "() => {}".to_string()
+        } else {
+            match classify_handler(value, self.source_type) {
+                Handler::Path | Handler::Function => self.expression(value),
+                Handler::Inline => self.expression(&format!("$event => ({})", value)),
+                Handler::Statements => self.expression(&format!("$event => {{ {} }}", value)),
+            }
+        };
+        let mut suffix = String::new();
+        let mut modifiers = Vec::new();
+        let mut keys = Vec::new();
+        for modifier in &directive.modifiers {
+            match *modifier {
+                "once" | "capture" | "passive" => suffix.push_str(&capitalize(modifier)),
+                m if EVENT_MODIFIERS.contains(&m) => modifiers.push(json(m)),
+                key => keys.push(json(key)),
+            }
+        }
+        if !modifiers.is_empty() {
+            handler = format!("{}({}, [{}])", self.helper("withModifiers"), handler, modifiers.join(", "));
+        }
+        if !keys.is_empty() {
+            handler = format!("{}({}, [{}])", self.helper("withKeys"), handler, keys.join(", "));
+        }
+        let event = match directive.arg.unwrap_or("") {
+            "click" if directive.modifiers.contains(&"right") => "contextmenu",
+            "click" if directive.modifiers.contains(&"middle") => "mouseup",
+            event => event,
+        };
+        if directive.dynamic {
+            let expr = self.expression(event);
+            return (format!("[{}({})]", self.helper("toHandlerKey"), expr), handler);
+        }
+        (json(&format!("on{}{}", capitalize(&camelize(event)), suffix)), handler)
+    }
+
+    fn model(&mut self, element: &Element, directive: &Directive, value: &str, component: bool, entries: &mut Vec<String>, directives: &mut Vec<String>) {
+        let target = self.expression(value);
+        let assign = self.expression(&format!("$event => ({} = $event)", value.trim()));
+        let modifiers: Vec<String> = directive.modifiers.iter().map(|m| format!("{}: true", json(m))).collect();
+        if component {
+            let prop = directive.arg.unwrap_or("modelValue");
+            entries.push(format!("{}: {}", json(prop), target));
+            entries.push(format!("{}: {}", json(&format!("onUpdate:{}", prop)), assign));
+            if !modifiers.is_empty() {
+                let key = if prop == "modelValue" { "modelModifiers".to_string() } else { format!("{}Modifiers", prop) };
+                entries.push(format!("{}: {{ {} }}", json(&key), modifiers.join(", ")));
+            }
+            return;
+        }
+        entries.push(format!("\"onUpdate:modelValue\": {}", assign));
+        let helper = match (element.tag.as_str(), attr(&element.attrs, "type")) {
+            ("select", _) => "vModelSelect",
+            ("input", Some("checkbox")) => "vModelCheckbox",
+            ("input", Some("radio")) => "vModelRadio",
+            ("input", None) if attr(&element.attrs, ":type").is_some() => "vModelDynamic",
+            _ => "vModelText",
+        };
+        let modifiers = if modifiers.is_empty() { String::new() } else { format!(", void 0, {{ {} }}", modifiers.join(", ")) };
+        directives.push(format!("[{}, {}{}]", self.helper(helper), target, modifiers));
+    }
+
+    // `v-focus`: `vFocus` from `<script setup>` or a registered directive.
+    fn custom_directive(&mut self, directive: &Directive, value: &str) -> String {
+        let local = format!("v{}", capitalize(&camelize(directive.name)));
+        let mut parts = vec![if self.bindings.contains(&local) {
+            format!("_ctx.{}", local)
+        } else {
+            format!("{}({})", self.helper("resolveDirective"), json(directive.name))
+        }];
+        parts.push(if value.trim().is_empty() { "void 0".to_string() } else { self.expression(value) });
+        if directive.arg.is_some() || !directive.modifiers.is_empty() {
+            parts.push(directive.arg.map_or("void 0".to_string(), json));
+        }
+        if !directive.modifiers.is_empty() {
+            let modifiers: Vec<String> = directive.modifiers.iter().map(|m| format!("{}: true", json(m))).collect();
+            parts.push(format!("{{ {} }}", modifiers.join(", ")));
+        }
+        format!("[{}]", parts.join(", "))
+    }
+
+    // A component's children as slot functions: `<template #name="props">`
+    // children are named slots, the rest the default one.
+    fn slots(&mut self, element: &Element) -> Option<String> {
+        let mut slots = Vec::new();
+        let mut rest = Vec::new();
+        for child in &element.children {
+            match child {
+                Node::Element(template) if template.tag == "template" => match slot_directive(template) {
+                    Some((name, params)) => slots.push(self.slot(&name, &params, &template.children)),
+                    None => rest.push(child.clone()),
+                },
+                _ => rest.push(child.clone()),
+            }
+        }
+        let own = slot_directive(element);
+        if own.is_some() || rest.iter().any(|node| !matches!(node, Node::Text(text) if text.trim().is_empty())) {
+            let (name, params) = own.unwrap_or_else(|| ("default".to_string(), String::new()));
+            let slot = self.slot(&name, &params, &rest);
+            slots.insert(0, slot);
+        }
+        (!slots.is_empty()).then(|| format!("{{ {} }}", slots.join(", ")))
+    }
+
+    fn slot(&mut self, name: &str, params: &str, children: &[Node]) -> String {
+        let body = self.scoped(params, |this| this.children(children).join(", "));
+        format!("{}: {}(({}) => [{}])", json(name), self.helper("withCtx"), params, body)
+    }
+
+    // `<slot name="x" :item="item">fallback</slot>`
+    fn slot_outlet(&mut self, element: &Element) -> String {
+        let name = match (attr(&element.attrs, ":name"), attr(&element.attrs, "name")) {
+            (Some(dynamic), _) => self.expression(dynamic),
+            (None, name) => json(name.unwrap_or("default")),
+        };
+        let attrs = element.attrs.iter().filter(|(attr, _)| attr != "name" && attr != ":name").cloned().collect();
+        let outlet = Element { tag: "slot".to_string(), attrs, children: Vec::new() };
+        let props = self.props(&outlet, false).0.unwrap_or_else(|| "{}".to_string());
+        let fallback = self.children(&element.children);
+        let render_slot = self.helper("renderSlot");
+        if fallback.is_empty() {
+            return format!("{}(_ctx.$slots, {}, {})", render_slot, name, props);
+        }
+        format!("{}(_ctx.$slots, {}, {}, () => [{}])", render_slot, name, props, fallback.join(", "))
+    }
+}
+
+// `v-slot:name="props"` or `#name="props"`: the slot's name and parameters.
+fn slot_directive(element: &Element) -> Option<(String, String)> {
+    element.attrs.iter().find_map(|(name, value)| {
+        let directive = Directive::parse(name).filter(|d| d.name == "slot")?;
+        Some((directive.arg.unwrap_or("default").to_string(), value.clone().unwrap_or_default()))
+    })
+}
+
+// Scoped styles.
+
+// Adds `[data-v-<hash>]` to the last compound selector of each rule, before
+// any pseudo-class or pseudo-element: `.list li:hover` becomes
+// `.list li[data-v-<hash>]:hover`. In `.list :deep(li)` it goes before the
+// descendant instead, and `:global(...)` selectors stay as they are. Rules in
+// `@media` and the like are scoped too; keyframes and font faces are not.
+fn scope_css(css: &str, scope_id: &str) -> String {
+    let attr = format!("[{}]", scope_id);
+    let mut out = String::with_capacity(css.len());
+    let mut rest = css;
+    loop {
+        let trimmed = rest.trim_start();
+        out.push_str(&rest[..rest.len() - trimmed.len()]);
+        rest = trimmed;
+        if let Some(after) = rest.strip_prefix("/*") {
+            let end = after.find("*/").map_or(after.len(), |end| end + 2);
+            out.push_str(&rest[..2 + end]);
+            rest = &after[end..];
+            continue;
+        }
+        let Some(open) = rest.find(['{', ';']) else {
+            out.push_str(rest);
+            return out;
+        };
+        if rest[open..].starts_with(';') {
+            out.push_str(&rest[..=open]);
+            rest = &rest[open + 1..];
+            continue;
+        }
+        let prelude = &rest[..open];
+        let close = block_end(rest, open);
+        let block = &rest[open + 1..close];
+        if let Some(at_rule) = prelude.strip_prefix('@') {
+            let name = at_rule.split(|c: char| !c.is_alphanumeric() && c != '-').next().unwrap_or("");
+            if matches!(name, "media" | "supports" | "container" | "layer" | "document") {
+                out.push_str(prelude);
+                out.push('{');
+                out.push_str(&scope_css(block, scope_id));
+                out.push('}');
+            } else {
+                out.push_str(&rest[..close]);
+                out.push('}');
+            }
+        } else {
+            let selectors: Vec<String> = split_top_level(prelude.trim(), ',').into_iter().map(|selector| scope_selector(selector.trim(), &attr)).collect();
+            out.push_str(&selectors.join(", "));
+            out.push_str(" {");
+            out.push_str(block);
+            out.push('}');
+        }
+        rest = rest.get(close + 1..).unwrap_or("");
+    }
+}
+
+// The index of the `}` closing the block opened at `open`, or the end.
+fn block_end(css: &str, open: usize) -> usize {
+    let mut depth = 0;
+    for (i, c) in css[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return open + i;
+                }
+            }
+            _ => {}
+        }
+    }
+    css.len()
+}
+
+// `text` split at `separator`s outside parentheses and brackets.
+fn split_top_level(text: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+fn scope_selector(selector: &str, attr: &str) -> String {
+    for (pseudo, deep) in [(":deep(", true), ("::v-deep(", true), (":global(", false)] {
+        let Some(start) = selector.find(pseudo) else { continue };
+        let inner_start = start + pseudo.len();
+        let inner_end = closing_paren(selector, inner_start);
+        let inner = &selector[inner_start..inner_end];
+        let after = selector.get(inner_end + 1..).unwrap_or("");
+        let before = selector[..start].trim_end();
+        if !deep {
+            return format!("{}{}{}", &selector[..start], inner, after);
+        }
+        let scoped = if before.is_empty() { attr.to_string() } else { insert_attr(before, attr) };
+        return format!("{} {}{}", scoped, inner, after);
+    }
+    insert_attr(selector, attr)
+}
+
+// The index of the `)` closing the parenthesis before `start`, or the end.
+fn closing_paren(text: &str, start: usize) -> usize {
+    let mut depth = 0;
+    for (i, c) in text[start..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth == 0 => return start + i,
+            ')' => depth -= 1,
+            _ => {}
+        }
+    }
+    text.len()
+}
+
+fn insert_attr(selector: &str, attr: &str) -> String {
+    let mut depth = 0;
+    let mut compound = 0;
+    for (i, c) in selector.char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ' ' | '>' | '+' | '~' if depth == 0 => compound = i + 1,
+            _ => {}
+        }
+    }
+    let mut depth = 0;
+    let mut at = selector.len();
+    for (i, c) in selector[compound..].char_indices() {
+        match c {
+            '(' | '[' => depth += 1,
+            ')' | ']' => depth -= 1,
+            ':' if depth == 0 => {
+                at = compound + i;
+                break;
+            }
+            _ => {}
+        }
+    }
+    format!("{}{}{}", &selector[..at], attr, &selector[at..])
+}
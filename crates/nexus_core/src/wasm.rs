@@ -0,0 +1,181 @@
+use crate::compiler::{self, CompileResult};
+
+// WebAssembly modules.
+//
+// A plain `import { add } from './math.wasm'` follows the ESM integration
+// proposal: the importer gets the instance's exports, and the modules named in
+// the binary's import section are imported and passed in as its imports.
+// Instantiating happens in the module factory, which is synchronous, so every
+// bundle compiles the `.wasm` modules it imports this way before running
+// (`preload_text`, `entry_text`).
+//
+// `?init` instead gives a function that instantiates with caller-provided
+// imports through `WebAssembly.instantiateStreaming`. Like any file, the
+// binary's URL is a `?url` import.
+
+/// Whether `source` imports a `.wasm` module's init function.
+pub fn is_wasm_init(source: &str) -> bool {
+    source.ends_with(".wasm?init")
+}
+
+pub fn is_wasm(id: &str) -> bool {
+    id.ends_with(".wasm")
+}
+
+/// Names in a binary's import and export sections.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct WasmInterface {
+    /// Module names imports come from, in order of first use.
+    pub imports: Vec<String>,
+    pub exports: Vec<String>,
+}
+
+impl WasmInterface {
+    /// `None` if `bytes` isn't a well-formed WebAssembly binary.
+    pub fn parse(bytes: &[u8]) -> Option<Self> {
+        let mut reader = Reader { bytes, pos: 0 };
+        if reader.take(4)? != b"\0asm" {
+            return None;
+        }
+        reader.take(4)?; // version
+        let mut interface = Self::default();
+        while reader.pos < bytes.len() {
+            let id = reader.byte()?;
+            let size = reader.leb()? as usize;
+            let mut section = Reader { bytes: reader.take(size)?, pos: 0 };
+            match id {
+                2 => {
+                    for _ in 0..section.leb()? {
+                        let module = section.name()?;
+                        section.name()?;
+                        section.import_desc()?;
+                        if !interface.imports.contains(&module) {
+                            interface.imports.push(module);
+                        }
+                    }
+                }
+                7 => {
+                    for _ in 0..section.leb()? {
+                        interface.exports.push(section.name()?);
+                        section.byte()?;
+                        section.leb()?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(interface)
+    }
+}
+
+struct Reader<'b> {
+    bytes: &'b [u8],
+    pos: usize,
+}
+
+impl<'b> Reader<'b> {
+    fn take(&mut self, n: usize) -> Option<&'b [u8]> {
+        let slice = self.bytes.get(self.pos..self.pos.checked_add(n)?)?;
+        self.pos += n;
+        Some(slice)
+    }
+
+    fn byte(&mut self) -> Option<u8> {
+        Some(self.take(1)?[0])
+    }
+
+    // Unsigned LEB128.
+    fn leb(&mut self) -> Option<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = self.byte()?;
+            value |= u64::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn name(&mut self) -> Option<String> {
+        let len = self.leb()? as usize;
+        String::from_utf8(self.take(len)?.to_vec()).ok()
+    }
+
+    fn limits(&mut self) -> Option<()> {
+        let flags = self.byte()?;
+        self.leb()?;
+        if flags & 1 != 0 {
+            self.leb()?;
+        }
+        Some(())
+    }
+
+    fn import_desc(&mut self) -> Option<()> {
+        match self.byte()? {
+            0x00 => self.leb().map(|_| ()),          // func: type index
+            0x01 => self.byte().and(self.limits()),  // table: reftype, limits
+            0x02 => self.limits(),                   // memory
+            0x03 => self.take(2).map(|_| ()),        // global: valtype, mutability
+            0x04 => self.byte().and(self.leb()).map(|_| ()), // tag: attribute, type index
+            _ => None,
+        }
+    }
+}
+
+/// JS for the `.wasm` module `id`. In production the binary is emitted as a
+/// hashed asset; the dev server serves it from its own path.
+pub fn compile_wasm(bytes: &[u8], id: &str, is_prod: bool) -> CompileResult {
+    let interface = WasmInterface::parse(bytes).unwrap_or_else(|| {
+        tracing::error!("Invalid WebAssembly module: {}", id);
+        WasmInterface::default()
+    });
+
+    let mut code = String::new();
+    let mut imports = Vec::new();
+    for (i, module) in interface.imports.iter().enumerate() {
+        let specifier = serde_json::to_string(module).unwrap();
+        code.push_str(&format!("import * as __nexus_wasm_import_{} from {};\n", i, specifier));
+        imports.push(format!("{}: __nexus_wasm_import_{}", specifier, i));
+    }
+    code.push_str(&format!(
+        "const __nexus_wasm_exports__ = __nexus_wasm__.instance(\"{}\", {{ {} }});\n",
+        id,
+        imports.join(", ")
+    ));
+    let mut exports = Vec::new();
+    for (i, name) in interface.exports.iter().filter(|name| is_identifier_name(name)).enumerate() {
+        code.push_str(&format!("const __nexus_wasm_export_{} = __nexus_wasm_exports__[\"{}\"];\n", i, name));
+        exports.push(format!("__nexus_wasm_export_{} as {}", i, name));
+    }
+    if !exports.is_empty() {
+        code.push_str(&format!("export {{ {} }};\n", exports.join(", ")));
+    }
+
+    let asset = is_prod.then(|| (compiler::hashed_asset_name(id, bytes), bytes.to_vec()));
+    CompileResult { code, sourcemap: None, css: None, asset }
+}
+
+// Exports that aren't valid export names in JS can't be imported by name.
+fn is_identifier_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '$')
+}
+
+/// Compiles `ids` ahead of running the bundle's modules.
+pub fn preload_text(ids: &[&str]) -> String {
+    if ids.is_empty() {
+        return String::new();
+    }
+    format!("__nexus_wasm__.preload({});\n", serde_json::to_string(ids).unwrap())
+}
+
+/// Runs `entry`, once preloaded `.wasm` modules are ready if there are any.
+pub fn entry_text(entry: &str, wait: bool) -> String {
+    if wait {
+        format!("__nexus_wasm__.ready().then(function() {{ __nexus_require__(\"{}\"); }});\n", entry)
+    } else {
+        format!("__nexus_require__(\"{}\");\n", entry)
+    }
+}
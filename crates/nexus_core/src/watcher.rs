@@ -10,13 +10,52 @@ pub struct HmrMessage {
     pub paths: Vec<String>,
 }
 
+use crate::define::Defines;
+use crate::loader::LoaderRegistry;
+use crate::plugin::{HotUpdate, PluginContainer};
+use crate::prebundle::DepsOptimizer;
 use crate::resolver::NexusResolver;
 
+/// Re-runs dependency pre-bundling whenever a lockfile at the project root
+/// changes, and reloads pages whose bundles changed.
+pub async fn watch_lockfiles(
+    root: String,
+    graph: Arc<RwLock<ModuleGraph>>,
+    tx: broadcast::Sender<HmrMessage>,
+    deps: Arc<DepsOptimizer>,
+) {
+    let (notif_tx, mut notif_rx) = tokio::sync::mpsc::channel(16);
+    let mut watcher = match RecommendedWatcher::new(move |res| {
+        let _ = notif_tx.blocking_send(res);
+    }, Config::default()) {
+        Ok(watcher) => watcher,
+        Err(e) => return tracing::error!("Failed to watch lockfiles: {}", e),
+    };
+    if let Err(e) = watcher.watch(Path::new(&root), RecursiveMode::NonRecursive) {
+        return tracing::error!("Failed to watch lockfiles: {}", e);
+    }
+
+    while let Some(res) = notif_rx.recv().await {
+        let Ok(event) = res else { continue };
+        if !event.paths.iter().any(|p| deps.is_lockfile(p)) {
+            continue;
+        }
+        let changed = deps.refresh(&graph, &[]);
+        if !changed.is_empty() {
+            tracing::info!("Lockfile changed, re-bundled dependencies: {:?}", changed);
+            let _ = tx.send(HmrMessage { paths: changed });
+        }
+    }
+}
+
 pub async fn start_watcher(
     root: String, 
     graph: Arc<RwLock<ModuleGraph>>, 
     tx: broadcast::Sender<HmrMessage>,
-    resolver: Arc<NexusResolver>
+    resolver: Arc<NexusResolver>,
+    defines: Arc<Defines>,
+    plugins: PluginContainer,
+    loaders: Arc<LoaderRegistry>,
 ) {
     let (notif_tx, mut notif_rx) = tokio::sync::mpsc::channel(100);
 
@@ -35,15 +74,20 @@ pub async fn start_watcher(
         match res {
             Ok(event) => {
                 // Minimal: treat any Modify/Create as reload candidate
-                // Filter for .js/.ts files
-                let relevant_paths: Vec<_> = event.paths.into_iter()
+                // Filter for files some loader handles
+                let mut relevant_paths: Vec<_> = event.paths.into_iter()
                     .filter(|p| {
                         let s = p.to_string_lossy();
                         // Week 9: Ignore node_modules
                         if s.contains("node_modules") {
                             return false; 
                         }
-                        s.ends_with(".js") || s.ends_with(".ts") || s.ends_with(".jsx") || s.ends_with(".tsx") || s.ends_with(".css") || s.ends_with(".json") || s.ends_with(".png") || s.ends_with(".jpg") || s.ends_with(".jpeg") || s.ends_with(".svg") || s.ends_with(".wasm")
+                        // Pre-bundled dependencies and the cache are ours.
+                        if s.contains(".nexus") {
+                            return false;
+                        }
+                        let id = p.strip_prefix(&root).map(|r| format!("/{}", r.to_string_lossy().replace('\\', "/")));
+                        loaders.get(id.as_deref().unwrap_or(&s)).is_some()
                     })
                     .collect();
 
@@ -51,19 +95,25 @@ pub async fn start_watcher(
                     continue;
                 }
 
-                // Handle changes
-                
-                // --- Correct Loop Implementation ---
-                for path in relevant_paths {
-                     // Read file (binary)
-                     let bytes = match std::fs::read(&path) {
-                         Ok(b) => b,
-                         Err(e) => {
-                             tracing::error!("Watcher failed to read {}: {}", path.display(), e);
-                             continue;
-                         }
-                     };
-                     
+                // Creating or deleting a file under an `import.meta.glob`
+                // changes what the importer's glob expands to.
+                {
+                    let g = graph.read().unwrap();
+                    for path in relevant_paths.clone() {
+                        let Ok(relative) = path.strip_prefix(&root) else { continue };
+                        let id = format!("/{}", relative.to_string_lossy().replace('\\', "/"));
+                        for importer in g.glob_importers(&id) {
+                            let importer = Path::new(&root).join(g.modules[importer.0].path.trim_start_matches('/'));
+                            if !relevant_paths.contains(&importer) {
+                                relevant_paths.push(importer);
+                            }
+                        }
+                    }
+                }
+
+                // Handle changes; files new imports lead to are queued too.
+                let mut queue: std::collections::VecDeque<_> = relevant_paths.into();
+                while let Some(path) = queue.pop_front() {
                      // Normalize path
                      let path_lossy = path.to_string_lossy();
                      let relative = if path_lossy.starts_with(&root) {
@@ -74,32 +124,56 @@ pub async fn start_watcher(
                      let normalized = relative.replace('\\', "/");
                      let virt_path = if normalized.starts_with('/') { normalized } else { format!("/{}", normalized) };
 
-                     let ext = path.extension().and_then(|s| s.to_str()).unwrap_or("");
+                     // Read file (binary), or what plugins load for it
+                     let bytes = match plugins.source(&virt_path, &path) {
+                         Ok(b) => b,
+                         Err(e) => {
+                             tracing::error!("Watcher failed to read {}: {}", path.display(), e);
+                             continue;
+                         }
+                     };
+
+                     let loader = loaders.loader(&virt_path);
 
                      // Week 8/11/12: Compile based on type
-                     let compiled = match ext {
-                         "css" => {
-                             let text = String::from_utf8_lossy(&bytes);
-                             crate::compiler::compile_css(&text, &virt_path, false)
-                         },
-                          "png" | "jpg" | "jpeg" | "gif" | "svg" | "wasm" | "json" => {
-                             crate::compiler::compile_asset(&bytes, &virt_path, false)
-                         },
-                         _ => {
-                             let text = String::from_utf8_lossy(&bytes);
-                             crate::compiler::compile(&text, &virt_path, false)
+                     let mut js_deps = None;
+                     let mut globs = None;
+                     let compiled = loaders.compile(loader, &bytes, &virt_path, false).unwrap_or_else(|e| Some(crate::loader::error_module(&virt_path, &e)));
+                     let mut compiled = match compiled {
+                         Some(compiled) => compiled,
+                         None => {
+                             // Dependencies come from the same parse.
+                             let source = String::from_utf8_lossy(&bytes).into_owned();
+                             let mut unit = crate::unit::CompilationUnit::parse_as(&virt_path, source, loader.source_type(&virt_path));
+                             unit.define(&defines);
+                             globs = Some(unit.expand_import_meta(Path::new(&root), false).globs);
+                             js_deps = Some(unit.dependencies());
+                             unit.compile_result()
                          }
                      };
 
+                        match plugins.transform(&virt_path, &mut compiled) {
+                            Ok(true) => js_deps = None,
+                            Ok(false) => {}
+                            Err(e) => tracing::error!("{}", e),
+                        }
+
                         // Week 9: Resolve Deps using new Resolver
-                        let deps = crate::parser::extract_dependencies(&compiled.code, &virt_path);
+                        let deps: Vec<(String, bool)> = js_deps.unwrap_or_else(|| crate::parser::extract_dependencies_detailed(&compiled.code, &virt_path));
                         let mut resolved_imports = std::collections::HashMap::new();
+                        let mut edges = Vec::new();
                         
                         // We need to resolve against the file's dir. 
                         // virt_path is URI-like. We should use `path` (absolute PathBuf) for resolution source.
                         
-                        for dep_spec in deps {
-                            if let Ok(resolved_abs) = resolver.resolve(&path, &dep_spec) {
+                        for (dep_spec, is_dynamic) in deps {
+                            let resolution = match plugins.resolve_id(&dep_spec, &path) {
+                                Ok(Some(resolved_abs)) => Ok(resolved_abs),
+                                Ok(None) => resolver.resolve(&path, &dep_spec),
+                                Err(e) => Err(e),
+                            };
+                            if let Ok(resolved_abs) = resolution {
+                                let resolved_abs = crate::compiler::with_query(&resolved_abs, &dep_spec);
                                 // Normalize for graph key (copy logic from lib.rs helper if possible, or duplicate)
                                 let normalized_abs = resolved_abs.to_string_lossy();
                                 let graph_key = if let Ok(rel) = resolved_abs.strip_prefix(&root) {
@@ -111,6 +185,10 @@ pub async fn start_watcher(
                                      let n = s.replace('\\', "/");
                                      if !n.starts_with('/') { format!("/{}", n) } else { n }
                                 };
+                                // A worker entry roots its own bundle, as in `update_module`.
+                                if crate::parser::worker_constructor(&dep_spec).is_none() {
+                                    edges.push((graph_key.clone(), resolved_abs.starts_with(&root) && !resolved_abs.to_string_lossy().contains("node_modules"), is_dynamic));
+                                }
                                 resolved_imports.insert(dep_spec, graph_key);
                             }
                             // If resolution fails in watcher, we just warn or ignore?
@@ -128,6 +206,24 @@ pub async fn start_watcher(
                                 
                                 g.update_compiled(id, &final_content, compiled.sourcemap);
                                 g.set_imports(id, resolved_imports); // Update imports map
+                                // New imports, such as a file created under an
+                                // `import.meta.glob`, join the graph and get compiled
+                                // in this batch, so the page bundle has them.
+                                for (key, is_source, is_dynamic) in edges {
+                                    let dep = match g.find_by_path(&key) {
+                                        Some(dep) => dep,
+                                        None if is_source => {
+                                            queue.push_back(crate::graph::module_file(Path::new(&root), &key));
+                                            g.add_module(&key, "")
+                                        }
+                                        None => continue,
+                                    };
+                                    let _ = g.add_dependency(id, dep, is_dynamic);
+                                }
+                                g.set_fingerprint(id, crate::graph::Fingerprint::of(&path, &bytes));
+                                if let Some(globs) = globs {
+                                    g.set_globs(id, globs);
+                                }
                                 
                                 // Now find roots (using updated graph structure? No, strictly structure is same if we don't reparse)
                                 // We use existing edges.
@@ -155,8 +251,22 @@ pub async fn start_watcher(
                                     }
                                 }
                             }
+
+                            // Files imported as `?raw`, `?url` or `?inline` have a module per variant.
+                            for query in crate::compiler::AssetQuery::ALL {
+                                let Some(id) = g.find_by_path(&format!("{}{}", virt_path, query.suffix())) else { continue };
+                                let variant = crate::compiler::compile_variant(&bytes, &virt_path, query, false);
+                                g.update_compiled(id, &variant.code, None);
+                                g.set_fingerprint(id, crate::graph::Fingerprint::of(&path, &bytes));
+                                for r in g.find_affected_roots(id) {
+                                    if let Some(m) = g.modules.get(r.0) {
+                                        roots_to_reload.push(m.path.clone());
+                                    }
+                                }
+                            }
                         }
                         
+                        let roots_to_reload = plugins.handle_hot_update(HotUpdate { file: virt_path.clone(), modules: roots_to_reload });
                         if !roots_to_reload.is_empty() {
                              tracing::info!("File Changed & Compiled. Reloading chunks: {:?}", roots_to_reload);
                              let _ = tx.send(HmrMessage { paths: roots_to_reload });
@@ -0,0 +1,65 @@
+use nexus_core::analyzer;
+use nexus_core::bundler::{self, BuildOptions};
+use tokio::fs;
+
+#[test]
+fn test_measure_sizes() {
+    let code = "function add(first, second) {\n    // adds\n    return first + second;\n}\nconsole.log(add(1, 2));\n".repeat(20);
+    let sizes = analyzer::measure(&code, "bloat.js");
+    assert_eq!(sizes.raw, code.len());
+    assert!(sizes.minified < sizes.raw);
+    assert!(sizes.gzip < sizes.minified);
+    assert!(sizes.brotli < sizes.minified);
+}
+
+#[tokio::test]
+async fn test_stats_and_treemap_output() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/analyzer_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+
+    fs::write(src.join("index.tsx"), r#"
+import { render } from './view';
+render();
+import('./lazy').then(m => m.lazy());
+"#).await.unwrap();
+    fs::write(src.join("view.ts"), "export function render() { console.log('view'); }").await.unwrap();
+    fs::write(src.join("lazy.ts"), "export function lazy() { console.log('lazy'); }").await.unwrap();
+
+    let options = BuildOptions { analyze: true, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+
+    let stats: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(root.join("dist/stats.json")).await.unwrap()).unwrap();
+    let chunks = stats["chunks"].as_array().unwrap();
+    let files: Vec<&str> = chunks.iter().map(|c| c["files"][0].as_str().unwrap()).collect();
+    assert!(files.contains(&"assets/main.js"));
+    assert!(files.contains(&"assets/vendor.js"));
+    assert!(files.iter().any(|f| f.contains("lazy")), "dynamic chunk is listed: {:?}", files);
+
+    let modules = stats["modules"].as_array().unwrap();
+    let view = modules.iter().find(|m| m["name"] == "./src/view.ts").expect("view module in stats");
+    assert_eq!(view["issuerName"], "./src/index.tsx");
+    assert_eq!(view["reasons"][0]["moduleName"], "./src/index.tsx");
+    for key in ["raw", "minified", "gzip", "brotli"] {
+        assert!(view["nexusSizes"][key].as_u64().unwrap() > 0, "missing {} size", key);
+    }
+    assert!(stats["entrypoints"]["main"]["assets"].as_array().unwrap().len() >= 2);
+
+    let html = fs::read_to_string(root.join("dist/treemap.html")).await.unwrap();
+    assert!(html.contains("/src/view.ts"));
+    assert!(!html.contains("__NEXUS_DATA__"));
+    assert!(!html.contains("<script src"), "treemap must be self-contained");
+}
+
+#[tokio::test]
+async fn test_analyzer_is_opt_in() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/analyzer_off_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), "console.log('hi');").await.unwrap();
+
+    bundler::build(root.to_str().unwrap()).await.expect("Build failed");
+    assert!(!root.join("dist/stats.json").exists());
+    assert!(!root.join("dist/treemap.html").exists());
+}
@@ -3,7 +3,7 @@ use nexus_core::compiler::compile_asset;
 #[test]
 fn test_compile_json() {
     let bytes = r#"{ "foo": "bar" }"#.as_bytes();
-    let result = compile_asset(bytes, "data.json");
+    let result = compile_asset(bytes, "data.json", false);
     
     // Should export default parsed JSON
     assert!(result.code.contains("export default { \"foo\": \"bar\" };"));
@@ -13,7 +13,7 @@ fn test_compile_json() {
 fn test_compile_small_image_inline() {
     // 5 bytes < 8KB
     let bytes = vec![1, 2, 3, 4, 5]; 
-    let result = compile_asset(&bytes, "icon.png");
+    let result = compile_asset(&bytes, "icon.png", false);
     
     // Should be Data URI
     // Expect: export default "data:image/png;base64,AQIDBAU="; (AQIDBAU= is base64 of 1,2,3,4,5)
@@ -27,7 +27,7 @@ fn test_compile_small_image_inline() {
 fn test_compile_large_image_url() {
     // > 8KB
     let bytes = vec![0; 9000];
-    let result = compile_asset(&bytes, "/src/large.png"); // Virtual path
+    let result = compile_asset(&bytes, "/src/large.png", false); // Virtual path
     
     // Should export raw URL
     assert!(result.code.contains("export default \"/src/large.png?raw\";"));
@@ -36,9 +36,9 @@ fn test_compile_large_image_url() {
 #[test]
 fn test_mime_guess() {
     let bytes = vec![0];
-    let result = compile_asset(&bytes, "test.svg");
+    let result = compile_asset(&bytes, "test.svg", false);
     assert!(result.code.contains("image/svg+xml"));
     
-    let result2 = compile_asset(&bytes, "test.wasm");
+    let result2 = compile_asset(&bytes, "test.wasm", false);
     assert!(result2.code.contains("application/wasm"));
 }
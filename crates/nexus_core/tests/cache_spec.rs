@@ -0,0 +1,110 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::cache::{self, CachedModule, TransformCache, CACHE_DIR};
+use nexus_core::define::Defines;
+use std::collections::BTreeMap;
+use tokio::fs;
+
+async fn setup_app(name: &str) -> std::path::PathBuf {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures").join(name);
+    let _ = fs::remove_dir_all(&root).await;
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), "import { greet } from './greet';\nconsole.log(greet());\n").await.unwrap();
+    fs::write(src.join("greet.ts"), "export function greet() { return 'HELLO_V1'; }").await.unwrap();
+    root
+}
+
+#[test]
+fn test_entries_are_keyed_by_content_and_config() {
+    let root = std::env::temp_dir().join("nexus_tests").join("transform_cache");
+    let _ = std::fs::remove_dir_all(&root);
+
+    let hash = cache::content_hash(b"export const a = 1;");
+    {
+        let cache = TransformCache::open(&root, "build").expect("cache opens");
+        let entry = CachedModule { code: "export const a = 1;\n".to_string(), ..CachedModule::default() };
+        cache.put_module("/src/a.ts", hash, &entry);
+        assert_eq!(cache.get_module("/src/a.ts", hash).unwrap().code, entry.code);
+        assert!(cache.get_module("/src/a.ts", cache::content_hash(b"export const a = 2;")).is_none());
+        assert!(cache.get_module("/src/b.ts", hash).is_none());
+        cache.flush();
+    }
+    let dev = TransformCache::open(&root, "dev").expect("cache reopens");
+    assert!(dev.get_module("/src/a.ts", hash).is_none(), "config is part of the key");
+}
+
+#[test]
+fn test_replaced_entries_are_dropped() {
+    let root = std::env::temp_dir().join("nexus_tests").join("transform_cache_evict");
+    let _ = std::fs::remove_dir_all(&root);
+
+    let cache = TransformCache::open(&root, "build").expect("cache opens");
+    let (v1, v2) = (cache::content_hash(b"v1"), cache::content_hash(b"v2"));
+    cache.put_module("/src/a.ts", v1, &CachedModule { code: "v1".to_string(), ..CachedModule::default() });
+    cache.put_module("/src/b.ts", v1, &CachedModule { code: "b".to_string(), ..CachedModule::default() });
+    cache.put_module("/src/a.ts", v2, &CachedModule { code: "v2".to_string(), ..CachedModule::default() });
+
+    assert!(cache.get_module("/src/a.ts", v1).is_none(), "the edit replaced the old entry");
+    assert_eq!(cache.get_module("/src/a.ts", v2).unwrap().code, "v2");
+    assert_eq!(cache.get_module("/src/b.ts", v1).unwrap().code, "b", "other modules keep theirs");
+}
+
+#[tokio::test]
+async fn test_rebuild_reuses_cache() {
+    let root = setup_app("cache_app").await;
+    bundler::build(root.to_str().unwrap()).await.expect("Build failed");
+    let first = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(root.join(CACHE_DIR).exists());
+
+    {
+        // The entry and its resolved imports were stored under its content hash.
+        let defines = Defines::load(&root, "production", &BTreeMap::new());
+        let cache = TransformCache::open(&root, &format!("build:{:x}", defines.hash())).unwrap();
+        let bytes = fs::read(root.join("src/index.tsx")).await.unwrap();
+        let entry = cache.get_module("/src/index.tsx", cache::content_hash(&bytes)).expect("entry cached");
+        assert_eq!(entry.resolved.len(), 1);
+        assert!(entry.resolved[0].1.ends_with("greet.ts"));
+    }
+
+    bundler::build(root.to_str().unwrap()).await.expect("Rebuild failed");
+    let second = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert_eq!(first, second);
+}
+
+#[tokio::test]
+async fn test_changed_file_misses_cache() {
+    let root = setup_app("cache_change_app").await;
+    bundler::build(root.to_str().unwrap()).await.expect("Build failed");
+
+    fs::write(root.join("src/greet.ts"), "export function greet() { return 'HELLO_V2'; }").await.unwrap();
+    bundler::build(root.to_str().unwrap()).await.expect("Rebuild failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.contains("HELLO_V2"));
+    assert!(!main.contains("HELLO_V1"));
+}
+
+#[tokio::test]
+async fn test_cache_can_be_disabled() {
+    let root = setup_app("cache_off_app").await;
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    assert!(!root.join(".nexus").exists());
+}
+
+#[tokio::test]
+async fn test_new_file_takes_over_resolution() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/cache_resolve_app");
+    let _ = fs::remove_dir_all(&root).await;
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.js"), "import { greet } from './greet';\nconsole.log(greet());\n").await.unwrap();
+    fs::write(src.join("greet.js"), "export function greet() { return 'FROM_JS'; }").await.unwrap();
+    bundler::build(root.to_str().unwrap()).await.expect("Build failed");
+
+    // `.ts` comes first in the resolver's extensions; the entry itself is unchanged.
+    fs::write(src.join("greet.ts"), "export function greet() { return 'FROM_TS'; }").await.unwrap();
+    bundler::build(root.to_str().unwrap()).await.expect("Rebuild failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.contains("FROM_TS"), "{}", main);
+    assert!(!main.contains("FROM_JS"));
+}
@@ -13,10 +13,10 @@ fn test_chunk_linearization() {
     let c_id = graph.add_module("c.js", "const c = 3;");
     
     let b_id = graph.add_module("b.js", "import './c.js';\nconst b = 2;");
-    graph.add_dependency(b_id, c_id).unwrap();
+    graph.add_dependency(b_id, c_id, false).unwrap();
     
     let a_id = graph.add_module("a.js", "import './b.js';\nconst a = 1;");
-    graph.add_dependency(a_id, b_id).unwrap();
+    graph.add_dependency(a_id, b_id, false).unwrap();
     
     let chunk_order = graph.linearize(a_id);
     
@@ -34,8 +34,8 @@ fn test_chunk_cycle_handling() {
      let a_id = graph.add_module("a.js", "");
      let b_id = graph.add_module("b.js", "");
      
-     graph.add_dependency(a_id, b_id).unwrap();
-     graph.add_dependency(b_id, a_id).unwrap();
+     graph.add_dependency(a_id, b_id, false).unwrap();
+     graph.add_dependency(b_id, a_id, false).unwrap();
      
      // Linearize from A
      // Expect A, B (in some valid order, not infinite loop)
@@ -0,0 +1,77 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::parser::transform_cjs;
+use nexus_core::unit::CompilationUnit;
+use std::collections::HashMap;
+use tokio::fs;
+
+#[test]
+fn test_commonjs_detection_and_require_dependencies() {
+    let cjs = CompilationUnit::parse("/src/legacy.js", r#"
+const util = require('./util');
+let optional;
+try { optional = require('optional-dep'); } catch (e) {}
+module.exports = function legacy() { return util.x + (optional ? 1 : 0); };
+"#.to_string());
+    assert!(cjs.is_commonjs());
+    let deps: Vec<String> = cjs.dependencies().into_iter().map(|(s, _)| s).collect();
+    assert_eq!(deps, vec!["./util", "optional-dep"]);
+    assert!(cjs.imports().iter().all(|i| i.is_require && !i.is_dynamic));
+
+    let esm = CompilationUnit::parse("/src/esm.js", "import x from './x';\nexport const y = require('./y');".to_string());
+    assert!(!esm.is_commonjs(), "ESM syntax wins over require");
+    assert!(esm.imports().iter().any(|i| i.source == "./y" && i.is_require));
+
+    let script = CompilationUnit::parse("/src/script.js", "console.log(1);".to_string());
+    assert!(!script.is_commonjs());
+}
+
+#[test]
+fn test_require_rewrite_and_es_module_flag() {
+    let imports = HashMap::from([("./util".to_string(), "/src/util.js".to_string())]);
+    let cjs = transform_cjs(
+        "const util = require('./util');\ntry { require('optional-dep'); } catch (e) {}\nmodule.exports = util;",
+        "/src/legacy.js",
+        &imports,
+    );
+    assert!(cjs.contains("require(\"/src/util.js\")"));
+    assert!(cjs.contains("require('optional-dep')"), "unresolved requires stay as written");
+    assert!(!cjs.contains("__esModule"), "CommonJS modules define their own exports");
+
+    let esm = transform_cjs("import util from './util';\nexport default util;", "/src/esm.js", &imports);
+    assert!(esm.starts_with("Object.defineProperty(exports, \"__esModule\", { value: true });"));
+    // Works for any `module.exports`, a string or a frozen object included.
+    assert!(esm.contains("const util = (function(m) { return m && m.__esModule ? m.default : m; })(require(\"/src/util.js\"));"), "{}", esm);
+    assert!(!nexus_core::runtime::NEXUS_RUNTIME_JS.contains("defineProperty(e, \"default\""), "exports are not patched");
+}
+
+#[tokio::test]
+async fn test_build_bundles_commonjs_modules() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/commonjs_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import legacy, { version } from './legacy';
+console.log(legacy(), version);
+"#).await.unwrap();
+    fs::write(src.join("legacy.js"), r#"
+const util = require('./util');
+let optional = null;
+try { optional = require('optional-dep'); } catch (e) {}
+function legacy() { return util.answer; }
+legacy.version = '1.0';
+module.exports = legacy;
+"#).await.unwrap();
+    fs::write(src.join("util.js"), "export const answer = 42;\nexport const unused = 1;").await.unwrap();
+
+    let options = BuildOptions { report: true, cache: false, ..BuildOptions::default() };
+    let report = bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+
+    assert!(main.contains("__nexus_register__(\"/src/legacy.js\""));
+    assert!(main.contains("require(\"/src/util.js\")"), "require is linked to the module id");
+    assert!(main.contains("require(\"optional-dep\")"), "optional require is kept for the catch to handle");
+    assert!(main.contains("legacy.version = \"1.0\""), "CommonJS modules are not shaken");
+
+    let util = report.module("/src/util.js").expect("required module is bundled");
+    assert!(util.used_exports.contains(&"unused".to_string()), "require uses the whole namespace");
+}
@@ -3,7 +3,7 @@ use nexus_core::compiler::compile;
 #[test]
 fn test_compile_ts_strip() {
     let source = "const add = (a: number, b: number): number => a + b;";
-    let res = compile(source, "test.ts");
+    let res = compile(source, "test.ts", false);
     
     // Types should be gone
     assert!(!res.code.contains(": number"));
@@ -13,7 +13,7 @@ fn test_compile_ts_strip() {
 #[test]
 fn test_compile_jsx() {
     let source = "export default () => <h1>Hello</h1>;";
-    let res = compile(source, "test.tsx");
+    let res = compile(source, "test.tsx", false);
     
     // Should contain factory call (e.g. React.createElement or similar default)
     // Oxc default is usually React.createElement or jsx() depending on config.
@@ -26,7 +26,7 @@ fn test_compile_jsx() {
 #[test]
 fn test_sourcemap_generation() {
     let source = "const x: number = 1;";
-    let res = compile(source, "test.ts");
+    let res = compile(source, "test.ts", false);
     
     assert!(res.sourcemap.is_some());
     let map = res.sourcemap.unwrap();
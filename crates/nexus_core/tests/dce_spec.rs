@@ -0,0 +1,120 @@
+use nexus_core::bundler;
+use nexus_core::parser::transform_tree_shake;
+use nexus_core::shaker;
+use std::collections::HashSet;
+use tokio::fs;
+
+fn used(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_unreferenced_helpers_removed() {
+    let source = r#"
+function helper() { return 1; }
+function otherHelper() { return 2; }
+export function used() { return helper(); }
+export function unused() { return otherHelper(); }
+"#;
+    let result = transform_tree_shake(source, "utils.js", &used(&["used"]));
+
+    assert!(result.contains("function helper()"), "helper is reachable from a used export");
+    assert!(result.contains("export function used()"));
+    assert!(!result.contains("function unused()"));
+    assert!(!result.contains("function otherHelper()"), "helper of a removed export should go too");
+}
+
+#[test]
+fn test_side_effects_kept() {
+    let source = r#"
+const registry = [];
+registry.push("loaded");
+export const unusedButImpure = compute();
+export const unusedPure = 1;
+"#;
+    let result = transform_tree_shake(source, "mod.js", &used(&[]));
+
+    assert!(result.contains("registry.push(\"loaded\")"));
+    assert!(result.contains("const registry = []"), "declaration referenced by a side effect");
+    assert!(result.contains("compute()"), "impure initializer must stay");
+    assert!(!result.contains("unusedPure"));
+}
+
+#[test]
+fn test_pure_annotation() {
+    let source = r#"
+export const Button = /*#__PURE__*/ createComponent("button");
+export const Link = createComponent("a");
+"#;
+    let info = shaker::analyze(source, "ui.js");
+    assert!(info.has_side_effects, "unannotated call is impure");
+
+    let result = transform_tree_shake(source, "ui.js", &used(&[]));
+    assert!(!result.contains("Button"));
+    assert!(result.contains("createComponent(\"a\")"));
+}
+
+#[test]
+fn test_unused_import_specifiers_removed() {
+    let source = r#"
+import { a, b } from "./dep";
+export function run() { return a(); }
+"#;
+    let result = transform_tree_shake(source, "main.js", &used(&["run"]));
+    assert!(result.contains("import { a } from \"./dep\";"));
+}
+
+#[tokio::test]
+async fn test_side_effect_free_module_pruned() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/dce_app");
+    let dist = root.join("dist");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+
+    fs::write(src.join("index.tsx"), r#"
+import { format } from './format';
+import { helper } from './pure';
+import './polyfill';
+
+console.log(format(1));
+"#).await.unwrap();
+
+    fs::write(src.join("format.ts"), r#"
+function pad(x) { return "0" + x; }
+export function format(x) { return pad(x); }
+export function parse(x) { return Number(x); }
+"#).await.unwrap();
+    // Imported but never referenced and free of side effects.
+    fs::write(src.join("pure.ts"), "export function helper() { return 'PURE_MODULE'; }").await.unwrap();
+    fs::write(src.join("polyfill.ts"), "window.POLYFILLED = true;").await.unwrap();
+
+    let res = bundler::build(root.to_str().unwrap()).await;
+    assert!(res.is_ok(), "Build failed");
+
+    let main = fs::read_to_string(dist.join("assets/main.js")).await.unwrap();
+    assert!(main.contains("function pad("));
+    assert!(!main.contains("function parse("));
+    assert!(!main.contains("PURE_MODULE"), "side-effect free module should be pruned");
+    assert!(!main.contains("/src/pure.ts"), "import of pruned module should be removed");
+    assert!(main.contains("POLYFILLED"), "side-effect import must be kept");
+}
+
+#[tokio::test]
+async fn test_side_effect_imports_kept_transitively() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/dce_transitive_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+
+    fs::write(src.join("index.js"), "import { x } from './setup';\nconsole.log('app');\n").await.unwrap();
+    // Nothing of its own is used, but it pulls in an effectful module.
+    fs::write(src.join("setup.js"), "import './polyfill';\nexport const x = 1;\n").await.unwrap();
+    fs::write(src.join("polyfill.js"), "window.POLYFILLED = true;\n").await.unwrap();
+
+    let res = bundler::build(root.to_str().unwrap()).await;
+    assert!(res.is_ok(), "Build failed");
+
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.contains("window.POLYFILLED = true"), "{}", main);
+    assert!(main.contains("/src/setup.js"), "module importing an effectful one is kept");
+    assert!(!main.contains("const x = 1"), "its unused exports still go");
+}
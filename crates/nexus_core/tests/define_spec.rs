@@ -0,0 +1,102 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::define::{self, Defines};
+use nexus_core::unit::CompilationUnit;
+use std::collections::BTreeMap;
+use tokio::fs;
+
+#[test]
+fn test_defines_replace_and_drop_dead_branches() {
+    let env = BTreeMap::from([("NEXUS_API_URL".to_string(), "https://api.example.com".to_string())]);
+    let user = BTreeMap::from([("__APP_VERSION__".to_string(), "\"1.2.0\"".to_string())]);
+    let defines = Defines::new("production", &env, &user);
+
+    let mut unit = CompilationUnit::parse("/src/app.js", r#"
+if (process.env.NODE_ENV !== 'production') {
+  require('./dev-checks');
+}
+const url = import.meta.env.NEXUS_API_URL;
+const label = import.meta.env.DEV ? 'dev' : 'prod';
+const missing = import.meta.env.NEXUS_MISSING;
+import.meta.env.PROD && console.log(__APP_VERSION__, import.meta.env.MODE);
+"#.to_string());
+    unit.define(&defines);
+    let code = unit.code();
+
+    assert!(unit.dependencies().is_empty(), "imports in dead branches are gone: {}", code);
+    assert!(!code.contains("if ("));
+    assert!(code.contains("const url = \"https://api.example.com\""));
+    assert!(code.contains("const label = \"prod\""));
+    assert!(code.contains("\"NEXUS_API_URL\"") && code.contains(".NEXUS_MISSING"), "unknown keys read off the env object");
+    assert!(code.contains("console.log(\"1.2.0\", \"production\")"));
+}
+
+#[test]
+fn test_dead_branches_keep_hoisted_declarations() {
+    let defines = Defines::new("production", &BTreeMap::new(), &BTreeMap::new());
+    let source = r#"
+if (process.env.NODE_ENV !== 'production') {
+  var debug = true;
+  for (var i = 0; i < 1; i++) {}
+  function helper() { var inner = 1; }
+} else {
+  console.log('prod');
+}
+console.log(typeof debug, typeof helper);
+"#;
+
+    let mut unit = CompilationUnit::parse("/src/flags.js", source.to_string());
+    unit.define(&defines);
+    let code = unit.code();
+    assert!(code.contains("var debug, i;"), "var bindings outlive the dead branch: {}", code);
+    assert!(code.contains("console.log(\"prod\")"));
+    assert!(!code.contains("inner") && !code.contains("helper()"), "{}", code);
+
+    let mut script = CompilationUnit::parse_as("/src/flags.cjs", source.to_string(), oxc_span::SourceType::cjs());
+    script.define(&defines);
+    assert!(script.code().contains("var debug, i, helper;"), "sloppy functions hoist too: {}", script.code());
+}
+
+#[test]
+fn test_env_files_by_mode_with_public_prefix() {
+    let root = std::env::temp_dir().join("nexus_tests").join("define_env");
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(&root).unwrap();
+    std::fs::write(root.join(".env"), "# shared\nNEXUS_API_URL=http://localhost\nNEXUS_NAME=\"My App\"\nDATABASE_PASSWORD=secret\n").unwrap();
+    std::fs::write(root.join(".env.production"), "export NEXUS_API_URL='https://api.example.com'\n").unwrap();
+
+    let env = define::load_env(&root, "production");
+    assert_eq!(env.get("NEXUS_API_URL").map(String::as_str), Some("https://api.example.com"), "mode file wins");
+    assert_eq!(env.get("NEXUS_NAME").map(String::as_str), Some("My App"));
+    assert!(!env.contains_key("DATABASE_PASSWORD"), "only prefixed variables are public");
+
+    let dev = define::load_env(&root, "development");
+    assert_eq!(dev.get("NEXUS_API_URL").map(String::as_str), Some("http://localhost"));
+}
+
+#[tokio::test]
+async fn test_build_drops_development_only_modules() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/define_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(root.join(".env"), "NEXUS_GREETING=hello from env\n").await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import { devtools } from './devtools';
+if (import.meta.env.DEV) {
+  devtools();
+}
+console.log(import.meta.env.NEXUS_GREETING, __BUILD_ID__);
+"#).await.unwrap();
+    fs::write(src.join("devtools.ts"), "export function devtools() { console.log('devtools attached'); }").await.unwrap();
+
+    let define = BTreeMap::from([("__BUILD_ID__".to_string(), "42".to_string())]);
+    let options = BuildOptions { cache: false, define, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.contains("console.log(\"hello from env\", 42)"));
+    assert!(!main.contains("devtools attached"), "unused after dead-branch elimination");
+
+    let options = BuildOptions { cache: false, mode: "development".to_string(), ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.contains("devtools attached"));
+}
@@ -0,0 +1,52 @@
+use futures::StreamExt;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_tungstenite::tungstenite::Message;
+
+const PORT: u16 = 4391;
+
+async fn setup(name: &str) -> PathBuf {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures").join(name);
+    let _ = fs::remove_dir_all(&root).await;
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(root.join("index.html"), r#"<script type="module" src="/src/main.ts"></script>"#).await.unwrap();
+    fs::write(src.join("main.ts"), "import { greet } from './greet';\nconsole.log(greet('x'));").await.unwrap();
+    fs::write(src.join("greet.ts"), "export const greet = (n: string) => 'hi ' + n;").await.unwrap();
+    root
+}
+
+async fn get(path: &str) -> String {
+    let mut stream = TcpStream::connect(("127.0.0.1", PORT)).await.unwrap();
+    let request = format!("GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n", path);
+    stream.write_all(request.as_bytes()).await.unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    response
+}
+
+#[tokio::test]
+async fn test_ws_sends_updates() {
+    let root = setup("dev_server_ws_app").await;
+    tokio::spawn(nexus_core::start_server(root.to_str().unwrap().to_string(), PORT));
+    for _ in 0..100 {
+        if TcpStream::connect(("127.0.0.1", PORT)).await.is_ok() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(50)).await;
+    }
+    assert!(get("/src/main.ts").await.starts_with("HTTP/1.1 200"));
+
+    let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://127.0.0.1:{}/ws", PORT)).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+    fs::write(root.join("src/greet.ts"), "export const greet = (n: string) => 'hello ' + n;").await.unwrap();
+
+    let message = tokio::time::timeout(Duration::from_secs(10), socket.next()).await.expect("an update within 10s");
+    let Some(Ok(Message::Text(text))) = message else { panic!("expected a text message, got {:?}", message) };
+    let update: serde_json::Value = serde_json::from_str(&text).unwrap();
+    assert_eq!(update["type"], "update");
+    assert!(update["paths"].as_array().unwrap().iter().any(|path| path == "/src/main.ts"), "{}", text);
+}
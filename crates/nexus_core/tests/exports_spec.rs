@@ -0,0 +1,92 @@
+use nexus_core::bundler;
+use nexus_core::exports::{ExportOrigin, ExportTable};
+use nexus_core::shaker;
+use std::collections::HashMap;
+use tokio::fs;
+
+fn imports(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+fn table() -> ExportTable {
+    let mut table = ExportTable::new();
+    let modules = [
+        ("/a.js", "export const a = 1; export default 'A';", vec![]),
+        ("/b.js", "export const b = 2;", vec![]),
+        ("/c.js", "import { b } from './b'; export { b as fromC };", vec![("./b", "/b.js")]),
+        (
+            "/barrel.js",
+            "export { a as renamed } from './a'; export * from './b'; export * from './c'; export * as nsA from './a';",
+            vec![("./a", "/a.js"), ("./b", "/b.js"), ("./c", "/c.js")],
+        ),
+    ];
+    for (id, source, deps) in modules {
+        let info = shaker::analyze(source, id);
+        table.add_module(id, &info, &imports(&deps));
+    }
+    table
+}
+
+#[test]
+fn test_resolve_renamed_reexport() {
+    let table = table();
+    assert_eq!(
+        table.resolve("/barrel.js", "renamed"),
+        Some(ExportOrigin::Local { module: "/a.js".to_string(), name: "a".to_string() })
+    );
+    assert_eq!(table.resolve("/barrel.js", "a"), None, "renamed export is not visible under its old name");
+}
+
+#[test]
+fn test_resolve_through_star_and_imported_binding() {
+    let table = table();
+    assert_eq!(
+        table.resolve("/barrel.js", "b"),
+        Some(ExportOrigin::Local { module: "/b.js".to_string(), name: "b".to_string() })
+    );
+    assert_eq!(
+        table.resolve("/barrel.js", "fromC"),
+        Some(ExportOrigin::Local { module: "/b.js".to_string(), name: "b".to_string() })
+    );
+    assert_eq!(table.resolve("/barrel.js", "nsA"), Some(ExportOrigin::Namespace { module: "/a.js".to_string() }));
+    assert_eq!(table.star_provider("/barrel.js", "fromC"), Some("/c.js"));
+    assert_eq!(table.resolve("/barrel.js", "default"), None, "export * skips default");
+}
+
+#[test]
+fn test_all_exports() {
+    let table = table();
+    let names: Vec<String> = table.all_exports("/barrel.js").into_iter().collect();
+    assert_eq!(names, vec!["b", "fromC", "nsA", "renamed"]);
+}
+
+#[tokio::test]
+async fn test_namespace_import_keeps_star_exports() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/namespace_app");
+    let dist = root.join("dist");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+
+    fs::write(src.join("index.tsx"), r#"
+import * as icons from './icons';
+import { plain } from './plain';
+Object.values(icons).forEach(icon => console.log(icon));
+console.log(plain);
+"#).await.unwrap();
+    fs::write(src.join("icons.ts"), "export * from './arrows';\nexport * from './shapes';\n").await.unwrap();
+    fs::write(src.join("arrows.ts"), "export const ArrowLeft = 'ARROW_LEFT';").await.unwrap();
+    fs::write(src.join("shapes.ts"), "export const Circle = 'CIRCLE_SHAPE';").await.unwrap();
+    fs::write(src.join("plain.ts"), r#"
+export * from './arrows';
+export const plain = 'PLAIN';
+export { Circle as Round } from './shapes';
+"#).await.unwrap();
+
+    let res = bundler::build(root.to_str().unwrap()).await;
+    assert!(res.is_ok(), "Build failed");
+
+    let main = fs::read_to_string(dist.join("assets/main.js")).await.unwrap();
+    assert!(main.contains("ARROW_LEFT"), "namespace import must keep exports behind export *");
+    assert!(main.contains("CIRCLE_SHAPE"));
+    assert!(!main.contains("Round"), "unused renamed re-export should be dropped");
+}
@@ -0,0 +1,101 @@
+use nexus_core::graph::{Fingerprint, ModuleGraph};
+use std::path::Path;
+
+fn project(name: &str) -> std::path::PathBuf {
+    let root = std::env::temp_dir().join("nexus_tests").join(name);
+    let _ = std::fs::remove_dir_all(&root);
+    std::fs::create_dir_all(root.join("src")).unwrap();
+    root
+}
+
+fn compiled(graph: &mut ModuleGraph, root: &Path, path: &str, source: &str) {
+    let file = root.join(path.trim_start_matches('/'));
+    std::fs::write(&file, source).unwrap();
+    let id = graph.find_by_path(path).unwrap_or_else(|| graph.add_module(path, ""));
+    graph.update_compiled(id, source, None);
+    graph.set_fingerprint(id, Fingerprint::of(&file, source.as_bytes()));
+}
+
+#[test]
+fn test_graph_roundtrip() {
+    let root = project("graph_roundtrip");
+    let mut graph = ModuleGraph::new();
+    compiled(&mut graph, &root, "/src/main.js", "import './lib.js';");
+    compiled(&mut graph, &root, "/src/lib.js", "export const x = 1;");
+    let main = graph.find_by_path("/src/main.js").unwrap();
+    let lib = graph.find_by_path("/src/lib.js").unwrap();
+    graph.add_dependency(main, lib, false).unwrap();
+    graph.set_imports(main, [("./lib.js".to_string(), "/src/lib.js".to_string())].into());
+
+    let file = root.join(".nexus/graph");
+    graph.save(&file, "dev:1").unwrap();
+    let mut restored = ModuleGraph::load(&file, "dev:1").expect("graph restores");
+
+    assert_eq!(restored.modules.len(), 2);
+    assert_eq!(restored.get_dependencies(main).unwrap(), &vec![lib]);
+    assert_eq!(restored.get_dependents(lib).unwrap(), &vec![main]);
+    assert_eq!(restored.modules[main.0].imports["./lib.js"], "/src/lib.js");
+    assert_eq!(restored.get_version(lib), graph.get_version(lib));
+    assert!(restored.validate(&root).is_empty(), "nothing changed on disk");
+    assert!(ModuleGraph::load(&file, "dev:2").is_none(), "compiled under another configuration");
+}
+
+#[test]
+fn test_validate_finds_changed_and_deleted_modules() {
+    let root = project("graph_validate");
+    let mut graph = ModuleGraph::new();
+    compiled(&mut graph, &root, "/src/a.js", "export const a = 1;");
+    compiled(&mut graph, &root, "/src/b.js", "export const b = 1;");
+    compiled(&mut graph, &root, "/src/c.js", "export const c = 1;");
+    let placeholder = graph.add_module("/src/never_compiled.js", "");
+
+    std::thread::sleep(std::time::Duration::from_millis(20));
+    std::fs::write(root.join("src/a.js"), "export const a = 2;").unwrap();
+    // Touched but identical content is not a change.
+    std::fs::write(root.join("src/b.js"), "export const b = 1;").unwrap();
+    std::fs::remove_file(root.join("src/c.js")).unwrap();
+
+    let a = graph.find_by_path("/src/a.js").unwrap();
+    let b = graph.find_by_path("/src/b.js").unwrap();
+    let c = graph.find_by_path("/src/c.js").unwrap();
+    let mut stale = graph.validate(&root);
+    stale.sort_by_key(|id| id.0);
+    assert_eq!(stale, vec![a, c]);
+    assert!(graph.modules[a.0].fingerprint.is_none());
+    assert!(graph.modules[b.0].fingerprint.is_some());
+    assert!(graph.modules[c.0].source.is_empty());
+    assert!(graph.modules[placeholder.0].fingerprint.is_none());
+
+    // A second pass only hashes files whose mtime moved; b is settled now.
+    assert_eq!(graph.validate(&root), Vec::new());
+}
+
+#[test]
+fn test_load_rejects_missing_or_corrupt_graph() {
+    let root = project("graph_corrupt");
+    let file = root.join(".nexus/graph");
+    assert!(ModuleGraph::load(&file, "dev").is_none());
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(&file, "{ not json").unwrap();
+    assert!(ModuleGraph::load(&file, "dev").is_none());
+}
+
+#[test]
+fn test_revision_tracks_unsaved_changes() {
+    let root = project("graph_revision");
+    let mut graph = ModuleGraph::new();
+    compiled(&mut graph, &root, "/src/main.js", "import './lib.js';");
+    let saved = graph.revision();
+    assert_eq!(graph.revision(), saved, "reading changes nothing");
+
+    compiled(&mut graph, &root, "/src/lib.js", "export const x = 1;");
+    let added = graph.revision();
+    assert!(added > saved, "a new module");
+    let main = graph.find_by_path("/src/main.js").unwrap();
+    let lib = graph.find_by_path("/src/lib.js").unwrap();
+    graph.add_dependency(main, lib, true).unwrap();
+    assert!(graph.revision() > added, "a new edge");
+    let linked = graph.revision();
+    compiled(&mut graph, &root, "/src/lib.js", "export const x = 2;");
+    assert!(graph.revision() > linked, "a recompiled module");
+}
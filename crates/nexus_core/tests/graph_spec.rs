@@ -10,7 +10,7 @@ fn test_graph_topology_and_invalidation() {
     let lib_id = graph.add_module("lib.js", "export const x = 1;");
 
     // 3. Add dependency: main -> lib
-    let res = graph.add_dependency(main_id, lib_id);
+    let res = graph.add_dependency(main_id, lib_id, false);
     assert!(res.is_ok(), "Failed to add dependency");
 
     // 4. Assert: main depends on lib, lib has main as dependent
@@ -35,3 +35,22 @@ fn test_graph_topology_and_invalidation() {
     let dependents_lib_after = graph.get_dependents(lib_id).expect("Should have incoming edges");
     assert!(dependents_lib_after.contains(&main_id), "lib should still have main as dependent");
 }
+
+#[test]
+fn test_reachable_follows_dynamic_imports() {
+    let mut graph = ModuleGraph::new();
+    let main = graph.add_module("/src/main.js", "");
+    let util = graph.add_module("/src/util.js", "");
+    let page = graph.add_module("/src/pages/a.js", "");
+    let page_dep = graph.add_module("/src/pages/a.css", "");
+    let unrelated = graph.add_module("/src/other.js", "");
+    graph.add_dependency(main, util, false).unwrap();
+    graph.add_dependency(main, page, true).unwrap();
+    graph.add_dependency(page, page_dep, false).unwrap();
+    graph.add_dependency(page, util, false).unwrap();
+
+    assert_eq!(graph.linearize(main), vec![util, main], "a static bundle leaves lazy modules out");
+    let reachable = graph.reachable(main);
+    assert_eq!(reachable, vec![util, page_dep, page, main], "dependencies first, each once");
+    assert!(!reachable.contains(&unrelated));
+}
@@ -12,8 +12,8 @@ fn test_find_affected_roots_simple() {
     let lib_id = graph.add_module("src/lib.js", "import './utils.js';");
     let main_id = graph.add_module("src/main.js", "import './lib.js';");
 
-    graph.add_dependency(main_id, lib_id).unwrap();
-    graph.add_dependency(lib_id, utils_id).unwrap();
+    graph.add_dependency(main_id, lib_id, false).unwrap();
+    graph.add_dependency(lib_id, utils_id, false).unwrap();
 
     // Case 1: Change Utils. Who is the root?
     // Reverse traversal: Utils -> Lib -> Main. Main has no incoming (root).
@@ -45,8 +45,8 @@ fn test_hmr_multiple_roots() {
     let app1_id = graph.add_module("app1.js", "");
     let app2_id = graph.add_module("app2.js", "");
     
-    graph.add_dependency(app1_id, shared_id).unwrap();
-    graph.add_dependency(app2_id, shared_id).unwrap();
+    graph.add_dependency(app1_id, shared_id, false).unwrap();
+    graph.add_dependency(app2_id, shared_id, false).unwrap();
     
     // Change Shared
     let roots = graph.find_affected_roots(shared_id);
@@ -83,12 +83,12 @@ fn test_hmr_circular() {
      let a_id = graph.add_module("a.js", "");
      let b_id = graph.add_module("b.js", "");
      
-     graph.add_dependency(a_id, b_id).unwrap();
-     graph.add_dependency(b_id, a_id).unwrap();
+     graph.add_dependency(a_id, b_id, false).unwrap();
+     graph.add_dependency(b_id, a_id, false).unwrap();
      
      // Case: Main -> A <-> B
      let main_id = graph.add_module("main.js", "");
-     graph.add_dependency(main_id, a_id).unwrap();
+     graph.add_dependency(main_id, a_id, false).unwrap();
      
      let roots = graph.find_affected_roots(b_id); // Change B
      // B -> A -> Main. Main is root.
@@ -0,0 +1,84 @@
+use nexus_core::bundler::{BuildOptions, BuildSession};
+use nexus_core::import_meta::glob_match;
+use nexus_core::unit::CompilationUnit;
+use std::path::{Path, PathBuf};
+
+fn write(root: &Path, path: &str, content: &str) {
+    let file = root.join(path);
+    std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+    std::fs::write(file, content).unwrap();
+}
+
+fn setup(root: PathBuf) -> PathBuf {
+    let _ = std::fs::remove_dir_all(&root);
+    write(&root, "src/pages/home.tsx", "export default function Home() { return 'home'; }");
+    write(&root, "src/pages/about.tsx", "export default function About() { return 'about'; }");
+    write(&root, "src/pages/_draft.tsx", "export default 'draft';");
+    write(&root, "src/pages/nested/deep.tsx", "export default 'deep';");
+    write(&root, "src/content/intro.md", "# Intro\n");
+    write(&root, "src/logo.png", &"x".repeat(64));
+    root
+}
+
+#[test]
+fn test_glob_patterns() {
+    assert!(glob_match("/src/pages/*.tsx", "/src/pages/home.tsx"));
+    assert!(!glob_match("/src/pages/*.tsx", "/src/pages/nested/deep.tsx"));
+    assert!(glob_match("/src/**/*.tsx", "/src/pages/nested/deep.tsx"));
+    assert!(glob_match("/src/**/*.tsx", "/src/app.tsx"));
+    assert!(glob_match("/src/*.{ts,tsx}", "/src/app.ts"));
+    assert!(glob_match("/src/pages/?ome.tsx", "/src/pages/home.tsx"));
+    assert!(!glob_match("/src/*.{ts,tsx}", "/src/app.js"));
+}
+
+#[test]
+fn test_glob_expands_to_imports() {
+    let root = setup(std::env::temp_dir().join("nexus_tests").join("import_meta_expand"));
+    let mut unit = CompilationUnit::parse("/src/routes.ts", r#"
+export const pages = import.meta.glob(['./pages/*.tsx', '!./pages/_*.tsx']);
+export const eager = import.meta.glob('/src/pages/**/*.tsx', { eager: true, import: 'default' });
+export const docs = import.meta.glob('./content/*.md', { as: 'raw', eager: true });
+export const here = import.meta.url;
+"#.to_string());
+    let output = unit.expand_import_meta(&root, false);
+    let code = unit.code();
+
+    assert_eq!(output.globs, vec!["/src/pages/*.tsx", "/src/pages/**/*.tsx", "/src/content/*.md"]);
+    assert!(code.contains("\"./pages/about.tsx\": () => import(\"./pages/about.tsx\")"));
+    assert!(!code.contains("\"./pages/_draft.tsx\":"), "negated patterns are excluded");
+    assert!(code.contains("import { default as __nexus_glob_1_2 } from \"./pages/home.tsx\""));
+    assert!(code.contains("\"/src/pages/nested/deep.tsx\": __nexus_glob_1_3"), "absolute patterns keep absolute keys");
+    assert!(code.contains("\"./content/intro.md\": \"# Intro\\n\""));
+    assert!(code.contains("new URL(\"/src/routes.ts\", self.location.href).href"));
+
+    let deps: Vec<String> = unit.dependencies().into_iter().map(|(s, _)| s).collect();
+    assert!(deps.contains(&"./pages/nested/deep.tsx".to_string()));
+    assert!(!deps.iter().any(|d| d.ends_with(".md")), "raw globs don't import");
+}
+
+#[tokio::test]
+async fn test_build_emits_url_assets_and_follows_new_glob_matches() {
+    let root = setup(std::env::current_dir().unwrap().join("tests/fixtures/import_meta_app"));
+    write(&root, "src/index.tsx", r#"
+const pages = import.meta.glob('./pages/*.tsx');
+const logo = new URL('./logo.png', import.meta.url);
+console.log(Object.keys(pages), logo.href);
+"#);
+
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    let mut session = BuildSession::new(root.to_str().unwrap(), &options).await.unwrap();
+    session.build().await.unwrap();
+    let assets = root.join("dist/assets");
+    let main = std::fs::read_to_string(assets.join("main.js")).unwrap();
+    assert!(assets.join("chunk-src-pages-home.tsx.js").exists(), "lazy glob entries are split");
+    let logo = std::fs::read_dir(&assets)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .find(|name| name.starts_with("logo-") && name.ends_with(".png"))
+        .expect("hashed asset emitted");
+    assert!(main.contains(&format!("new URL(\"/assets/{}\", self.location.href)", logo)));
+
+    write(&root, "src/pages/contact.tsx", "export default function Contact() { return 'contact'; }");
+    session.rebuild(&[root.join("src/pages/contact.tsx")]).await.unwrap();
+    assert!(assets.join("chunk-src-pages-contact.tsx.js").exists(), "new match is picked up on rebuild");
+}
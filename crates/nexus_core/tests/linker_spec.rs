@@ -27,7 +27,7 @@ fn test_cjs_transform_imports() {
     
     let result = transform_cjs(source, "test.js", &imports);
     
-    assert!(result.contains("const x = require(\"/src/utils.js\").default;"));
+    assert!(result.contains("const x = (function(m) { return m && m.__esModule ? m.default : m; })(require(\"/src/utils.js\"));"));
     assert!(result.contains("const y = require(\"/src/other.js\").y;"));
 }
 
@@ -87,3 +87,21 @@ fn test_runtime_bootstrap_presence() {
     // 3. module.id
     assert!(NEXUS_RUNTIME_JS.contains("id: id,"));
 }
+
+#[test]
+fn test_cjs_transform_reexports() {
+    let source = "export { a as b } from './a'; export * from './c'; export * as ns from './d';";
+    let mut imports = HashMap::new();
+    imports.insert("./a".to_string(), "/src/a.js".to_string());
+    imports.insert("./c".to_string(), "/src/c.js".to_string());
+    imports.insert("./d".to_string(), "/src/d.js".to_string());
+
+    let result = transform_cjs(source, "test.js", &imports);
+
+    assert!(!result.contains("export "), "no ESM syntax may survive: {}", result);
+    assert!(result.contains("require(\"/src/a.js\")"));
+    assert!(result.contains(".a; } });"), "renamed re-export reads the original name");
+    assert!(result.contains("Object.defineProperty(exports, \"b\""));
+    assert!(result.contains("(require(\"/src/c.js\"));"));
+    assert!(result.contains("Object.defineProperty(exports, \"ns\""));
+}
@@ -0,0 +1,71 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::loader::{Loader, LoaderRegistry};
+use std::collections::BTreeMap;
+use tokio::fs;
+
+fn rules(entries: &[(&str, &str)]) -> BTreeMap<String, Loader> {
+    entries.iter().map(|(key, name)| (key.to_string(), name.parse().unwrap())).collect()
+}
+
+#[test]
+fn test_registry_lookup() {
+    let defaults = LoaderRegistry::default();
+    assert_eq!(defaults.get("/src/App.tsx"), Some(Loader::Tsx));
+    assert_eq!(defaults.get("/src/logo.png?inline"), Some(Loader::Asset));
+    assert_eq!(defaults.get("/src/shader.glsl"), None);
+    assert_eq!(defaults.loader("/src/shader.glsl"), Loader::Js, "unknown files are parsed as JS");
+    assert_eq!(defaults.cache_key(), "");
+
+    let registry = LoaderRegistry::new(&rules(&[(".glsl", "text"), ("md", "text"), ("json", "file"), ("src/legal/**", "copy")]));
+    assert_eq!(registry.get("/src/shader.glsl"), Some(Loader::Text));
+    assert_eq!(registry.get("/src/README.md"), Some(Loader::Text));
+    assert_eq!(registry.get("/src/data.json"), Some(Loader::File), "project rules override the defaults");
+    assert_eq!(registry.get("/src/legal/LICENSE.md"), Some(Loader::Copy), "globs come before extensions");
+    assert_eq!(registry.cache_key(), ":/src/legal/**=copy:glsl=text:json=file:md=text");
+
+    let names = LoaderRegistry::new(&rules(&[("*.frag", "text"), ("**/fixtures/*.json", "copy")]));
+    assert_eq!(names.get("/shader.frag"), Some(Loader::Text));
+    assert_eq!(names.get("/src/gl/shaders/light.frag"), Some(Loader::Text), "`*.frag` matches in any directory");
+    assert_eq!(names.get("/src/gl/shaders/light.frag.ts"), Some(Loader::Ts));
+    assert_eq!(names.get("/test/fixtures/user.json"), Some(Loader::Copy));
+    assert_eq!(names.get("/src/user.json"), Some(Loader::Json));
+    assert_eq!(names.cache_key(), ":/**/fixtures/*.json=copy:/**/*.frag=text");
+
+    let err = "yaml".parse::<Loader>().unwrap_err();
+    assert!(err.to_string().starts_with("unknown loader `yaml`, expected one of js, jsx, ts"));
+}
+
+#[tokio::test]
+async fn test_build_uses_project_loaders() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/loader_app");
+    let src = root.join("src");
+    let _ = fs::remove_dir_all(&root).await;
+    fs::create_dir_all(src.join("legal")).await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import shader from './shader.glsl';
+import key from './key.bin';
+import license from './legal/LICENSE.txt';
+import mock from './mock.css';
+import { double } from './math.es';
+console.log(shader, key, license, mock, double(2));
+"#).await.unwrap();
+    fs::write(src.join("shader.glsl"), "void main() {}").await.unwrap();
+    fs::write(src.join("key.bin"), [0u8, 1, 2]).await.unwrap();
+    fs::write(src.join("legal/LICENSE.txt"), "MIT").await.unwrap();
+    fs::write(src.join("mock.css"), "body { color: red; }").await.unwrap();
+    fs::write(src.join("math.es"), "export function double(n: number): number { return n * 2; }").await.unwrap();
+
+    let loader = rules(&[(".glsl", "text"), (".bin", "base64"), ("src/legal/**", "copy"), (".css", "empty"), (".es", "ts")]);
+    let options = BuildOptions { cache: false, loader, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+
+    let dist = root.join("dist");
+    let main = fs::read_to_string(dist.join("assets/main.js")).await.unwrap();
+    assert!(main.contains("exports.default = \"void main() {}\";"));
+    assert!(main.contains("exports.default = \"AAEC\";"));
+    assert!(main.contains("exports.default = \"/assets/LICENSE.txt\";"));
+    assert!(main.contains("function double(n) {\n\treturn n * 2;\n}\nObject.defineProperty(exports, \"double\""), "`.es` compiled as TypeScript");
+    assert_eq!(fs::read_to_string(dist.join("assets/LICENSE.txt")).await.unwrap(), "MIT");
+    let css = fs::read_to_string(dist.join("assets/style.css")).await.unwrap_or_default();
+    assert!(!css.contains("red") && main.contains("exports.default = undefined;"), "`.css` emptied");
+}
@@ -0,0 +1,130 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::loader::{Loader, LoaderRegistry};
+use nexus_core::mdx::compile_mdx;
+use tokio::fs;
+
+const GUIDE: &str = r#"---
+title: Getting started
+draft: false
+order: 3
+tags: [guide, "setup"]
+author:
+  name: Ada
+  links:
+    - https://ada.dev
+summary: >
+  Two lines,
+  folded.
+---
+import { Chart } from './Chart';
+export const meta = { section: 'docs' };
+
+# Getting *started*
+
+Run `npm i nexus`, then read the [docs](https://nexus.dev "Docs").
+
+## Install
+
+- one
+- two **bold**
+  - nested
+- [x] done
+
+## Install
+
+```ts
+const ok: boolean = 1 < 2;
+```
+
+| Name | Size |
+|:-----|-----:|
+| a | 1 |
+
+<Chart title="Sizes" />
+
+<Callout>
+  Be **careful** with {meta.section}.
+</Callout>
+"#;
+
+#[test]
+fn test_mdx_compiles_to_jsx() {
+    let code = compile_mdx(GUIDE.as_bytes(), "/docs/guide.mdx", false).code;
+    assert!(code.contains("import { Chart } from \"./Chart\";"), "{}", code);
+    assert!(code.contains("export const meta = { section: \"docs\" };"), "{}", code);
+    assert!(code.contains("<_components.h1 id={\"getting-started\"}>{\"Getting \"}<_components.em>{\"started\"}</_components.em></_components.h1>"), "{}", code);
+    assert!(code.contains("<_components.code>{\"npm i nexus\"}</_components.code>"), "{}", code);
+    assert!(code.contains("<_components.a href={\"https://nexus.dev\"} title={\"Docs\"}>{\"docs\"}</_components.a>"), "{}", code);
+    assert!(code.contains("<_components.li>{\"two \"}<_components.strong>{\"bold\"}</_components.strong><_components.ul><_components.li>{\"nested\"}</_components.li></_components.ul></_components.li>"), "tight lists skip <p>: {}", code);
+    assert!(code.contains("<_components.input type=\"checkbox\" checked={true} disabled />{\"done\"}"), "{}", code);
+    assert!(code.contains("<_components.code className={\"language-ts\"}>{\"const ok: boolean = 1 < 2;\\n\"}</_components.code>"), "{}", code);
+    assert!(code.contains("<_components.th style={{ textAlign: \"right\" }}>{\"Size\"}</_components.th>"), "{}", code);
+    assert!(code.contains("<Chart title=\"Sizes\" /><Callout>"), "JSX passes through: {}", code);
+    assert!(code.contains("<_components.strong>{\"careful\"}</_components.strong>{\" with \"}{meta.section}"), "{}", code);
+    assert!(code.contains("...props.components"), "{}", code);
+    assert!(code.contains("export default function MDXContent(props = {}) {\n\treturn _createMdxContent(props);"), "{}", code);
+}
+
+#[test]
+fn test_frontmatter_and_toc() {
+    let code = compile_mdx(GUIDE.as_bytes(), "/docs/guide.mdx", false).code;
+    let frontmatter = code.split("export const frontmatter = ").nth(1).unwrap().split(";\n").next().unwrap();
+    let frontmatter: serde_json::Value = serde_json::from_str(frontmatter).unwrap();
+    assert_eq!(
+        frontmatter,
+        serde_json::json!({
+            "title": "Getting started",
+            "draft": false,
+            "order": 3,
+            "tags": ["guide", "setup"],
+            "author": { "name": "Ada", "links": ["https://ada.dev"] },
+            "summary": "Two lines, folded.\n",
+        })
+    );
+    let toc = code.split("export const toc = ").nth(1).unwrap().split(";\n").next().unwrap();
+    let toc: serde_json::Value = serde_json::from_str(toc).unwrap();
+    assert_eq!(
+        toc,
+        serde_json::json!([
+            { "depth": 1, "text": "Getting started", "id": "getting-started" },
+            { "depth": 2, "text": "Install", "id": "install" },
+            { "depth": 2, "text": "Install", "id": "install-1" },
+        ]),
+        "repeated headings get numbered ids"
+    );
+}
+
+#[test]
+fn test_plain_markdown() {
+    assert_eq!(LoaderRegistry::default().get("/README.md"), Some(Loader::Mdx));
+    let source = "# Readme\n\nimport x from 'y'\n\nBraces {stay} and <b>tags</b> too.\n\n<div align=\"center\">\n  <img src=\"logo.png\">\n</div>\n";
+    let code = compile_mdx(source.as_bytes(), "/README.md", false).code;
+    assert!(code.contains("export const frontmatter = {};"), "{}", code);
+    assert!(code.contains("<_components.p>{\"import x from 'y'\"}</_components.p>"), "no ESM in Markdown: {}", code);
+    assert!(code.contains("{\"Braces {stay} and <b>tags</b> too.\"}"), "{}", code);
+    assert!(code.contains("<div dangerouslySetInnerHTML={{ __html: \"<div align=\\\"center\\\">\\n<img src=\\\"logo.png\\\">\\n</div>\" }} />"), "{}", code);
+}
+
+#[tokio::test]
+async fn test_build_docs_app() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/mdx_app");
+    let src = root.join("src");
+    let _ = fs::remove_dir_all(&root).await;
+    fs::create_dir_all(&src).await.unwrap();
+    fs::create_dir_all(root.join("node_modules/react")).await.unwrap();
+    fs::write(root.join("node_modules/react/package.json"), r#"{ "name": "react", "main": "index.js" }"#).await.unwrap();
+    fs::write(root.join("node_modules/react/index.js"), "exports.createElement = function() { return 'react-element'; };").await.unwrap();
+    fs::write(src.join("index.tsx"), "import Guide, { toc, frontmatter } from './guide.mdx';\nconsole.log(frontmatter.title, toc.length, Guide);\n").await.unwrap();
+    fs::write(src.join("guide.mdx"), "---\ntitle: Guide\n---\nimport { Chart } from './Chart';\n\n# Guide\n\n<Chart />\n").await.unwrap();
+    fs::write(src.join("Chart.tsx"), "import * as React from 'react';\nexport function Chart(): string { return React.createElement('svg'); }\n").await.unwrap();
+
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.contains("__nexus_register__(\"/src/guide.mdx\""));
+    assert!(main.contains("__nexus_register__(\"/src/Chart.tsx\""), "imports inside documents are followed");
+    assert!(main.contains("\"title\": \"Guide\""), "{}", main);
+    assert!(main.contains("\"id\": \"guide\""), "{}", main);
+    let vendor = fs::read_to_string(root.join("dist/assets/vendor.js")).await.unwrap();
+    assert!(vendor.contains("react-element"));
+}
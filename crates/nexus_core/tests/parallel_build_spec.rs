@@ -0,0 +1,79 @@
+use nexus_core::bundler::{self, BuildOptions};
+use tokio::fs;
+
+async fn setup_app(name: &str) -> std::path::PathBuf {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures").join(name);
+    let src = root.join("src");
+    fs::create_dir_all(src.join("shared")).await.unwrap();
+
+    let mut entry = String::new();
+    for i in 0..12 {
+        fs::write(src.join(format!("page_{}.css", i)), format!(".page-{i} {{ order: {i}; }}\n")).await.unwrap();
+        fs::write(
+            src.join(format!("page_{}.ts", i)),
+            format!(
+                "import {{ helper }} from './shared';\nimport './page_{i}.css';\nexport const page_{i} = helper({i});\nexport const title_{i} = 'Page {i}';\nexport const path_{i} = '/{i}';\nexport const meta_{i} = {{ order: {i} }};\n"
+            ),
+        )
+        .await
+        .unwrap();
+        entry.push_str(&format!(
+            "import {{ page_{i}, title_{i}, path_{i}, meta_{i} }} from './page_{i}';\nconsole.log(page_{i}, title_{i}, path_{i}, meta_{i});\n"
+        ));
+    }
+    for i in 0..4 {
+        entry.push_str(&format!("import('./lazy_{i}').then(m => m.run());\n"));
+        fs::write(src.join(format!("lazy_{i}.ts")), format!("import {{ helper }} from './shared';\nexport function run() {{ return helper({i}); }}"))
+            .await
+            .unwrap();
+    }
+    fs::write(src.join("index.tsx"), entry).await.unwrap();
+    fs::write(src.join("shared/index.ts"), "export function helper(x) { return x * 2; }").await.unwrap();
+    root
+}
+
+struct Output {
+    main: String,
+    report: String,
+    css: String,
+}
+
+async fn build_output(name: &str, threads: usize) -> Output {
+    let root = setup_app(name).await;
+    let options = BuildOptions { threads: Some(threads), report: true, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    let report = fs::read_to_string(root.join("dist/report.json")).await.unwrap();
+    let css = fs::read_to_string(root.join("dist/assets/style.css")).await.unwrap();
+    Output { main, report, css }
+}
+
+#[tokio::test]
+async fn test_parallel_build_is_deterministic() {
+    let serial = build_output("parallel_app_1", 1).await;
+    let parallel = build_output("parallel_app_4", 4).await;
+
+    assert!(serial.main.contains("page_11"));
+    assert!(serial.main.contains("window.__nexus_chunk_map__"), "the lazy chunks are mapped in main.js");
+    let pages: Vec<_> = (0..12).map(|i| serial.css.find(&format!(".page-{i} ")).expect("page css bundled")).collect();
+    assert!(pages.windows(2).all(|w| w[0] < w[1]), "css follows import order: {}", serial.css);
+    assert_eq!(serial.main, parallel.main, "output must not depend on thread count");
+    assert_eq!(serial.css, parallel.css, "style.css must not depend on thread count");
+    assert_eq!(serial.report, parallel.report);
+
+    let again = build_output("parallel_app_4", 4).await;
+    assert_eq!(parallel.main, again.main, "output must not change between runs");
+    assert_eq!(parallel.css, again.css);
+    assert_eq!(parallel.report, again.report);
+}
+
+#[tokio::test]
+async fn test_shared_module_discovered_once() {
+    let root = setup_app("parallel_shared_app").await;
+    let options = BuildOptions { threads: Some(4), ..BuildOptions::default() };
+    let report = bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+
+    let shared: Vec<_> = report.modules.iter().filter(|m| m.id == "/src/shared/index.ts").collect();
+    assert_eq!(shared.len(), 1);
+    assert_eq!(shared[0].importers.len(), 16, "every page plus the lazy chunks import it");
+}
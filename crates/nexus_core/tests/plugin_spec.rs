@@ -0,0 +1,207 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::compiler::CompileResult;
+use nexus_core::plugin::*;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Barrier, Mutex};
+use tokio::fs;
+
+// Appends its name to every module it transforms.
+struct Tag {
+    name: &'static str,
+    order: Order,
+    filter: Filter,
+}
+
+impl Plugin for Tag {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn order(&self) -> Order {
+        self.order
+    }
+
+    fn filters(&self) -> HookFilters {
+        HookFilters { transform: self.filter.clone(), ..HookFilters::default() }
+    }
+
+    fn transform(&self, code: &str, _id: &str) -> HookResult<TransformOutput> {
+        Ok(Some(TransformOutput { code: format!("{}/*{}*/", code, self.name), map: None }))
+    }
+
+    fn handle_hot_update(&self, update: &HotUpdate) -> Option<Vec<String>> {
+        (self.name == "quiet" && update.file.ends_with(".css")).then(Vec::new)
+    }
+}
+
+fn tag(name: &'static str, order: Order, include: &[&str]) -> Arc<dyn Plugin> {
+    Arc::new(Tag { name, order, filter: Filter::include(include) })
+}
+
+#[test]
+fn test_hooks_run_in_order_and_respect_filters() {
+    let plugins = PluginContainer::new(vec![
+        tag("post", Order::Post, &[]),
+        tag("normal", Order::Normal, &[]),
+        tag("pre", Order::Pre, &["**/*.ts"]),
+        tag("styles", Order::Normal, &["**/*.css"]),
+    ]);
+    assert_eq!(format!("{:?}", plugins), "[\"pre\", \"normal\", \"styles\", \"post\"]");
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("a.ts");
+    std::fs::write(&file, "let a = 1;").unwrap();
+    let source = plugins.source("/a.ts", &file).unwrap();
+    assert_eq!(source, b"let a = 1;/*pre*/", "only `Pre` plugins see the source");
+
+    let mut compiled = CompileResult { code: "let a = 1;".into(), sourcemap: Some("{}".into()), css: None, asset: None };
+    assert!(plugins.transform("/a.ts", &mut compiled).unwrap());
+    assert_eq!(compiled.code, "let a = 1;/*normal*//*post*/");
+    assert_eq!(compiled.sourcemap, None);
+
+    let update = HotUpdate { file: "/src/a.css".into(), modules: vec!["/src/a.css".into()] };
+    assert!(PluginContainer::new(vec![tag("quiet", Order::Normal, &[])]).handle_hot_update(update).is_empty());
+}
+
+// Output depends on `banner`, so the cache key does too.
+struct Banner {
+    banner: &'static str,
+}
+
+impl Plugin for Banner {
+    fn name(&self) -> &str {
+        "banner"
+    }
+
+    fn cache_key(&self) -> String {
+        format!("banner:{}", self.banner)
+    }
+}
+
+#[test]
+fn test_cache_key_follows_plugin_options() {
+    let key = |banner| PluginContainer::new(vec![Arc::new(Banner { banner }) as Arc<dyn Plugin>]).cache_key();
+    assert_eq!(key("v1"), ":banner:v1");
+    assert_ne!(key("v1"), key("v2"), "changed options invalidate cached transforms");
+    assert_eq!(PluginContainer::new(vec![tag("plain", Order::Normal, &[])]).cache_key(), ":plain", "the name by default");
+}
+
+// `virtual:build-info` plus the other build hooks, recording what it saw.
+#[derive(Default)]
+struct BuildInfo {
+    parsed: Mutex<Vec<ModuleInfo>>,
+    chunks: Mutex<Vec<String>>,
+}
+
+impl Plugin for BuildInfo {
+    fn name(&self) -> &str {
+        "build-info"
+    }
+
+    fn filters(&self) -> HookFilters {
+        HookFilters {
+            resolve_id: Filter::include(&["virtual:*"]),
+            load: Filter::include(&["/virtual:*"]),
+            transform: Filter { exclude: vec!["**/node_modules/**".into()], ..Filter::default() },
+        }
+    }
+
+    fn resolve_id(&self, specifier: &str, importer: &Path) -> HookResult<PathBuf> {
+        let root = importer.ancestors().find(|dir| dir.ends_with("src")).and_then(Path::parent).unwrap();
+        Ok(Some(root.join(specifier)))
+    }
+
+    fn load(&self, _id: &str, _path: &Path) -> HookResult<Vec<u8>> {
+        Ok(Some(b"export const version = '1.2.3';".to_vec()))
+    }
+
+    fn transform(&self, code: &str, id: &str) -> HookResult<TransformOutput> {
+        if !code.contains("__GREETING__") {
+            return Ok(None);
+        }
+        if id.ends_with("broken.ts") {
+            return Err(std::io::Error::other("unsupported greeting"));
+        }
+        Ok(Some(TransformOutput { code: code.replace("__GREETING__", "\"hello\""), map: None }))
+    }
+
+    fn module_parsed(&self, module: &ModuleInfo) {
+        self.parsed.lock().unwrap().push(module.clone());
+    }
+
+    fn render_chunk(&self, code: &str, chunk: &ChunkInfo) -> HookResult<String> {
+        self.chunks.lock().unwrap().push(chunk.name.clone());
+        Ok(chunk.is_entry.then(|| format!("/* build-info */\n{}", code)))
+    }
+
+    fn generate_bundle(&self, bundle: &mut OutputBundle) -> std::io::Result<()> {
+        let files = bundle.keys().cloned().collect::<Vec<_>>().join("\n");
+        bundle.insert("meta/files.txt".to_string(), files.into_bytes());
+        bundle.remove("assets/style.css");
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_build_goes_through_plugins() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/plugin_app");
+    let src = root.join("src");
+    let _ = fs::remove_dir_all(&root).await;
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import { version } from 'virtual:build-info';
+import { greet } from './greet';
+console.log(version, greet());
+"#).await.unwrap();
+    fs::write(src.join("greet.ts"), "export function greet() { return __GREETING__; }").await.unwrap();
+
+    let plugin = Arc::new(BuildInfo::default());
+    let options = BuildOptions { cache: false, plugins: PluginContainer::new(vec![plugin.clone()]), ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.starts_with("/* build-info */\n"));
+    assert!(main.contains("__nexus_register__(\"/virtual:build-info\""));
+    assert!(main.contains("const version = \"1.2.3\";"));
+    assert!(main.contains("return \"hello\";"));
+    assert!(!root.join("dist/assets/style.css").exists());
+    let files = fs::read_to_string(root.join("dist/meta/files.txt")).await.unwrap();
+    assert!(files.contains("assets/main.js") && files.contains("index.html"));
+
+    let entry = plugin.parsed.lock().unwrap().iter().find(|m| m.id == "/src/index.tsx").cloned().expect("entry parsed");
+    assert_eq!(entry.imported_ids, vec!["/virtual:build-info", "/src/greet.ts"]);
+    assert_eq!(*plugin.chunks.lock().unwrap(), vec!["vendor", "main"]);
+
+    // Errors fail the build and name the plugin.
+    fs::rename(src.join("greet.ts"), src.join("broken.ts")).await.unwrap();
+    fs::write(src.join("index.tsx"), "import './broken';").await.unwrap();
+    let err = bundler::build_with_options(root.to_str().unwrap(), &options).await.unwrap_err();
+    assert_eq!(err.to_string(), "[plugin build-info] transform: unsupported greeting");
+}
+
+#[test]
+fn test_batcher_groups_concurrent_calls() {
+    let batches = Arc::new(AtomicUsize::new(0));
+    let counter = batches.clone();
+    let batcher = Arc::new(Batcher::new("double", move |calls: Vec<usize>| {
+        counter.fetch_add(1, Ordering::SeqCst);
+        std::thread::sleep(std::time::Duration::from_millis(50));
+        calls.into_iter().map(|n| n * 2).collect()
+    }));
+
+    let barrier = Arc::new(Barrier::new(8));
+    let threads = (0..8)
+        .map(|n| {
+            let (batcher, barrier) = (batcher.clone(), barrier.clone());
+            std::thread::spawn(move || {
+                barrier.wait();
+                batcher.call(n).unwrap()
+            })
+        })
+        .collect::<Vec<_>>();
+    let results = threads.into_iter().map(|t| t.join().unwrap()).collect::<Vec<_>>();
+
+    assert_eq!(results, vec![0, 2, 4, 6, 8, 10, 12, 14]);
+    assert!(batches.load(Ordering::SeqCst) < 8, "calls made while a batch runs share the next one");
+}
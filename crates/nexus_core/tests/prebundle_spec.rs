@@ -0,0 +1,131 @@
+use nexus_core::graph::ModuleGraph;
+use nexus_core::prebundle::{self, DepsOptimizer, DEPS_DIR};
+use nexus_core::resolver::NexusResolver;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+fn write(root: &Path, path: &str, content: &str) {
+    let file = root.join(path);
+    fs::create_dir_all(file.parent().unwrap()).unwrap();
+    fs::write(file, content).unwrap();
+}
+
+fn setup(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join("nexus_tests").join(name);
+    let _ = fs::remove_dir_all(&root);
+    write(&root, "package-lock.json", r#"{ "lockfileVersion": 3 }"#);
+    write(&root, "src/main.js", "import { hello } from 'cjs-lib';\nimport { a } from 'esm-lib';\nimport './local.js';\nhello(a);\n");
+    write(&root, "src/local.js", "import data from 'esm-lib/data.json';\nconsole.log(data);\n");
+
+    write(&root, "node_modules/cjs-lib/package.json", r#"{ "name": "cjs-lib", "main": "index.js" }"#);
+    write(&root, "node_modules/cjs-lib/index.js", r#"
+if (process.env.NODE_ENV === 'production') {
+  module.exports = require('./prod.js');
+} else {
+  module.exports = require('./dev.js');
+}
+"#);
+    write(&root, "node_modules/cjs-lib/dev.js", "exports.hello = function (x) { console.log('dev', x); };");
+    write(&root, "node_modules/cjs-lib/prod.js", "exports.hello = function () {};");
+
+    write(&root, "node_modules/esm-lib/package.json", r#"{ "name": "esm-lib", "module": "index.js" }"#);
+    write(&root, "node_modules/esm-lib/index.js", "export * from './a.js';\n");
+    write(&root, "node_modules/esm-lib/a.js", "import { hello } from 'cjs-lib';\nexport const a = 'a';\nhello(a);\n");
+    write(&root, "node_modules/esm-lib/data.json", r#"{ "answer": 42 }"#);
+    root
+}
+
+#[test]
+fn test_scan_finds_bare_imports_across_app_modules() {
+    let root = setup("prebundle_scan");
+    let found = prebundle::scan_dependencies(&root, &NexusResolver::new(&root));
+    let found: Vec<&str> = found.iter().map(String::as_str).collect();
+    assert_eq!(found, vec!["cjs-lib", "esm-lib", "esm-lib/data.json"]);
+}
+
+#[test]
+fn test_dependencies_are_bundled_into_single_modules() {
+    let root = setup("prebundle_bundle");
+    let manifest = prebundle::optimize(&root, &NexusResolver::new(&root)).unwrap();
+    assert_eq!(manifest.get("cjs-lib"), Some("/.nexus/deps/cjs-lib.js"));
+
+    let cjs = fs::read_to_string(root.join(DEPS_DIR).join("cjs-lib.js")).unwrap();
+    assert!(cjs.contains("\"/node_modules/cjs-lib/dev.js\": [function(require, module, exports)"));
+    assert!(!cjs.contains("process.env.NODE_ENV"), "NODE_ENV is replaced");
+    assert!(!cjs.contains("/node_modules/cjs-lib/prod.js"), "the production branch is dropped");
+    assert!(cjs.contains("\"./dev.js\":\"/node_modules/cjs-lib/dev.js\""), "requires are mapped inside the bundle");
+    assert!(cjs.contains("default: __nexus_dep__"), "CommonJS exports get a default");
+
+    let esm = fs::read_to_string(root.join(DEPS_DIR).join("esm-lib.js")).unwrap();
+    assert!(esm.contains("/node_modules/esm-lib/a.js"));
+    assert!(esm.contains("require(\"/.nexus/deps/cjs-lib.js\")"), "other packages stay external");
+    assert!(!esm.contains("/node_modules/cjs-lib/dev.js"), "cjs-lib is not duplicated");
+    let data = manifest.get("esm-lib/data.json").unwrap();
+    assert!(data.starts_with("/.nexus/deps/esm-lib_data.json-"), "{}", data);
+    assert!(prebundle::bundle_path(&root, data).exists());
+}
+
+#[test]
+fn test_bundles_are_reused_until_lockfile_changes() {
+    let root = setup("prebundle_reuse");
+    let resolver = NexusResolver::new(&root);
+    let bundle = root.join(DEPS_DIR).join("cjs-lib.js");
+    let first = prebundle::optimize(&root, &resolver).unwrap();
+    fs::write(&bundle, "// stale").unwrap();
+
+    let second = prebundle::optimize(&root, &resolver).unwrap();
+    assert_eq!(first, second);
+    assert_eq!(fs::read_to_string(&bundle).unwrap(), "// stale", "unchanged lockfile reuses bundles");
+
+    write(&root, "package-lock.json", r#"{ "lockfileVersion": 3, "packages": {} }"#);
+    let third = prebundle::optimize(&root, &resolver).unwrap();
+    assert_ne!(first.lock_hash, third.lock_hash);
+    assert!(fs::read_to_string(&bundle).unwrap().contains("Pre-bundled by nexus"));
+}
+
+#[test]
+fn test_bundle_names_do_not_collide() {
+    let root = setup("prebundle_names");
+    write(&root, "src/main.js", "import 'a/b';\nimport 'a_b';\n");
+    write(&root, "node_modules/a/package.json", r#"{ "name": "a" }"#);
+    write(&root, "node_modules/a/b.js", "window.FROM_A_B = 1;");
+    write(&root, "node_modules/a_b/package.json", r#"{ "name": "a_b" }"#);
+    write(&root, "node_modules/a_b/index.js", "window.FROM_A_UNDERSCORE_B = 1;");
+
+    let manifest = prebundle::optimize(&root, &NexusResolver::new(&root)).unwrap();
+    let (nested, flat) = (manifest.get("a/b").unwrap(), manifest.get("a_b").unwrap());
+    assert_ne!(nested, flat);
+    assert!(fs::read_to_string(prebundle::bundle_path(&root, nested)).unwrap().contains("FROM_A_B"));
+    assert!(fs::read_to_string(prebundle::bundle_path(&root, flat)).unwrap().contains("FROM_A_UNDERSCORE_B"));
+}
+
+#[test]
+fn test_optimizer_follows_new_imports_and_lockfile() {
+    let root = setup("prebundle_refresh");
+    write(&root, "node_modules/late-lib/package.json", r#"{ "name": "late-lib", "main": "index.js" }"#);
+    write(&root, "node_modules/late-lib/index.js", "exports.late = true;");
+    let deps = DepsOptimizer::new(&root, Arc::new(NexusResolver::new(&root)));
+    let main = root.join("src/main.js");
+    assert!(deps.get("late-lib").is_none());
+
+    // The app starts importing a package during the session.
+    write(&root, "src/local.js", "import 'late-lib';\n");
+    assert!(deps.is_missing(&main, "late-lib"));
+    assert!(!deps.is_missing(&main, "cjs-lib"), "already bundled");
+    let graph = RwLock::new(ModuleGraph::new());
+    let cjs_id = deps.get("cjs-lib").unwrap();
+    graph.write().unwrap().add_module(&cjs_id, "// served before");
+    let changed = deps.refresh(&graph, &["late-lib".to_string()]);
+    assert_eq!(deps.get("late-lib").as_deref(), Some("/.nexus/deps/late-lib.js"));
+    assert_eq!(changed, vec![cjs_id.clone()], "bundles in the graph are brought up to date");
+    assert!(graph.read().unwrap().modules[0].source.contains("Pre-bundled by nexus"));
+
+    assert!(deps.refresh(&graph, &[]).is_empty(), "nothing changed since");
+    assert!(deps.is_lockfile(&root.join("package-lock.json")));
+    assert!(!deps.is_lockfile(&root.join("src/package-lock.json")));
+    write(&root, "node_modules/cjs-lib/dev.js", "exports.hello = function () { return 'updated'; };");
+    write(&root, "package-lock.json", r#"{ "lockfileVersion": 3, "packages": { "cjs-lib": {} } }"#);
+    assert_eq!(deps.refresh(&graph, &[]), vec![cjs_id]);
+    assert!(graph.read().unwrap().modules[0].source.contains("updated"));
+}
@@ -0,0 +1,65 @@
+use nexus_core::bundler::{BuildOptions, BuildSession};
+use nexus_core::compiler::{compile_variant, split_query, with_query, AssetQuery};
+use std::path::Path;
+use tokio::fs;
+
+#[test]
+fn test_query_variants() {
+    assert_eq!(split_query("/src/logo.svg?raw"), ("/src/logo.svg", Some(AssetQuery::Raw)));
+    assert_eq!(split_query("/src/worker.ts?worker"), ("/src/worker.ts?worker", None), "other queries aren't variants");
+    assert_eq!(with_query(Path::new("/app/src/a.png"), "./a.png?inline"), Path::new("/app/src/a.png?inline"));
+    assert_eq!(with_query(Path::new("/app/src/a.png"), "./a.png"), Path::new("/app/src/a.png"));
+
+    let svg = b"<svg>\"hi\"</svg>";
+    let raw = compile_variant(svg, "/src/logo.svg", AssetQuery::Raw, true);
+    assert_eq!(raw.code, "export default \"<svg>\\\"hi\\\"</svg>\";");
+    let inline = compile_variant(svg, "/src/logo.svg", AssetQuery::Inline, true);
+    assert!(inline.code.starts_with("export default \"data:image/svg+xml;base64,"));
+    assert!(inline.asset.is_none());
+
+    let url = compile_variant(svg, "/src/logo.svg", AssetQuery::Url, true);
+    let (name, bytes) = url.asset.expect("always emitted");
+    assert!(name.starts_with("assets/logo-") && name.ends_with(".svg"));
+    assert_eq!(bytes, svg);
+    assert_eq!(url.code, format!("export default \"/{}\";", name));
+    let dev = compile_variant(svg, "/src/logo.svg", AssetQuery::Url, false);
+    assert_eq!(dev.code, "export default \"/src/logo.svg?raw\";");
+}
+
+#[tokio::test]
+async fn test_one_file_under_several_variants() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/query_app");
+    let src = root.join("src");
+    let _ = fs::remove_dir_all(&root).await;
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import logo from './logo.svg';
+import logoSource from './logo.svg?raw';
+import logoUrl from './logo.svg?url';
+import notes from './notes.md?raw';
+console.log(logo, logoSource, logoUrl, notes);
+"#).await.unwrap();
+    fs::write(src.join("logo.svg"), "<svg></svg>").await.unwrap();
+    fs::write(src.join("notes.md"), "# Notes").await.unwrap();
+
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    let mut session = BuildSession::new(root.to_str().unwrap(), &options).await.unwrap();
+    session.build().await.unwrap();
+    let assets = root.join("dist/assets");
+    let main = fs::read_to_string(assets.join("main.js")).await.unwrap();
+    assert!(main.contains("(require(\"/src/logo.svg\"))"));
+    assert!(main.contains("__nexus_register__(\"/src/logo.svg?raw\""));
+    assert!(main.contains("exports.default = \"<svg></svg>\";"));
+    assert!(main.contains("exports.default = \"# Notes\";"));
+    let logo = std::fs::read_dir(&assets)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .find(|name| name.starts_with("logo-") && name.ends_with(".svg"))
+        .expect("?url emits the file even when small");
+    assert!(main.contains(&format!("exports.default = \"/assets/{}\";", logo)));
+
+    fs::write(src.join("notes.md"), "# Updated").await.unwrap();
+    session.rebuild(&[src.join("notes.md")]).await.unwrap();
+    let main = fs::read_to_string(assets.join("main.js")).await.unwrap();
+    assert!(main.contains("exports.default = \"# Updated\";"), "variants rebuild with their file");
+}
@@ -1,6 +1,4 @@
 use nexus_core::compiler::compile;
-use nexus_core::parser::transform_cjs;
-use std::collections::HashMap;
 
 // Note: Testing actual React Refresh runtime execution requires a browser or jsdom.
 // Here we verifying:
@@ -24,7 +22,7 @@ fn test_react_refresh_transform_enabled() {
     // Oxc with `development: true` and `refresh: Some(...)` should emit `_s = $RefreshSig$()` etc.
     // Or at least `_source` properties.
     
-    let compiled = compile(source, "src/App.tsx");
+    let compiled = compile(source, "src/App.tsx", false);
     println!("Compiled code:\n{}", compiled.code);
     
     // Oxc Refresh transform usually emits:
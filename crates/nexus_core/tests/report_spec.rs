@@ -0,0 +1,59 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::report::BuildReport;
+use tokio::fs;
+
+async fn setup_app(name: &str) -> std::path::PathBuf {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures").join(name);
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+
+    fs::write(src.join("index.tsx"), r#"
+import { render } from './view';
+import { unusedHelper } from './unused';
+render();
+"#).await.unwrap();
+    fs::write(src.join("view.ts"), r#"
+import { format } from './format';
+export function render() { console.log(format("x")); }
+export function hydrate() { return 1; }
+"#).await.unwrap();
+    fs::write(src.join("format.ts"), "export function format(x) { return '[' + x + ']'; }").await.unwrap();
+    fs::write(src.join("unused.ts"), "export function unusedHelper() {}").await.unwrap();
+    root
+}
+
+#[tokio::test]
+async fn test_report_contents() {
+    let root = setup_app("report_app").await;
+    let options = BuildOptions { report: true, ..BuildOptions::default() };
+    let report = bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+
+    let view = report.module("/src/view.ts").expect("view is live");
+    assert_eq!(view.chunk, "main.js");
+    assert_eq!(view.used_exports, vec!["render"]);
+    assert_eq!(view.removed_exports, vec!["hydrate"]);
+    assert!(view.size_after < view.size_before);
+    assert_eq!(view.importers, vec!["/src/index.tsx"]);
+
+    assert!(report.pruned.contains(&"/src/unused.ts".to_string()));
+
+    // Written to disk as JSON
+    let json = fs::read_to_string(root.join("dist/report.json")).await.unwrap();
+    let parsed: BuildReport = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed.modules.len(), report.modules.len());
+}
+
+#[tokio::test]
+async fn test_why_returns_shortest_path() {
+    let root = setup_app("why_app").await;
+    let report = bundler::build_with_options(root.to_str().unwrap(), &BuildOptions::default())
+        .await
+        .expect("Build failed");
+
+    assert_eq!(
+        report.why("/src/format.ts").unwrap(),
+        ["/src/index.tsx", "/src/view.ts", "/src/format.ts"]
+    );
+    assert!(report.why("/src/unused.ts").is_none(), "pruned modules have no chain");
+    assert!(!root.join("dist/report.json").exists(), "report file is opt-in");
+}
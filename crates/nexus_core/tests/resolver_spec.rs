@@ -1,6 +1,6 @@
 use nexus_core::resolver::NexusResolver;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::PathBuf;
 
 // Helper to create temp workspace
 fn setup_workspace(name: &str) -> PathBuf {
@@ -1,5 +1,4 @@
 use nexus_core::bundler;
-use std::path::{Path, PathBuf};
 use tokio::fs;
 
 #[tokio::test]
@@ -0,0 +1,86 @@
+use nexus_core::bundler;
+use nexus_core::resolver::{NexusResolver, SideEffects};
+use std::fs;
+
+fn setup_workspace(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join("nexus_tests").join(name);
+    if path.exists() {
+        fs::remove_dir_all(&path).unwrap();
+    }
+    fs::create_dir_all(&path).unwrap();
+    path
+}
+
+#[test]
+fn test_side_effects_flag() {
+    let root = setup_workspace("side_effects_flag");
+    let pkg = root.join("node_modules/pure-lib");
+    fs::create_dir_all(pkg.join("lib")).unwrap();
+    fs::write(pkg.join("package.json"), r#"{"main": "lib/index.js", "sideEffects": false}"#).unwrap();
+    fs::write(pkg.join("lib/index.js"), "").unwrap();
+
+    let resolver = NexusResolver::new(&root);
+    let (dir, side_effects) = resolver.package_side_effects(&pkg.join("lib/index.js")).unwrap();
+    assert_eq!(dir, pkg);
+    assert_eq!(side_effects, Some(SideEffects::Flag(false)));
+    assert_eq!(resolver.has_side_effects(&pkg.join("lib/index.js")), Some(false));
+}
+
+#[test]
+fn test_side_effects_globs() {
+    let root = setup_workspace("side_effects_globs");
+    let pkg = root.join("node_modules/styled-lib");
+    fs::create_dir_all(pkg.join("src")).unwrap();
+    fs::write(
+        pkg.join("package.json"),
+        r#"{"sideEffects": ["*.css", "./src/polyfill.js"]}"#,
+    )
+    .unwrap();
+
+    let resolver = NexusResolver::new(&root);
+    assert_eq!(resolver.has_side_effects(&pkg.join("src/theme/button.css")), Some(true));
+    assert_eq!(resolver.has_side_effects(&pkg.join("src/polyfill.js")), Some(true));
+    assert_eq!(resolver.has_side_effects(&pkg.join("src/button.js")), Some(false));
+}
+
+#[test]
+fn test_side_effects_undeclared() {
+    let root = setup_workspace("side_effects_undeclared");
+    fs::write(root.join("package.json"), r#"{"name": "app"}"#).unwrap();
+    let resolver = NexusResolver::new(&root);
+    assert_eq!(resolver.has_side_effects(&root.join("index.js")), None);
+}
+
+#[tokio::test]
+async fn test_barrel_pruning() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/barrel_app");
+    let dist = root.join("dist");
+    let src = root.join("src");
+    let lib = root.join("node_modules/big-ui-lib");
+    tokio::fs::create_dir_all(&src).await.unwrap();
+    tokio::fs::create_dir_all(&lib).await.unwrap();
+
+    tokio::fs::write(src.join("index.tsx"), r#"
+import { Button } from 'big-ui-lib';
+console.log(Button);
+"#).await.unwrap();
+
+    tokio::fs::write(lib.join("package.json"), r#"{"main": "index.js", "sideEffects": false}"#).await.unwrap();
+    tokio::fs::write(lib.join("index.js"), r#"
+export * from './Button';
+export * from './Table';
+export { Chart } from './Chart';
+"#).await.unwrap();
+    tokio::fs::write(lib.join("Button.js"), "export const Button = 'BUTTON_COMPONENT';").await.unwrap();
+    // Effectful code, but the package promises it can be dropped.
+    tokio::fs::write(lib.join("Table.js"), "console.log('TABLE_LOADED'); export const Table = 1;").await.unwrap();
+    tokio::fs::write(lib.join("Chart.js"), "console.log('CHART_LOADED'); export const Chart = 2;").await.unwrap();
+
+    let res = bundler::build(root.to_str().unwrap()).await;
+    assert!(res.is_ok(), "Build failed");
+
+    let vendor = tokio::fs::read_to_string(dist.join("assets/vendor.js")).await.unwrap();
+    assert!(vendor.contains("BUTTON_COMPONENT"));
+    assert!(!vendor.contains("TABLE_LOADED"), "Table.js should be pruned");
+    assert!(!vendor.contains("CHART_LOADED"), "Chart.js should be pruned");
+}
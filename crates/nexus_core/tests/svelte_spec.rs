@@ -0,0 +1,136 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::loader::{Loader, LoaderRegistry};
+use nexus_core::svelte::{SvelteCompiler, SvelteHook, SvelteOutput};
+use std::sync::{Arc, Mutex};
+use tokio::fs;
+
+// Stands in for `svelte/compiler`: a JS script is kept (a TS one dropped), the
+// markup becomes a string, the style gets a scoping class.
+#[derive(Clone, Default)]
+struct StubCompiler {
+    sources: Arc<Mutex<Vec<String>>>,
+}
+
+fn block<'s>(source: &'s str, tag: &str) -> &'s str {
+    let Some(start) = source.find(&format!("<{}", tag)) else { return "" };
+    let open = start + source[start..].find('>').unwrap() + 1;
+    &source[open..open + source[open..].find(&format!("</{}>", tag)).unwrap()]
+}
+
+impl SvelteCompiler for StubCompiler {
+    fn name(&self) -> &str {
+        "svelte@test"
+    }
+
+    fn compile(&self, source: &str, _id: &str, dev: bool) -> std::io::Result<SvelteOutput> {
+        self.sources.lock().unwrap().push(source.to_string());
+        if source.contains("{#if") && !source.contains("{/if}") {
+            return Err(std::io::Error::other("Expected {/if}"));
+        }
+        let markup = source.rsplit("</script>").next().unwrap().split("<style>").next().unwrap().trim();
+        let mut js = format!(
+            "import * as $ from 'svelte/internal/client';\n{}\nfunction Component() {{ return $.text({:?}); }}\n",
+            if source.contains("lang=\"ts\"") { "" } else { block(source, "script") },
+            markup,
+        );
+        if dev {
+            js.push_str("if (import.meta.hot) import.meta.hot.accept((module) => Component.swap(module.default));\n");
+        }
+        js.push_str("export default Component;\n");
+        let css = block(source, "style").replace(".title", ".title.svelte-1x2y3z");
+        Ok(SvelteOutput { js, css: Some(css) })
+    }
+}
+
+#[test]
+fn test_svelte_compiles_for_dev() {
+    let stub = StubCompiler::default();
+    let registry = LoaderRegistry::default().with_svelte(Some(SvelteHook::new(stub.clone())));
+    let source = r#"<script lang="ts">
+  let   count:number=1+1;
+</script>
+<h1 class="title">{count}</h1>
+<style>.title { color: orange; }</style>
+"#;
+    let compiled = registry.compile(Loader::Svelte, source.as_bytes(), "/src/Counter.svelte", false).unwrap().unwrap();
+    let seen = stub.sources.lock().unwrap()[0].clone();
+    assert!(seen.contains("let count = 1 + 1;"), "TS has its types stripped first: {}", seen);
+    assert!(seen.contains("<h1 class=\"title\">{count}</h1>"), "markup is left alone: {}", seen);
+
+    let code = &compiled.code;
+    assert!(!code.contains("import.meta.hot"), "{}", code);
+    assert!(code.contains("__nexus_hot__.accept((module) => Component.swap(module.default))"), "{}", code);
+    assert!(code.contains("__NEXUS_SVELTE__"), "{}", code);
+    assert!(code.contains("nexus-style-") && code.contains(".title.svelte-1x2y3z"), "styles are injected in dev: {}", code);
+    assert!(compiled.css.is_none());
+}
+
+#[test]
+fn test_svelte_typescript_is_stripped() {
+    let stub = StubCompiler::default();
+    let registry = LoaderRegistry::default().with_svelte(Some(SvelteHook::new(stub.clone())));
+    let source = r#"<script lang="ts">
+  import type { User } from './types';
+  import Avatar from './Avatar.svelte';
+  interface Props { user: User }
+  let { user }: Props = $props();
+  let name: string = user.name as string;
+  let initials: string[] = $derived(name.split(' ').map((part: string) => part[0]!));
+</script>
+<Avatar {initials} />
+"#;
+    registry.compile(Loader::Svelte, source.as_bytes(), "/src/Profile.svelte", false).unwrap();
+    let seen = stub.sources.lock().unwrap()[0].clone();
+    let script = block(&seen, "script");
+    assert!(script.contains("let name = user.name;"), "{}", script);
+    assert!(script.contains("let { user } = $props();"), "{}", script);
+    assert!(script.contains("name.split(\" \").map((part) => part[0])"), "{}", script);
+    assert!(!script.contains("./types") && !script.contains("interface"), "type-only code goes: {}", script);
+    assert!(script.contains("import Avatar from \"./Avatar.svelte\";"), "imports the markup uses stay: {}", script);
+}
+
+#[test]
+fn test_compiler_errors_and_cache_key() {
+    let registry = LoaderRegistry::default();
+    assert_eq!(registry.get("/src/App.svelte"), Some(Loader::Svelte));
+    let Err(err) = registry.compile(Loader::Svelte, b"<h1>hi</h1>", "/src/App.svelte", true) else { panic!("compiled without a compiler") };
+    assert!(err.to_string().contains("no Svelte compiler"), "{}", err);
+
+    let registry = registry.with_svelte(Some(SvelteHook::new(StubCompiler::default())));
+    assert_eq!(registry.cache_key(), ":svelte@test", "output depends on the compiler");
+    let Err(err) = registry.compile(Loader::Svelte, b"{#if ok}<p>ok</p>", "/src/Broken.svelte", true) else { panic!("compiled a broken component") };
+    assert_eq!(err.to_string(), "[svelte@test] /src/Broken.svelte: Expected {/if}");
+}
+
+#[tokio::test]
+async fn test_build_svelte_app() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/svelte_app");
+    let src = root.join("src");
+    let _ = fs::remove_dir_all(&root).await;
+    fs::create_dir_all(&src).await.unwrap();
+    let client = root.join("node_modules/svelte/internal/client");
+    fs::create_dir_all(&client).await.unwrap();
+    fs::write(root.join("node_modules/svelte/package.json"), r#"{ "name": "svelte" }"#).await.unwrap();
+    fs::write(client.join("index.js"), "exports.text = function(value) { return 'svelte-text:' + value; };").await.unwrap();
+    fs::write(src.join("index.js"), "import App from './App.svelte';\nconsole.log(App());\n").await.unwrap();
+    fs::write(src.join("App.svelte"), r#"<script>
+  import { greeting } from './greeting.js';
+  console.log(greeting);
+</script>
+<h1 class="title">{greeting}</h1>
+<style>.title { color: orange; }</style>
+"#).await.unwrap();
+    fs::write(src.join("greeting.js"), "export const greeting = 'hello';\n").await.unwrap();
+
+    let options = BuildOptions { cache: false, svelte: Some(SvelteHook::new(StubCompiler::default())), ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let dist = root.join("dist");
+    let main = fs::read_to_string(dist.join("assets/main.js")).await.unwrap();
+    assert!(main.contains("__nexus_register__(\"/src/App.svelte\""));
+    assert!(main.contains("__nexus_register__(\"/src/greeting.js\""), "imports inside components are followed");
+    assert!(!main.contains("__nexus_hot__") && !main.contains("import.meta.hot"), "no HMR code in builds");
+    let css = fs::read_to_string(dist.join("assets/style.css")).await.unwrap();
+    assert!(css.contains(".title.svelte-1x2y3z") && css.contains("color: orange"), "{}", css);
+    let vendor = fs::read_to_string(dist.join("assets/vendor.js")).await.unwrap();
+    assert!(vendor.contains("svelte-text:"));
+}
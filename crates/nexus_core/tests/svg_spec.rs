@@ -0,0 +1,68 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::compiler::{compile_variant, AssetQuery};
+use nexus_core::svg::compile_svg;
+use tokio::fs;
+
+const ICON: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Generator: Sketch -->
+<svg xmlns="http://www.w3.org/2000/svg" xmlns:sketch="http://www.bohemiancoding.com/sketch/ns" viewBox="0 0 24 24" class="icon">
+  <metadata>made with love</metadata>
+  <title>Star &amp; moon</title>
+  <g>
+    <g fill="none" sketch:type="MSLayerGroup">
+      <path d="M12 2l3 7h7" stroke-width="2" style="stroke-linecap: round; --accent: red"/>
+    </g>
+    <g/>
+  </g>
+</svg>
+"#;
+
+#[test]
+fn test_svg_compiles_to_component() {
+    let dev = compile_svg(ICON.as_bytes(), "/src/star.svg", false);
+    assert!(dev.code.starts_with("export default \"data:image/svg+xml;base64,"), "the default import is still the URL");
+    assert!(dev.code.contains("import * as React from \"react\";"));
+    assert!(dev.code.contains("export { SvgComponent as ReactComponent };"));
+    assert!(dev.code.contains(
+        "React.createElement(\"svg\", Object.assign({ \"xmlns\": \"http://www.w3.org/2000/svg\", \"xmlnsSketch\": \"http://www.bohemiancoding.com/sketch/ns\", \"viewBox\": \"0 0 24 24\", \"className\": \"icon\" }, props)"
+    ));
+    assert!(dev.code.contains("React.createElement(\"title\", {  }, \"Star & moon\")"));
+    assert!(dev.code.contains("\"strokeWidth\": \"2\", \"style\": { \"strokeLinecap\": \"round\", \"--accent\": \"red\" }"));
+    assert!(dev.code.contains("made with love"), "only production builds optimise");
+
+    let prod = compile_variant(ICON.as_bytes(), "/src/star.svg", AssetQuery::React, true);
+    assert!(prod.code.ends_with("export default SvgComponent;\n"));
+    assert!(!prod.code.contains("metadata") && !prod.code.contains("sketch"), "editor data is stripped: {}", prod.code);
+    assert!(
+        prod.code.contains("React.createElement(\"path\", { \"fill\": \"none\", \"d\": \"M12 2l3 7h7\""),
+        "groups collapse into their only child: {}",
+        prod.code
+    );
+    assert!(!prod.code.contains("\"g\""));
+}
+
+#[tokio::test]
+async fn test_build_shakes_unused_components() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/svg_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::create_dir_all(root.join("node_modules/react")).await.unwrap();
+    fs::write(root.join("node_modules/react/package.json"), r#"{ "name": "react", "main": "index.js" }"#).await.unwrap();
+    fs::write(root.join("node_modules/react/index.js"), "exports.createElement = function() { return 'react-element'; };").await.unwrap();
+    fs::write(src.join("star.svg"), ICON).await.unwrap();
+    fs::write(src.join("logo.svg"), "<svg><circle r=\"4\"/></svg>").await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import Star from './star.svg?react';
+import logoUrl from './logo.svg';
+console.log(Star({ width: 24 }), logoUrl);
+"#).await.unwrap();
+
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let main = fs::read_to_string(root.join("dist/assets/main.js")).await.unwrap();
+    assert!(main.contains("__nexus_register__(\"/src/star.svg?react\""));
+    assert!(main.contains("React.createElement(\"path\""));
+    assert!(!main.contains("React.createElement(\"circle\""), "unused components are shaken");
+    let vendor = fs::read_to_string(root.join("dist/assets/vendor.js")).await.unwrap();
+    assert!(vendor.contains("react-element"));
+}
@@ -0,0 +1,98 @@
+use nexus_core::compiler::compile;
+
+#[test]
+fn test_types_are_erased() {
+    let source = r#"import type { Config } from './config';
+import { type Plugin, load, PluginOptions } from './plugins';
+import Theme from './theme';
+import { Logger } from './logger';
+import './polyfills';
+export interface Options { debug?: boolean }
+export type Mode = 'dev' | 'prod';
+declare const __DEV__: boolean;
+declare module 'virtual:routes' {}
+function pick<T>(items: T[], index?: number): T;
+function pick<T>(items: T[], index = 0): T | undefined {
+    return items[index]!;
+}
+export function start(config: Config, mode: Mode = 'dev', log?: Logger.Instance): Plugin[] {
+    const plugins = load<Plugin>(config) as Plugin[];
+    const options: PluginOptions = {};
+    const first = pick(plugins)?.name!;
+    (config as any).mode = mode satisfies string;
+    return plugins;
+}
+export { Options as Settings };
+"#;
+    let code = compile(source, "/src/start.ts", false).code;
+    assert!(code.contains("import { load } from \"./plugins\";"), "imports only types use go: {}", code);
+    assert!(code.contains("import Theme from \"./theme\";\nimport \"./polyfills\";"), "unused imports stay: {}", code);
+    for gone in ["./config", "Logger", "PluginOptions", "interface", "Mode", "__DEV__", "virtual:routes", "Settings", "<T>", "!", " as ", "satisfies", ": "] {
+        assert!(!code.contains(gone), "`{}` is left in: {}", gone, code);
+    }
+    assert_eq!(code.matches("function pick").count(), 1, "overloads go: {}", code);
+    assert!(code.contains("function pick(items, index = 0) {\n\treturn items[index];\n}"), "{}", code);
+    assert!(code.contains("const first = pick(plugins)?.name;"), "{}", code);
+    assert!(code.contains("config.mode = mode;"), "{}", code);
+}
+
+#[test]
+fn test_enums_and_parameter_properties() {
+    let source = r#"export enum Direction { Up = 1, Down, Left = Up << 4, Right }
+enum Label { Ok = 'ok', Error = `error` }
+declare enum Ambient { A }
+abstract class Base {
+    abstract name(): string;
+}
+export class Service extends Base implements Disposable {
+    private static count: number = 0;
+    declare readonly kind: string;
+    [key: string]: unknown;
+    constructor(private readonly url: string, public retries?: number) {
+        super();
+        Service.count++;
+    }
+    name(): string { return this.url; }
+    [Symbol.dispose](): void {}
+}
+console.log(Label.Ok);
+"#;
+    let code = compile(source, "/src/service.ts", false).code;
+    assert!(code.contains("export var Direction = function(Direction) {"), "{}", code);
+    assert!(code.contains("Direction[Direction[\"Up\"] = 1] = \"Up\";"), "{}", code);
+    assert!(code.contains("Direction[Direction[\"Down\"] = 2] = \"Down\";"), "{}", code);
+    assert!(code.contains("Direction[Direction[\"Left\"] = Direction[\"Up\"] << 4] = \"Left\";"), "{}", code);
+    assert!(code.contains("Direction[Direction[\"Right\"] = Direction[\"Left\"] + 1] = \"Right\";"), "{}", code);
+    assert!(code.contains("}(Direction || {});"), "{}", code);
+    assert!(code.contains("Label[\"Ok\"] = \"ok\";\n\tLabel[\"Error\"] = `error`;"), "string members have no reverse mapping: {}", code);
+    assert!(!code.contains("Ambient"), "{}", code);
+
+    assert!(code.contains("class Base {}"), "{}", code);
+    assert!(code.contains("export class Service extends Base {"), "{}", code);
+    assert!(code.contains("static count = 0;"), "{}", code);
+    assert!(!code.contains("kind") && !code.contains("key") && !code.contains("private"), "{}", code);
+    assert!(
+        code.contains("constructor(url, retries) {\n\t\tsuper();\n\t\tthis.url = url;\n\t\tthis.retries = retries;\n\t\tService.count++;"),
+        "parameter properties are assigned after `super()`: {}",
+        code
+    );
+    assert!(code.contains("[Symbol.dispose]() {}"), "{}", code);
+}
+
+#[test]
+fn test_jsx_keeps_component_imports() {
+    let source = r#"import React from 'react';
+import { Button, type ButtonProps } from './Button';
+import { Icon } from './Icon';
+import { Tooltip } from './Tooltip';
+export function Toolbar(props: ButtonProps, tip?: Tooltip) {
+    return <div><Button {...props} /></div>;
+}
+"#;
+    let code = compile(source, "/src/Toolbar.tsx", false).code;
+    assert!(code.contains("import React from \"react\";"), "{}", code);
+    assert!(code.contains("import { Button } from \"./Button\";"), "{}", code);
+    assert!(code.contains("import { Icon } from \"./Icon\";"), "{}", code);
+    assert!(!code.contains("Tooltip"), "{}", code);
+    assert!(code.contains("export function Toolbar(props, tip) {"), "{}", code);
+}
@@ -0,0 +1,77 @@
+use nexus_core::unit::CompilationUnit;
+use std::collections::{HashMap, HashSet};
+
+fn set(names: &[&str]) -> HashSet<String> {
+    names.iter().map(|s| s.to_string()).collect()
+}
+
+const SOURCE: &str = r#"
+import { format, unusedImport } from './format';
+import './polyfill';
+export function render() { return format('x'); }
+export function hydrate() { return 1; }
+export default class App {}
+const lazy = () => import('./lazy');
+export { lazy };
+"#;
+
+#[test]
+fn test_one_parse_serves_every_analysis() {
+    let unit = CompilationUnit::parse("/src/view.ts", SOURCE.to_string());
+    assert!(!unit.has_errors());
+
+    let deps = unit.dependencies();
+    assert!(deps.contains(&("./format".to_string(), false)));
+    assert!(deps.contains(&("./lazy".to_string(), true)));
+
+    let imports = unit.imports();
+    assert_eq!(imports.iter().filter(|i| !i.is_dynamic).count(), 2);
+
+    let bindings: Vec<String> = unit.shake_info().export_bindings().into_iter().map(|(e, _)| e).collect();
+    for name in ["render", "hydrate", "default", "lazy"] {
+        assert!(bindings.contains(&name.to_string()), "missing export {}", name);
+    }
+}
+
+#[test]
+fn test_shake_then_link_in_place() {
+    let mut unit = CompilationUnit::parse("/src/view.ts", SOURCE.to_string());
+    unit.shake(&set(&["render", "lazy"]), &HashSet::new());
+    let shaken = unit.code();
+    assert!(shaken.contains("export function render()"));
+    assert!(!shaken.contains("hydrate"));
+    assert!(!shaken.contains("unusedImport"));
+    assert!(!shaken.contains("class App"), "unused default class is dropped");
+
+    let imports: HashMap<String, String> = [("./format", "/src/format.ts"), ("./polyfill", "/src/polyfill.ts"), ("./lazy", "/src/lazy.ts")]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect();
+    unit.link(&imports);
+    let linked = unit.code();
+    assert!(!linked.contains("import "), "no ESM syntax may survive: {}", linked);
+    assert!(!linked.contains("export "), "no ESM syntax may survive: {}", linked);
+    assert!(linked.contains("const format = require(\"/src/format.ts\").format;"));
+    assert!(linked.contains("require(\"/src/polyfill.ts\");"), "side-effect import is kept");
+    assert!(linked.contains("__nexus_import__(\"/src/lazy.ts\")"));
+    assert!(linked.contains("Object.defineProperty(exports, \"render\""));
+    assert!(linked.contains("Object.defineProperty(exports, \"lazy\""));
+}
+
+#[test]
+fn test_link_default_declaration() {
+    let mut unit = CompilationUnit::parse("/src/app.js", "export default function App() { return 1; }".to_string());
+    unit.link(&HashMap::new());
+    let linked = unit.code();
+    assert!(linked.contains("exports.default = function App()"), "{}", linked);
+}
+
+#[test]
+fn test_parse_errors_leave_unit_untouched() {
+    let mut unit = CompilationUnit::parse("/src/broken.js", "export const = ;".to_string());
+    assert!(unit.has_errors());
+    assert!(unit.imports().is_empty());
+    assert!(unit.shake_info().has_side_effects);
+    unit.shake(&HashSet::new(), &HashSet::new());
+    unit.link(&HashMap::new());
+}
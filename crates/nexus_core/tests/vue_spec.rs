@@ -0,0 +1,305 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::loader::{Loader, LoaderRegistry};
+use nexus_core::vue::{compile_vue, VueCompiler, VueHook, VueOutput};
+use tokio::fs;
+
+const COUNTER: &str = r#"<script setup>
+import { ref } from 'vue';
+const count = ref(0);
+</script>
+
+<template>
+  <button class="counter" @click="count++">{{ count }}</button>
+</template>
+
+<style scoped>
+.counter { color: red; }
+</style>
+"#;
+
+const NATIVE_COUNTER: &str = r#"<script setup lang="ts">
+import { ref, computed } from 'vue';
+import Badge from './Badge.vue';
+
+interface Props { title: string; step?: number; tags: string[] }
+const props = withDefaults(defineProps<Props>(), { step: 1 });
+const emit = defineEmits<{ (e: 'change', value: number): void }>();
+const count = ref(0);
+const doubled = computed(() => count.value * 2);
+function increment() {
+  count.value += props.step;
+  emit('change', count.value);
+}
+defineExpose({ increment });
+</script>
+
+<template>
+  <div class="counter" :class="{ big: count > 10 }">
+    <h1>{{ title }} &amp; more</h1>
+    <p v-if="count === 0">Nothing yet</p>
+    <p v-else-if="count < 5">A few: {{ count }}</p>
+    <p v-else>Lots</p>
+    <ul>
+      <li v-for="(tag, i) in tags" :key="tag">{{ i }}: {{ tag.toUpperCase() }}</li>
+    </ul>
+    <input v-model.trim="name" @keyup.enter="increment">
+    <button @click="increment" @click.prevent="count = 0">+{{ step }}</button>
+    <Badge :value="doubled" @reset="count = 0">
+      <template #label="{ text }">{{ text }}!</template>
+    </Badge>
+    <slot name="footer" :count="count">No footer</slot>
+  </div>
+</template>
+
+<style scoped>
+.counter h1, .counter > p:first-child { color: red; }
+.counter :deep(.badge) { margin: 0; }
+@media (min-width: 600px) { .counter { padding: 2px; } }
+@keyframes pulse { from { opacity: 0; } to { opacity: 1; } }
+</style>
+"#;
+
+// Compiled without a `VueCompiler`.
+fn native(source: &str, is_prod: bool) -> nexus_core::compiler::CompileResult {
+    compile_vue(None, source.as_bytes(), "/src/Counter.vue", is_prod).unwrap()
+}
+
+// The `script`/`template` hashes the module records for HMR.
+fn hmr_hashes(code: &str) -> (String, String) {
+    let hash = |key: &str| code.split(&format!("{}: \"", key)).nth(1).unwrap()[..8].to_string();
+    (hash("script"), hash("template"))
+}
+
+#[test]
+fn test_sfc_compiles() {
+    let dev = native(NATIVE_COUNTER, false).code;
+    assert!(dev.starts_with("import { Fragment as _Fragment, createCommentVNode as _createCommentVNode, h as _h,"));
+    assert!(dev.contains("import { ref, computed } from \"vue\";\nimport Badge from \"./Badge.vue\";\nconst __sfc__ = {"), "imports are hoisted: {}", dev);
+
+    // `<script setup lang="ts">`: macros become options, bindings are returned.
+    assert!(dev.contains("props: _mergeDefaults({\n\t\t\"title\": {\n\t\t\ttype: String,\n\t\t\trequired: true\n\t\t},\n\t\t\"step\": {\n\t\t\ttype: Number,\n\t\t\trequired: false\n\t\t},\n\t\t\"tags\": {\n\t\t\ttype: Array,"));
+    assert!(dev.contains("}, { step: 1 }),\n\temits: [\"change\"],\n\tsetup(__props, { emit: __emit, expose: __expose }) {"));
+    assert!(dev.contains("const props = __props;\n\t\tconst emit = __emit;"));
+    assert!(dev.contains("count.value += props.step;"), "types are parsed: {}", dev);
+    assert!(dev.contains("__expose({ increment });"));
+    assert!(dev.contains("return {\n\t\t\tBadge,\n\t\t\tcomputed,\n\t\t\tcount,\n\t\t\tdoubled,\n\t\t\temit,\n\t\t\tincrement,\n\t\t\tprops,\n\t\t\tref\n\t\t};"));
+
+    // The template's render function.
+    assert!(dev.contains("_h(\"div\", { class: [\"counter\", { big: _ctx.count > 10 }] }, ["));
+    assert!(dev.contains("_h(\"h1\", null, [_toDisplayString(_ctx.title) + \" & more\"]),"));
+    assert!(dev.contains(
+        "_ctx.count === 0 ? _h(\"p\", null, [\"Nothing yet\"]) : _ctx.count < 5 ? _h(\"p\", null, [\"A few: \" + _toDisplayString(_ctx.count)]) : _h(\"p\", null, [\"Lots\"]),"
+    ));
+    assert!(dev.contains("_renderList(_ctx.tags, (tag, i) => _h(\"li\", { \"key\": tag }, [_toDisplayString(i) + \": \" + _toDisplayString(tag.toUpperCase())]))"));
+    assert!(dev.contains("\"onUpdate:modelValue\": ($event) => _ctx.name = $event,\n\t\t\t\"onKeyup\": _withKeys(_ctx.increment, [\"enter\"])"));
+    assert!(dev.contains("_vModelText,\n\t\t\t_ctx.name,\n\t\t\tvoid 0,\n\t\t\t{ \"trim\": true }"));
+    assert!(dev.contains("\"onClick\": [_ctx.increment, _withModifiers(($event) => _ctx.count = 0, [\"prevent\"])]"), "handlers of one event run in turn");
+    assert!(dev.contains("_h(_ctx.Badge, {\n\t\t\t\"value\": _ctx.doubled,\n\t\t\t\"onReset\": ($event) => _ctx.count = 0\n\t\t}, { \"label\": _withCtx(({ text }) => [_toDisplayString(text) + \"!\"]) }),"));
+    assert!(dev.contains("_renderSlot(_ctx.$slots, \"footer\", { \"count\": _ctx.count }, () => [\"No footer\"])"));
+    assert!(dev.contains("__sfc__.render = render;\n__sfc__.__scopeId = \"data-v-"));
+    assert!(dev.ends_with("export default __sfc__;\n"));
+}
+
+#[test]
+fn test_scoped_styles_and_hot_updates() {
+    let dev = native(NATIVE_COUNTER, false);
+    let scope = dev.code.split("__scopeId = \"").nth(1).unwrap()[..15].to_string();
+    assert!(scope.starts_with("data-v-"));
+    assert!(dev.code.contains("runtime.rerender(__sfc__.__hmrId, __sfc__.render)"));
+    assert!(dev.code.contains("style.textContent = "), "dev styles are injected");
+
+    let prod = native(NATIVE_COUNTER, true);
+    assert!(!prod.code.contains("__hmrId") && !prod.code.contains("textContent"));
+    let css = prod.css.unwrap();
+    assert!(css.contains(&format!(".counter h1[{0}], .counter > p[{0}]:first-child {{", scope)), "{}", css);
+    assert!(css.contains(&format!(".counter[{}] .badge {{", scope)), ":deep() scopes its parent: {}", css);
+    assert!(css.contains(&format!("@media (width >= 600px) {{\n  .counter[{}] {{", scope)), "{}", css);
+    assert!(css.contains("@keyframes pulse {\n  from {"), "keyframes are not scoped: {}", css);
+
+    // Template edits re-render, style edits don't touch the component, script edits reload it.
+    let (script, template) = hmr_hashes(&dev.code);
+    let edit = |from: &str, to: &str| hmr_hashes(&native(&NATIVE_COUNTER.replace(from, to), false).code);
+    let (script_after, template_after) = edit("Nothing yet", "Nothing at all");
+    assert_eq!(script_after, script);
+    assert_ne!(template_after, template);
+    assert_eq!(edit("color: red", "color: blue"), (script.clone(), template.clone()));
+    let (script_after, template_after) = edit("ref(0)", "ref(1)");
+    assert_ne!(script_after, script);
+    assert_eq!(template_after, template);
+}
+
+#[test]
+fn test_css_modules() {
+    let card = r#"<template><div :class="[$style.card, classes.title]">hi</div></template>
+<style module>
+.card { color: red; }
+.wide { composes: card; width: 100%; }
+</style>
+<style module="classes" scoped>.title { font-weight: bold; }</style>
+"#;
+    let prod = native(card, true);
+    assert!(prod.code.contains("class: [_ctx.$style.card, _ctx.classes.title]"), "{}", prod.code);
+    let modules = prod.code.split("__sfc__.__cssModules = ").nth(1).unwrap().split(";\n").next().unwrap();
+    let modules: serde_json::Value = serde_json::from_str(modules).unwrap();
+    let class = |module: &str, name: &str| modules[module][name].as_str().unwrap().to_string();
+    let (card_class, title_class) = (class("$style", "card"), class("classes", "title"));
+    assert!(card_class.ends_with("_card") && title_class.ends_with("_title"), "{}", modules);
+    assert_eq!(class("$style", "wide"), format!("{} {}", card_class.replace("_card", "_wide"), card_class), "composed classes come along");
+    let css = prod.css.unwrap();
+    assert!(css.contains(&format!(".{} {{\n  color: red;", card_class)), "{}", css);
+    assert!(css.contains(&format!(".{}[data-v-", title_class)), "module styles can be scoped too: {}", css);
+    assert!(!css.contains(".card"), "{}", css);
+
+    // Style edits keep the names; new classes reload the component.
+    let (script, template) = hmr_hashes(&native(card, false).code);
+    assert_eq!(hmr_hashes(&native(&card.replace("color: red", "color: blue"), false).code), (script.clone(), template.clone()));
+    let (script_after, template_after) = hmr_hashes(&native(&card.replace(".wide", ".narrow"), false).code);
+    assert_ne!(script_after, script);
+    assert_eq!(template_after, template);
+
+    let Err(err) = compile_vue(None, b"<style module>.a { composes: b from './b.css'; }</style>", "/src/A.vue", true) else { panic!("dropped a composition") };
+    assert_eq!(err.to_string(), "/src/A.vue: <style module>: can't compose from ./b.css");
+}
+
+#[tokio::test]
+async fn test_build_vue_app() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/vue_app");
+    let src = root.join("src");
+    let _ = fs::remove_dir_all(&root).await;
+    fs::create_dir_all(&src).await.unwrap();
+    fs::create_dir_all(root.join("node_modules/vue")).await.unwrap();
+    fs::write(root.join("node_modules/vue/package.json"), r#"{ "name": "vue", "main": "index.js" }"#).await.unwrap();
+    fs::write(root.join("node_modules/vue/index.js"), "exports.h = function() { return 'vue-vnode'; };\nexports.toDisplayString = String;").await.unwrap();
+    fs::write(src.join("index.js"), "import App from './App.vue';\nconsole.log(App.render({ msg: 'hi' }));\n").await.unwrap();
+    fs::write(src.join("App.vue"), r#"<script>
+import Child from './Child.vue';
+export default { components: { Child }, data: () => ({ msg: 'hi' }) };
+</script>
+<template><p class="title">{{ msg }}</p></template>
+<style scoped>.title { color: teal; }</style>
+"#).await.unwrap();
+    fs::write(src.join("Child.vue"), "<template><span>child</span></template>\n").await.unwrap();
+
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let dist = root.join("dist");
+    let main = fs::read_to_string(dist.join("assets/main.js")).await.unwrap();
+    assert!(main.contains("__nexus_register__(\"/src/App.vue\""));
+    assert!(main.contains("__nexus_register__(\"/src/Child.vue\""), "imports inside components are followed");
+    assert!(main.contains("const __default__ = {"));
+    assert!(main.contains("_h(\"p\", { class: \"title\" }, [_toDisplayString(_ctx.msg)])"));
+    let css = fs::read_to_string(dist.join("assets/style.css")).await.unwrap();
+    assert!(css.contains(".title[data-v-") && css.contains("color: teal"), "{}", css);
+    let vendor = fs::read_to_string(dist.join("assets/vendor.js")).await.unwrap();
+    assert!(vendor.contains("vue-vnode"));
+}
+
+// Stands in for `vue/compiler-sfc`: the script is kept, the template becomes
+// a render function returning its markup, a scoped style gets an attribute.
+// Like the real one without `sass` installed, it fails on `lang="scss"`.
+struct StubCompiler;
+
+fn block<'s>(source: &'s str, tag: &str) -> &'s str {
+    let Some(start) = source.find(&format!("<{}", tag)) else { return "" };
+    let open = start + source[start..].find('>').unwrap() + 1;
+    &source[open..open + source[open..].find(&format!("</{}>", tag)).unwrap()]
+}
+
+impl VueCompiler for StubCompiler {
+    fn name(&self) -> &str {
+        "vue@test"
+    }
+
+    fn compile(&self, source: &str, _id: &str, _dev: bool) -> std::io::Result<VueOutput> {
+        if source.contains("lang=\"scss\"") {
+            return Err(std::io::Error::other("Preprocessor dependency \"sass\" not found. Did you install it?"));
+        }
+        let (script, template) = (block(source, "script"), block(source, "template"));
+        let js = format!(
+            "{}\nconst __sfc__ = {{ setup: () => ({{ count }}) }};\nfunction render() {{ return {:?}; }}\n__sfc__.render = render;\n__sfc__.__scopeId = \"data-v-1x2y3z\";",
+            script.trim(),
+            template.trim(),
+        );
+        let css = block(source, "style").replace(".counter", ".counter[data-v-1x2y3z]");
+        if source.contains("<style module>") {
+            return Ok(VueOutput { js, modules: vec![("$style".to_string(), css)], script: script.to_string(), template: template.to_string(), ..VueOutput::default() });
+        }
+        Ok(VueOutput { js, css: Some(css), script: script.to_string(), template: template.to_string(), ..VueOutput::default() })
+    }
+}
+
+fn registry() -> LoaderRegistry {
+    LoaderRegistry::default().with_vue(Some(VueHook::new(StubCompiler)))
+}
+
+fn compile(source: &str, is_prod: bool) -> nexus_core::compiler::CompileResult {
+    registry().compile(Loader::Vue, source.as_bytes(), "/src/Counter.vue", is_prod).unwrap().unwrap()
+}
+
+#[test]
+fn test_sfc_compiles_through_the_compiler() {
+    let dev = compile(COUNTER, false);
+    let code = &dev.code;
+    assert!(code.starts_with("import { ref } from \"vue\";\nconst count = ref(0);\nconst __sfc__ = {"), "{}", code);
+    assert!(code.contains("__sfc__.__hmrId = \""), "{}", code);
+    assert!(code.contains("runtime.rerender(__sfc__.__hmrId, __sfc__.render)"));
+    assert!(code.contains("nexus-style-") && code.contains(".counter[data-v-1x2y3z]"), "styles are injected in dev: {}", code);
+    assert!(code.ends_with("export default __sfc__;\n"));
+    assert!(dev.css.is_none());
+
+    let prod = compile(COUNTER, true);
+    assert!(!prod.code.contains("__hmrId") && !prod.code.contains("nexus-style-"));
+    assert!(prod.css.unwrap().contains(".counter[data-v-1x2y3z]"));
+
+    let modules = compile(&COUNTER.replace("<style scoped>", "<style module>"), true);
+    assert!(modules.code.contains("__sfc__.__cssModules = { \"$style\": { \"counter\": \""), "the compiler's module styles get names: {}", modules.code);
+    assert!(!modules.css.unwrap().contains(".counter["));
+}
+
+#[test]
+fn test_hot_updates_follow_the_changed_block() {
+    let (script, template) = hmr_hashes(&compile(COUNTER, false).code);
+    let edit = |from: &str, to: &str| hmr_hashes(&compile(&COUNTER.replace(from, to), false).code);
+    let (script_after, template_after) = edit("{{ count }}", "Count: {{ count }}");
+    assert_eq!(script_after, script);
+    assert_ne!(template_after, template, "template edits re-render");
+    assert_eq!(edit("color: red", "color: blue"), (script.clone(), template.clone()), "style edits don't touch the component");
+    let (script_after, template_after) = edit("ref(0)", "ref(1)");
+    assert_ne!(script_after, script, "script edits reload");
+    assert_eq!(template_after, template);
+}
+
+#[test]
+fn test_compiler_errors_and_cache_key() {
+    assert_eq!(LoaderRegistry::default().cache_key(), "");
+    assert_eq!(registry().cache_key(), ":vue@test", "output depends on the compiler");
+    let theme = "<template><p class=\"theme\">hi</p></template>\n<style lang=\"scss\">$c: red; .theme { color: $c; }</style>\n";
+    let Err(err) = LoaderRegistry::default().compile(Loader::Vue, theme.as_bytes(), "/src/Theme.vue", true) else { panic!("dropped a style it couldn't preprocess") };
+    assert_eq!(err.to_string(), "/src/Theme.vue: <style lang=\"scss\"> needs the project's `vue/compiler-sfc` and a preprocessor");
+    let Err(err) = registry().compile(Loader::Vue, theme.as_bytes(), "/src/Theme.vue", true) else { panic!("dropped a style it couldn't preprocess") };
+    assert_eq!(err.to_string(), "[vue@test] /src/Theme.vue: Preprocessor dependency \"sass\" not found. Did you install it?");
+}
+
+#[tokio::test]
+async fn test_build_vue_app_with_compiler() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/vue_hook_app");
+    let src = root.join("src");
+    let _ = fs::remove_dir_all(&root).await;
+    fs::create_dir_all(&src).await.unwrap();
+    fs::create_dir_all(root.join("node_modules/vue")).await.unwrap();
+    fs::write(root.join("node_modules/vue/package.json"), r#"{ "name": "vue", "main": "index.js" }"#).await.unwrap();
+    fs::write(root.join("node_modules/vue/index.js"), "exports.ref = function(value) { return { value: value, vue: 'vue-ref' }; };").await.unwrap();
+    fs::write(src.join("index.js"), "import Counter from './Counter.vue';\nconsole.log(Counter.render());\n").await.unwrap();
+    fs::write(src.join("Counter.vue"), COUNTER).await.unwrap();
+
+    let options = BuildOptions { cache: false, vue: Some(VueHook::new(StubCompiler)), ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let dist = root.join("dist");
+    let main = fs::read_to_string(dist.join("assets/main.js")).await.unwrap();
+    assert!(main.contains("__nexus_register__(\"/src/Counter.vue\""));
+    assert!(main.contains("<button class=\\\"counter\\\""), "{}", main);
+    let css = fs::read_to_string(dist.join("assets/style.css")).await.unwrap();
+    assert!(css.contains(".counter[data-v-1x2y3z]") && css.contains("color: red"), "{}", css);
+    let vendor = fs::read_to_string(dist.join("assets/vendor.js")).await.unwrap();
+    assert!(vendor.contains("vue-ref"), "imports inside components are followed");
+}
@@ -0,0 +1,82 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::parser::transform_cjs;
+use nexus_core::wasm::{compile_wasm, WasmInterface};
+use std::collections::HashMap;
+use tokio::fs;
+
+// (func (import "./env.js" "log") (param i32 i32) (result i32))
+// (func (export "add") (export "add-one") (param i32 i32) (result i32) local.get 0 local.get 1 i32.add)
+fn math_wasm() -> Vec<u8> {
+    let mut bytes = b"\0asm\x01\0\0\0".to_vec();
+    bytes.extend([0x01, 0x07, 0x01, 0x60, 0x02, 0x7f, 0x7f, 0x01, 0x7f]);
+    bytes.extend([0x02, 0x10, 0x01, 0x08]);
+    bytes.extend(b"./env.js\x03log\x00\x00");
+    bytes.extend([0x03, 0x02, 0x01, 0x00]);
+    bytes.extend([0x07, 0x11, 0x02]);
+    bytes.extend(b"\x03add\x00\x01\x07add-one\x00\x01");
+    bytes.extend([0x0a, 0x09, 0x01, 0x07, 0x00, 0x20, 0x00, 0x20, 0x01, 0x6a, 0x0b]);
+    bytes
+}
+
+#[test]
+fn test_wasm_module_exposes_instance_exports() {
+    let interface = WasmInterface::parse(&math_wasm()).expect("valid module");
+    assert_eq!(interface.imports, vec!["./env.js"]);
+    assert_eq!(interface.exports, vec!["add", "add-one"]);
+    assert!(WasmInterface::parse(b"not wasm").is_none());
+
+    let dev = compile_wasm(&math_wasm(), "/src/math.wasm", false);
+    assert!(dev.code.contains("import * as __nexus_wasm_import_0 from \"./env.js\""));
+    assert!(dev.code.contains("__nexus_wasm__.instance(\"/src/math.wasm\", { \"./env.js\": __nexus_wasm_import_0 })"));
+    assert!(dev.code.contains("export { __nexus_wasm_export_0 as add };"), "invalid names can't be imported");
+    assert!(dev.asset.is_none());
+
+    let (name, _) = compile_wasm(&math_wasm(), "/src/math.wasm", true).asset.expect("emitted in production");
+    assert!(name.starts_with("assets/math-") && name.ends_with(".wasm") && name.len() == "assets/math-12345678.wasm".len());
+}
+
+#[test]
+fn test_init_and_url_imports() {
+    let source = r#"
+import init from './math.wasm?init';
+import mathUrl from './math.wasm?url';
+"#;
+    let imports = HashMap::from([
+        ("./math.wasm?init".to_string(), "/src/math.wasm".to_string()),
+        ("./math.wasm?url".to_string(), "/src/math.wasm?url".to_string()),
+    ]);
+    let code = transform_cjs(source, "/src/main.ts", &imports);
+    assert!(code.contains("const init = function(imports) { return __nexus_wasm__.instantiate(\"/src/math.wasm\", imports); };"));
+    assert!(code.contains("const mathUrl = (function(m) { return m && m.__esModule ? m.default : m; })(require(\"/src/math.wasm?url\"));"), "the binary is a file like any other");
+    assert!(!code.contains("require(\"/src/math.wasm\")"), "nothing is instantiated on import");
+}
+
+#[tokio::test]
+async fn test_build_preloads_wasm_and_emits_hashed_binary() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/wasm_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import { add } from './math.wasm';
+import mathUrl from './math.wasm?url';
+console.log(add(1, 2), mathUrl);
+"#).await.unwrap();
+    fs::write(src.join("env.js"), "export function log(a, b) { console.log(a, b); return 0; }").await.unwrap();
+    fs::write(src.join("math.wasm"), math_wasm()).await.unwrap();
+
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let assets = root.join("dist/assets");
+    let binary = std::fs::read_dir(&assets)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .find(|name| name.starts_with("math-") && name.ends_with(".wasm"))
+        .expect("hashed binary emitted");
+    assert_eq!(fs::read(assets.join(&binary)).await.unwrap(), math_wasm());
+
+    let main = fs::read_to_string(assets.join("main.js")).await.unwrap();
+    assert!(main.starts_with("__nexus_wasm__.preload([\"/src/math.wasm\"]);"));
+    assert!(main.contains("__nexus_register__(\"/src/env.js\""), "imports of the binary are bundled");
+    assert!(main.contains(&format!("window.__nexus_asset_map__ = {{\"/src/math.wasm\":\"/assets/{}\"}};", binary)));
+    assert!(main.contains("__nexus_wasm__.ready().then(function() { __nexus_require__(\"/src/index.tsx\"); });"));
+}
@@ -0,0 +1,95 @@
+use nexus_core::bundler::{self, BuildOptions, BuildSession};
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs;
+
+async fn setup(name: &str) -> PathBuf {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures").join(name);
+    let _ = fs::remove_dir_all(&root).await;
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+import { greet } from './greet';
+import './style.css';
+greet();
+import('./lazy').then(m => m.lazy());
+"#).await.unwrap();
+    fs::write(src.join("greet.ts"), "export function greet() { console.log('hello'); }").await.unwrap();
+    fs::write(src.join("lazy.ts"), "export function lazy() { console.log('lazy'); }").await.unwrap();
+    fs::write(src.join("style.css"), "body { color: red; }").await.unwrap();
+    root
+}
+
+#[tokio::test]
+async fn test_rebuild_rewrites_only_changed_outputs() {
+    let root = setup("watch_incremental_app").await;
+    let assets = root.join("dist/assets");
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    let mut session = BuildSession::new(root.to_str().unwrap(), &options).await.unwrap();
+    session.build().await.unwrap();
+    assert!(session.rewritten().contains(&assets.join("vendor.js")));
+
+    let greet = root.join("src/greet.ts");
+    fs::write(&greet, "import { name } from './name';\nexport function greet() { console.log('hi ' + name); }").await.unwrap();
+    fs::write(root.join("src/name.ts"), "export const name = 'nexus';").await.unwrap();
+    let report = session.rebuild(&[greet, root.join("src/name.ts")]).await.unwrap();
+
+    let main = fs::read_to_string(assets.join("main.js")).await.unwrap();
+    assert!(main.contains("\"hi \""), "changed module is recompiled");
+    assert!(main.contains("/src/name.ts"), "newly imported module is loaded");
+    assert!(report.module("/src/name.ts").is_some());
+    assert_eq!(session.rewritten(), &[assets.join("main.js")], "only main.js changed");
+}
+
+#[tokio::test]
+async fn test_rebuild_matches_fresh_build_and_removes_stale_chunks() {
+    let root = setup("watch_remove_app").await;
+    let assets = root.join("dist/assets");
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    let mut session = BuildSession::new(root.to_str().unwrap(), &options).await.unwrap();
+    session.build().await.unwrap();
+    assert!(assets.join("chunk-src-lazy.ts.js").exists());
+
+    let index = root.join("src/index.tsx");
+    fs::write(&index, "import { greet } from './greet';\ngreet();\n").await.unwrap();
+    let incremental = session.rebuild(&[index]).await.unwrap();
+    assert!(!assets.join("chunk-src-lazy.ts.js").exists(), "chunk no longer produced is removed");
+    assert!(incremental.module("/src/lazy.ts").is_none());
+    assert!(!incremental.pruned.contains(&"/src/lazy.ts".to_string()), "unreachable modules leave the graph");
+
+    let main = fs::read_to_string(assets.join("main.js")).await.unwrap();
+    let fresh = bundler::build_with_options(root.to_str().unwrap(), &options).await.unwrap();
+    assert_eq!(main, fs::read_to_string(assets.join("main.js")).await.unwrap());
+    assert_eq!(incremental.to_json(), fresh.to_json());
+}
+
+#[tokio::test]
+async fn test_watch_rebuilds_on_file_change() {
+    let root = setup("watch_live_app").await;
+    let main = root.join("dist/assets/main.js");
+    let watch_root = root.to_str().unwrap().to_string();
+    let handle = tokio::spawn(async move {
+        let options = BuildOptions { cache: false, ..BuildOptions::default() };
+        bundler::watch(&watch_root, &options).await
+    });
+
+    let wait_for = |needle: &'static str| {
+        let main = main.clone();
+        async move {
+            for _ in 0..200 {
+                if fs::read_to_string(&main).await.map(|c| c.contains(needle)).unwrap_or(false) {
+                    return true;
+                }
+                tokio::time::sleep(Duration::from_millis(25)).await;
+            }
+            false
+        }
+    };
+    assert!(wait_for("\"hello\"").await, "initial build");
+    // Let the watcher register before editing.
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    fs::write(root.join("src/greet.ts"), "export function greet() { console.log('changed'); }").await.unwrap();
+    assert!(wait_for("\"changed\"").await, "rebuilt after change");
+    handle.abort();
+}
@@ -0,0 +1,77 @@
+use nexus_core::bundler::{self, BuildOptions};
+use nexus_core::parser::{transform_cjs, worker_constructor};
+use nexus_core::unit::CompilationUnit;
+use std::collections::HashMap;
+use tokio::fs;
+
+#[test]
+fn test_worker_constructors_are_dependencies() {
+    let unit = CompilationUnit::parse("/src/main.ts", r#"
+import Parser from './parser.ts?worker';
+const worker = new Worker(new URL('./compute.ts', import.meta.url), { type: 'module' });
+const shared = new SharedWorker(new URL('./state.ts', import.meta.url));
+const remote = new Worker('/static/legacy.js');
+"#.to_string());
+    let deps = unit.dependencies();
+
+    assert!(deps.contains(&("./parser.ts?worker".to_string(), false)));
+    assert!(deps.contains(&("./compute.ts?worker".to_string(), false)));
+    assert!(deps.contains(&("./state.ts?sharedworker".to_string(), false)));
+    assert_eq!(deps.len(), 3, "plain script URLs are left alone");
+    assert_eq!(worker_constructor("./state.ts?sharedworker"), Some("SharedWorker"));
+    assert_eq!(worker_constructor("./state.ts"), None);
+}
+
+#[test]
+fn test_worker_urls_are_linked_through_the_runtime() {
+    let source = r#"
+import Parser from './parser.ts?worker';
+const worker = new Worker(new URL('./compute.ts', import.meta.url));
+"#;
+    let imports = HashMap::from([
+        ("./parser.ts?worker".to_string(), "/src/parser.ts".to_string()),
+        ("./compute.ts?worker".to_string(), "/src/compute.ts".to_string()),
+    ]);
+
+    let mut unit = CompilationUnit::parse("/src/main.ts", source.to_string());
+    unit.link(&imports);
+    let linked = unit.code();
+    assert!(linked.contains("new Worker(__nexus_worker__(\"/src/parser.ts\"), options)"));
+    assert!(linked.contains("new Worker(__nexus_worker__(\"/src/compute.ts\"))"));
+
+    let dev = transform_cjs(source, "/src/main.ts", &imports);
+    assert!(dev.contains("__nexus_worker__(\"/src/compute.ts\")"));
+    assert!(!dev.contains("require(\"/src/parser.ts\")"), "the worker doesn't run on the page");
+}
+
+#[tokio::test]
+async fn test_build_emits_worker_bundles() {
+    let root = std::env::current_dir().unwrap().join("tests/fixtures/worker_app");
+    let src = root.join("src");
+    fs::create_dir_all(&src).await.unwrap();
+    fs::write(src.join("index.tsx"), r#"
+const worker = new Worker(new URL('./compute.ts', import.meta.url));
+worker.postMessage(21);
+"#).await.unwrap();
+    fs::write(src.join("compute.ts"), r#"
+import { double } from './math';
+self.onmessage = (e) => self.postMessage(double(e.data));
+"#).await.unwrap();
+    fs::write(src.join("math.ts"), "export function double(n) { return n * 2; }").await.unwrap();
+
+    let options = BuildOptions { cache: false, ..BuildOptions::default() };
+    bundler::build_with_options(root.to_str().unwrap(), &options).await.expect("Build failed");
+    let assets = root.join("dist/assets");
+    let worker = std::fs::read_dir(&assets)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().into_owned())
+        .find(|name| name.starts_with("worker-compute-") && name.ends_with(".js"))
+        .expect("hashed worker bundle emitted");
+    let worker_code = fs::read_to_string(assets.join(&worker)).await.unwrap();
+    assert!(worker_code.contains("__nexus_register__(\"/src/math.ts\""));
+    assert!(worker_code.contains("__nexus_require__(\"/src/compute.ts\")"));
+
+    let main = fs::read_to_string(assets.join("main.js")).await.unwrap();
+    assert!(!main.contains("__nexus_register__(\"/src/compute.ts\""), "worker code stays out of the page");
+    assert!(main.contains(&format!("\"/src/compute.ts\":\"/assets/{}\"", worker)));
+}
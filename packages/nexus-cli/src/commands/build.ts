@@ -1,15 +1,29 @@
 import pc from 'picocolors';
+import { build } from '@apexjs/core';
+import { loadConfig, resolvePlugins } from '../config';
 
 interface BuildOptions {
     config: string;
+    mode?: string;
 }
 
 export async function buildProduction(options: BuildOptions): Promise<void> {
     console.log(pc.dim(`  Config: ${options.config}\n`));
 
-    // For v0.1, we delegate to Rollup
-    // TODO: Implement production Virtual Chunking in v0.2
-    console.log(pc.yellow('⚠️  v0.1 delegates to Rollup for production builds\n'));
-    console.log(pc.dim('  [Not yet implemented - Issue #33]'));
-    console.log(pc.dim('  For now, use: npx rollup -c\n'));
+    const root = process.cwd();
+    const config = await loadConfig(root, options.config);
+
+    try {
+        await build(root, await resolvePlugins(config, root, 'build', options.config), {
+            loader: config.loader,
+            svelte: config.svelte,
+            vue: config.vue,
+            mode: options.mode ?? config.mode,
+            define: config.define,
+        });
+    } catch (err) {
+        console.error(pc.red('Build failed:'), err instanceof Error ? err.message : err);
+        process.exit(1);
+    }
+    console.log(pc.green('  Done: dist/\n'));
 }
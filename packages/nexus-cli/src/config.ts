@@ -0,0 +1,87 @@
+import crypto from 'crypto';
+import { build } from 'esbuild';
+import fs from 'fs';
+import path from 'path';
+import { pathToFileURL } from 'url';
+import { fromVite } from '@apexjs/core';
+import type { Loader, NexusPlugin, SvelteCompilerModule, VitePluginOption, VueCompilerModule } from '@apexjs/core';
+
+export interface NexusConfig {
+    plugins?: NexusPlugin[];
+    /** Vite plugins, as in `vite.config`; run through the compatibility layer. */
+    vitePlugins?: VitePluginOption[];
+    /** Loaders by extension or glob, e.g. `{ '.glsl': 'text' }`. */
+    loader?: Record<string, Loader>;
+    /** The Svelte compiler; by default the project's `svelte/compiler`. `false` turns `.svelte` off. */
+    svelte?: SvelteCompilerModule | false;
+    /** The Vue compiler; by default the project's `vue/compiler-sfc`, else Nexus' own. `false` picks Nexus' own. */
+    vue?: VueCompilerModule | false;
+    /** Selects `.env.[mode]`; `development` for `nexus dev`, `production` for `nexus build`. */
+    mode?: string;
+    /** Compile-time replacements, e.g. `{ __APP_VERSION__: '"1.2.0"' }`. */
+    define?: Record<string, string | number | boolean | null | object>;
+}
+
+export function defineConfig(config: NexusConfig): NexusConfig {
+    return config;
+}
+
+// Kept as a real `import()`: TypeScript would turn it into `require()`,
+// which can't load an ES module config.
+const importModule = new Function('specifier', 'return import(specifier)') as (specifier: string) => Promise<any>;
+
+// Node can't import TypeScript, so `.ts` configs are bundled into ES module
+// JS first. Packages stay external and are imported from the project.
+async function bundleConfig(configPath: string): Promise<string> {
+    const result = await build({
+        entryPoints: [configPath],
+        bundle: true,
+        write: false,
+        platform: 'node',
+        format: 'esm',
+        target: `node${process.versions.node}`,
+        packages: 'external',
+        sourcemap: 'inline',
+        logLevel: 'silent',
+        define: { 'import.meta.url': JSON.stringify(pathToFileURL(configPath).href) },
+    });
+    return result.outputFiles[0].text;
+}
+
+export async function loadConfig(root: string, file: string): Promise<NexusConfig> {
+    const configPath = path.resolve(root, file);
+    if (!fs.existsSync(configPath)) {
+        return {};
+    }
+    if (!/\.[cm]?tsx?$/.test(configPath)) {
+        const module = await importModule(pathToFileURL(configPath).href);
+        return module.default ?? {};
+    }
+    // Written next to the config, so `node_modules` resolve the same.
+    const output = `${configPath}.timestamp-${Date.now()}.mjs`;
+    fs.writeFileSync(output, await bundleConfig(configPath));
+    try {
+        const module = await importModule(pathToFileURL(output).href);
+        return module.default ?? {};
+    } finally {
+        fs.rmSync(output, { force: true });
+    }
+}
+
+// Plugin options are only known to the config file, so its content is part
+// of every plugin's cache key: editing it drops the cached transforms.
+export function configHash(root: string, file: string): string | undefined {
+    const configPath = path.resolve(root, file);
+    if (!fs.existsSync(configPath)) {
+        return undefined;
+    }
+    return crypto.createHash('sha256').update(fs.readFileSync(configPath)).digest('hex').slice(0, 16);
+}
+
+export async function resolvePlugins(config: NexusConfig, root: string, command: 'serve' | 'build', file: string): Promise<NexusPlugin[]> {
+    const hash = configHash(root, file);
+    const plugins = (config.plugins ?? []).map((plugin) =>
+        plugin.cacheKey || !hash ? plugin : { ...plugin, cacheKey: `${plugin.name}@${hash}` });
+    const vitePlugins = await fromVite(config.vitePlugins ?? [], { root, command, configHash: hash });
+    return [...plugins, ...vitePlugins];
+}
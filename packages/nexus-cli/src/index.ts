@@ -17,6 +17,7 @@ program
     .description('Start development server')
     .option('-p, --port <port>', 'Port to run server on', '3000')
     .option('-c, --config <path>', 'Path to config file', 'nexus.config.ts')
+    .option('-m, --mode <mode>', 'Mode for .env files and import.meta.env.MODE')
     .action(async (options) => {
         console.log(pc.cyan('\n🚀 Nexus Dev Server\n'));
         await startDevServer(options);
@@ -26,6 +27,7 @@ program
     .command('build')
     .description('Build for production')
     .option('-c, --config <path>', 'Path to config file', 'nexus.config.ts')
+    .option('-m, --mode <mode>', 'Mode for .env files and import.meta.env.MODE')
     .action(async (options) => {
         console.log(pc.cyan('\n📦 Building for production...\n'));
         await buildProduction(options);